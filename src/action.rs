@@ -1,12 +1,40 @@
-use std::{fmt, string::ToString};
+use std::sync::Arc;
 
-use serde::{
-  de::{self, Deserializer, Visitor},
-  Deserialize, Serialize,
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter};
+
+use crate::{
+  attach::AttachedDatabase,
+  components::{
+    db::{DbColumn, DbTable},
+    health::ConnectionHealth,
+    ComponentKind,
+  },
+  join_builder::ForeignKey,
+  notices::Notice,
+  server::ServerInfo,
+  sql::{Nulls, QueryEvent},
 };
-use strum::Display;
 
-use crate::components::{db::DbTable, ComponentKind};
+/// What to do with a query asked to run while the connection is busy with another one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter)]
+pub enum BusyQueryChoice {
+  /// Cancel the running query and run the new one once it stopped.
+  CancelCurrent,
+  /// Run the new query after the running one finishes.
+  Queue,
+  Discard,
+}
+
+impl BusyQueryChoice {
+  pub fn label(&self) -> &'static str {
+    match self {
+      BusyQueryChoice::CancelCurrent => "c  Cancel the running query and run this one",
+      BusyQueryChoice::Queue => "q  Queue this one to run next",
+      BusyQueryChoice::Discard => "d  Discard this one",
+    }
+  }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Display, Deserialize)]
 pub enum Action {
@@ -29,12 +57,80 @@ pub enum Action {
   LoadSelectedTable,
   LoadTables(String),
   LoadTable(String),
-  QueryResult(Vec<String>, Vec<Vec<String>>),
+  QueryResult(Vec<String>, Vec<Vec<String>>, Nulls),
+  /// A further result set of the running statements, after the first arrived as [`Action::QueryResult`].
+  QueryResultSet(Vec<String>, Vec<Vec<String>>, Nulls),
+  /// A server notice or warning of the running statements, such as `RAISE NOTICE` output.
+  QueryNotice(Notice),
   FocusQuery,
   FocusResults,
   FocusHome,
   SelectComponent(ComponentKind),
   ExecuteQuery,
   HandleQuery(String),
+  /// The running query failed, with its error to show with a hint when its SQLSTATE has one.
+  QueryError(String),
+  /// The query run with the id ended, with its error if it failed. Its results and notices were dispatched before.
+  QueryFinished(u64, Option<String>),
+  /// A query asked to run while another one is still running, for the user to decide what happens to it.
+  QueryBusy(String),
+  /// What the user chose to do with a query that arrived while another one was running.
+  ResolveBusyQuery(String, BusyQueryChoice),
+  /// The running query could not be cancelled, with the reason. A query chosen to replace it runs once it finishes.
+  QueryNotCancelled(String),
+  /// A retry was scheduled: attempt, maximum attempts and the error of the failed attempt.
+  QueryRetrying(u32, u32, String),
   RowDetails,
+  CountTableRows(String),
+  TableRowCount(String, String),
+  PreviewTable(String),
+  TablePreview(String, Vec<String>, Vec<Vec<String>>),
+  LoadTableColumns(DbTable),
+  TableColumnsLoaded(DbTable, Vec<DbColumn>),
+  /// Attaches a SQLite database file under an alias.
+  AttachDatabase(String, String),
+  DetachDatabase(String),
+  /// The attached SQLite databases and their tables, qualified with the alias as schema.
+  DatabasesAttached(Vec<AttachedDatabase>, Vec<DbTable>),
+  /// Loads the foreign keys from and to the table for the join builder.
+  LoadForeignKeys(DbTable),
+  ForeignKeysLoaded(DbTable, Vec<ForeignKey>),
+  ToggleHealth,
+  CheckHealth,
+  HealthChecked(ConnectionHealth),
+  ServerDetected(ServerInfo),
+  /// Runs the given EXPLAIN (FORMAT JSON) statement for exporting its plan.
+  ExportPlan(String),
+  PlanLoaded(String),
+  /// Rows serialized so far and total rows of a copy of all rows.
+  CopyProgress(usize, usize),
+  /// All rows were copied: row count and size of the copied text.
+  RowsCopied(usize, usize),
+  CopyFailed(String),
+  /// A profile's post-export command, run in the background, failed with this error.
+  PostCommandFailed(String),
+  /// Rows matching the results filter, computed in the background, with the generation of the filter run: their
+  /// positions and the rows themselves, shared with the unfiltered results when every row matched.
+  ResultsFiltered(u64, Vec<usize>, Arc<Vec<Vec<String>>>),
+  /// Asks for the value of a connection preamble placeholder before the pending query runs.
+  PromptPreambleValue(String),
+  /// Value entered for a connection preamble placeholder.
+  PreambleValue(String, String),
+  /// Shows the team's recent queries from the shared history table in the results.
+  LoadTeamHistory,
+  /// Headers and rows of the team's recent queries, shown like results that have no query to re-run.
+  TeamHistoryLoaded(Vec<String>, Vec<Vec<String>>),
+  ToggleDebug,
+  TogglePerf,
+}
+
+impl From<QueryEvent> for Action {
+  fn from(event: QueryEvent) -> Self {
+    match event {
+      QueryEvent::Results(headers, rows, nulls) => Action::QueryResult(headers, rows, nulls),
+      QueryEvent::ResultSet(headers, rows, nulls) => Action::QueryResultSet(headers, rows, nulls),
+      QueryEvent::Notice(notice) => Action::QueryNotice(notice),
+      QueryEvent::Retrying(attempt, max_attempts, error) => Action::QueryRetrying(attempt, max_attempts, error),
+    }
+  }
 }
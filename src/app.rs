@@ -1,33 +1,41 @@
 use std::{
+  collections::VecDeque,
   sync::Arc,
   thread,
   time::{Duration, Instant},
 };
 
-use color_eyre::eyre::{self, anyhow, Result};
+use color_eyre::eyre::Result;
 use crossterm::event::KeyEvent;
 use ratatui::prelude::Rect;
-use serde::{Deserialize, Serialize};
-use sqlx::{
-  postgres::{PgColumn, PgPoolOptions, PgRow},
-  types::Uuid,
-  Column, Postgres, Row,
-};
 use tokio::sync::mpsc;
-use tokio_stream::StreamExt;
-use toml::Value;
 
 use crate::{
-  action::Action,
+  action::{Action, BusyQueryChoice},
+  attach,
+  capabilities::Capabilities,
   components::{
-    db::{Db, DbTable},
+    db::{Db, DbColumn},
+    debug::DebugOverlay,
     fps::FpsCounter,
+    health::{ConnectionHealth, HealthPanel},
     home::Home,
     Component, ComponentKind,
   },
   config::Config,
+  connection, crash,
+  events::{self, Event, EventLog},
+  join_builder::ForeignKey,
+  keymap::{self, Command, Scope},
+  lock::IdleLock,
   mode::Mode,
-  sql::Queryer,
+  perf,
+  preamble::Preamble,
+  schema,
+  server::{ServerInfo, ServerKind},
+  snippets,
+  sql::{self, Queryer},
+  team_history::TeamHistory,
   tui,
 };
 
@@ -41,61 +49,216 @@ pub struct App {
   pub should_suspend: bool,
   pub mode: Mode,
   pub last_tick_key_events: Vec<KeyEvent>,
-  pool: sqlx::Pool<sqlx::Postgres>,
   db: Arc<dyn Queryer>,
+  lock: IdleLock,
+  team_history: Option<TeamHistory>,
+  events: Option<EventLog>,
+  capabilities: Capabilities,
+  /// Statements run before each query of the Postgres connection.
+  preamble: Option<Preamble>,
+  /// Query waiting for preamble values to be entered.
+  pending_query: Option<String>,
+  /// Query sent to the database that hasn't finished yet, with the id of its run and when it started. Queries asked to
+  /// run meanwhile wait for the user to cancel, queue or discard them.
+  running_query: Option<RunningQuery>,
+  queued_queries: VecDeque<String>,
+  /// Id of the last query run, telling its [`Action::QueryFinished`] apart from earlier runs'.
+  query_runs: u64,
 }
 
-static CONFIG: &'static [u8] = include_bytes!("../config.toml");
-
-fn to_connection(config: &str) -> Result<String> {
-  let app_config_contents = std::str::from_utf8(CONFIG)?;
-  let app_config = toml::from_str::<Value>(&app_config_contents)?;
-  let v = app_config["connections"][0]["host"].clone();
-  let host = app_config["connections"][0]["host"].as_str().map_or("localhost", |v| v);
-  let _port = app_config["connections"][0]["port"].as_integer().unwrap_or(5432);
-  let username = app_config["connections"][0]["username"].as_str().map_or("postgres", |v| v);
-  let password = app_config["connections"][0]["password"].as_str().map_or("", |v| v);
-  let database = app_config["connections"][0]["database"].as_str().map_or("postgres", |v| v);
-  let connection = format!("postgres://{}:{}@{}/{}", username, password, host, database);
-
-  Ok(connection)
+struct RunningQuery {
+  run: u64,
+  query: String,
+  attempt: u32,
+  started: Instant,
 }
 
 impl App {
-  pub async fn new(tick_rate: f64, frame_rate: f64, filename: Option<String>) -> Result<Self> {
+  pub async fn new(
+    tick_rate: f64,
+    frame_rate: f64,
+    filename: Option<String>,
+    debug: bool,
+    events: Option<EventLog>,
+    capabilities: Capabilities,
+  ) -> Result<Self> {
     // let home = Home::new();
     // let fps = FpsCounter::default();
-    let db = Db::new();
+    capabilities.install();
+    let mut db = Db::new();
     let config = Config::new()?;
     let mode = Mode::Home;
-    let connection = to_connection("config.toml")?;
-    let pool = PgPoolOptions::new().max_connections(5).connect(&connection).await?;
-    let db_conn: Arc<dyn Queryer> = match &filename {
-      Some(f) => Arc::new(crate::sql::Sqlite::new(&f).await?),
-      None => Arc::new(crate::sql::Postgres::new(&connection).await?),
-    };
-    let postgres = crate::sql::Postgres::new(&connection).await?;
+    let (connection, db_conn) = connection::connect(filename.as_deref()).await?;
+    db.set_connection(filename.clone().unwrap_or_else(|| crash::redact_connection(&connection)));
+    db.set_database_file(filename.clone());
+    crash::set_config_summary(format!(
+      "connection: {}\nsettings: {:?}\nexport directory: {:?}\nexport profiles: {:?}",
+      filename.clone().unwrap_or_else(|| crash::redact_connection(&connection)),
+      config.settings,
+      config.export.directory,
+      config.export.profiles.keys().collect::<Vec<_>>(),
+    ));
+
+    let lock_password = if config.lock.require_password && filename.is_none() { connection::password() } else { None };
+    let lock = IdleLock::new(config.lock.idle_timeout(), lock_password);
+    let team_history = TeamHistory::new(&config.team_history);
+    let preamble = if filename.is_none() { connection::preamble() } else { None };
 
     Ok(Self {
       tick_rate,
       frame_rate,
       filename,
       // components: vec![Box::new(home), Box::new(fps)],
-      components: vec![Box::new(db)],
+      components: vec![
+        Box::new(db),
+        Box::new(HealthPanel::new()),
+        Box::new(DebugOverlay::new(debug)),
+        Box::new(FpsCounter::new()),
+      ],
       should_quit: false,
       should_suspend: false,
       config,
       mode,
       last_tick_key_events: Vec::new(),
-      pool,
       db: db_conn,
+      lock,
+      team_history,
+      events,
+      capabilities,
+      preamble,
+      pending_query: None,
+      running_query: None,
+      queued_queries: VecDeque::new(),
+      query_runs: 0,
     })
   }
 
+  fn server_kind(&self) -> ServerKind {
+    if self.filename.is_some() {
+      ServerKind::Sqlite
+    } else {
+      ServerKind::Postgres
+    }
+  }
+
+  /// Query returning `(version, backend count, replication lag)` for the health panel.
+  fn health_query(&self) -> &'static str {
+    if self.filename.is_some() {
+      "SELECT sqlite_version(), 'n/a', 'n/a'"
+    } else {
+      "SELECT version(), (SELECT count(*) FROM pg_stat_activity)::text, CASE WHEN pg_is_in_recovery() THEN \
+       COALESCE(EXTRACT(EPOCH FROM now() - pg_last_xact_replay_timestamp())::text || ' s', 'unknown') ELSE 'not a \
+       replica' END"
+    }
+  }
+
+  /// Attaches the database `file` as `alias`, or detaches `alias` without a file, then reloads the attached databases
+  /// and their tables.
+  fn change_attachments(&self, alias: String, file: Option<String>, tx: mpsc::UnboundedSender<Action>) {
+    let db = self.db.clone();
+    tokio::spawn(async move {
+      let changed = match &file {
+        Some(file) => db.attach(file, &alias).await,
+        None => db.detach(&alias).await,
+      };
+      let action = match changed.and(attach::attached_databases(db.as_ref()).await) {
+        Ok((databases, tables)) => Action::DatabasesAttached(databases, tables),
+        Err(e) => Action::Error(format!("Error attaching database: {:?}", e)),
+      };
+      dispatch(tx, action).await
+    });
+  }
+
+  /// Runs `q` in the background, retrying it while it fails with a transient error, and reports its end as
+  /// [`Action::QueryFinished`] tagged with a new run id.
+  fn run_query(&mut self, q: &str, action_tx: &mpsc::UnboundedSender<Action>) {
+    self.emit(Event::QueryStarted { query: q.to_string(), attempt: 1 });
+    self.query_runs += 1;
+    let run = self.query_runs;
+    self.running_query = Some(RunningQuery { run, query: q.to_string(), attempt: 1, started: Instant::now() });
+    self.db.set_preamble(self.preamble.as_ref().map(Preamble::sql));
+    let (q, tx, db, retry) = (q.to_string(), action_tx.clone(), self.db.clone(), self.config.retry.clone());
+    tokio::spawn(async move {
+      let error = sql::run_into(db.as_ref(), &q, &retry, tx.clone()).await.err().map(|e| format!("{e:?}"));
+      dispatch(tx, Action::QueryFinished(run, error)).await
+    });
+  }
+
+  /// Records the failed attempt of the running query that is being retried.
+  fn retry_query(&mut self, attempt: u32, error: &str) {
+    let Some(running) = &mut self.running_query else {
+      return;
+    };
+    let query = running.query.clone();
+    running.attempt = attempt;
+    log::warn!("Query attempt {} failed, retrying: {error}", attempt - 1);
+    self.emit(Event::QueryFailed { query: query.clone(), attempt: attempt - 1, error: error.to_string() });
+    self.emit(Event::QueryStarted { query, attempt });
+  }
+
+  /// Records the end of the running query and starts the next queued one.
+  fn finish_query(&mut self, error: Option<String>, action_tx: &mpsc::UnboundedSender<Action>) -> Result<()> {
+    let Some(RunningQuery { query: q, attempt, started, .. }) = self.running_query.take() else {
+      return Ok(());
+    };
+    match error {
+      None => {
+        let duration_ms = events::duration_ms(started.elapsed());
+        self.emit(Event::QueryCompleted { query: q.clone(), duration_ms });
+        if let Some(team_history) = self.team_history.clone() {
+          let duration = started.elapsed();
+          tokio::spawn(async move { team_history.record(&q, duration).await });
+        }
+      },
+      Some(error) => {
+        // Successful queries record it when their results arrive, before the results are applied.
+        perf::record_query_database(started.elapsed());
+        self.emit(Event::QueryFailed { query: q.clone(), attempt, error: error.clone() });
+        let message = if attempt > 1 {
+          format!("Error executing query after {attempt} attempts: {error}")
+        } else {
+          format!("Error executing query: {error}")
+        };
+        action_tx.send(Action::QueryError(message))?;
+      },
+    }
+    if let Some(q) = self.queued_queries.pop_front() {
+      action_tx.send(Action::HandleQuery(q))?;
+    }
+    Ok(())
+  }
+
+  /// Asks the database to cancel the running query. The query then fails, which starts the next queued one.
+  fn cancel_running_query(&self, tx: &mpsc::UnboundedSender<Action>) {
+    if self.running_query.is_none() {
+      return;
+    }
+    let (db, tx) = (self.db.clone(), tx.clone());
+    tokio::spawn(async move {
+      let reason = match db.cancel().await {
+        Ok(true) => {
+          log::info!("Cancelled the running query");
+          return;
+        },
+        Ok(false) => "this database can't cancel queries".to_string(),
+        Err(e) => format!("{e}"),
+      };
+      log::warn!("Unable to cancel the running query: {reason}");
+      let _ = tx.send(Action::QueryNotCancelled(reason));
+    });
+  }
+
+  fn emit(&self, event: Event) {
+    if let Some(events) = &self.events {
+      events.emit(event);
+    }
+  }
+
   pub async fn run(&mut self) -> Result<()> {
     let (action_tx, mut action_rx) = mpsc::unbounded_channel();
+    self.emit(Event::Started { version: env!("CARGO_PKG_VERSION").to_string() });
 
-    let mut tui = tui::Tui::new()?.tick_rate(self.tick_rate).frame_rate(self.frame_rate);
+    let mut tui = tui::Tui::new()?.tick_rate(self.tick_rate).frame_rate(self.frame_rate).paste(true);
     // tui.mouse(true);
     tui.enter()?;
 
@@ -112,48 +275,102 @@ impl App {
     }
 
     init(action_tx.clone(), self.db.clone())?;
+    detect_server(action_tx.clone(), self.db.clone(), self.server_kind());
 
     loop {
       if let Some(e) = tui.next().await {
-        match e {
-          tui::Event::Quit => action_tx.send(Action::Quit)?,
-          tui::Event::Tick => action_tx.send(Action::Tick)?,
-          tui::Event::Render => action_tx.send(Action::Render)?,
-          tui::Event::Resize(x, y) => action_tx.send(Action::Resize(x, y))?,
-          tui::Event::Key(key) => {
-            if let Some(keymap) = self.config.keybindings.get(&self.mode) {
-              if let Some(action) = keymap.get(&vec![key]) {
-                log::info!("Got action: {action:?}");
-                action_tx.send(action.clone())?;
-              } else {
-                // If the key was not handled as a single key action,
-                // then consider it for multi-key combinations.
-                self.last_tick_key_events.push(key);
-
-                // Check for multi-key combinations
-                if let Some(action) = keymap.get(&self.last_tick_key_events) {
+        let is_input = matches!(e, tui::Event::Key(_) | tui::Event::Mouse(_) | tui::Event::Paste(_));
+        if is_input && self.lock.is_locked() {
+          // Nothing reaches the components until the lock screen is dismissed.
+          if let tui::Event::Key(key) = e {
+            self.lock.handle_key(key);
+          }
+        } else {
+          if is_input {
+            self.lock.touch();
+          }
+          match e {
+            tui::Event::Quit => action_tx.send(Action::Quit)?,
+            tui::Event::Tick => action_tx.send(Action::Tick)?,
+            tui::Event::Render => action_tx.send(Action::Render)?,
+            tui::Event::Resize(x, y) => action_tx.send(Action::Resize(x, y))?,
+            tui::Event::Suspend => action_tx.send(Action::Suspend)?,
+            tui::Event::Resume => {
+              // Continued while suspended, the terminal may have been reset by the shell.
+              tui.resume()?;
+              action_tx.send(Action::Resume)?;
+            },
+            tui::Event::Key(key) => {
+              let is_bound =
+                self.config.keybindings.get(&self.mode).is_some_and(|keymap| keymap.contains_key(&vec![key]));
+              if !is_bound && keymap::lookup(Scope::Global, &key) == Some(Command::Suspend) {
+                action_tx.send(Action::Suspend)?;
+              }
+              if keymap::lookup(Scope::Global, &key) == Some(Command::Lock) {
+                self.lock.lock();
+              }
+              if let Some(keymap) = self.config.keybindings.get(&self.mode) {
+                if let Some(action) = keymap.get(&vec![key]) {
                   log::info!("Got action: {action:?}");
                   action_tx.send(action.clone())?;
+                } else {
+                  // If the key was not handled as a single key action,
+                  // then consider it for multi-key combinations.
+                  self.last_tick_key_events.push(key);
+
+                  // Check for multi-key combinations
+                  if let Some(action) = keymap.get(&self.last_tick_key_events) {
+                    log::info!("Got action: {action:?}");
+                    action_tx.send(action.clone())?;
+                  }
                 }
-              }
-            };
-          },
-          _ => {},
-        }
-        for component in self.components.iter_mut() {
-          if let Some(action) = component.handle_events(Some(e.clone()))? {
-            action_tx.send(action)?;
+              };
+            },
+            _ => {},
+          }
+          for component in self.components.iter_mut() {
+            if let Some(action) = component.handle_events(Some(e.clone()))? {
+              action_tx.send(action)?;
+            }
           }
         }
       }
 
       while let Ok(action) = action_rx.try_recv() {
-        if action != Action::Tick && action != Action::Render {
+        let started = Instant::now();
+        let is_frequent = action == Action::Tick || action == Action::Render;
+        if !is_frequent {
           log::debug!("{action:?}");
+          crash::record_action(&action);
+        }
+        let action = match action {
+          Action::HandleQuery(q) if self.running_query.is_some() => Action::QueryBusy(q),
+          action => action,
+        };
+        match &action {
+          Action::QueryResult(headers, rows, _) => {
+            if let Some(running) = &self.running_query {
+              perf::record_query_database(running.started.elapsed());
+            }
+            self.emit(Event::Results { columns: headers.len(), rows: rows.len() });
+          },
+          Action::Error(message) | Action::QueryError(message) => self.emit(Event::Error { message: message.clone() }),
+          _ => {},
+        }
+        if let Action::QueryRetrying(attempt, _, ref error) = action {
+          self.retry_query(attempt, error);
+        }
+        if let Action::QueryFinished(run, ref error) = action {
+          if self.running_query.as_ref().is_some_and(|running| running.run == run) {
+            self.finish_query(error.clone(), &action_tx)?;
+          }
         }
         match action {
           Action::Tick => {
             self.last_tick_key_events.drain(..);
+            if self.lock.check_idle(Instant::now()) {
+              log::info!("Locked after {} idle minutes", self.config.lock.idle_minutes);
+            }
           },
           Action::Quit => self.should_quit = true,
           Action::Suspend => self.should_suspend = true,
@@ -167,6 +384,12 @@ impl App {
                   action_tx.send(Action::Error(format!("Failed to draw: {:?}", e))).unwrap();
                 }
               }
+              if self.lock.is_locked() {
+                self.lock.render(f);
+              }
+              if !self.capabilities.is_full() {
+                self.capabilities.degrade(f.buffer_mut());
+              }
             })?;
           },
           Action::Render => {
@@ -177,16 +400,22 @@ impl App {
                   action_tx.send(Action::Error(format!("Failed to draw: {:?}", e))).unwrap();
                 }
               }
+              if self.lock.is_locked() {
+                self.lock.render(f);
+              }
+              if !self.capabilities.is_full() {
+                self.capabilities.degrade(f.buffer_mut());
+              }
             })?;
+            perf::record_draw(started.elapsed());
           },
           Action::LoadTable(ref table_name) => {
-            // println!("Load Table: {}", table_name);
-            let q = format!("SELECT * from {}", table_name);
-            query(&q, action_tx.clone(), self.db.clone()).await?;
+            // Like any query, it waits for the running one and runs after the preamble.
+            action_tx.send(Action::HandleQuery(snippets::select_all_query(table_name)))?;
           },
           Action::LoadTables(ref search) => {
             // println!("Load Tables");
-            load_tables(&self.pool, action_tx.clone(), search).await?;
+            load_tables(self.db.clone(), action_tx.clone(), search).await?;
           },
           Action::SelectComponent(ref kind) => {
             match kind {
@@ -204,11 +433,145 @@ impl App {
               },
             }
           },
+          Action::CountTableRows(ref table) => {
+            let q = snippets::count_rows_query(table);
+            let tx = action_tx.clone();
+            let db = self.db.clone();
+            let table = table.clone();
+            tokio::spawn(async move {
+              let action = match db.fetch(&q).await {
+                Ok((_, rows, _)) => {
+                  Action::TableRowCount(table, rows.first().and_then(|r| r.first()).cloned().unwrap_or_default())
+                },
+                Err(e) => Action::Error(format!("Error counting rows: {:?}", e)),
+              };
+              dispatch(tx, action).await
+            });
+          },
+          Action::PreviewTable(ref table) => {
+            let q = snippets::preview_query(table);
+            let tx = action_tx.clone();
+            let db = self.db.clone();
+            let table = table.clone();
+            tokio::spawn(async move {
+              let action = match db.fetch(&q).await {
+                Ok((headers, rows, _)) => Action::TablePreview(table, headers, rows),
+                Err(e) => Action::Error(format!("Error previewing table: {:?}", e)),
+              };
+              dispatch(tx, action).await
+            });
+          },
+          Action::ExportPlan(ref q) => {
+            let (tx, db, q) = (action_tx.clone(), self.db.clone(), q.clone());
+            tokio::spawn(async move {
+              let action = match db.fetch(&q).await {
+                Ok((_, rows, _)) => {
+                  match rows.into_iter().next().and_then(|row| row.into_iter().next()) {
+                    Some(plan) => Action::PlanLoaded(plan),
+                    None => Action::Error("EXPLAIN returned no plan".to_string()),
+                  }
+                },
+                Err(e) => Action::Error(format!("Error explaining query: {:?}", e)),
+              };
+              dispatch(tx, action).await
+            });
+          },
+          Action::LoadForeignKeys(ref table) => {
+            let (tx, db, q, table) = (
+              action_tx.clone(),
+              self.db.clone(),
+              schema::foreign_keys_query(self.server_kind(), table),
+              table.clone(),
+            );
+            tokio::spawn(async move {
+              let action = match db.fetch(&q).await {
+                Ok((_, rows, _)) => Action::ForeignKeysLoaded(table, ForeignKey::from_rows(rows)),
+                Err(e) => Action::Error(format!("Error loading foreign keys: {:?}", e)),
+              };
+              dispatch(tx, action).await
+            });
+          },
+          Action::LoadTableColumns(ref table) => {
+            let q = schema::columns_query(self.server_kind(), table);
+            let tx = action_tx.clone();
+            let db = self.db.clone();
+            let table = table.clone();
+            tokio::spawn(async move {
+              let action = match db.fetch(&q).await {
+                Ok((_, rows, _)) => Action::TableColumnsLoaded(table, DbColumn::from_rows(rows)),
+                Err(e) => Action::Error(format!("Error loading columns: {:?}", e)),
+              };
+              dispatch(tx, action).await
+            });
+          },
+          Action::AttachDatabase(ref file, ref alias) => {
+            self.change_attachments(alias.clone(), Some(file.clone()), action_tx.clone());
+          },
+          Action::DetachDatabase(ref alias) => {
+            self.change_attachments(alias.clone(), None, action_tx.clone());
+          },
+          Action::CheckHealth => {
+            let tx = action_tx.clone();
+            let db = self.db.clone();
+            let name = self.filename.clone().unwrap_or_else(|| "postgres".to_string());
+            let stats_query = self.health_query();
+            tokio::spawn(async move {
+              dispatch(tx, Action::HealthChecked(check_health(name, stats_query, db).await)).await
+            });
+          },
+          Action::LoadTeamHistory => {
+            match self.team_history.clone() {
+              Some(team_history) => {
+                let tx = action_tx.clone();
+                tokio::spawn(async move {
+                  let action = match team_history.recent().await {
+                    Ok((headers, rows)) => Action::TeamHistoryLoaded(headers, rows),
+                    Err(e) => Action::Error(format!("Error loading team history: {e:?}")),
+                  };
+                  dispatch(tx, action).await
+                });
+              },
+              None => {
+                action_tx.send(Action::Error("Set team_history.url in the config to share history".to_string()))?
+              },
+            }
+          },
           Action::HandleQuery(ref q) => {
-            // println!("Execute Query: {}", q);
-            if let Err(e) = query(q, action_tx.clone(), self.db.clone()).await {
-              // println!("Error executing query: {:?}", e);
-              dispatch(action_tx.clone(), Action::Error(format!("Error executing query: {:?}", e))).await?;
+            match self.preamble.as_ref().and_then(Preamble::missing) {
+              Some(name) => {
+                self.pending_query = Some(q.clone());
+                action_tx.send(Action::PromptPreambleValue(name))?;
+              },
+              None => self.run_query(q, &action_tx),
+            }
+          },
+          Action::PreambleValue(ref name, ref value) => {
+            if let Some(preamble) = &mut self.preamble {
+              preamble.set(name, value);
+            }
+            match self.preamble.as_ref().and_then(Preamble::missing) {
+              Some(name) => action_tx.send(Action::PromptPreambleValue(name))?,
+              None => {
+                if let Some(q) = self.pending_query.take() {
+                  self.run_query(&q, &action_tx);
+                }
+              },
+            }
+          },
+          Action::ResolveBusyQuery(ref q, choice) => {
+            match choice {
+              BusyQueryChoice::CancelCurrent => {
+                self.queued_queries.push_front(q.clone());
+                self.cancel_running_query(&action_tx);
+              },
+              BusyQueryChoice::Queue => self.queued_queries.push_back(q.clone()),
+              BusyQueryChoice::Discard => {},
+            }
+            // The running query may have finished while the user was choosing.
+            if self.running_query.is_none() {
+              if let Some(q) = self.queued_queries.pop_front() {
+                action_tx.send(Action::HandleQuery(q))?;
+              }
             }
           },
           _ => {},
@@ -218,53 +581,39 @@ impl App {
             action_tx.send(action)?
           };
         }
+        if !is_frequent {
+          perf::record_action(started.elapsed());
+        }
       }
 
       if self.should_suspend {
         tui.suspend()?;
+        // Execution continues here once the shell resumes the job.
+        tui.resume()?;
         action_tx.send(Action::Resume)?;
-        tui = tui::Tui::new()?.tick_rate(self.tick_rate).frame_rate(self.frame_rate);
-        // tui.mouse(true);
-        tui.enter()?;
       } else if self.should_quit {
         tui.stop()?;
         break;
       }
     }
     tui.exit()?;
+    self.emit(Event::Exited);
     Ok(())
   }
 }
 
 pub async fn dispatch(tx: tokio::sync::mpsc::UnboundedSender<Action>, action: Action) -> Result<()> {
   if let Err(e) = tx.send(action) {
-    println!("Error dipatching: {:?}", e);
+    log::error!("Error dispatching: {:?}", e);
   }
 
   Ok(())
 }
 
-async fn load_tables(
-  pool: &sqlx::Pool<sqlx::Postgres>,
-  tx: tokio::sync::mpsc::UnboundedSender<Action>,
-  search: &str,
-) -> Result<()> {
-  let mut rows =
-    sqlx::query("SELECT * FROM information_schema.tables WHERE table_catalog = $1").bind("postgres").fetch(pool);
-
-  let mut tables = Vec::new();
-  while let Ok(Some(row)) = rows.try_next().await {
-    let name: String = row.try_get("table_name").unwrap_or_default();
-    let schema: String = row.try_get("table_schema").unwrap_or_default();
-    tables.push(DbTable { name, schema });
-  }
-
+async fn load_tables(db: Arc<dyn Queryer>, tx: tokio::sync::mpsc::UnboundedSender<Action>, search: &str) -> Result<()> {
+  let mut tables = db.load_tables(search).await?;
   tables.sort_by(|a, b| a.name.cmp(&b.name));
-  let t = if search.is_empty() { tables } else { tables.iter().filter(|t| t.name.contains(search)).cloned().collect() };
-
-  dispatch(tx, Action::TablesLoaded(t)).await?;
-
-  Ok(())
+  dispatch(tx, Action::TablesLoaded(tables)).await
 }
 
 // fn init(tx: tokio::sync::mpsc::UnboundedSender<Action>, pool: sqlx::Pool<sqlx::Postgres>) -> Result<()> {
@@ -273,14 +622,52 @@ fn init(tx: tokio::sync::mpsc::UnboundedSender<Action>, db: Arc<dyn Queryer>) ->
     // let pool = pool.clone();
     thread::sleep(Duration::from_millis(200));
 
-    if let Err(e) = db.load_tables(tx, "").await {
-      println!("Error sending load table event.");
+    if let Err(e) = load_tables(db, tx.clone(), "").await {
+      log::error!("Error loading tables: {:?}", e);
+      let _ = tx.send(Action::Error(format!("Error loading tables: {:?}", e)));
     }
   });
   Ok(())
 }
 
-async fn query(q: &str, tx: tokio::sync::mpsc::UnboundedSender<Action>, db: Arc<dyn Queryer>) -> Result<()> {
-  db.query(q, tx).await?;
-  Ok(())
+/// Detects the server version in the background and dispatches [`Action::ServerDetected`].
+fn detect_server(tx: tokio::sync::mpsc::UnboundedSender<Action>, db: Arc<dyn Queryer>, kind: ServerKind) {
+  tokio::spawn(async move {
+    match db.fetch(ServerInfo::detect_query(kind)).await {
+      Ok((_, rows, _)) => {
+        let info = ServerInfo::from_row(kind, rows.first().map(Vec::as_slice).unwrap_or_default());
+        log::info!("Detected server: {info}");
+        dispatch(tx, Action::ServerDetected(info)).await
+      },
+      Err(e) => {
+        log::error!("Unable to detect server version: {e}");
+        Ok(())
+      },
+    }
+  });
+}
+
+/// Pings the connection and collects server statistics, recording failures on the returned snapshot.
+async fn check_health(name: String, stats_query: &str, db: Arc<dyn Queryer>) -> ConnectionHealth {
+  let mut health =
+    ConnectionHealth { name, checked_at: chrono::Local::now().format("%H:%M:%S").to_string(), ..Default::default() };
+
+  let started = Instant::now();
+  if let Err(e) = db.fetch("SELECT 1").await {
+    health.error = Some(e.to_string());
+    return health;
+  }
+  health.latency_ms = Some(started.elapsed().as_millis() as u64);
+
+  match db.fetch(stats_query).await {
+    Ok((_, rows, _)) => {
+      if let Some(row) = rows.first() {
+        health.version = row.first().cloned();
+        health.backends = row.get(1).cloned();
+        health.replication_lag = row.get(2).cloned();
+      }
+    },
+    Err(e) => health.error = Some(e.to_string()),
+  }
+  health
 }
@@ -0,0 +1,105 @@
+use std::path::Path;
+
+use color_eyre::eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  schema::DbTable,
+  snippets::{quote_identifier, quote_literal},
+  sql::Queryer,
+};
+
+/// Schemas SQLite reserves for the connection's own databases, which can't be used as aliases.
+const RESERVED_ALIASES: &[&str] = &["main", "temp"];
+
+/// A database file attached to a SQLite connection with `ATTACH DATABASE`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttachedDatabase {
+  /// Schema name the file's tables are qualified with.
+  pub alias: String,
+  pub file: String,
+}
+
+/// The file and alias of the attach prompt's `path [AS alias]`, the alias defaulting to the file's stem.
+pub fn parse(input: &str) -> Result<(String, String), String> {
+  let input = input.trim();
+  let lower = input.to_ascii_lowercase();
+  let (file, alias) = match lower.rfind(" as ") {
+    Some(at) => (input[..at].trim(), input[at + 4..].trim().to_string()),
+    None => {
+      let stem = Path::new(input).file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+      let alias: String =
+        stem.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' }).collect();
+      (input, alias)
+    },
+  };
+  if file.is_empty() {
+    return Err("Enter the path of a database file, optionally followed by AS alias".to_string());
+  }
+  if alias.is_empty() || RESERVED_ALIASES.contains(&alias.to_ascii_lowercase().as_str()) {
+    return Err(format!("{alias:?} can't be used as an alias, add AS alias"));
+  }
+  Ok((file.to_string(), alias))
+}
+
+pub fn attach_statement(file: &str, alias: &str) -> String {
+  format!("ATTACH DATABASE {} AS {}", quote_literal(file), quote_identifier(alias))
+}
+
+pub fn detach_statement(alias: &str) -> String {
+  format!("DETACH DATABASE {}", quote_identifier(alias))
+}
+
+/// Query returning `(alias, file)` rows for the attached databases.
+pub fn databases_query() -> String {
+  format!(
+    "SELECT name, file FROM pragma_database_list WHERE name NOT IN ({}) ORDER BY seq",
+    RESERVED_ALIASES.iter().map(|alias| quote_literal(alias)).collect::<Vec<_>>().join(", ")
+  )
+}
+
+/// Query returning the names of the tables and views of the attached database `alias`.
+pub fn tables_query(alias: &str) -> String {
+  format!("SELECT name FROM {}.sqlite_master WHERE type IN ('table', 'view') ORDER BY name", quote_identifier(alias))
+}
+
+/// The databases attached to the SQLite connection, with their tables qualified by alias.
+pub async fn attached_databases(db: &dyn Queryer) -> Result<(Vec<AttachedDatabase>, Vec<DbTable>)> {
+  let (_, rows, _) = db.fetch(&databases_query()).await?;
+  let databases: Vec<AttachedDatabase> = rows
+    .into_iter()
+    .map(|mut row| {
+      row.resize(2, String::new());
+      AttachedDatabase { alias: row[0].clone(), file: row[1].clone() }
+    })
+    .collect();
+  let mut tables = Vec::new();
+  for database in &databases {
+    let (_, rows, _) = db.fetch(&tables_query(&database.alias)).await?;
+    tables.extend(
+      rows
+        .into_iter()
+        .filter_map(|row| row.into_iter().next())
+        .map(|name| DbTable { name, schema: database.alias.clone() }),
+    );
+  }
+  Ok((databases, tables))
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_parse() {
+    assert_eq!(parse("/tmp/Snapshot-2024.db"), Ok(("/tmp/Snapshot-2024.db".to_string(), "snapshot_2024".to_string())));
+    assert_eq!(parse(" ../old app.db AS Before "), Ok(("../old app.db".to_string(), "Before".to_string())));
+    assert!(parse("main.db").is_err());
+    assert!(parse("old.db AS temp").is_err());
+    assert!(parse("").is_err());
+    assert_eq!(attach_statement("it's.db", "Before"), "ATTACH DATABASE 'it''s.db' AS \"Before\"");
+    assert_eq!(tables_query("old"), "SELECT name FROM old.sqlite_master WHERE type IN ('table', 'view') ORDER BY name");
+  }
+}
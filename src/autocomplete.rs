@@ -0,0 +1,357 @@
+use std::{cmp::Ordering, collections::BTreeMap};
+
+use serde::{Deserialize, Serialize};
+use strum::Display;
+
+/// Most suggestions shown at once.
+pub const MAX_SUGGESTIONS: usize = 10;
+
+/// Built-in snippet triggers and their expansions. User snippets from the config take precedence.
+pub const SNIPPETS: &[(&str, &str)] = &[
+  ("sel*", "SELECT * FROM "),
+  ("selc", "SELECT count(*) FROM "),
+  ("ins", "INSERT INTO  () VALUES ()"),
+  ("upd", "UPDATE  SET  WHERE "),
+  ("del", "DELETE FROM  WHERE "),
+  ("grp", "GROUP BY "),
+  ("ord", "ORDER BY "),
+];
+
+/// Keywords with the short description shown in the documentation panel.
+pub const KEYWORDS: &[(&str, &str)] = &[
+  ("SELECT", "Retrieve rows from tables or expressions"),
+  ("FROM", "Tables or subqueries to read rows from"),
+  ("WHERE", "Filter rows with a condition"),
+  ("AND", "True when both conditions are true"),
+  ("OR", "True when either condition is true"),
+  ("NOT", "Negate a condition"),
+  ("NULL", "Missing or unknown value"),
+  ("IS", "Compare with NULL or a boolean, e.g. IS NULL"),
+  ("IN", "Match any value in a list or subquery"),
+  ("LIKE", "Case-sensitive pattern match with % and _"),
+  ("ILIKE", "Case-insensitive LIKE (Postgres)"),
+  ("BETWEEN", "Inclusive range check"),
+  ("JOIN", "Combine rows of two tables on a condition"),
+  ("LEFT", "LEFT JOIN keeps every row of the left table"),
+  ("RIGHT", "RIGHT JOIN keeps every row of the right table"),
+  ("INNER", "INNER JOIN keeps matching rows only"),
+  ("OUTER", "Keeps unmatched rows of a join"),
+  ("FULL", "FULL JOIN keeps unmatched rows of both tables"),
+  ("ON", "Join condition"),
+  ("USING", "Join on identically named columns"),
+  ("GROUP BY", "Aggregate rows sharing the same values"),
+  ("ORDER BY", "Sort the result"),
+  ("HAVING", "Filter groups after aggregation"),
+  ("LIMIT", "Return at most N rows"),
+  ("OFFSET", "Skip the first N rows"),
+  ("DISTINCT", "Remove duplicate rows"),
+  ("AS", "Name a column or table alias"),
+  ("ASC", "Ascending sort order"),
+  ("DESC", "Descending sort order"),
+  ("UNION", "Combine the rows of two queries"),
+  ("ALL", "Keep duplicates in UNION, or compare with every value"),
+  ("WITH", "Common table expressions usable in the query"),
+  ("CASE", "Conditional expression"),
+  ("WHEN", "Condition of a CASE branch"),
+  ("THEN", "Result of a CASE branch"),
+  ("ELSE", "Fallback result of a CASE"),
+  ("END", "Closes a CASE expression"),
+  ("INSERT INTO", "Add rows to a table"),
+  ("VALUES", "Literal rows"),
+  ("UPDATE", "Modify rows of a table"),
+  ("SET", "Columns to change in an UPDATE"),
+  ("DELETE FROM", "Remove rows from a table"),
+  ("RETURNING", "Return the affected rows (Postgres, SQLite)"),
+  ("EXISTS", "True when a subquery returns any row"),
+];
+
+pub const FUNCTIONS: &[(&str, &str)] = &[
+  ("COUNT", "count(expr) - number of non-null values, count(*) counts rows"),
+  ("SUM", "sum(expr) - total of the values"),
+  ("AVG", "avg(expr) - average of the values"),
+  ("MIN", "min(expr) - smallest value"),
+  ("MAX", "max(expr) - largest value"),
+  ("COALESCE", "coalesce(a, b, ...) - first non-null argument"),
+  ("NOW", "now() - current transaction timestamp"),
+  ("LOWER", "lower(text) - lowercase text"),
+  ("UPPER", "upper(text) - uppercase text"),
+  ("LENGTH", "length(text) - number of characters"),
+];
+
+/// Text added after an accepted suggestion.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trailing {
+  #[default]
+  None,
+  Space,
+  Comma,
+}
+
+impl Trailing {
+  pub fn text(&self) -> &'static str {
+    match self {
+      Trailing::None => "",
+      Trailing::Space => " ",
+      Trailing::Comma => ", ",
+    }
+  }
+}
+
+/// How the completion popup accepts suggestions, the `[completion]` config table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompletionConfig {
+  /// Tab accepts the selected suggestion; when false it selects the next one.
+  pub tab_accepts: bool,
+  /// Enter accepts the selected suggestion; when false it closes the popup and inserts a newline.
+  pub enter_accepts: bool,
+  /// Added after accepted columns, tables, functions and keywords. Snippets and history are inserted as is.
+  pub trailing: Trailing,
+  /// Replace the whole word under the cursor instead of only the part before it.
+  pub replace_word: bool,
+}
+
+impl Default for CompletionConfig {
+  fn default() -> Self {
+    Self { tab_accepts: true, enter_accepts: true, trailing: Trailing::None, replace_word: false }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display)]
+pub enum SuggestionKind {
+  Snippet,
+  Column,
+  Table,
+  History,
+  Function,
+  Keyword,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+  pub text: String,
+  pub kind: SuggestionKind,
+  /// Lines shown in the documentation panel while the suggestion is selected.
+  pub documentation: Vec<String>,
+  /// Text inserted when accepted, if different from `text` (snippet expansions).
+  pub replacement: Option<String>,
+}
+
+impl SuggestionKind {
+  /// Short tag shown in front of each suggestion.
+  pub fn icon(&self) -> &'static str {
+    match self {
+      SuggestionKind::Snippet => "»",
+      SuggestionKind::Column => "c",
+      SuggestionKind::Table => "t",
+      SuggestionKind::History => "h",
+      SuggestionKind::Function => "f",
+      SuggestionKind::Keyword => "k",
+    }
+  }
+}
+
+impl Suggestion {
+  pub fn new(text: impl Into<String>, kind: SuggestionKind) -> Self {
+    Self { text: text.into(), kind, documentation: Vec::new(), replacement: None }
+  }
+
+  /// The text to insert when the suggestion is accepted.
+  pub fn insert_text(&self) -> &str {
+    self.replacement.as_deref().unwrap_or(&self.text)
+  }
+
+  pub fn with_documentation(mut self, documentation: Vec<String>) -> Self {
+    self.documentation = documentation;
+    self
+  }
+}
+
+/// Keyword and function suggestions documented with their descriptions.
+pub fn builtin_suggestions() -> impl Iterator<Item = Suggestion> {
+  let keywords = KEYWORDS.iter().map(|(k, doc)| (k, doc, SuggestionKind::Keyword));
+  let functions = FUNCTIONS.iter().map(|(f, doc)| (f, doc, SuggestionKind::Function));
+  keywords
+    .chain(functions)
+    .map(|(text, doc, kind)| Suggestion::new(*text, kind).with_documentation(vec![doc.to_string()]))
+}
+
+/// Built-in snippets merged with the user's `snippets` config, as trigger suggestions that expand to their body.
+pub fn snippet_suggestions(user: &BTreeMap<String, String>) -> impl Iterator<Item = Suggestion> {
+  let mut snippets: BTreeMap<String, String> =
+    SNIPPETS.iter().map(|(trigger, body)| (trigger.to_string(), body.to_string())).collect();
+  snippets.extend(user.iter().map(|(trigger, body)| (trigger.clone(), body.clone())));
+  snippets.into_iter().map(|(trigger, body)| {
+    let mut suggestion =
+      Suggestion::new(trigger, SuggestionKind::Snippet).with_documentation(body.lines().map(String::from).collect());
+    suggestion.replacement = Some(body);
+    suggestion
+  })
+}
+
+fn is_word_char(c: char) -> bool {
+  c.is_alphanumeric() || c == '_' || c == '*'
+}
+
+/// Number of word characters following `col`, replaced too when accepting with `replace_word`.
+pub fn word_after_cursor(line: &str, col: usize) -> usize {
+  line.chars().skip(col).take_while(|c| is_word_char(*c)).count()
+}
+
+/// The identifier being typed at `col` (a character index) in `line`, and the column it starts at.
+///
+/// `*` counts as part of the word so snippet triggers like `sel*` can be completed.
+pub fn word_before_cursor(line: &str, col: usize) -> (usize, String) {
+  let before: Vec<char> = line.chars().take(col).collect();
+  let start = before.iter().rposition(|c| !is_word_char(*c)).map_or(0, |i| i + 1);
+  (start, before[start..].iter().collect())
+}
+
+/// The table or alias qualifying the word starting at `start`, e.g. `o` when completing `o.na`.
+pub fn qualifier_before(line: &str, start: usize) -> Option<String> {
+  let chars: Vec<char> = line.chars().take(start).collect();
+  if chars.last() != Some(&'.') {
+    return None;
+  }
+  let (_, qualifier) = word_before_cursor(&chars[..chars.len() - 1].iter().collect::<String>(), chars.len() - 1);
+  (!qualifier.is_empty()).then_some(qualifier)
+}
+
+fn is_keyword(word: &str) -> bool {
+  KEYWORDS.iter().flat_map(|(k, _)| k.split(' ')).any(|k| k.eq_ignore_ascii_case(word))
+}
+
+/// `(table, alias)` pairs declared after FROM and JOIN in `sql`, with an optional AS skipped.
+pub fn table_aliases(sql: &str) -> Vec<(String, String)> {
+  let words: Vec<&str> = sql.split_whitespace().collect();
+  let is_identifier = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.');
+  let mut aliases = Vec::new();
+  for (i, word) in words.iter().enumerate() {
+    if !(word.eq_ignore_ascii_case("FROM") || word.eq_ignore_ascii_case("JOIN")) {
+      continue;
+    }
+    let Some(table) = words.get(i + 1) else {
+      continue;
+    };
+    let mut alias = words.get(i + 2).copied();
+    if alias.is_some_and(|a| a.eq_ignore_ascii_case("AS")) {
+      alias = words.get(i + 3).copied();
+    }
+    if let Some(alias) = alias.map(|a| a.trim_end_matches([',', ';'])) {
+      if is_identifier(table) && is_identifier(alias) && !is_keyword(alias) {
+        aliases.push((table.to_string(), alias.to_string()));
+      }
+    }
+  }
+  aliases
+}
+
+/// The table `qualifier` refers to in `sql`: the aliased table, otherwise the qualifier itself. Schema prefixes
+/// are dropped.
+pub fn resolve_qualifier(sql: &str, qualifier: &str) -> String {
+  let table = table_aliases(sql)
+    .into_iter()
+    .find(|(_, alias)| alias.eq_ignore_ascii_case(qualifier))
+    .map_or_else(|| qualifier.to_string(), |(table, _)| table);
+  table.rsplit('.').next().unwrap_or_default().to_string()
+}
+
+/// Candidates starting with `prefix` (case-insensitively), ordered by kind then name, without duplicates. History
+/// suggestions keep their given order, which is already ranked by use.
+pub fn complete(prefix: &str, candidates: impl IntoIterator<Item = Suggestion>) -> Vec<Suggestion> {
+  let prefix = prefix.to_lowercase();
+  let mut matches: Vec<Suggestion> = candidates
+    .into_iter()
+    .filter(|s| s.text.to_lowercase().starts_with(&prefix) && !s.text.eq_ignore_ascii_case(&prefix))
+    .collect();
+  matches.sort_by(|a, b| {
+    a.kind
+      .cmp(&b.kind)
+      .then_with(|| if a.kind == SuggestionKind::History { Ordering::Equal } else { a.text.cmp(&b.text) })
+  });
+  matches.dedup_by(|a, b| a.text == b.text);
+  matches.truncate(MAX_SUGGESTIONS);
+  matches
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_word_before_cursor() {
+    assert_eq!(word_before_cursor("SELECT * FROM us", 16), (14, "us".to_string()));
+    assert_eq!(word_before_cursor("sel", 3), (0, "sel".to_string()));
+    assert_eq!(word_before_cursor("a.b_c", 5), (2, "b_c".to_string()));
+    assert_eq!(word_before_cursor("x ", 2), (2, String::new()));
+  }
+
+  #[test]
+  fn test_completion_config_defaults() {
+    let config: CompletionConfig = toml::from_str("trailing = \"comma\"\ntab_accepts = false").unwrap();
+    assert_eq!(config, CompletionConfig {
+      tab_accepts: false,
+      trailing: Trailing::Comma,
+      ..CompletionConfig::default()
+    });
+  }
+
+  #[test]
+  fn test_word_after_cursor() {
+    assert_eq!(word_after_cursor("SELECT nam FROM", 9), 1);
+    assert_eq!(word_after_cursor("SELECT name FROM", 11), 0);
+  }
+
+  #[test]
+  fn test_table_aliases() {
+    let sql = "SELECT * FROM users u JOIN orders AS o ON o.user_id = u.id WHERE u.active;";
+    assert_eq!(table_aliases(sql), vec![
+      ("users".to_string(), "u".to_string()),
+      ("orders".to_string(), "o".to_string())
+    ]);
+    assert_eq!(table_aliases("SELECT * FROM users WHERE id = 1"), Vec::new());
+  }
+
+  #[test]
+  fn test_alias_resolution() {
+    let sql = "SELECT o. FROM public.orders o JOIN customers AS c ON c.id = o.customer_id";
+    assert_eq!(table_aliases(sql), vec![
+      ("public.orders".to_string(), "o".to_string()),
+      ("customers".to_string(), "c".to_string())
+    ]);
+    assert_eq!(resolve_qualifier(sql, "o"), "orders");
+    assert_eq!(resolve_qualifier(sql, "C"), "customers");
+    assert_eq!(resolve_qualifier(sql, "users"), "users");
+    assert_eq!(qualifier_before("SELECT o.na", 9), Some("o".to_string()));
+    assert_eq!(qualifier_before("SELECT na", 7), None);
+  }
+
+  #[test]
+  fn test_complete() {
+    let candidates = vec![
+      Suggestion::new("SELECT", SuggestionKind::Keyword),
+      Suggestion::new("SUM", SuggestionKind::Function),
+      Suggestion::new("sessions", SuggestionKind::Table),
+      Suggestion::new("seq", SuggestionKind::Column),
+      Suggestion::new("users", SuggestionKind::Table),
+    ];
+    let texts: Vec<_> = complete("se", candidates).into_iter().map(|s| s.text).collect();
+    assert_eq!(texts, vec!["seq", "sessions", "SELECT"]);
+  }
+
+  #[test]
+  fn test_user_snippets_override_builtin() {
+    let user = BTreeMap::from([("sel*".to_string(), "SELECT id FROM ".to_string())]);
+    let matches = complete("sel", snippet_suggestions(&user));
+    assert_eq!(matches[0].text, "sel*");
+    assert_eq!(matches[0].insert_text(), "SELECT id FROM ");
+  }
+
+  #[test]
+  fn test_builtin_suggestions_are_documented() {
+    assert!(builtin_suggestions().all(|s| !s.documentation.is_empty()));
+  }
+}
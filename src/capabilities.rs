@@ -0,0 +1,172 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use ratatui::{buffer::Buffer, style::Color, symbols::border, widgets::Block};
+
+/// Terminals known to lack line drawing characters and extended colors.
+const BASIC_TERMS: &[&str] = &["linux", "vt100", "vt102", "vt220", "dumb", "ansi", "cons25"];
+
+/// Borders for terminals without line drawing characters.
+const ASCII_BORDER: border::Set = border::Set {
+  top_left: "+",
+  top_right: "+",
+  bottom_left: "+",
+  bottom_right: "+",
+  vertical_left: "|",
+  vertical_right: "|",
+  horizontal_top: "-",
+  horizontal_bottom: "-",
+};
+
+/// Whether [`block`] draws [`ASCII_BORDER`], set by [`Capabilities::install`].
+static ASCII_BORDERS: AtomicBool = AtomicBool::new(false);
+
+/// The xterm defaults of the 16 basic colors, used to find the closest one to an extended color.
+const BASIC_COLORS: [(Color, (u8, u8, u8)); 16] = [
+  (Color::Black, (0, 0, 0)),
+  (Color::Red, (205, 0, 0)),
+  (Color::Green, (0, 205, 0)),
+  (Color::Yellow, (205, 205, 0)),
+  (Color::Blue, (0, 0, 238)),
+  (Color::Magenta, (205, 0, 205)),
+  (Color::Cyan, (0, 205, 205)),
+  (Color::Gray, (229, 229, 229)),
+  (Color::DarkGray, (127, 127, 127)),
+  (Color::LightRed, (255, 0, 0)),
+  (Color::LightGreen, (0, 255, 0)),
+  (Color::LightYellow, (255, 255, 0)),
+  (Color::LightBlue, (92, 92, 255)),
+  (Color::LightMagenta, (255, 0, 255)),
+  (Color::LightCyan, (0, 255, 255)),
+  (Color::White, (255, 255, 255)),
+];
+
+/// What the terminal can display, so borders can fall back to ASCII and frames to the 16 basic colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+  pub unicode: bool,
+  /// Whether colors beyond the basic 16 (the 256 color palette or RGB) display correctly.
+  pub extended_colors: bool,
+}
+
+impl Default for Capabilities {
+  fn default() -> Self {
+    Self { unicode: true, extended_colors: true }
+  }
+}
+
+impl Capabilities {
+  /// ASCII only and 16 colors, as requested with `--ascii`.
+  pub const BASIC: Self = Self { unicode: false, extended_colors: false };
+
+  /// Detects the capabilities from `TERM`, `COLORTERM` and the locale.
+  pub fn detect() -> Self {
+    let var = |name: &str| std::env::var(name).unwrap_or_default();
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"].iter().map(|name| var(name)).find(|value| !value.is_empty());
+    Self::from_env(&var("TERM"), &var("COLORTERM"), locale.as_deref())
+  }
+
+  fn from_env(term: &str, colorterm: &str, locale: Option<&str>) -> Self {
+    if term.is_empty() {
+      // Windows terminals don't set TERM.
+      return Self::default();
+    }
+    let basic_term = BASIC_TERMS.contains(&term);
+    // An unset locale is common over SSH and in containers, only an explicit non UTF-8 one rules unicode out.
+    let utf8_locale = locale.is_none_or(|l| {
+      let l = l.to_uppercase();
+      l.contains("UTF-8") || l.contains("UTF8")
+    });
+    // Plain `xterm`, `screen` or `tmux` usually run in a terminal with 256 colors or more, only terminals known to
+    // be basic or that say so in their name fall back.
+    let few_colors = term.ends_with("-16color") || term.ends_with("-8color") || term.ends_with("-mono");
+    let extended_colors = matches!(colorterm, "truecolor" | "24bit") || !few_colors;
+    Self { unicode: !basic_term && utf8_locale, extended_colors: !basic_term && extended_colors }
+  }
+
+  /// Makes [`block`] draw borders this terminal can show.
+  pub fn install(&self) {
+    ASCII_BORDERS.store(!self.unicode, Ordering::Relaxed);
+  }
+
+  pub fn is_full(&self) -> bool {
+    self.unicode && self.extended_colors
+  }
+
+  /// Rewrites the colors of a rendered frame for this terminal, extended colors become the closest basic color. The
+  /// text is kept, box drawing characters in the results included, borders are ASCII through [`block`] instead.
+  pub fn degrade(&self, buffer: &mut Buffer) {
+    if self.extended_colors {
+      return;
+    }
+    for cell in buffer.content.iter_mut() {
+      cell.fg = basic_color(cell.fg);
+      cell.bg = basic_color(cell.bg);
+    }
+  }
+}
+
+/// A block whose borders use line drawing characters, or ASCII on terminals without them.
+pub fn block<'a>() -> Block<'a> {
+  Block::default().border_set(if ASCII_BORDERS.load(Ordering::Relaxed) { ASCII_BORDER } else { border::PLAIN })
+}
+
+/// The basic color closest to `color`.
+fn basic_color(color: Color) -> Color {
+  let rgb = match color {
+    Color::Indexed(i) if i < 16 => return BASIC_COLORS[i as usize].0,
+    Color::Indexed(i) if i < 232 => {
+      let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+      let i = i - 16;
+      (level(i / 36), level(i / 6 % 6), level(i % 6))
+    },
+    Color::Indexed(i) => {
+      let grey = 8 + (i - 232) * 10;
+      (grey, grey, grey)
+    },
+    Color::Rgb(r, g, b) => (r, g, b),
+    color => return color,
+  };
+  let distance = |(r, g, b): (u8, u8, u8)| {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+    d(r, rgb.0) + d(g, rgb.1) + d(b, rgb.2)
+  };
+  BASIC_COLORS.iter().min_by_key(|(_, basic)| distance(*basic)).map_or(color, |(basic, _)| *basic)
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_from_env() {
+    assert_eq!(Capabilities::from_env("xterm-256color", "", Some("en_US.UTF-8")), Capabilities::default());
+    assert_eq!(Capabilities::from_env("linux", "", Some("en_US.UTF-8")), Capabilities::BASIC);
+    assert_eq!(Capabilities::from_env("xterm", "", Some("C")), Capabilities { unicode: false, extended_colors: true });
+    assert_eq!(Capabilities::from_env("xterm-16color", "", None), Capabilities {
+      unicode: true,
+      extended_colors: false
+    });
+    assert_eq!(Capabilities::from_env("xterm-16color", "truecolor", None), Capabilities::default());
+    // Plain xterm, screen and tmux keep their colors.
+    for term in ["xterm", "screen", "tmux"] {
+      assert_eq!(Capabilities::from_env(term, "", Some("en_US.UTF-8")), Capabilities::default());
+    }
+    assert_eq!(Capabilities::from_env("", "", None), Capabilities::default());
+  }
+
+  #[test]
+  fn test_degrade() {
+    let mut buffer = Buffer::empty(ratatui::layout::Rect::new(0, 0, 2, 1));
+    buffer.get_mut(0, 0).set_symbol("─").set_fg(Color::Indexed(196));
+    Capabilities::BASIC.degrade(&mut buffer);
+    // Cell contents are kept, only the colors change.
+    assert_eq!(buffer.get(0, 0).symbol(), "─");
+    assert_eq!(buffer.get(0, 0).fg, Color::LightRed);
+    assert_eq!(basic_color(Color::Indexed(236)), Color::Black);
+    assert_eq!(basic_color(Color::Indexed(196)), Color::LightRed);
+    assert_eq!(basic_color(Color::Rgb(250, 250, 250)), Color::White);
+    assert_eq!(basic_color(Color::Cyan), Color::Cyan);
+  }
+}
@@ -0,0 +1,166 @@
+use chrono::{DateTime, Utc};
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use strum::Display;
+
+use crate::snippets;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+#[strum(serialize_all = "lowercase")]
+pub enum EpochUnit {
+  Seconds,
+  Milliseconds,
+  Microseconds,
+  Nanoseconds,
+}
+
+/// Interprets a cell value as a Unix epoch timestamp.
+///
+/// The unit is inferred from the number of integer digits, so only values that land roughly between 1973 and 2286
+/// are recognized. Fractional seconds are accepted.
+pub fn epoch_to_datetime(value: &str) -> Option<(EpochUnit, DateTime<Utc>)> {
+  let value = value.trim();
+  let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+  let digits = whole.strip_prefix('-').unwrap_or(whole);
+  if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) || !fraction.chars().all(|c| c.is_ascii_digit()) {
+    return None;
+  }
+
+  let whole: i64 = whole.parse().ok()?;
+  let (unit, per_second) = match digits.len() {
+    9 | 10 => (EpochUnit::Seconds, 1),
+    12 | 13 => (EpochUnit::Milliseconds, 1_000),
+    15 | 16 => (EpochUnit::Microseconds, 1_000_000),
+    18 | 19 => (EpochUnit::Nanoseconds, 1_000_000_000),
+    _ => return None,
+  };
+  if unit != EpochUnit::Seconds && !fraction.is_empty() {
+    return None;
+  }
+
+  let secs = whole.div_euclid(per_second);
+  let mut nanos = (whole.rem_euclid(per_second) * (1_000_000_000 / per_second)) as u32;
+  if !fraction.is_empty() {
+    let padded = format!("{fraction:0<9}");
+    nanos = padded[..9].parse().ok()?;
+  }
+
+  DateTime::<Utc>::from_timestamp(secs, nanos).map(|dt| (unit, dt))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Digests {
+  pub value: String,
+  pub md5: String,
+  pub sha256: String,
+}
+
+/// Hex encoded md5 and sha256 digests of a cell value.
+pub fn digests(value: &str) -> Digests {
+  Digests {
+    value: value.to_string(),
+    md5: format!("{:x}", Md5::digest(value.as_bytes())),
+    sha256: format!("{:x}", Sha256::digest(value.as_bytes())),
+  }
+}
+
+/// `column = value` for pasting a field into a WHERE clause or a chat, `column IS NULL` for NULL.
+pub fn sql_condition(column: &str, value: Option<&str>) -> String {
+  match value {
+    None => format!("{column} IS NULL"),
+    Some(value) if snippets::is_numeric(value) => format!("{column} = {value}"),
+    Some(value) => format!("{column} = {}", snippets::quote_literal(value)),
+  }
+}
+
+/// `"column": value` JSON object member, with numbers unquoted and NULL as `null`.
+pub fn json_field(column: &str, value: Option<&str>) -> String {
+  let value = match value {
+    None => serde_json::Value::Null,
+    Some(value) if snippets::is_numeric(value) => {
+      serde_json::from_str(value).unwrap_or_else(|_| serde_json::Value::from(value))
+    },
+    Some(value) => serde_json::Value::from(value),
+  };
+  format!("{}: {value}", serde_json::Value::from(column))
+}
+
+/// Splits `value` into display lines at line breaks and every `width` characters.
+pub fn wrap_lines(value: &str, width: usize) -> Vec<String> {
+  let width = width.max(1);
+  value
+    .split('\n')
+    .flat_map(|line| {
+      let chars: Vec<char> = line.trim_end_matches('\r').chars().collect();
+      if chars.is_empty() {
+        vec![String::new()]
+      } else {
+        chars.chunks(width).map(|chunk| chunk.iter().collect()).collect()
+      }
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_epoch_seconds() {
+    let (unit, dt) = epoch_to_datetime("1700000000").unwrap();
+    assert_eq!(unit, EpochUnit::Seconds);
+    assert_eq!(dt.to_rfc3339(), "2023-11-14T22:13:20+00:00");
+  }
+
+  #[test]
+  fn test_epoch_fractional_seconds() {
+    let (_, dt) = epoch_to_datetime("1700000000.5").unwrap();
+    assert_eq!(dt.timestamp_subsec_millis(), 500);
+  }
+
+  #[test]
+  fn test_epoch_millis() {
+    let (unit, dt) = epoch_to_datetime("1700000000123").unwrap();
+    assert_eq!(unit, EpochUnit::Milliseconds);
+    assert_eq!(dt.timestamp_subsec_millis(), 123);
+  }
+
+  #[test]
+  fn test_sql_condition() {
+    assert_eq!(sql_condition("id", Some("42")), "id = 42");
+    assert_eq!(sql_condition("name", Some("O'Brien")), "name = 'O''Brien'");
+    assert_eq!(sql_condition("deleted_at", None), "deleted_at IS NULL");
+    assert_eq!(sql_condition("note", Some("NULL")), "note = 'NULL'");
+  }
+
+  #[test]
+  fn test_json_field() {
+    assert_eq!(json_field("id", Some("42")), r#""id": 42"#);
+    assert_eq!(json_field("name", Some(r#"say "hi""#)), r#""name": "say \"hi\"""#);
+    assert_eq!(json_field("deleted_at", None), r#""deleted_at": null"#);
+    assert_eq!(json_field("note", Some("NULL")), r#""note": "NULL""#);
+  }
+
+  #[test]
+  fn test_wrap_lines() {
+    assert_eq!(wrap_lines("abcdef\r\n\ngh", 4), vec!["abcd", "ef", "", "gh"]);
+    assert_eq!(wrap_lines("", 4), vec![""]);
+  }
+
+  #[test]
+  fn test_digests() {
+    let d = digests("abc");
+    assert_eq!(d.md5, "900150983cd24fb0d6963f7d28e17f72");
+    assert_eq!(d.sha256, "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+  }
+
+  #[test]
+  fn test_epoch_rejects_other_values() {
+    assert_eq!(epoch_to_datetime("42"), None);
+    assert_eq!(epoch_to_datetime("abc"), None);
+    assert_eq!(epoch_to_datetime(""), None);
+    assert_eq!(epoch_to_datetime("1700000000123.5"), None);
+  }
+}
@@ -1,12 +1,15 @@
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
-use crate::utils::version;
+use crate::{export::ExportFormat, import::ImportSource, utils::version};
 
 #[derive(Parser, Debug)]
 #[command(author, version = version(), about)]
 pub struct Cli {
+  #[command(subcommand)]
+  pub command: Option<Commands>,
+
   #[arg(short, long, value_name = "FLOAT", help = "Tick rate, i.e. number of ticks per second", default_value_t = 1.0)]
   pub tick_rate: f64,
 
@@ -19,6 +22,62 @@ pub struct Cli {
   )]
   pub frame_rate: f64,
 
-  #[arg(short, long, value_name = "FILE", help = "Sqlite database file to use")]
+  #[arg(short, long, global = true, value_name = "FILE", help = "Sqlite database file to use")]
   pub filename: Option<String>,
+
+  #[arg(long, help = "Show the action log overlay on startup (toggle with F12)")]
+  pub debug: bool,
+
+  #[arg(long, help = "ASCII borders and the 16 basic colors, for terminals that garble the UI")]
+  pub ascii: bool,
+
+  #[arg(
+    long,
+    value_name = "PATH",
+    help = "Also write query and error events as JSON lines to this file, pipe or /dev/fd/N"
+  )]
+  pub json_events: Option<PathBuf>,
+
+  #[arg(long, value_enum, value_name = "SOURCE", help = "Import another tool's history or saved scripts and exit")]
+  pub import: Option<ImportSource>,
+
+  #[arg(long, value_name = "PATH", requires = "import", help = "File or directory to import instead of the default")]
+  pub import_path: Option<PathBuf>,
+}
+
+/// What to run, the interactive UI when no subcommand is given.
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum Commands {
+  /// Start the interactive terminal UI (the default)
+  Tui,
+  /// Run SQL and print the results as CSV or JSON
+  Exec {
+    #[arg(value_name = "SQL", help = "Statements to run, read from stdin when omitted")]
+    sql: Option<String>,
+    #[arg(long, help = "Print each result as a JSON array of objects")]
+    json: bool,
+  },
+  /// List the tables of the database
+  Tables {
+    #[arg(long, help = "Print the tables as JSON")]
+    json: bool,
+    #[arg(long, default_value = "", help = "Only list tables whose name contains this text")]
+    search: String,
+    #[arg(long, help = "Include system catalogs and tables outside the public schema")]
+    all: bool,
+  },
+  /// Run a query and write its results to a file
+  Export {
+    #[arg(value_name = "SQL", help = "Query whose results are exported")]
+    sql: String,
+    #[arg(long, value_enum, help = "File format, defaults to the configured default export format")]
+    format: Option<ExportFormat>,
+    #[arg(short, long, value_name = "PATH", help = "File to write, defaults to a new file in the export directory")]
+    output: Option<PathBuf>,
+  },
+  /// Run a job saved from the export menu, e.g. from cron
+  RunJob {
+    #[arg(value_name = "NAME", help = "Name the job was saved as")]
+    name: String,
+  },
 }
@@ -0,0 +1,245 @@
+use std::borrow::Cow;
+
+use ratatui::layout::Alignment;
+use serde::{Deserialize, Serialize};
+use strum::EnumIter;
+use uuid::Uuid;
+
+use crate::export::ColumnType;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Align {
+  #[default]
+  Left,
+  Center,
+  Right,
+}
+
+impl From<Align> for Alignment {
+  fn from(align: Align) -> Self {
+    match align {
+      Align::Left => Alignment::Left,
+      Align::Center => Alignment::Center,
+      Align::Right => Alignment::Right,
+    }
+  }
+}
+
+/// Display transformation of cell values. The row details always show the raw value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, EnumIter)]
+#[serde(rename_all = "snake_case")]
+pub enum Formatter {
+  /// Integer cents shown as units with two decimals, e.g. `12345` as `123.45`.
+  Currency,
+  /// UUIDs cut to their first 8 characters.
+  ShortUuid,
+  /// Integers with thousands separators, e.g. `1,234,567`.
+  Thousands,
+  /// Byte counts in binary units, e.g. `1536` as `1.5 KiB`.
+  Bytes,
+  /// Seconds as days, hours, minutes and seconds, e.g. `3723` as `1h 2m 3s`.
+  Duration,
+}
+
+impl Formatter {
+  pub fn label(&self) -> &'static str {
+    match self {
+      Formatter::Currency => "Cents as currency",
+      Formatter::ShortUuid => "Short UUID",
+      Formatter::Thousands => "Thousands separators",
+      Formatter::Bytes => "Bytes as human size",
+      Formatter::Duration => "Seconds as duration",
+    }
+  }
+
+  /// The formatted value, or the value unchanged when it doesn't fit the formatter.
+  pub fn apply<'a>(&self, value: &'a str) -> Cow<'a, str> {
+    match self {
+      Formatter::Currency => {
+        match value.parse::<i64>() {
+          Ok(cents) => {
+            let sign = if cents < 0 { "-" } else { "" };
+            let cents = cents.unsigned_abs();
+            Cow::Owned(format!("{sign}{}.{:02}", group_thousands(&(cents / 100).to_string()), cents % 100))
+          },
+          Err(_) => Cow::Borrowed(value),
+        }
+      },
+      Formatter::ShortUuid if Uuid::parse_str(value).is_ok() => Cow::Owned(format!("{}…", &value[..8])),
+      Formatter::Thousands if value.parse::<i64>().is_ok() => {
+        let (sign, digits) = value.split_at(usize::from(value.starts_with('-')));
+        Cow::Owned(format!("{sign}{}", group_thousands(digits)))
+      },
+      Formatter::Bytes => value.parse::<i64>().map_or(Cow::Borrowed(value), |bytes| Cow::Owned(human_size(bytes))),
+      Formatter::Duration => {
+        match value.parse::<f64>() {
+          Ok(seconds) if seconds.is_finite() => Cow::Owned(duration(seconds)),
+          _ => Cow::Borrowed(value),
+        }
+      },
+      _ => Cow::Borrowed(value),
+    }
+  }
+}
+
+fn human_size(bytes: i64) -> String {
+  const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+  let sign = if bytes < 0 { "-" } else { "" };
+  let mut size = bytes.unsigned_abs() as f64;
+  if size < 1024.0 {
+    return format!("{bytes} B");
+  }
+  let mut unit = 0;
+  size /= 1024.0;
+  while size >= 1024.0 && unit + 1 < UNITS.len() {
+    size /= 1024.0;
+    unit += 1;
+  }
+  format!("{sign}{size:.1} {}", UNITS[unit])
+}
+
+fn duration(seconds: f64) -> String {
+  let sign = if seconds < 0.0 { "-" } else { "" };
+  let seconds = seconds.abs();
+  let whole = seconds.trunc() as u64;
+  let mut parts: Vec<String> = [(whole / 86_400, "d"), (whole / 3_600 % 24, "h"), (whole / 60 % 60, "m")]
+    .iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, unit)| format!("{count}{unit}"))
+    .collect();
+  // Seconds within the last minute, with any fraction.
+  let rest = seconds - (whole - whole % 60) as f64;
+  if rest > 0.0 || parts.is_empty() {
+    parts.push(format!("{}s", (rest * 1000.0).round() / 1000.0));
+  }
+  format!("{sign}{}", parts.join(" "))
+}
+
+fn group_thousands(digits: &str) -> String {
+  let mut grouped = String::new();
+  for (i, c) in digits.chars().enumerate() {
+    if i > 0 && (digits.len() - i).is_multiple_of(3) {
+      grouped.push(',');
+    }
+    grouped.push(c);
+  }
+  grouped
+}
+
+/// Alignment and formatting for result columns matching a name pattern and/or an inferred type, from the
+/// `columns` config list. Rules are checked in order and the first match sets each of `align` and `format`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColumnRule {
+  /// Column name pattern, case-insensitive, where `*` matches any characters, e.g. `*_count`.
+  pub name: Option<String>,
+  /// Type inferred from the column's values.
+  #[serde(rename = "type")]
+  pub column_type: Option<ColumnType>,
+  pub align: Option<Align>,
+  pub format: Option<Formatter>,
+}
+
+impl ColumnRule {
+  fn matches(&self, name: &str, column_type: ColumnType) -> bool {
+    self.name.as_deref().is_none_or(|pattern| glob_match(pattern, name))
+      && self.column_type.is_none_or(|t| t == column_type)
+  }
+}
+
+/// Resolved display rules of one column.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ColumnStyle {
+  pub align: Align,
+  pub format: Option<Formatter>,
+}
+
+impl ColumnStyle {
+  pub fn resolve(rules: &[ColumnRule], name: &str, column_type: ColumnType) -> Self {
+    let matching = || rules.iter().filter(|r| r.matches(name, column_type));
+    Self { align: matching().find_map(|r| r.align).unwrap_or_default(), format: matching().find_map(|r| r.format) }
+  }
+
+  pub fn display<'a>(&self, value: &'a str) -> Cow<'a, str> {
+    match self.format {
+      Some(format) => format.apply(value),
+      None => Cow::Borrowed(value),
+    }
+  }
+}
+
+/// Case-insensitive match of `text` against `pattern`, where `*` matches any run of characters.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+  let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+  let text: Vec<char> = text.to_lowercase().chars().collect();
+  let (mut p, mut t) = (0, 0);
+  // Position of the last `*` and the text index it was tried at, for backtracking.
+  let mut star: Option<(usize, usize)> = None;
+  while t < text.len() {
+    if p < pattern.len() && pattern[p] == '*' {
+      star = Some((p, t));
+      p += 1;
+    } else if p < pattern.len() && pattern[p] == text[t] {
+      p += 1;
+      t += 1;
+    } else if let Some((star_p, star_t)) = star {
+      p = star_p + 1;
+      t = star_t + 1;
+      star = Some((star_p, star_t + 1));
+    } else {
+      return false;
+    }
+  }
+  pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_glob_match() {
+    assert!(glob_match("*_count", "order_COUNT"));
+    assert!(glob_match("*id*", "user_id"));
+    assert!(!glob_match("*_count", "count_orders"));
+    assert!(glob_match("*", ""));
+  }
+
+  #[test]
+  fn test_formatters() {
+    assert_eq!(Formatter::Currency.apply("123456"), "1,234.56");
+    assert_eq!(Formatter::Currency.apply("-5"), "-0.05");
+    assert_eq!(Formatter::Thousands.apply("-1234567"), "-1,234,567");
+    assert_eq!(Formatter::ShortUuid.apply("67e55044-10b1-426f-9247-bb680e5fe0c8"), "67e55044…");
+    assert_eq!(Formatter::ShortUuid.apply("not a uuid"), "not a uuid");
+    assert_eq!(Formatter::Bytes.apply("512"), "512 B");
+    assert_eq!(Formatter::Bytes.apply("1536"), "1.5 KiB");
+    assert_eq!(Formatter::Bytes.apply("-3221225472"), "-3.0 GiB");
+    assert_eq!(Formatter::Duration.apply("3723"), "1h 2m 3s");
+    assert_eq!(Formatter::Duration.apply("90061.25"), "1d 1h 1m 1.25s");
+    assert_eq!(Formatter::Duration.apply("7200"), "2h");
+    assert_eq!(Formatter::Duration.apply("0"), "0s");
+  }
+
+  #[test]
+  fn test_resolve_first_match_per_attribute() {
+    let rules: Vec<ColumnRule> = toml::from_str::<toml::Table>(
+      r#"
+      columns = [
+        { name = "*_cents", format = "currency" },
+        { type = "integer", align = "right" },
+      ]
+      "#,
+    )
+    .unwrap()["columns"]
+      .clone()
+      .try_into()
+      .unwrap();
+    let style = ColumnStyle::resolve(&rules, "price_cents", ColumnType::Integer);
+    assert_eq!(style, ColumnStyle { align: Align::Right, format: Some(Formatter::Currency) });
+    assert_eq!(ColumnStyle::resolve(&rules, "name", ColumnType::Text), ColumnStyle::default());
+  }
+}
@@ -0,0 +1,128 @@
+use std::io::{Read, Write};
+
+use color_eyre::eyre::{eyre, Result};
+use serde_json::{Map, Value};
+
+use crate::{cli::Commands, config::Config, connection::connect, export, jobs::Jobs, sql::Nulls, statement};
+
+/// Runs a scripting subcommand against the same connection the UI would use, writing to stdout. Jobs connect to
+/// their own database.
+pub async fn run(command: Commands, filename: Option<String>, config: &Config) -> Result<()> {
+  match command {
+    Commands::Tui => Err(eyre!("The tui subcommand runs the interactive UI")),
+    Commands::RunJob { name } => run_job(&name, filename, config).await,
+    Commands::Exec { sql, json } => {
+      let (_, db) = connect(filename.as_deref()).await?;
+      let sql = match sql {
+        Some(sql) => sql,
+        None => {
+          let mut sql = String::new();
+          std::io::stdin().read_to_string(&mut sql)?;
+          sql
+        },
+      };
+      let lines: Vec<String> = sql.lines().map(String::from).collect();
+      for (i, statement) in statement::split(&lines).iter().enumerate() {
+        let (headers, rows, nulls) = db.fetch(&statement.text).await?;
+        let mut out = std::io::stdout().lock();
+        if i > 0 {
+          writeln!(out)?;
+        }
+        if json {
+          writeln!(out, "{}", serde_json::to_string(&rows_to_json(&headers, &cells(&rows, &nulls)))?)?;
+        } else {
+          let headers: Vec<&String> = headers.iter().collect();
+          export::write_csv(&mut out, &headers, &cells(&rows, &nulls), &config.export.csv)?;
+        }
+      }
+      Ok(())
+    },
+    Commands::Tables { json, search, all } => {
+      let (_, db) = connect(filename.as_deref()).await?;
+      let mut tables = db.load_tables(&search).await?;
+      tables.retain(|t| all || (!t.is_system() && t.is_default_schema()));
+      let mut out = std::io::stdout().lock();
+      if json {
+        writeln!(out, "{}", serde_json::to_string(&tables)?)?;
+      } else {
+        for table in &tables {
+          writeln!(out, "{}", table.display_name())?;
+        }
+      }
+      Ok(())
+    },
+    Commands::Export { sql, format, output } => {
+      let format = format.unwrap_or(config.settings.default_export_format);
+      let path = output.unwrap_or_else(|| config.export.next_path(format.extension()));
+      let (_, db) = connect(filename.as_deref()).await?;
+      let (headers, rows, nulls) = db.fetch(&sql).await?;
+      let table = config.export.table_name(Some(&sql));
+      let headers: Vec<&String> = headers.iter().collect();
+      config.export.write(format, &path, &table, &headers, &cells(&rows, &nulls))?;
+      writeln!(std::io::stdout(), "Exported {} rows to {}", rows.len(), path.display())?;
+      Ok(())
+    },
+  }
+}
+
+/// Runs a saved job's query against its database, `filename` when it has none, and exports the results with its
+/// profile, waiting for the profile's post-export command.
+async fn run_job(name: &str, filename: Option<String>, config: &Config) -> Result<()> {
+  let jobs = Jobs::load()?;
+  let job = jobs.get(name)?;
+  let profile = config
+    .export
+    .profiles
+    .get(&job.profile)
+    .ok_or_else(|| eyre!("Job {name} uses unknown export profile {}", job.profile))?;
+  let (_, db) = connect(job.connection.as_deref().or(filename.as_deref())).await?;
+  let (headers, rows, nulls) = db.fetch(&job.query).await?;
+  let table = config.export.table_name(Some(&job.query));
+  let headers: Vec<&String> = headers.iter().collect();
+  let path = config.export.write_profile(&job.profile, profile, &table, &headers, &cells(&rows, &nulls))?;
+  if let Some(command) = &profile.post_command {
+    export::wait_for_post_command(command, &path)?;
+  }
+  writeln!(std::io::stdout(), "Job {name} exported {} rows to {}", rows.len(), path.display())?;
+  Ok(())
+}
+
+/// The cells of `rows` with the NULL ones as `None`, as the exports take them.
+fn cells<'a>(rows: &'a [Vec<String>], nulls: &Nulls) -> Vec<Vec<Option<&'a String>>> {
+  rows.iter().enumerate().map(|(i, row)| nulls.cells(i, row)).collect()
+}
+
+/// Result rows as JSON objects keyed by column, with NULLs as `null`.
+fn rows_to_json(headers: &[String], rows: &[Vec<Option<&String>>]) -> Value {
+  let objects = rows
+    .iter()
+    .map(|row| {
+      let object: Map<String, Value> = headers
+        .iter()
+        .zip(row)
+        .map(|(h, v)| (h.clone(), v.map_or(Value::Null, |v| Value::String(v.clone()))))
+        .collect();
+      Value::Object(object)
+    })
+    .collect();
+  Value::Array(objects)
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_rows_to_json() {
+    let headers = vec!["id".to_string(), "name".to_string()];
+    let rows = vec![vec!["1".to_string(), "NULL".to_string()], vec!["2".to_string(), "NULL".to_string()]];
+    let mut nulls = Nulls::default();
+    nulls.insert(0, 1);
+    assert_eq!(
+      rows_to_json(&headers, &cells(&rows, &nulls)).to_string(),
+      r#"[{"id":"1","name":null},{"id":"2","name":"NULL"}]"#
+    );
+  }
+}
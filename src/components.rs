@@ -11,8 +11,13 @@ use crate::{
 };
 
 pub mod db;
+pub mod debug;
 pub mod fps;
+pub mod health;
 pub mod home;
+pub mod popup_search;
+pub mod text_input;
+pub mod values_editor;
 pub mod vim;
 
 #[derive(Default, Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
@@ -62,6 +67,7 @@ pub trait Component {
   /// # Returns
   ///
   /// * `Result<()>` - An Ok result or an error.
+  #[allow(unused_variables)]
   fn init(&mut self, area: Rect) -> Result<()> {
     Ok(())
   }
@@ -78,6 +84,7 @@ pub trait Component {
     let r = match event {
       Some(Event::Key(key_event)) => self.handle_key_events(key_event)?,
       Some(Event::Mouse(mouse_event)) => self.handle_mouse_events(mouse_event)?,
+      Some(Event::Paste(text)) => self.handle_paste_event(text)?,
       _ => None,
     };
     Ok(r)
@@ -108,6 +115,19 @@ pub trait Component {
   fn handle_mouse_events(&mut self, mouse: MouseEvent) -> Result<Option<Action>> {
     Ok(None)
   }
+  /// Handle text pasted into the terminal and produce actions if necessary.
+  ///
+  /// # Arguments
+  ///
+  /// * `text` - The pasted text.
+  ///
+  /// # Returns
+  ///
+  /// * `Result<Option<Action>>` - An action to be processed or none.
+  #[allow(unused_variables)]
+  fn handle_paste_event(&mut self, text: String) -> Result<Option<Action>> {
+    Ok(None)
+  }
   /// Update the state of the component based on a received action. (REQUIRED)
   ///
   /// # Arguments
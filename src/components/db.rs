@@ -1,38 +1,212 @@
 use std::{
-  collections::{BTreeMap, HashMap},
+  collections::{BTreeMap, HashMap, HashSet},
   fmt::Display,
+  path::{Path, PathBuf},
   rc::Rc,
-  time::Duration,
+  sync::Arc,
+  time::{Duration, Instant},
 };
 
+use chrono::{Local, TimeZone};
 use clipboard::{ClipboardContext, ClipboardProvider};
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{eyre, Result};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{prelude::*, widgets::*};
-use serde::{Deserialize, Serialize};
-use sqlx::{postgres::PgPoolOptions, Postgres, Row};
-use strum::Display;
+use strum::{Display, IntoEnumIterator};
 use tokio::sync::mpsc::UnboundedSender;
-use tokio_stream::StreamExt;
 use tui_popup::Popup;
-use tui_textarea::{Input, TextArea};
+use tui_textarea::{CursorMove, Input, TextArea};
+use uuid::Uuid;
 
 use super::{
   vim::{Mode, Transition},
   Component, ComponentKind, Frame,
 };
 use crate::{
-  action::Action,
-  components::vim::Vim,
-  config::{Config, KeyBindings},
+  action::{Action, BusyQueryChoice},
+  attach::{self, AttachedDatabase},
+  autocomplete::{self, Suggestion, SuggestionKind},
+  capabilities, cell,
+  column_format::{ColumnStyle, Formatter},
+  components::{
+    popup_search::PopupSearch,
+    text_input::TextInput,
+    values_editor::{ValuesEditor, ValuesEditorEvent},
+    vim::Vim,
+  },
+  config::Config,
+  custom_commands::PendingCommand,
+  diff::{self, DiffRow},
+  error_hints::{self, ErrorHint, HintAction},
+  export::{self, ExportFormat},
+  format, fuzzy,
+  history::QueryHistory,
+  jobs::{Job, Jobs},
+  join_builder::{JoinBuilder, JoinStep},
+  json_path::JsonPath,
+  keymap::{self, ChordMatch, Command, Scope},
+  library::Library,
+  locale::Locale,
+  notices::{Notice, NoticeLog, Severity},
+  outline::{self, OutlineEntry},
+  perf,
+  plan::{self, BufferUsage, PlanFormat, PlanLine},
+  plan_hints::{self, IndexHint},
+  registers::Registers,
+  results_filter::{self, ResultsFilter},
+  retry,
+  schema_change::ColumnChange,
+  server::{ServerInfo, ServerKind},
+  settings::SettingKind,
+  snippets,
+  sql::{Nulls, ResultSet},
+  statement, tags,
+  undo::UndoStack,
 };
 
 const VISIBLE_COLUMNS: usize = 3;
+const PREVIEW_COLUMN_WIDTH: u16 = 20;
+const PREVIEW_COLUMN_SPACING: u16 = 2;
+/// Characters the value column of the row details moves per h/l.
+const DETAIL_SCROLL_STEP: usize = 20;
+/// Results with at least this many rows are filtered on a blocking task.
+const BACKGROUND_FILTER_ROWS: usize = 20_000;
+/// Largest count prefix, so a held digit key can't queue an endless movement.
+const MAX_COUNT: usize = 9999;
+/// Width of the name column of the row details.
+const DETAIL_NAME_WIDTH: u16 = 40;
+/// Values longer than this show their length in the row details.
+const DETAIL_LENGTH_THRESHOLD: usize = 40;
+/// Results larger than this (estimated in memory) ask for confirmation before being copied.
+const COPY_WARNING_BYTES: usize = 10 * 1024 * 1024;
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
-pub struct DbTable {
-  pub name: String,
-  pub schema: String,
+/// Width the hint of an error is wrapped to in the error popup.
+const ERROR_HINT_WIDTH: usize = 70;
+
+/// Most recent server notices shown below the results.
+const MAX_NOTICE_LINES: usize = 5;
+
+pub use crate::schema::{DbColumn, DbTable};
+
+/// An entry of the export menu.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ExportTarget {
+  Format(ExportFormat),
+  Profile(String),
+}
+
+/// Open completion popup for the word being typed in the editor.
+#[derive(Debug, Default)]
+struct Completion {
+  /// Column where the completed word starts on the cursor's line.
+  start_col: usize,
+  suggestions: Vec<Suggestion>,
+  selected: usize,
+}
+
+/// Column list of a table with an optional `/` filter.
+#[derive(Debug, Default)]
+struct SchemaPopup {
+  table: DbTable,
+  columns: Vec<DbColumn>,
+  /// Filters the columns by name and highlights the matched text.
+  filter: PopupSearch,
+  selected: usize,
+}
+
+impl SchemaPopup {
+  fn filtered_columns(&self) -> Vec<&DbColumn> {
+    let filter = self.filter.query();
+    self.columns.iter().filter(|c| c.name.to_lowercase().contains(&filter)).collect()
+  }
+}
+
+/// Executed statements, most recent first, searchable by query text and note.
+#[derive(Debug, Default)]
+struct HistoryPopup {
+  search: TextInput,
+  is_searching: bool,
+  /// Indices into the history of the entries matching the search.
+  matches: Vec<usize>,
+  selected: usize,
+  /// Note being edited for the selected entry.
+  note: Option<TextInput>,
+}
+
+/// A named copy of the results view, with its filter and column order, that later queries don't overwrite.
+#[derive(Debug, Clone)]
+struct ResultSnapshot {
+  name: String,
+  taken_at: String,
+  query: Option<String>,
+  headers: Vec<String>,
+  rows: Arc<Vec<Vec<String>>>,
+  nulls: Nulls,
+  filter: String,
+  column_order: Vec<usize>,
+  selected_row: usize,
+  selected_column: usize,
+}
+
+/// SQL generated by a UI action, shown for confirmation before the action runs it.
+#[derive(Debug)]
+struct GeneratedSql {
+  sql: String,
+  action: Action,
+  /// Selected entry of [`GENERATED_SQL_CHOICES`].
+  choice: usize,
+}
+
+/// A destructive UI operation that [`Command::Undo`] reverts.
+#[derive(Debug)]
+enum UndoEntry {
+  /// Editor text replaced by a loaded or generated query or by formatting.
+  Editor(String),
+  /// A deleted snapshot and its position in the list.
+  Snapshot(usize, ResultSnapshot),
+  /// The previous note of the history entry with this query and execution time.
+  HistoryNote { query: String, executed_at: i64, note: Option<String> },
+}
+
+const GENERATED_SQL_CHOICES: [&str; 3] = ["Execute", "Edit", "Cancel"];
+
+/// Two values compared side by side in the cell diff popup.
+#[derive(Debug, Default)]
+struct CellDiff {
+  left: String,
+  right: String,
+  rows: Vec<DiffRow>,
+  scroll: usize,
+}
+
+/// An EXPLAIN result drawn as a tree.
+#[derive(Debug)]
+struct PlanView {
+  format: PlanFormat,
+  lines: Vec<PlanLine>,
+  scroll: usize,
+}
+
+/// Rows fetched for the quick-peek popup, kept apart from the main results grid.
+#[derive(Debug, Default)]
+struct TablePreview {
+  table: String,
+  headers: Vec<String>,
+  rows: Vec<Vec<String>>,
+  column_offset: usize,
+}
+
+impl TablePreview {
+  fn area(screen: Rect) -> Rect {
+    centered_rect(90, 70, screen)
+  }
+
+  /// Largest column offset that still fills the popup, so scrolling stops once the last column is visible.
+  fn max_column_offset(&self, screen: Rect) -> usize {
+    let inner_width = Self::area(screen).width.saturating_sub(2);
+    let visible = ((inner_width + PREVIEW_COLUMN_SPACING) / (PREVIEW_COLUMN_WIDTH + PREVIEW_COLUMN_SPACING)).max(1);
+    self.headers.len().saturating_sub(visible as usize)
+  }
 }
 
 #[derive(Default)]
@@ -40,31 +214,252 @@ pub struct Db<'a> {
   command_tx: Option<UnboundedSender<Action>>,
   config: Config,
   tables: Vec<DbTable>,
+  /// Every loaded table, before hiding system objects.
+  all_tables: Vec<DbTable>,
   selected_table_index: usize,
   selected_row_index: usize,
   selected_headers: Vec<String>,
-  query_results: Vec<Vec<String>>,
+  query_results: Arc<Vec<Vec<String>>>,
+  /// Unfiltered rows of the last result, `query_results` holds the rows passing the results filter and shares these
+  /// while the filter keeps every row.
+  all_results: Arc<Vec<Vec<String>>>,
+  /// NULL cells of `all_results`, whose text alone doesn't tell them from `'NULL'` strings.
+  all_nulls: Nulls,
+  /// Display rules of each result column, by result column index.
+  column_styles: Vec<ColumnStyle>,
+  /// Formatters chosen in the column format popup by column name, overriding the `columns` config. `None` shows
+  /// raw values.
+  format_overrides: HashMap<String, Option<Formatter>>,
+  /// Selected entry of the column format popup, while it is open.
+  format_menu_index: Option<usize>,
+  /// Positions in `all_results` of the rows the filter kept, `None` while all rows are shown.
+  filtered_rows: Option<Vec<usize>>,
+  locale: Locale,
+  results_filter: ResultsFilter,
   selected_component: ComponentKind,
   query_input: TextArea<'a>,
   vim_editor: Vim,
   horizonal_scroll_offset: usize,
   show_row_details: bool,
-  table_search_query: String,
+  table_search: TextInput,
   is_searching_tables: bool,
   row_is_selected: bool,
   detail_row_index: usize,
+  /// Characters hidden at the start of each value in the row details.
+  detail_value_offset: usize,
+  /// Fields (in display order) shown in full across several lines in the row details.
+  expanded_fields: HashSet<usize>,
   error_message: Option<String>,
+  /// Hint explaining the error of a failed query, the other errors have none.
+  error_hint: Option<&'static ErrorHint>,
+  /// Whether the error popup shows the raw error instead of its hint.
+  error_raw: bool,
+  selected_column_index: usize,
+  column_order: Vec<usize>,
+  saved_column_orders: HashMap<Vec<String>, Vec<usize>>,
+  query_scroll_top: (u16, u16),
+  time_snippet_index: Option<usize>,
+  registers: Registers,
+  /// Query asked to run while another one is running and the selected choice of what to do with it.
+  busy_query: Option<(String, usize)>,
+  /// Queries waiting for the running one, shown in the footer.
+  queued_queries: usize,
+  /// Body rows that fit in the results table when it was last drawn, for paging.
+  results_page_rows: usize,
+  /// First row drawn in the results table, kept between frames so paging scrolls the body with the selection.
+  results_offset: usize,
+  /// Search typed in the jump to column popup and the selected match, while it is open.
+  column_picker: Option<(TextInput, usize)>,
+  /// Search of the custom command palette and the selected match.
+  command_palette: Option<(TextInput, usize)>,
+  /// Custom command whose placeholder values are being asked for, with the value typed so far.
+  pending_command: Option<(PendingCommand, TextInput)>,
+  /// Selected entry of the registers popup, while it is open.
+  register_index: Option<usize>,
+  /// Statements and CTEs of the editor, with their `(row, col)` position.
+  outline: Vec<(OutlineEntry, (usize, usize))>,
+  outline_index: Option<usize>,
+  /// Hints for the EXPLAIN ANALYZE plan in the results.
+  index_hints: Vec<IndexHint>,
+  index_hint_index: Option<usize>,
+  /// Format of the EXPLAIN output in the results, if they are a plan.
+  plan_format: Option<PlanFormat>,
+  plan_view: Option<PlanView>,
+  /// Buffer usage of an `EXPLAIN (ANALYZE, BUFFERS)` plan in the results, shown in the footer.
+  buffer_usage: Option<BufferUsage>,
+  /// Every result set of the last run; the slot of the one shown is empty while its rows are the results.
+  result_sets: Vec<ResultSet>,
+  result_set_index: usize,
+  notices: NoticeLog,
+  /// Scroll offset of the notices popup, while it is open.
+  notices_scroll: Option<usize>,
+  generated_sql: Option<GeneratedSql>,
+  status_message: Option<String>,
+  /// Rows serialized and total rows of a running copy of all rows.
+  copy_progress: Option<(usize, usize)>,
+  /// A large copy was announced and pressing its key again right away goes ahead.
+  large_copy_confirmed: bool,
+  /// The command whose EXPLAIN ANALYZE was announced, as it runs the statement, and that goes ahead when pressed again
+  /// right away.
+  analyze_announced: Option<Command>,
+  cell_digests: Option<cell::Digests>,
+  /// Label and value of the cell marked for a diff.
+  marked_cell: Option<(String, String)>,
+  cell_diff: Option<CellDiff>,
+  table_row_counts: HashMap<String, String>,
+  table_preview: Option<TablePreview>,
+  schema_popup: Option<SchemaPopup>,
+  history_popup: Option<HistoryPopup>,
+  export_menu_index: Option<usize>,
+  last_query: Option<String>,
+  /// Statements sent with the auto `LIMIT` added, to the statement as written that history and `last_query` keep.
+  limited_queries: HashMap<String, String>,
+  /// Query that produced the current result set, to notice when a re-run returns different columns.
+  results_query: Option<String>,
+  /// Headers of the columns extracted with a JSON path, which a re-run doesn't return.
+  json_path_columns: HashSet<String>,
+  /// Count being typed before a movement key, e.g. the `5` of `5j`.
+  count_prefix: Option<usize>,
+  /// Count typed before the last key, repeating the movement action that key triggers.
+  pending_count: Option<usize>,
+  help_search: PopupSearch,
+  /// When the statement last recorded in the history was sent, until its result or error arrives.
+  query_started: Option<Instant>,
+  /// Estimated memory held by the result rows.
+  results_bytes: usize,
+  /// The rows were dropped with [`Command::DropResults`], `r` re-runs the query that produced them.
+  results_dropped: bool,
+  settings_index: Option<usize>,
+  server: Option<ServerInfo>,
+  screen: Rect,
+  help_index: Option<usize>,
+  completion: Option<Completion>,
+  column_cache: HashMap<String, Vec<DbColumn>>,
+  join_builder: Option<JoinBuilder>,
+  editor_cursor: Option<(u16, u16)>,
+  history: QueryHistory,
+  /// Snippets and saved queries from the `library.directory` config, if set.
+  library: Option<Library>,
+  library_checked: Option<Instant>,
+  /// Name being entered for saving the current statement to the library.
+  library_save_name: Option<TextInput>,
+  /// Sqlite file the UI is connected to, recorded with saved jobs.
+  database_file: Option<String>,
+  /// Export profile and name being entered for saving the last query as a job.
+  job_save: Option<(String, TextInput)>,
+  /// `path AS alias` being entered for attaching another SQLite database.
+  attach_input: Option<TextInput>,
+  /// SQLite databases attached to the connection and their tables, listed after the main database's.
+  attached: Vec<AttachedDatabase>,
+  attached_tables: Vec<DbTable>,
+  snapshots: Vec<ResultSnapshot>,
+  /// Name being typed for a new snapshot.
+  snapshot_name: Option<TextInput>,
+  /// Connection preamble placeholder being asked for, with the value typed so far.
+  preamble_prompt: Option<(String, TextInput)>,
+  undo: UndoStack<UndoEntry>,
+  /// Editor text swapped out by [`Command::AlternateQuery`], swapped back in by the next one.
+  alternate_query: Option<String>,
+  /// Keys typed after the leader key while a chord is pending.
+  chord: Option<String>,
+  /// Result column index and path being typed for a JSON path column.
+  json_path_prompt: Option<(usize, TextInput)>,
+  /// Selected entry of the snapshots popup, while it is open.
+  snapshot_index: Option<usize>,
+  values_editor: Option<ValuesEditor>,
+  /// Statement whose JSON plan is being fetched for export.
+  pending_plan_query: Option<String>,
+  /// Attempt of the running query when it is being retried.
+  retry_attempt: Option<u32>,
+  /// Table whose columns were requested for completion rather than for the schema popup.
+  completion_columns_pending: Option<String>,
 }
 
 impl<'a> Db<'a> {
   pub fn new() -> Self {
-    Self::default()
+    Self { history: QueryHistory::load(), locale: Locale::from_env(), ..Self::default() }
   }
 
   fn column_count(&self) -> usize {
     self.selected_headers.len()
   }
 
+  /// Returns `values` (a header list or a result row) in the current display order.
+  fn ordered<'b, T>(&self, values: &'b [T]) -> Vec<&'b T> {
+    self.column_order.iter().filter_map(|i| values.get(*i)).collect()
+  }
+
+  fn reset_column_order(&mut self) {
+    self.column_order = self
+      .saved_column_orders
+      .get(&self.selected_headers)
+      .filter(|order| order.len() == self.column_count())
+      .cloned()
+      .unwrap_or_else(|| (0..self.column_count()).collect());
+    self.selected_column_index = 0;
+  }
+
+  /// Display positions of the columns matching the column picker's search, best match first.
+  fn column_picker_matches(&self) -> Vec<usize> {
+    let Some((input, _)) = &self.column_picker else {
+      return Vec::new();
+    };
+    fuzzy::rank(input.value(), self.ordered(&self.selected_headers).into_iter().map(String::as_str))
+  }
+
+  /// Indices of the custom commands matching the palette's search, best match first.
+  fn command_palette_matches(&self) -> Vec<usize> {
+    let Some((input, _)) = &self.command_palette else {
+      return Vec::new();
+    };
+    fuzzy::rank(input.value(), self.config.commands.iter().map(|command| command.name.as_str()))
+  }
+
+  /// Asks for the next placeholder of `pending`, or runs its SQL once every placeholder has a value.
+  fn run_custom_command(&mut self, pending: PendingCommand) -> Option<Action> {
+    if pending.missing().is_some() {
+      self.pending_command = Some((pending, TextInput::default()));
+      return None;
+    }
+    let sql = pending.sql();
+    self.status_message = Some(format!("Running {}", pending.command.name));
+    self.confirm_generated(sql.clone(), Action::HandleQuery(sql))
+  }
+
+  /// Moves the selected row, or the selected field of the row details, by `delta` rows and scrolls the table body
+  /// along, as Ctrl-d/Ctrl-u and PageDown/PageUp do.
+  fn page_results(&mut self, delta: isize) {
+    if self.row_is_selected {
+      let fields = self.query_results.get(self.selected_row_index).map_or(0, Vec::len);
+      self.detail_row_index = self.detail_row_index.saturating_add_signed(delta).min(fields.saturating_sub(1));
+      return;
+    }
+    let last = self.query_results.len().saturating_sub(1);
+    self.selected_row_index = self.selected_row_index.saturating_add_signed(delta).min(last);
+    let max_offset = self.query_results.len().saturating_sub(self.results_page_rows);
+    self.results_offset = self.results_offset.saturating_add_signed(delta).min(max_offset);
+  }
+
+  fn select_column(&mut self, index: usize) {
+    if self.column_count() == 0 {
+      return;
+    }
+    self.selected_column_index = index.min(self.column_count() - 1);
+    self.horizonal_scroll_offset = self.selected_column_index / VISIBLE_COLUMNS;
+  }
+
+  /// Moves the selected column one position left (`-1`) or right (`1`) in the display order.
+  fn shift_selected_column(&mut self, delta: isize) {
+    let from = self.selected_column_index;
+    let to = from as isize + delta;
+    if to < 0 || to as usize >= self.column_order.len() {
+      return;
+    }
+    self.column_order.swap(from, to as usize);
+    self.saved_column_orders.insert(self.selected_headers.clone(), self.column_order.clone());
+    self.select_column(to as usize);
+  }
+
   fn json(&self) -> Option<String> {
     if self.query_results.is_empty() {
       return None;
@@ -72,7 +467,7 @@ impl<'a> Db<'a> {
 
     let json_str = if self.row_is_selected {
       if let Some(selected_row) = self.query_results.get(self.selected_row_index) {
-        if let Some(selected_cell) = selected_row.get(self.detail_row_index) {
+        if let Some(selected_cell) = self.ordered(selected_row).get(self.detail_row_index) {
           selected_cell.to_string()
         } else {
           String::new()
@@ -95,241 +490,3172 @@ impl<'a> Db<'a> {
     Some(json_str)
   }
 
-  fn table_row_count(&self) -> usize {
-    self.tables.len()
+  /// The statement under the cursor as written, or the whole editor when there is none.
+  fn statement_text(&self) -> String {
+    let lines = self.query_input.lines();
+    match statement::at_cursor(lines, self.query_input.cursor()) {
+      Some(s) => s.text,
+      None => lines.join("\n"),
+    }
   }
 
-  fn render_table_list(&mut self, f: &mut Frame<'_>, chunks: Rc<[Rect]>) -> Result<Rc<[Rect]>> {
-    let table_chunks = Layout::default()
-      .direction(Direction::Horizontal)
-      .constraints([Constraint::Percentage(20), Constraint::Percentage(80)].as_ref())
-      .split(chunks[1]);
+  /// Runs `statement` with the auto `LIMIT` setting applied. The limit only applies to what runs, history and the
+  /// previous query keep the statement as written.
+  fn execute(&mut self, statement: String) -> Action {
+    let Some(limit) = self.config.settings.auto_limit else {
+      return Action::HandleQuery(statement);
+    };
+    let limited = statement::with_limit(&statement, limit);
+    if limited != statement {
+      self.limited_queries.insert(limited.clone(), statement);
+    }
+    Action::HandleQuery(limited)
+  }
 
-    let tables_border_color = if self.selected_component == ComponentKind::Home { Color::Cyan } else { Color::White };
-    let tables = Block::default()
-      .borders(Borders::ALL)
-      .style(Style::default().fg(tables_border_color))
-      .title("Tables")
-      .border_type(BorderType::Plain);
+  /// Keywords, loaded tables and the columns of tables whose schema has been fetched.
+  fn completion_candidates(&self) -> Vec<Suggestion> {
+    let tables = self.tables.iter().map(|t| {
+      let mut documentation = vec![format!("schema: {}", t.schema)];
+      if let Some(columns) = self.column_cache.get(&t.name) {
+        documentation.push(format!("columns: {}", columns.len()));
+      }
+      Suggestion::new(&t.name, SuggestionKind::Table).with_documentation(documentation)
+    });
+    let columns =
+      self.column_cache.iter().flat_map(|(table, columns)| columns.iter().map(move |c| column_suggestion(table, c)));
+    let ctes = outline::ctes(&self.statement_text());
+    let cte_names = ctes.iter().map(|cte| {
+      Suggestion::new(&cte.name, SuggestionKind::Table)
+        .with_documentation(vec!["common table expression".to_string(), format!("columns: {}", cte.columns.len())])
+    });
+    let cte_columns =
+      ctes.iter().flat_map(|cte| cte.columns.iter().map(|column| cte_column_suggestion(&cte.name, column)));
+    // Entries of the query library, overridden by snippets of the same name in the config.
+    let mut snippets = self.library.as_ref().map(Library::snippets).unwrap_or_default();
+    snippets.extend(self.config.snippets.clone());
+    autocomplete::snippet_suggestions(&snippets)
+      .chain(autocomplete::builtin_suggestions())
+      .chain(cte_names)
+      .chain(tables)
+      .chain(cte_columns)
+      .chain(columns)
+      .chain(self.history.suggestions())
+      .collect()
+  }
 
-    let table_list_chunks = if self.is_searching_tables {
-      Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Length(3), Constraint::Min(1)].as_ref())
-        .split(table_chunks[0])
-    } else {
-      table_chunks.clone()
+  /// Library entries with a tag starting with `prefix`, completed as snippets.
+  fn tagged_snippet_suggestions(&self, prefix: &str) -> Vec<Suggestion> {
+    let Some(library) = &self.library else {
+      return Vec::new();
     };
+    library
+      .tagged(prefix)
+      .into_iter()
+      .map(|entry| {
+        let tags: Vec<String> = entry.tags.iter().map(|tag| format!("#{tag}")).collect();
+        let mut documentation = vec![format!("tags: {}", tags.join(" "))];
+        documentation.extend(entry.description.clone());
+        documentation.extend(entry.body.lines().map(String::from));
+        let mut suggestion = Suggestion::new(&entry.name, SuggestionKind::Snippet).with_documentation(documentation);
+        suggestion.replacement = Some(entry.body.clone());
+        suggestion
+      })
+      .collect()
+  }
 
-    if self.is_searching_tables {
-      let search_block = Block::default().borders(Borders::ALL).title("Search");
-      let search_text =
-        Paragraph::new(Text::styled(format!("{}", self.table_search_query), Style::default().fg(Color::Yellow)))
-          .block(search_block);
-      f.render_widget(search_text, table_list_chunks[0]);
+  /// Columns of `table` only, for completing after `table.` or `alias.`. Columns that haven't been fetched yet are
+  /// requested and the popup refreshes once they arrive.
+  fn table_column_suggestions(&mut self, table: &str) -> Vec<Suggestion> {
+    let Some(table) = self.tables.iter().find(|t| t.name.eq_ignore_ascii_case(table)).cloned() else {
+      return Vec::new();
+    };
+    match self.column_cache.get(&table.name) {
+      Some(columns) => columns.iter().map(|c| column_suggestion(&table.name, c)).collect(),
+      None => {
+        if self.completion_columns_pending.as_ref() != Some(&table.name) {
+          self.completion_columns_pending = Some(table.name.clone());
+          if let Some(tx) = &self.command_tx {
+            let _ = tx.send(Action::LoadTableColumns(table));
+          }
+        }
+        Vec::new()
+      },
     }
+  }
 
-    let table_render_chunk = if self.is_searching_tables { table_list_chunks[1] } else { table_list_chunks[0] };
+  /// Columns listed in the join builder's column step, by index in [`JoinBuilder::tables`]. Columns that haven't been
+  /// fetched yet are requested and listed once they arrive.
+  fn join_columns(&self) -> Vec<(usize, String)> {
+    let Some(builder) = &self.join_builder else {
+      return Vec::new();
+    };
+    builder
+      .tables()
+      .into_iter()
+      .enumerate()
+      .flat_map(|(i, table)| {
+        self.column_cache.get(&table.name).into_iter().flatten().map(move |column| (i, column.name.clone()))
+      })
+      .collect()
+  }
 
-    let mut table_list_state = ListState::default();
-    table_list_state.select(Some(self.selected_table_index));
-    let items: Vec<ListItem> = self.tables.iter().map(|t| ListItem::new(t.name.to_string())).collect();
+  fn handle_join_builder_key(&mut self, key: KeyEvent) -> Option<Action> {
+    let columns = self.join_columns();
+    let builder = self.join_builder.as_mut()?;
+    let count = match builder.step {
+      JoinStep::Relations => builder.foreign_keys.as_ref().map_or(0, Vec::len),
+      JoinStep::Columns => columns.len(),
+    };
+    match (builder.step, key.code) {
+      (_, KeyCode::Char('j') | KeyCode::Down) if count > 0 => builder.selected = (builder.selected + 1) % count,
+      (_, KeyCode::Char('k') | KeyCode::Up) if count > 0 => {
+        builder.selected = builder.selected.checked_sub(1).unwrap_or(count - 1)
+      },
+      (JoinStep::Relations, KeyCode::Char(' ')) if count > 0 => builder.toggle_join(builder.selected),
+      (JoinStep::Relations, KeyCode::Enter) => {
+        builder.step = JoinStep::Columns;
+        builder.selected = 0;
+        let missing: Vec<_> =
+          builder.tables().into_iter().filter(|table| !self.column_cache.contains_key(&table.name)).cloned().collect();
+        if let Some(tx) = &self.command_tx {
+          for table in missing {
+            let _ = tx.send(Action::LoadTableColumns(table));
+          }
+        }
+      },
+      (JoinStep::Columns, KeyCode::Char(' ')) => {
+        if let Some((table, column)) = columns.get(builder.selected) {
+          builder.toggle_column(*table, column);
+        }
+      },
+      (JoinStep::Columns, KeyCode::Backspace | KeyCode::Char('h')) => {
+        builder.step = JoinStep::Relations;
+        builder.selected = 0;
+      },
+      (JoinStep::Columns, KeyCode::Enter) => {
+        let query = builder.query();
+        self.join_builder = None;
+        self.set_query(&query);
+        self.selected_component = ComponentKind::Query;
+        return Some(Action::SelectComponent(ComponentKind::Query));
+      },
+      (_, KeyCode::Esc | KeyCode::Char('q')) => self.join_builder = None,
+      _ => {},
+    }
+    None
+  }
 
-    let list = List::new(items)
-      .block(tables)
-      .highlight_style(Style::default().bg(Color::Yellow).fg(Color::Black).add_modifier(Modifier::BOLD));
-    f.render_stateful_widget(list, table_render_chunk, &mut table_list_state);
+  /// Recomputes suggestions for the word at the cursor, closing the popup when nothing matches.
+  fn update_completion(&mut self) {
+    let (row, col) = self.query_input.cursor();
+    let line = self.query_input.lines().get(row).map(String::as_str).unwrap_or_default();
+    let (mut start_col, prefix) = autocomplete::word_before_cursor(line, col);
+    let is_tag = start_col > 0 && line.chars().nth(start_col - 1) == Some('#');
+    let suggestions = match autocomplete::qualifier_before(line, start_col) {
+      // `#tag` lists the library entries with a matching tag, replacing the tag with the chosen one.
+      _ if is_tag => {
+        start_col -= 1;
+        self.tagged_snippet_suggestions(&prefix)
+      },
+      Some(qualifier) => {
+        let sql = self.statement_text();
+        let table = autocomplete::resolve_qualifier(&sql, &qualifier);
+        match outline::ctes(&sql).into_iter().find(|cte| cte.name.eq_ignore_ascii_case(&table)) {
+          Some(cte) => {
+            autocomplete::complete(&prefix, cte.columns.iter().map(|column| cte_column_suggestion(&cte.name, column)))
+          },
+          None => autocomplete::complete(&prefix, self.table_column_suggestions(&table)),
+        }
+      },
+      None if prefix.is_empty() => Vec::new(),
+      None => autocomplete::complete(&prefix, self.completion_candidates()),
+    };
+    self.completion =
+      if suggestions.is_empty() { None } else { Some(Completion { start_col, suggestions, selected: 0 }) };
+  }
 
-    Ok(table_chunks)
+  fn accept_completion(&mut self) {
+    let Some(completion) = self.completion.take() else {
+      return;
+    };
+    let options = &self.config.completion;
+    let (row, col) = self.query_input.cursor();
+    if options.replace_word {
+      let line = self.query_input.lines().get(row).map(String::as_str).unwrap_or_default();
+      for _ in 0..autocomplete::word_after_cursor(line, col) {
+        self.query_input.delete_next_char();
+      }
+    }
+    for _ in completion.start_col..col {
+      self.query_input.delete_char();
+    }
+    let suggestion = &completion.suggestions[completion.selected];
+    self.query_input.insert_str(suggestion.insert_text());
+    if !matches!(suggestion.kind, SuggestionKind::Snippet | SuggestionKind::History) {
+      self.query_input.insert_str(options.trailing.text());
+    }
   }
 
-  fn render_query_input(&mut self, f: &mut Frame<'_>, chunks: Rc<[Rect]>) -> Result<Rc<[Rect]>> {
-    let query_chunks = Layout::default()
-      .direction(Direction::Vertical)
-      .constraints([Constraint::Percentage(20), Constraint::Percentage(80)].as_ref())
-      .split(chunks[1]);
+  fn handle_completion_key(&mut self, key: KeyEvent) -> bool {
+    let Some(completion) = &mut self.completion else {
+      return false;
+    };
+    let count = completion.suggestions.len();
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let options = &self.config.completion;
+    match key.code {
+      KeyCode::Tab if !options.tab_accepts => completion.selected = (completion.selected + 1) % count,
+      KeyCode::BackTab if !options.tab_accepts => {
+        completion.selected = completion.selected.checked_sub(1).unwrap_or(count - 1)
+      },
+      // Let the editor insert the newline.
+      KeyCode::Enter if !options.enter_accepts => {
+        self.completion = None;
+        return false;
+      },
+      KeyCode::Down => completion.selected = (completion.selected + 1) % count,
+      KeyCode::Char('n') if ctrl => completion.selected = (completion.selected + 1) % count,
+      KeyCode::Up => completion.selected = completion.selected.checked_sub(1).unwrap_or(count - 1),
+      KeyCode::Char('p') if ctrl => completion.selected = completion.selected.checked_sub(1).unwrap_or(count - 1),
+      KeyCode::Tab | KeyCode::Enter => self.accept_completion(),
+      KeyCode::Esc => self.completion = None,
+      _ => return false,
+    }
+    true
+  }
 
-    let query_border_color = if self.selected_component == ComponentKind::Query { Color::Cyan } else { Color::White };
-    let border_style = Style::default().fg(query_border_color);
-    let input_block = Block::default().borders(Borders::ALL).border_style(border_style).title("Query");
-    let style = ratatui::style::Style::default().bg(query_border_color).add_modifier(Modifier::REVERSED);
-    self.query_input.set_block(input_block);
+  /// Passes on `action`, or holds it until its SQL is confirmed when the confirm generated SQL setting is on.
+  fn confirm_generated(&mut self, sql: String, action: Action) -> Option<Action> {
+    if !self.config.settings.confirm_generated_sql {
+      return Some(action);
+    }
+    self.generated_sql = Some(GeneratedSql { sql, action, choice: 0 });
+    None
+  }
 
-    f.render_widget(self.query_input.widget(), query_chunks[0]);
+  /// Replaces the editor contents with `query`, keeping the previous text for undo.
+  fn set_query(&mut self, query: &str) {
+    let previous = self.query_input.lines().join("\n");
+    if !previous.trim().is_empty() && previous != query {
+      self.undo.push("replacing the editor text", UndoEntry::Editor(previous));
+    }
+    self.replace_editor_text(query);
+  }
 
-    Ok(query_chunks)
+  fn replace_editor_text(&mut self, text: &str) {
+    self.query_input.select_all();
+    self.query_input.cut();
+    self.query_input.insert_str(text);
   }
 
-  fn render_query_result_details(&mut self, f: &mut Frame<'_>, chunks: Rc<[Rect]>) -> Result<Rc<[Rect]>> {
-    let table_chunks = Layout::default()
-      .direction(Direction::Vertical)
-      .constraints([Constraint::Min(1), Constraint::Length(1)].as_ref())
-      .split(chunks[1]);
+  /// Swaps the editor text with the alternate query, or with the most recently executed query that differs from it.
+  fn swap_alternate_query(&mut self) {
+    let current = self.query_input.lines().join("\n");
+    let alternate = self.alternate_query.take().filter(|query| query.trim() != current.trim()).or_else(|| {
+      self.history.entries().iter().rev().map(|entry| entry.query.clone()).find(|query| query.trim() != current.trim())
+    });
+    let Some(alternate) = alternate else {
+      self.status_message = Some("No other executed query to switch to".to_string());
+      return;
+    };
+    self.replace_editor_text(&alternate);
+    self.alternate_query = Some(current);
+  }
 
-    if let Some(selected_row) = self.query_results.get(self.selected_row_index) {
-      let normal_style = Style::default();
-      let header_cells = ["Name", "value"]
-        .iter()
-        .map(|h| Cell::from(h.to_string()).style(Style::default().fg(Color::Red).bg(Color::Green)));
-      let header = ratatui::widgets::Row::new(header_cells).style(normal_style).height(1);
+  /// Reverts the most recent operation on the undo stack.
+  fn undo(&mut self) {
+    let Some((description, entry)) = self.undo.pop() else {
+      self.status_message = Some("Nothing to undo".to_string());
+      return;
+    };
+    match entry {
+      UndoEntry::Editor(text) => self.replace_editor_text(&text),
+      UndoEntry::Snapshot(index, snapshot) => self.snapshots.insert(index.min(self.snapshots.len()), snapshot),
+      UndoEntry::HistoryNote { query, executed_at, note } => {
+        let index = self.history.entries().iter().position(|e| e.query == query && e.executed_at == executed_at);
+        let Some(index) = index else {
+          self.status_message = Some("The history entry is no longer in the history".to_string());
+          return;
+        };
+        if let Err(e) = self.history.set_note(index, note) {
+          self.show_error(format!("Unable to restore the note: {e}"));
+          return;
+        }
+        if let Some(popup) = &mut self.history_popup {
+          popup.matches = self.history.search(popup.search.value());
+        }
+      },
+    }
+    self.status_message = Some(format!("Undid {description}"));
+  }
+
+  /// Runs the current statement's plan using the EXPLAIN syntax the connected server supports. The auto `LIMIT`
+  /// setting doesn't apply, the plan is the statement's as written. Analyzing a statement that writes runs when
+  /// `confirmed`, that is when the previous key asked for it too.
+  fn explain_current_statement(&mut self, analyze: bool, confirmed: bool) -> Option<Action> {
+    let statement = self.statement_text();
+    if analyze && !confirmed && !retry::is_read_only(&statement) {
+      self.analyze_announced = Some(Command::ExplainAnalyze);
+      self.status_message =
+        Some("EXPLAIN ANALYZE runs the statement and this one writes, press Alt-a again to run it".to_string());
+      return None;
+    }
+    let prefix = match &self.server {
+      Some(server) => server.explain_prefix(analyze),
+      None if analyze => Ok("EXPLAIN ANALYZE"),
+      None => Ok("EXPLAIN"),
+    };
+    match prefix {
+      Ok(prefix) => Some(Action::HandleQuery(format!("{prefix} {statement}"))),
+      Err(e) => {
+        self.status_message = Some(e);
+        None
+      },
+    }
+  }
+
+  /// Asks for the current statement's plan as JSON, written to a file once it arrives. The plan has run times when
+  /// `confirmed`, that is when the previous key asked for them too, as EXPLAIN ANALYZE runs the statement.
+  fn export_plan(&mut self, confirmed: bool) -> Option<Action> {
+    let statement = self.statement_text();
+    // Statements that write are only planned, even a SELECT may call a function with side effects so analyzing one
+    // is confirmed first.
+    let analyze = retry::is_read_only(&statement);
+    if analyze && !confirmed {
+      self.analyze_announced = Some(Command::ExportPlan);
+      self.status_message =
+        Some("The plan gets run times by running the statement, press Alt-p again to export it".to_string());
+      return None;
+    }
+    let prefix = match &self.server {
+      Some(server) => server.explain_json_prefix(analyze),
+      None => Err("Server not detected yet".to_string()),
+    };
+    match prefix {
+      Ok(prefix) => {
+        let explain = format!("{prefix} {statement}");
+        self.pending_plan_query = Some(statement);
+        Some(Action::ExportPlan(explain))
+      },
+      Err(e) => {
+        self.status_message = Some(e);
+        None
+      },
+    }
+  }
+
+  /// Writes a plan requested by [`Db::export_plan`] and copies a shareable JSON blob of it.
+  fn save_plan(&mut self, plan: String) {
+    let query = self.pending_plan_query.take().unwrap_or_default();
+    let path = self.config.export.next_path("plan.json");
+    if let Err(e) = export::write_plan_file(&path, &plan) {
+      self.show_error(format!("Unable to write plan to {}: {e}", path.display()));
+      return;
+    }
+    self.status_message = Some(format!("Plan written to {}", path.display()));
+    let title = statement::from_table(&query).unwrap_or_else(|| "query-crafter plan".to_string());
+    match export::plan_share_blob(&title, &plan, &query) {
+      Ok(blob) => self.copy_to_clipboard(blob),
+      Err(e) => log::warn!("Unable to build the plan share blob: {e}"),
+    }
+  }
+
+  /// Lists the `public` tables, plus every other schema and the system objects when those are shown, followed by the
+  /// tables of attached SQLite databases grouped by alias.
+  fn apply_table_visibility(&mut self) {
+    let show_system = self.config.settings.show_system_objects;
+    self.tables = self
+      .all_tables
+      .iter()
+      .filter(|t| !self.attached.iter().any(|database| database.alias == t.schema))
+      .chain(&self.attached_tables)
+      .filter(|t| show_system || ((t.is_default_schema() || self.is_attached(t)) && !t.is_system()))
+      .cloned()
+      .collect();
+    self.selected_table_index = self.selected_table_index.min(self.tables.len().saturating_sub(1));
+  }
+
+  fn is_attached(&self, table: &DbTable) -> bool {
+    self.attached.iter().any(|database| database.alias == table.schema)
+  }
+
+  /// Keeps selections and scroll offsets within bounds after the terminal size changes.
+  fn clamp_to_screen(&mut self, screen: Rect) {
+    self.screen = screen;
+    self.selected_row_index = self.selected_row_index.min(self.query_results.len().saturating_sub(1));
+    self.detail_row_index = self.detail_row_index.min(self.column_count().saturating_sub(1));
+    self.select_column(self.selected_column_index);
+    if let Some(preview) = &mut self.table_preview {
+      preview.column_offset = preview.column_offset.min(preview.max_column_offset(screen));
+    }
+  }
+
+  /// Tints the background of the statement that will be executed when the buffer holds more than one.
+  fn highlight_current_statement(&mut self, f: &mut Frame<'_>, area: Rect) {
+    let inner = area.inner(&Margin { vertical: 1, horizontal: 1 });
+    if inner.width == 0 || inner.height == 0 {
+      return;
+    }
+
+    // Mirror the textarea's own viewport tracking so highlighted cells line up with the rendered text.
+    let (row, col) = self.query_input.cursor();
+    let next_top = |top: u16, cursor: u16, length: u16| {
+      if cursor < top {
+        cursor
+      } else if top + length <= cursor {
+        cursor + 1 - length
+      } else {
+        top
+      }
+    };
+    let top_row = next_top(self.query_scroll_top.0, row as u16, inner.height);
+    let top_col = next_top(self.query_scroll_top.1, col as u16, inner.width);
+    self.query_scroll_top = (top_row, top_col);
+    self.editor_cursor = Some((inner.x + col as u16 - top_col, inner.y + row as u16 - top_row));
+
+    let lines = self.query_input.lines();
+    let statements = statement::split(lines);
+    if statements.len() < 2 {
+      return;
+    }
+    let Some(current) = statement::at_cursor(lines, (row, col)) else {
+      return;
+    };
+
+    let tint = Color::DarkGray;
+    let buf = f.buffer_mut();
+    for (line_row, line) in lines.iter().enumerate().take(current.end.0 + 1).skip(current.start.0) {
+      if line_row < top_row as usize || line_row >= (top_row + inner.height) as usize {
+        continue;
+      }
+      let from = if line_row == current.start.0 { current.start.1 } else { 0 };
+      let to = if line_row == current.end.0 { current.end.1 } else { line.chars().count() };
+      let y = inner.y + (line_row - top_row as usize) as u16;
+      for c in from.max(top_col as usize)..to.min((top_col + inner.width) as usize) {
+        let x = inner.x + (c - top_col as usize) as u16;
+        buf.get_mut(x, y).set_bg(tint);
+      }
+    }
+  }
+
+  /// The value under the cursor: the highlighted field in row details, otherwise the cell in the selected column.
+  fn selected_cell(&self) -> Option<&String> {
+    let row = self.query_results.get(self.selected_row_index)?;
+    let column = if self.row_is_selected { self.detail_row_index } else { self.selected_column_index };
+    self.ordered(row).get(column).copied()
+  }
+
+  fn selected_header(&self) -> Option<&String> {
+    let column = if self.row_is_selected { self.detail_row_index } else { self.selected_column_index };
+    self.ordered(&self.selected_headers).get(column).copied()
+  }
+
+  /// Where the selected cell is, e.g. `row 12, email`, numbering rows in the full result.
+  fn selected_cell_label(&self) -> String {
+    let row = self.filtered_rows.as_ref().and_then(|rows| rows.get(self.selected_row_index)).copied();
+    let header = self.selected_header().cloned().unwrap_or_default();
+    format!("row {}, {header}", row.unwrap_or(self.selected_row_index) + 1)
+  }
+
+  /// Compares the selected cell with the marked cell, or with the clipboard when no cell is marked.
+  fn diff_cell(&mut self) {
+    let Some(value) = self.selected_cell().cloned() else {
+      return;
+    };
+    let (left, other) = match self.marked_cell.clone() {
+      Some(marked) => marked,
+      None => {
+        match self.clipboard_text() {
+          Ok(text) => ("clipboard".to_string(), text),
+          Err(e) => {
+            self.status_message = Some(format!("Mark a cell with m first, the clipboard is unavailable: {e}"));
+            return;
+          },
+        }
+      },
+    };
+    let rows = diff::side_by_side(&other, &value);
+    if !rows.iter().any(DiffRow::is_changed) {
+      self.status_message = Some(format!("No differences between {left} and {}", self.selected_cell_label()));
+    }
+    self.cell_diff = Some(CellDiff { left, right: self.selected_cell_label(), rows, scroll: 0 });
+  }
+
+  /// Copies the selected field together with its column name, formatted by `format`.
+  fn copy_field(&mut self, format: fn(&str, Option<&str>) -> String) {
+    let column = if self.row_is_selected { self.detail_row_index } else { self.selected_column_index };
+    let (Some(header), Some(cells)) = (self.selected_header(), self.shown_cells(self.selected_row_index)) else {
+      return;
+    };
+    let Some(value) = self.ordered(&cells).get(column).copied() else {
+      return;
+    };
+    let text = format(header, value.map(String::as_str));
+    self.status_message = Some(format!("Copied {text}"));
+    self.copy_to_clipboard(text);
+  }
+
+  /// Copies every row of the (filtered) results as tab-separated text. Serializing and setting the clipboard run on
+  /// a blocking task that reports progress, so large results don't stall rendering. A large copy runs when
+  /// `confirmed`, that is when the previous key asked for it too.
+  fn copy_all_rows(&mut self, confirmed: bool) {
+    if self.copy_progress.is_some() {
+      self.status_message = Some("A copy is already running".to_string());
+      return;
+    }
+    if self.query_results.is_empty() {
+      self.status_message = Some("Nothing to copy".to_string());
+      return;
+    }
+    let estimate = perf::estimate_result_bytes(&self.selected_headers, &self.query_results);
+    if estimate >= COPY_WARNING_BYTES && !confirmed {
+      self.large_copy_confirmed = true;
+      self.status_message = Some(format!(
+        "{} rows, about {}, press Y again to copy them",
+        self.query_results.len(),
+        perf::format_bytes(estimate)
+      ));
+      return;
+    }
+    let Some(tx) = self.command_tx.clone() else {
+      return;
+    };
+    let (headers, rows, nulls, order) =
+      (self.selected_headers.clone(), Arc::clone(&self.query_results), self.shown_nulls(), self.column_order.clone());
+    let options =
+      export::CsvOptions { delimiter: export::Delimiter::Tab, bom: false, ..self.config.export.csv.clone() };
+    self.copy_progress = Some((0, rows.len()));
+    tokio::task::spawn_blocking(move || {
+      let action = match copy_rows(&tx, &headers, &rows, &nulls, &order, &options) {
+        Ok(bytes) => Action::RowsCopied(rows.len(), bytes),
+        Err(e) => Action::CopyFailed(format!("{e}")),
+      };
+      let _ = tx.send(action);
+    });
+  }
+
+  fn show_epoch(&mut self) {
+    let Some(value) = self.selected_cell() else {
+      return;
+    };
+    self.status_message = Some(match cell::epoch_to_datetime(value) {
+      Some((unit, dt)) => {
+        format!("{value} ({unit}) = {} / {}", dt.format("%Y-%m-%d %H:%M:%S%.3f UTC"), dt.with_timezone(&Local))
+      },
+      None => format!("{value} is not an epoch timestamp"),
+    });
+  }
+
+  /// Copies to the OS clipboard and the registers, which the editor pastes from with p.
+  fn copy_to_clipboard(&mut self, contents: String) {
+    self.registers.yank(contents.as_str());
+    self.query_input.set_yank_text(contents.as_str());
+    let copied = ClipboardProvider::new().and_then(|mut ctx: ClipboardContext| ctx.set_contents(contents));
+    if let Err(e) = copied {
+      log::warn!("Unable to copy to clipboard: {e}");
+      self.status_message = Some(format!("Clipboard unavailable ({e}), p in the editor pastes the copy"));
+    }
+  }
+
+  /// The OS clipboard's text, or the latest register when the clipboard can't be read.
+  fn clipboard_text(&self) -> Result<String, String> {
+    match ClipboardProvider::new().and_then(|mut ctx: ClipboardContext| ctx.get_contents()) {
+      Ok(text) => Ok(text),
+      Err(e) => self.registers.latest().map(String::from).ok_or_else(|| e.to_string()),
+    }
+  }
+
+  /// Records text yanked in the editor in the registers and pipes it to the OS clipboard.
+  fn editor_yanked(&mut self, text: String) {
+    self.registers.yank(text.as_str());
+    if let Err(e) = ClipboardProvider::new().and_then(|mut ctx: ClipboardContext| ctx.set_contents(text)) {
+      log::warn!("Unable to copy the yank to the clipboard: {e}");
+    }
+  }
 
-      let rows = selected_row
+  /// Applies the `columns` config rules to the headers and the types inferred from the values.
+  fn resolve_column_styles(&self) -> Vec<ColumnStyle> {
+    let mut styles = if self.config.columns.is_empty() {
+      vec![ColumnStyle::default(); self.selected_headers.len()]
+    } else {
+      let rows: Vec<Vec<Option<&String>>> =
+        self.all_results.iter().enumerate().map(|(i, r)| self.all_nulls.cells(i, r)).collect();
+      let types = export::infer_column_types(self.selected_headers.len(), &rows);
+      self
+        .selected_headers
         .iter()
-        .zip(self.selected_headers.iter())
-        .map(|(c, r)| {
-          let cells = [Cell::from(r.to_string()), Cell::from(c.to_string())];
-          ratatui::widgets::Row::new(cells).height(1).bottom_margin(1)
-        })
-        .collect::<Vec<_>>();
+        .zip(types)
+        .map(|(header, column_type)| ColumnStyle::resolve(&self.config.columns, header, column_type))
+        .collect()
+    };
+    for (style, header) in styles.iter_mut().zip(&self.selected_headers) {
+      if let Some(format) = self.format_overrides.get(header) {
+        style.format = *format;
+      }
+    }
+    styles
+  }
 
-      let status_text =
-        Paragraph::new(Text::styled(format!("Rows: {}", rows.len()), Style::default().fg(Color::Yellow)));
-      f.render_widget(status_text, table_chunks[1]);
+  /// Entries of the column format popup: raw values, then each formatter.
+  fn format_choices() -> Vec<Option<Formatter>> {
+    std::iter::once(None).chain(Formatter::iter().map(Some)).collect()
+  }
+
+  /// Displays the selected column with `format`, for this and later results with a column of the same name.
+  fn set_column_format(&mut self, format: Option<Formatter>) {
+    let Some(header) = self.selected_header().cloned() else {
+      return;
+    };
+    self.format_overrides.insert(header.clone(), format);
+    self.column_styles = self.resolve_column_styles();
+    self.status_message = Some(match format {
+      Some(format) => format!("Showing {header} as {}", format.label().to_lowercase()),
+      None => format!("Showing raw values of {header}"),
+    });
+  }
+
+  fn take_snapshot(&mut self, name: &str) {
+    let name = match name.trim() {
+      "" => format!("snapshot {}", self.snapshots.len() + 1),
+      name => name.to_string(),
+    };
+    self.snapshots.retain(|s| s.name != name);
+    self.snapshots.push(ResultSnapshot {
+      name: name.clone(),
+      taken_at: Local::now().format("%H:%M:%S").to_string(),
+      query: self.last_query.clone(),
+      headers: self.selected_headers.clone(),
+      rows: Arc::clone(&self.all_results),
+      nulls: self.all_nulls.clone(),
+      filter: self.results_filter.input.value().to_string(),
+      column_order: self.column_order.clone(),
+      selected_row: self.selected_row_index,
+      selected_column: self.selected_column_index,
+    });
+    self.status_message = Some(format!("Saved snapshot '{name}', press O to reopen it"));
+  }
+
+  /// Puts a snapshot back into the results grid, replacing the current results.
+  fn open_snapshot(&mut self, index: usize) {
+    let Some(snapshot) = self.snapshots.get(index).cloned() else {
+      return;
+    };
+    self.result_sets.clear();
+    self.notices.detach();
+    self.selected_headers = snapshot.headers;
+    self.all_results = snapshot.rows;
+    self.all_nulls = snapshot.nulls;
+    self.column_styles = self.resolve_column_styles();
+    self.column_order = snapshot.column_order;
+    self.results_query = snapshot.query.clone();
+    self.last_query = snapshot.query;
+    self.detect_plan();
+    self.results_bytes = perf::estimate_result_bytes(&self.selected_headers, &self.all_results);
+    self.results_dropped = false;
+    self.expanded_fields.clear();
+    self.results_filter.invalidate();
+    self.results_filter.input.set_value(snapshot.filter);
+    self.apply_results_filter();
+    self.selected_row_index = snapshot.selected_row.min(self.query_results.len().saturating_sub(1));
+    self.selected_column_index = snapshot.selected_column;
+    self.horizonal_scroll_offset = snapshot.selected_column / VISIBLE_COLUMNS;
+    self.status_message = Some(format!("Opened snapshot '{}'", snapshot.name));
+  }
+
+  /// Detects whether the results are an EXPLAIN plan and the buffer usage it reports.
+  fn detect_plan(&mut self) {
+    self.plan_format = plan::detect(&self.selected_headers, &self.all_results);
+    self.buffer_usage = self.plan_format.and_then(|format| plan::buffers(format, &self.all_results));
+  }
+
+  /// Shows another result set of the last run, keeping the filter text.
+  fn show_result_set(&mut self, index: usize) {
+    if index == self.result_set_index || index >= self.result_sets.len() {
+      return;
+    }
+    // Releases the shared rows first, so they move back into the result set without a copy.
+    self.query_results = Arc::default();
+    self.result_sets[self.result_set_index] = (
+      std::mem::take(&mut self.selected_headers),
+      Arc::unwrap_or_clone(std::mem::take(&mut self.all_results)),
+      std::mem::take(&mut self.all_nulls),
+    );
+    let (headers, rows, nulls) = std::mem::take(&mut self.result_sets[index]);
+    (self.selected_headers, self.all_results, self.all_nulls) = (headers, Arc::new(rows), nulls);
+    self.result_set_index = index;
+    self.column_styles = self.resolve_column_styles();
+    self.reset_column_order();
+    self.detect_plan();
+    self.results_bytes = perf::estimate_result_bytes(&self.selected_headers, &self.all_results);
+    self.results_dropped = false;
+    self.expanded_fields.clear();
+    self.results_filter.invalidate();
+    self.apply_results_filter();
+    self.selected_row_index = 0;
+    self.selected_column_index = 0;
+    self.horizonal_scroll_offset = 0;
+    self.status_message = Some(format!("Result set {} of {}", index + 1, self.result_sets.len()));
+  }
+
+  /// Shows the rows of the last result that pass the results filter. Large results are filtered, and their matching
+  /// rows collected, on a blocking task that reports back with [`Action::ResultsFiltered`], so typing doesn't stall
+  /// rendering.
+  fn apply_results_filter(&mut self) {
+    let job = self.results_filter.prepare(&self.all_results);
+    let generation = job.generation;
+    match self.command_tx.clone() {
+      Some(tx) if self.all_results.len() >= BACKGROUND_FILTER_ROWS && !job.is_trivial() => {
+        let rows = Arc::clone(&self.all_results);
+        tokio::task::spawn_blocking(move || {
+          let matches = job.run();
+          let shown = matching_rows(&rows, &matches);
+          let _ = tx.send(Action::ResultsFiltered(generation, matches, shown));
+        });
+      },
+      _ => {
+        let matches = job.run();
+        let shown = matching_rows(&self.all_results, &matches);
+        self.show_filtered(generation, matches, shown);
+      },
+    }
+  }
+
+  /// Appends a column holding what `path` matches in each row's value of result column `source`, placed after it.
+  fn add_json_path_column(&mut self, source: usize, path: &str) {
+    let path_expr = match JsonPath::parse(path) {
+      Ok(path_expr) => path_expr,
+      Err(e) => {
+        self.status_message = Some(e);
+        return;
+      },
+    };
+    let Some(source_name) = self.selected_headers.get(source).cloned() else {
+      return;
+    };
+    let index = self.selected_headers.len();
+    let header = format!("{source_name} {}", path.trim());
+    self.json_path_columns.insert(header.clone());
+    self.selected_headers.push(header);
+    // Releases the shared rows first, so they are extended in place unless a snapshot holds them.
+    self.query_results = Arc::default();
+    for row in Arc::make_mut(&mut self.all_results) {
+      let value = row.get(source).map(|cell| path_expr.extract(cell)).unwrap_or_default();
+      row.push(value);
+    }
+    self.query_results = match &self.filtered_rows {
+      Some(rows) => matching_rows(&self.all_results, rows),
+      None => Arc::clone(&self.all_results),
+    };
+    // The indexed rows lack the new column, the filter searches it once they are indexed again.
+    self.results_filter.invalidate();
+    self.apply_results_filter();
+    self.column_styles = self.resolve_column_styles();
+    self.results_bytes = perf::estimate_result_bytes(&self.selected_headers, &self.all_results);
+    let position = self.column_order.iter().position(|i| *i == source).map_or(self.column_order.len(), |p| p + 1);
+    self.column_order.insert(position, index);
+    self.select_column(position);
+    let matched = self.all_results.iter().filter(|row| !row[index].is_empty()).count();
+    self.status_message = Some(format!("{path} matched in {matched} of {} rows", self.all_results.len()));
+  }
+
+  fn show_filtered(&mut self, generation: u64, matches: Vec<usize>, shown: Arc<Vec<Vec<String>>>) {
+    if !self.results_filter.finish(generation, &matches) {
+      return;
+    }
+    self.query_results = shown;
+    self.filtered_rows = (matches.len() != self.all_results.len()).then_some(matches);
+    self.selected_row_index = self.selected_row_index.min(self.query_results.len().saturating_sub(1));
+  }
+
+  fn handle_results_filter_key(&mut self, key: KeyEvent) {
+    match key.code {
+      KeyCode::Enter => self.results_filter.remember(self.last_query.as_deref()),
+      KeyCode::Esc => {
+        self.results_filter.input.clear();
+        self.results_filter.is_editing = false;
+      },
+      KeyCode::Up => self.results_filter.recall(true),
+      KeyCode::Down => self.results_filter.recall(false),
+      _ => {
+        self.results_filter.input.handle_key(key);
+      },
+    }
+    self.apply_results_filter();
+  }
+
+  fn insert_uuid(&mut self) {
+    let uuid = Uuid::new_v4().to_string();
+    self.query_input.insert_str(&uuid);
+    self.status_message = Some(format!("Inserted {uuid}"));
+  }
+
+  /// The count prefix extended by `key` when it is a digit typed in the tables or results panel. A leading `0` is not
+  /// a count.
+  fn count_digit(&self, key: KeyEvent) -> Option<usize> {
+    let counts = match self.selected_component {
+      ComponentKind::Home => !self.is_searching_tables,
+      ComponentKind::Results => !self.results_filter.is_editing,
+      _ => false,
+    };
+    let KeyCode::Char(c) = key.code else {
+      return None;
+    };
+    let digit = c.to_digit(10)? as usize;
+    if !counts || !key.modifiers.is_empty() || (digit == 0 && self.pending_count.is_none()) {
+      return None;
+    }
+    Some((self.pending_count.unwrap_or_default() * 10 + digit).min(MAX_COUNT))
+  }
+
+  /// Replaces the editor contents with their formatted text, leaving comments and dollar-quoted bodies intact.
+  fn format_query(&mut self) {
+    let text = self.query_input.lines().join("\n");
+    let formatted = format::format_all(&text, &self.config.format);
+    if formatted != text.trim_end() {
+      self.set_query(&formatted);
+    }
+    self.status_message = Some("Formatted the query".to_string());
+  }
+
+  /// Opens the outline popup at the entry containing the cursor.
+  fn open_outline(&mut self) {
+    let lines = self.query_input.lines();
+    let mut starts = Vec::with_capacity(lines.len());
+    let mut offset = 0;
+    for line in lines {
+      starts.push(offset);
+      offset += line.chars().count() + 1;
+    }
+    let position = |offset: usize| {
+      let row = starts.partition_point(|start| *start <= offset).saturating_sub(1);
+      (row, offset - starts.get(row).copied().unwrap_or_default())
+    };
+    self.outline =
+      outline::outline(&lines.join("\n")).into_iter().map(|entry| (entry.clone(), position(entry.offset))).collect();
+    if self.outline.is_empty() {
+      self.status_message = Some("Nothing to outline".to_string());
+      return;
+    }
+    let cursor = self.query_input.cursor();
+    self.outline_index = Some(self.outline.iter().rposition(|(_, start)| *start <= cursor).unwrap_or_default());
+  }
+
+  fn handle_schema_popup_key(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+    let Some(popup) = &mut self.schema_popup else {
+      return Ok(None);
+    };
+
+    if popup.filter.is_editing {
+      if popup.filter.handle_key(key) {
+        popup.selected = 0;
+      }
+      return Ok(None);
+    }
+
+    let count = popup.filtered_columns().len();
+    match key.code {
+      KeyCode::Char('/') => popup.filter.is_editing = true,
+      // Every listed column matches the filter, so n/N step through them like j/k.
+      KeyCode::Char('j') | KeyCode::Char('n') | KeyCode::Down if count > 0 => {
+        popup.selected = (popup.selected + 1) % count
+      },
+      KeyCode::Char('k') | KeyCode::Char('N') | KeyCode::Up if count > 0 => {
+        popup.selected = popup.selected.checked_sub(1).unwrap_or(count - 1)
+      },
+      KeyCode::Char('y') => {
+        let names = popup.filtered_columns().iter().map(|c| c.name.to_string()).collect::<Vec<_>>().join(", ");
+        let table = popup.table.qualified_name();
+        if self.query_input.is_empty() {
+          self.query_input.insert_str(format!("SELECT {names} FROM {table}"));
+        } else {
+          self.query_input.insert_str(names);
+        }
+        self.schema_popup = None;
+        return Ok(Some(Action::FocusQuery));
+      },
+      KeyCode::Esc | KeyCode::Char('q') => self.schema_popup = None,
+      _ => {},
+    }
+
+    Ok(None)
+  }
+
+  /// Sets the connection recorded with history entries.
+  pub fn set_connection(&mut self, connection: impl Into<String>) {
+    self.history.set_connection(connection);
+  }
+
+  /// Sets the Sqlite file saved jobs connect to, made absolute so jobs run from any directory.
+  pub fn set_database_file(&mut self, file: Option<String>) {
+    self.database_file = file.map(|file| std::fs::canonicalize(&file).map_or(file, |path| path.display().to_string()));
+  }
+
+  fn complete_history(&mut self, row_count: Option<usize>, duration: Duration, error: Option<String>) {
+    if let Err(e) = self.history.complete_last(row_count, duration, error) {
+      log::warn!("Failed to save the query outcome to the history: {e}");
+    }
+  }
+
+  fn open_history(&mut self) {
+    let matches = self.history.search("");
+    self.history_popup = Some(HistoryPopup { matches, ..HistoryPopup::default() });
+  }
+
+  /// Tags used in the history and the query library, sorted.
+  fn known_tags(&self) -> Vec<String> {
+    let mut tags = self.history.tags();
+    tags.extend(self.library.as_ref().map(Library::tags).unwrap_or_default());
+    tags.sort();
+    tags.dedup();
+    tags
+  }
+
+  fn handle_history_popup_key(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+    let known_tags = if key.code == KeyCode::Tab { self.known_tags() } else { Vec::new() };
+    let Some(popup) = &mut self.history_popup else {
+      return Ok(None);
+    };
+
+    if let Some(note) = &mut popup.note {
+      match key.code {
+        KeyCode::Enter => {
+          let note = note.value().to_string();
+          popup.note = None;
+          if let Some(index) = popup.matches.get(popup.selected).copied() {
+            let entry = &self.history.entries()[index];
+            let previous = UndoEntry::HistoryNote {
+              query: entry.query.clone(),
+              executed_at: entry.executed_at,
+              note: entry.note.clone(),
+            };
+            if entry.note.as_deref().unwrap_or_default() != note.trim() {
+              self.undo.push("changing a history note", previous);
+            }
+            if let Err(e) = self.history.set_note(index, Some(note)) {
+              self.show_error(format!("Unable to save the note: {e}"));
+            }
+          }
+        },
+        KeyCode::Esc => popup.note = None,
+        KeyCode::Tab => {
+          if let Some(completed) = tags::complete(note.value(), &known_tags) {
+            note.set_value(completed);
+          }
+        },
+        _ => {
+          note.handle_key(key);
+        },
+      }
+      return Ok(None);
+    }
+
+    if popup.is_searching {
+      match key.code {
+        KeyCode::Enter => popup.is_searching = false,
+        KeyCode::Esc => {
+          popup.search.clear();
+          popup.is_searching = false;
+        },
+        KeyCode::Tab => {
+          if let Some(completed) = tags::complete(popup.search.value(), &known_tags) {
+            popup.search.set_value(completed);
+          }
+        },
+        _ => {
+          popup.search.handle_key(key);
+        },
+      }
+      popup.matches = self.history.search(popup.search.value());
+      popup.selected = 0;
+      return Ok(None);
+    }
+
+    let count = popup.matches.len();
+    match key.code {
+      KeyCode::Char('/') => popup.is_searching = true,
+      KeyCode::Char('j') | KeyCode::Down if count > 0 => popup.selected = (popup.selected + 1) % count,
+      KeyCode::Char('k') | KeyCode::Up if count > 0 => {
+        popup.selected = popup.selected.checked_sub(1).unwrap_or(count - 1)
+      },
+      KeyCode::Char('n') if count > 0 => {
+        let mut note = TextInput::default();
+        note.set_value(self.history.entries()[popup.matches[popup.selected]].note.clone().unwrap_or_default());
+        popup.note = Some(note);
+      },
+      KeyCode::Enter if count > 0 => {
+        let query = self.history.entries()[popup.matches[popup.selected]].query.clone();
+        self.history_popup = None;
+        self.set_query(&query);
+        return Ok(Some(Action::FocusQuery));
+      },
+      KeyCode::Char('J') if count > 0 => self.export_history(false),
+      KeyCode::Char('S') if count > 0 => self.export_history(true),
+      KeyCode::Esc | KeyCode::Char('q') => self.history_popup = None,
+      _ => {},
+    }
+
+    Ok(None)
+  }
+
+  /// Writes the history entries matching the popup's search, oldest first, as JSON or as a SQL script.
+  fn export_history(&mut self, as_sql: bool) {
+    let Some(popup) = &self.history_popup else {
+      return;
+    };
+    let path = self.config.export.next_path(if as_sql { "history.sql" } else { "history.json" });
+    match self.history.export(&popup.matches, as_sql, &path) {
+      Ok(count) => self.status_message = Some(format!("Exported {count} history entries to {}", path.display())),
+      Err(e) => self.show_error(format!("History export failed: {e}")),
+    }
+  }
+
+  fn export_targets(&self) -> Vec<(String, ExportTarget)> {
+    ExportFormat::iter()
+      .map(|format| (format.to_string(), ExportTarget::Format(format)))
+      .chain(
+        self
+          .config
+          .export
+          .profiles
+          .iter()
+          .map(|(name, profile)| (format!("{name} ({})", profile.format), ExportTarget::Profile(name.to_string()))),
+      )
+      .collect()
+  }
+
+  fn export_to_target(&mut self, target: ExportTarget) {
+    if self.query_results.is_empty() {
+      self.status_message = Some("Nothing to export".to_string());
+      return;
+    }
+
+    match target {
+      ExportTarget::Format(format) => {
+        let path = self.config.export.next_path(format.extension());
+        self.export_results(format, &path);
+      },
+      ExportTarget::Profile(name) => {
+        let Some(profile) = self.config.export.profiles.get(&name).cloned() else {
+          return;
+        };
+        let table = self.config.export.table_name(self.last_query.as_deref());
+        let headers = self.ordered(&self.selected_headers);
+        let rows = self.export_rows();
+        match self.config.export.write_profile(&name, &profile, &table, &headers, &rows) {
+          Ok(path) => {
+            self.status_message = Some(format!("Exported {} rows to {}", rows.len(), path.display()));
+            if let Some(command) = profile.post_command {
+              self.run_post_command(command, path);
+            }
+          },
+          Err(e) => self.show_error(format!("Export failed: {e}")),
+        }
+      },
+    }
+  }
+
+  /// Runs a profile's post-export command on a blocking task, reporting a failure in the status line.
+  fn run_post_command(&self, command: String, file: PathBuf) {
+    let Some(tx) = self.command_tx.clone() else {
+      return;
+    };
+    tokio::task::spawn_blocking(move || {
+      match export::wait_for_post_command(&command, &file) {
+        Ok(()) => log::info!("Post-export command succeeded: {command}"),
+        Err(e) => {
+          let _ = tx.send(Action::PostCommandFailed(format!("{e}")));
+        },
+      }
+    });
+  }
+
+  /// Writes the results, in display order, to `path` in the given format using the configured options.
+  fn export_results(&mut self, format: ExportFormat, path: &Path) {
+    let table = self.config.export.table_name(self.last_query.as_deref());
+    let headers = self.ordered(&self.selected_headers);
+    let rows = self.export_rows();
+    match self.config.export.write(format, path, &table, &headers, &rows) {
+      Ok(()) => self.status_message = Some(format!("Exported {} rows to {}", rows.len(), path.display())),
+      Err(e) => self.show_error(format!("Export failed: {e}")),
+    }
+  }
+
+  /// Rows the results show, in display order, with their NULL cells as `None`.
+  fn export_rows(&self) -> Vec<Vec<Option<&String>>> {
+    let nulls = self.shown_nulls();
+    self
+      .query_results
+      .iter()
+      .enumerate()
+      .map(|(i, r)| self.ordered(&nulls.cells(i, r)).into_iter().copied().collect())
+      .collect()
+  }
+
+  /// The cells of the shown row at `index`, with its NULL ones as `None`.
+  fn shown_cells(&self, index: usize) -> Option<Vec<Option<&String>>> {
+    let row = self.query_results.get(index)?;
+    let position = self.filtered_rows.as_ref().and_then(|rows| rows.get(index)).copied().unwrap_or(index);
+    Some(self.all_nulls.cells(position, row))
+  }
+
+  /// NULL cells of the rows the results show, by their position among them.
+  fn shown_nulls(&self) -> Nulls {
+    match &self.filtered_rows {
+      Some(rows) => self.all_nulls.select(rows),
+      None => self.all_nulls.clone(),
+    }
+  }
+
+  /// Results panel title with the row count and estimated memory of the result set.
+  fn results_title(&self) -> String {
+    let title = if self.results_dropped {
+      "Results (dropped, r to re-run)".to_string()
+    } else if self.all_results.is_empty() {
+      "Results".to_string()
+    } else {
+      format!("Results ({} rows, ~{})", self.all_results.len(), perf::format_bytes(self.results_bytes))
+    };
+    let title = match self.result_sets.len() {
+      0 | 1 => title,
+      count => format!("{title} [set {} of {count}]", self.result_set_index + 1),
+    };
+    let count = self.column_count();
+    if count > VISIBLE_COLUMNS {
+      let first = self.horizonal_scroll_offset * VISIBLE_COLUMNS + 1;
+      let last = (first + VISIBLE_COLUMNS - 1).min(count);
+      format!("{title} columns {first}–{last} of {count}, | to jump")
+    } else {
+      title
+    }
+  }
+
+  /// Frees the result rows while keeping the headers and query, so a large result doesn't stay in memory.
+  fn drop_results(&mut self) {
+    let (rows, bytes) = (self.all_results.len(), self.results_bytes);
+    self.all_results = Arc::default();
+    self.all_nulls = Nulls::default();
+    self.query_results = Arc::default();
+    self.filtered_rows = None;
+    self.results_bytes = 0;
+    self.results_dropped = true;
+    self.selected_row_index = 0;
+    self.row_is_selected = false;
+    self.status_message = Some(format!("Dropped {rows} rows (~{}), press r to re-run", perf::format_bytes(bytes)));
+  }
+
+  /// Inserts the clipboard contents at the cursor as a quoted `IN (...)` list.
+  fn paste_in_list(&mut self) {
+    match self.clipboard_text() {
+      Ok(text) => {
+        if let Some(list) = snippets::in_list(&text) {
+          self.query_input.insert_str(list);
+        }
+      },
+      Err(e) => {
+        log::warn!("Unable to read clipboard: {e}");
+        self.status_message = Some(format!("Clipboard unavailable: {e}"));
+      },
+    }
+  }
+
+  fn table_row_count(&self) -> usize {
+    self.tables.len()
+  }
+
+  fn render_table_list(&mut self, f: &mut Frame<'_>, chunks: Rc<[Rect]>) -> Result<Rc<[Rect]>> {
+    let table_chunks = Layout::default()
+      .direction(Direction::Horizontal)
+      .constraints([Constraint::Percentage(20), Constraint::Percentage(80)].as_ref())
+      .split(chunks[1]);
+
+    let tables_border_color = self.config.settings.theme.border_color(self.selected_component == ComponentKind::Home);
+    let title = match self.attached.len() {
+      0 => "Tables".to_string(),
+      count => format!("Tables (+{count} attached, X to detach)"),
+    };
+    let tables =
+      capabilities::block().borders(Borders::ALL).style(Style::default().fg(tables_border_color)).title(title);
+
+    let table_list_chunks = if self.is_searching_tables {
+      Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(1)].as_ref())
+        .split(table_chunks[0])
+    } else {
+      table_chunks.clone()
+    };
+
+    if self.is_searching_tables {
+      let search_block = capabilities::block().borders(Borders::ALL).title("Search");
+      let style = Style::default().fg(Color::Yellow);
+      self.table_search.render(f, table_list_chunks[0], search_block, style, true);
+    }
+
+    let table_render_chunk = if self.is_searching_tables { table_list_chunks[1] } else { table_list_chunks[0] };
+
+    let mut table_list_state = ListState::default();
+    table_list_state.select(Some(self.selected_table_index));
+    let items: Vec<ListItem> = self
+      .tables
+      .iter()
+      .map(|t| {
+        match self.table_row_counts.get(&t.qualified_name()) {
+          Some(count) => {
+            ListItem::new(Line::from(vec![Span::raw(t.display_name()), Span::raw(" "), format!("({count})").dim()]))
+          },
+          None => ListItem::new(t.display_name()),
+        }
+      })
+      .collect();
+
+    let list = List::new(items).block(tables).highlight_style(self.config.settings.theme.highlight_style());
+    f.render_stateful_widget(list, table_render_chunk, &mut table_list_state);
+
+    Ok(table_chunks)
+  }
+
+  fn render_query_input(&mut self, f: &mut Frame<'_>, chunks: Rc<[Rect]>) -> Result<Rc<[Rect]>> {
+    let query_chunks = Layout::default()
+      .direction(Direction::Vertical)
+      .constraints([Constraint::Percentage(20), Constraint::Percentage(80)].as_ref())
+      .split(chunks[1]);
+
+    let query_border_color = self.config.settings.theme.border_color(self.selected_component == ComponentKind::Query);
+    let border_style = Style::default().fg(query_border_color);
+    let input_block = capabilities::block().borders(Borders::ALL).border_style(border_style).title("Query");
+    let style = ratatui::style::Style::default().bg(query_border_color).add_modifier(Modifier::REVERSED);
+    self.query_input.set_block(input_block);
+
+    f.render_widget(self.query_input.widget(), query_chunks[0]);
+    self.highlight_current_statement(f, query_chunks[0]);
+
+    Ok(query_chunks)
+  }
+
+  fn render_query_result_details(&mut self, f: &mut Frame<'_>, chunks: Rc<[Rect]>) -> Result<Rc<[Rect]>> {
+    let table_chunks = Layout::default()
+      .direction(Direction::Vertical)
+      .constraints([Constraint::Min(1), Constraint::Length(1)].as_ref())
+      .split(chunks[1]);
+
+    if let Some(selected_row) = self.shown_cells(self.selected_row_index) {
+      let normal_style = Style::default();
+      let header_cells = ["Name", "value"]
+        .iter()
+        .map(|h| Cell::from(h.to_string()).style(Style::default().fg(Color::Red).bg(Color::Green)));
+      let header = ratatui::widgets::Row::new(header_cells).style(normal_style).height(1);
+
+      // Borders, highlight symbol and column spacing.
+      let value_width = table_chunks[0].width.saturating_sub(DETAIL_NAME_WIDTH + 14) as usize;
+      let placeholder = Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC);
+      let rows = self
+        .ordered(&selected_row)
+        .into_iter()
+        .zip(self.ordered(&self.selected_headers))
+        .enumerate()
+        .map(|(i, (c, r))| {
+          let is_null = c.is_none();
+          let c = c.map_or("", |c| c.as_str());
+          let length = c.chars().count();
+          let mut name = vec![Span::raw(r.to_string())];
+          if length > DETAIL_LENGTH_THRESHOLD {
+            name.push(Span::styled(format!(" ({length} chars)"), Style::default().fg(Color::DarkGray)));
+          }
+          let value = if is_null {
+            Text::styled("NULL", placeholder)
+          } else if c.is_empty() {
+            Text::styled("(empty)", placeholder)
+          } else if self.expanded_fields.contains(&i) {
+            Text::from(cell::wrap_lines(c, value_width).into_iter().map(Line::from).collect::<Vec<_>>())
+          } else {
+            let first_line: String = c.chars().skip(self.detail_value_offset).collect();
+            let mut line = vec![Span::raw(first_line.lines().next().unwrap_or_default().to_string())];
+            if c.contains('\n') {
+              line.push(Span::styled(" … (o to expand)", Style::default().fg(Color::DarkGray)));
+            }
+            Text::from(Line::from(line))
+          };
+          let height = value.height() as u16;
+          ratatui::widgets::Row::new([Cell::from(Line::from(name)), Cell::from(value)]).height(height).bottom_margin(1)
+        })
+        .collect::<Vec<_>>();
+
+      let status_text =
+        Paragraph::new(Text::styled(format!("Rows: {}", rows.len()), Style::default().fg(Color::Yellow)));
+      f.render_widget(status_text, table_chunks[1]);
+
+      let results_border_color =
+        self.config.settings.theme.border_color(self.selected_component == ComponentKind::Results);
+      let mut table_state = TableState::default();
+      table_state.select(Some(self.detail_row_index));
+      let title = match self.detail_value_offset {
+        0 => "Results (h/l to scroll values)".to_string(),
+        offset => format!("Results (values from character {})", offset + 1),
+      };
+      let result_table = Table::default()
+        .rows(rows)
+        .header(header)
+        .column_spacing(10)
+        .block(capabilities::block().borders(Borders::ALL).title(title).fg(results_border_color))
+        .highlight_symbol(">>")
+        .highlight_style(self.config.settings.theme.highlight_style())
+        .widths(&[Constraint::Length(DETAIL_NAME_WIDTH), Constraint::Min(40)]);
+
+      f.render_stateful_widget(result_table, table_chunks[0], &mut table_state);
+    }
+
+    Ok(chunks)
+  }
+
+  fn render_query_results(&mut self, f: &mut Frame<'_>, chunks: Rc<[Rect]>) -> Result<Rc<[Rect]>> {
+    if self.row_is_selected {
+      self.render_query_result_details(f, chunks)
+    } else {
+      self.render_query_results_table(f, chunks)
+    }
+  }
+
+  fn render_query_results_table(&mut self, f: &mut Frame<'_>, chunks: Rc<[Rect]>) -> Result<Rc<[Rect]>> {
+    let show_filter = self.results_filter.is_editing || self.results_filter.is_active();
+    let filter_height = if show_filter { 3 } else { 0 };
+    let notices = self.notices.current();
+    let notices_height = if notices.is_empty() { 0 } else { notices.len().min(MAX_NOTICE_LINES) as u16 + 2 };
+    let table_chunks = Layout::default()
+      .direction(Direction::Vertical)
+      .constraints(
+        [
+          Constraint::Length(filter_height),
+          Constraint::Min(1),
+          Constraint::Length(notices_height),
+          Constraint::Length(1),
+        ]
+        .as_ref(),
+      )
+      .split(chunks[1]);
+
+    if show_filter {
+      let filter_block =
+        capabilities::block().borders(Borders::ALL).title("Filter (up/down recent, enter to keep, esc to clear)");
+      let style = if self.results_filter.is_editing { Style::default().fg(Color::Yellow) } else { Style::default() };
+      self.results_filter.input.render(f, table_chunks[0], filter_block, style, self.results_filter.is_editing);
+    }
+
+    let skip_count = self.horizonal_scroll_offset * VISIBLE_COLUMNS;
+    let normal_style = Style::default();
+    let header_cells =
+      self.ordered(&self.selected_headers).into_iter().enumerate().skip(skip_count).take(VISIBLE_COLUMNS).map(
+        |(i, h)| {
+          let style = if i == self.selected_column_index {
+            self.config.settings.theme.highlight_style()
+          } else {
+            Style::default().fg(Color::Red).bg(Color::Green)
+          };
+          Cell::from(h.to_string()).style(style)
+        },
+      );
+    let settings = &self.config.settings;
+    let number_width = if settings.row_numbers { self.all_results.len().max(1).to_string().len() as u16 } else { 0 };
+    let number_cell =
+      |text: String| Cell::from(Line::from(text).alignment(Alignment::Right)).style(Style::default().dim());
+    let header_cells = settings.row_numbers.then(|| number_cell("#".to_string())).into_iter().chain(header_cells);
+    let header = ratatui::widgets::Row::new(header_cells).style(normal_style).height(1);
+
+    let rows = self
+      .query_results
+      .iter()
+      .enumerate()
+      .map(|(i, r)| {
+        let cells = self
+          .ordered(r)
+          .into_iter()
+          .zip(self.ordered(&self.column_styles))
+          .skip(skip_count)
+          .take(VISIBLE_COLUMNS)
+          .map(|(c, style)| {
+            let value = match style.format {
+              None if settings.locale_format => self.locale.display(c),
+              _ => style.display(c),
+            };
+            Cell::from(Line::from(value.into_owned()).alignment(style.align.into()))
+          });
+        let number = self.filtered_rows.as_ref().and_then(|rows| rows.get(i)).copied().unwrap_or(i) + 1;
+        let cells = settings.row_numbers.then(|| number_cell(number.to_string())).into_iter().chain(cells);
+        let style = if settings.striped_rows && i % 2 == 1 { settings.theme.stripe_style() } else { Style::default() };
+        ratatui::widgets::Row::new(cells).height(1).bottom_margin(settings.density.row_margin()).style(style)
+      })
+      .collect::<Vec<_>>();
+
+    let mut status = format!("Rows: {}", rows.len());
+    if self.results_filter.is_active() {
+      status.push_str(&format!(" of {}", self.all_results.len()));
+    }
+    if let Some(timing) = perf::snapshot().query {
+      status.push_str(&format!(" | {}", timing.breakdown()));
+    }
+    if let Some(count) = self.count_prefix {
+      status.push_str(&format!(" | {count}"));
+    }
+    if self.queued_queries > 0 {
+      status.push_str(&format!(" | {} queued", self.queued_queries));
+    }
+    if let Some(usage) = &self.buffer_usage {
+      status.push_str(&format!(" | {}", usage.summary()));
+    }
+    let warnings = self.notices.current().iter().filter(|notice| notice.severity == Severity::Warning).count();
+    if warnings > 0 {
+      status.push_str(&format!(" | {warnings} warning(s)"));
+    }
+    if let Some(message) = &self.status_message {
+      status.push_str(&format!(" | {message}"));
+    }
+    let status_text = Paragraph::new(Text::styled(status, Style::default().fg(Color::Yellow)));
+    f.render_widget(status_text, table_chunks[3]);
+
+    if !notices.is_empty() {
+      let lines: Vec<Line> =
+        notices.iter().skip(notices.len().saturating_sub(MAX_NOTICE_LINES)).map(notice_line).collect();
+      let title = format!("Notices ({}, ! to list all)", notices.len());
+      let notices = Paragraph::new(lines).block(capabilities::block().borders(Borders::ALL).title(title));
+      f.render_widget(notices, table_chunks[2]);
+    }
+
+    let results_border_color =
+      self.config.settings.theme.border_color(self.selected_component == ComponentKind::Results);
+    // Keep the selection inside the body from where it was last drawn, below the fixed header.
+    let row_height = 1 + settings.density.row_margin() as usize;
+    self.results_page_rows = (table_chunks[1].height.saturating_sub(3) as usize / row_height).max(1);
+    if self.selected_row_index < self.results_offset {
+      self.results_offset = self.selected_row_index;
+    } else if self.selected_row_index >= self.results_offset + self.results_page_rows {
+      self.results_offset = self.selected_row_index + 1 - self.results_page_rows;
+    }
+    let mut table_state =
+      TableState::default().with_offset(self.results_offset).with_selected(Some(self.selected_row_index));
+    let result_table = Table::default()
+      .rows(rows)
+      .header(header)
+      .column_spacing(10)
+      .block(capabilities::block().borders(Borders::ALL).title(self.results_title()).fg(results_border_color))
+      .highlight_style(self.config.settings.theme.highlight_style())
+      .widths(
+        (number_width > 0)
+          .then_some(Constraint::Length(number_width))
+          .into_iter()
+          .chain([Constraint::Length(40); VISIBLE_COLUMNS]),
+      );
+
+    f.render_stateful_widget(result_table, table_chunks[1], &mut table_state);
+    self.results_offset = table_state.offset();
+
+    if self.show_row_details {
+      if let Some(json_str) = self.json() {
+        let popup = Popup::new("Row Details", json_str);
+        f.render_widget(popup.to_widget(), f.size());
+      }
+    }
+
+    Ok(chunks)
+  }
+
+  fn render_library_save(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    if let Some(name) = &self.library_save_name {
+      let area = centered_rect(40, 10, f.size());
+      let area = Rect { height: 3.min(area.height), ..area };
+      let block = capabilities::block().borders(Borders::ALL).title("Save to Library as");
+      f.render_widget(Clear, area);
+      name.render(f, area, block, Style::default(), true);
+    }
+
+    Ok(())
+  }
+
+  fn render_job_save(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    if let Some((profile, name)) = &self.job_save {
+      let area = centered_rect(40, 10, f.size());
+      let area = Rect { height: 3.min(area.height), ..area };
+      let block = capabilities::block().borders(Borders::ALL).title(format!("Save Job Exporting with {profile} as"));
+      f.render_widget(Clear, area);
+      name.render(f, area, block, Style::default(), true);
+    }
+
+    Ok(())
+  }
+
+  fn render_attach_input(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    if let Some(input) = &self.attach_input {
+      let area = centered_rect(50, 10, f.size());
+      let area = Rect { height: 3.min(area.height), ..area };
+      let block = capabilities::block().borders(Borders::ALL).title("Attach Database (path AS alias)");
+      f.render_widget(Clear, area);
+      input.render(f, area, block, Style::default(), true);
+    }
+
+    Ok(())
+  }
+
+  fn render_preamble_prompt(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    if let Some((name, value)) = &self.preamble_prompt {
+      let area = centered_rect(40, 10, f.size());
+      let area = Rect { height: 3.min(area.height), ..area };
+      let block =
+        capabilities::block().borders(Borders::ALL).title(format!("Value for {{{name}}} in the connection preamble"));
+      f.render_widget(Clear, area);
+      value.render(f, area, block, Style::default(), true);
+    }
+
+    Ok(())
+  }
+
+  fn render_json_path_prompt(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    if let Some((source, path)) = &self.json_path_prompt {
+      let area = centered_rect(50, 10, f.size());
+      let area = Rect { height: 3.min(area.height), ..area };
+      let name = self.selected_headers.get(*source).map(String::as_str).unwrap_or_default();
+      let block = capabilities::block().borders(Borders::ALL).title(format!("JSON path into {name}, e.g. $.user.id"));
+      f.render_widget(Clear, area);
+      path.render(f, area, block, Style::default(), true);
+    }
+
+    Ok(())
+  }
+
+  fn render_join_builder(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    let Some(builder) = &self.join_builder else {
+      return Ok(());
+    };
+    let checkbox = |checked: bool| if checked { "[x]" } else { "[ ]" };
+    let (title, items): (String, Vec<ListItem>) = match builder.step {
+      JoinStep::Relations => {
+        let title = format!("Join {} (space to choose, enter for columns, esc to close)", builder.base.display_name());
+        let items = match &builder.foreign_keys {
+          None => vec![ListItem::new("Loading foreign keys...")],
+          Some(keys) if keys.is_empty() => vec![ListItem::new("No foreign keys from or to this table")],
+          Some(keys) => {
+            keys
+              .iter()
+              .enumerate()
+              .map(|(i, key)| {
+                ListItem::new(format!("{} {}", checkbox(builder.joined.contains(&i)), key.describe(&builder.base)))
+              })
+              .collect()
+          },
+        };
+        (title, items)
+      },
+      JoinStep::Columns => {
+        let tables = builder.tables();
+        let items = self
+          .join_columns()
+          .into_iter()
+          .map(|(table, column)| {
+            let checked = builder.is_column_selected(table, &column);
+            ListItem::new(format!("{} {}.{column}", checkbox(checked), tables[table].display_name()))
+          })
+          .collect();
+        ("Columns (space to choose, enter to insert the query, h to go back)".to_string(), items)
+      },
+    };
+    let area = centered_rect(60, 60, f.size());
+    let list = List::new(items)
+      .block(capabilities::block().borders(Borders::ALL).title(title))
+      .highlight_style(self.config.settings.theme.highlight_style());
+    let mut state = ListState::default();
+    state.select(Some(builder.selected));
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut state);
+
+    Ok(())
+  }
+
+  fn render_snapshots(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    if let Some(name) = &self.snapshot_name {
+      let area = centered_rect(40, 10, f.size());
+      let area = Rect { height: 3.min(area.height), ..area };
+      let block = capabilities::block().borders(Borders::ALL).title("Snapshot name");
+      f.render_widget(Clear, area);
+      name.render(f, area, block, Style::default(), true);
+    }
+    if let Some(selected) = self.snapshot_index {
+      let area = centered_rect(60, 40, f.size());
+      let items: Vec<ListItem> = self
+        .snapshots
+        .iter()
+        .map(|snapshot| {
+          let mut details = format!("  {} rows at {}", snapshot.rows.len(), snapshot.taken_at);
+          if !snapshot.filter.is_empty() {
+            details.push_str(&format!(", filtered by '{}'", snapshot.filter));
+          }
+          ListItem::new(vec![Line::from(snapshot.name.clone()), Line::styled(details, Style::default().dim())])
+        })
+        .collect();
+      let list = List::new(items)
+        .block(
+          capabilities::block().borders(Borders::ALL).title("Snapshots (enter to open, d to delete, esc to close)"),
+        )
+        .highlight_style(self.config.settings.theme.highlight_style());
+      let mut state = ListState::default();
+      state.select(Some(selected));
+      f.render_widget(Clear, area);
+      f.render_stateful_widget(list, area, &mut state);
+    }
+
+    Ok(())
+  }
+
+  /// Rereads the library when its files changed outside the app, at most once per poll interval.
+  fn poll_library(&mut self) {
+    let Some(library) = &mut self.library else {
+      return;
+    };
+    if self.library_checked.is_some_and(|checked| checked.elapsed() < self.config.library.poll_interval()) {
+      return;
+    }
+    self.library_checked = Some(Instant::now());
+    match library.reload_if_changed() {
+      Ok(true) => self.status_message = Some(format!("Query library reloaded ({} entries)", library.entries().len())),
+      Ok(false) => {},
+      Err(e) => log::warn!("Unable to check query library {}: {e}", library.directory.display()),
+    }
+  }
+
+  fn save_job(&mut self, name: &str, profile: String) {
+    let Some(query) = self.last_query.clone() else {
+      self.status_message = Some("Run a query before saving it as a job".to_string());
+      return;
+    };
+    let job = Job { connection: self.database_file.clone(), query, profile };
+    match Jobs::load().and_then(|mut jobs| jobs.save(name, job).map(Path::to_path_buf)) {
+      Ok(path) => {
+        self.status_message =
+          Some(format!("Saved job to {}, run it with `query-crafter run-job {}`", path.display(), name.trim()))
+      },
+      Err(e) => self.show_error(format!("Unable to save the job: {e}")),
+    }
+  }
+
+  fn save_to_library(&mut self, name: &str) {
+    let statement = self.statement_text();
+    let Some(library) = &mut self.library else {
+      return;
+    };
+    match library.save(name, &statement) {
+      Ok(path) => self.status_message = Some(format!("Saved to {}", path.display())),
+      Err(e) => self.show_error(format!("Unable to save to the library: {e}")),
+    }
+  }
+
+  fn render_time_snippets(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    if let Some(selected) = self.time_snippet_index {
+      let area = centered_rect(40, 40, f.size());
+      let items: Vec<ListItem> = snippets::TIME_EXPRESSIONS.iter().map(|e| ListItem::new(e.to_string())).collect();
+      let list = List::new(items)
+        .block(capabilities::block().borders(Borders::ALL).title("Insert Time Expression"))
+        .highlight_style(self.config.settings.theme.highlight_style());
+      let mut state = ListState::default();
+      state.select(Some(selected));
+      f.render_widget(Clear, area);
+      f.render_stateful_widget(list, area, &mut state);
+    }
+
+    Ok(())
+  }
+
+  fn render_registers(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    if let Some(selected) = self.register_index {
+      let area = centered_rect(60, 40, f.size());
+      let items: Vec<ListItem> = self
+        .registers
+        .iter()
+        .enumerate()
+        .map(|(i, text)| {
+          let first = text.lines().next().unwrap_or_default();
+          let more = if text.lines().nth(1).is_some() {
+            format!(" … ({} lines)", text.lines().count())
+          } else {
+            String::new()
+          };
+          ListItem::new(Line::from(vec![
+            Span::styled(format!("{i} "), Style::default().dim()),
+            Span::raw(first.to_string()),
+            Span::styled(more, Style::default().dim()),
+          ]))
+        })
+        .collect();
+      let list = List::new(items)
+        .block(capabilities::block().borders(Borders::ALL).title("Insert from Registers (0-9 or j/k, enter)"))
+        .highlight_style(self.config.settings.theme.highlight_style());
+      let mut state = ListState::default();
+      state.select(Some(selected));
+      f.render_widget(Clear, area);
+      f.render_stateful_widget(list, area, &mut state);
+    }
+
+    Ok(())
+  }
+
+  /// Scope of the leader chords, `None` where the leader key is typed as text or has no chords.
+  fn chord_scope(&self) -> Option<Scope> {
+    match self.selected_component {
+      ComponentKind::Home if !self.is_searching_tables => Some(Scope::Tables),
+      ComponentKind::Results if !self.results_filter.is_editing => Some(Scope::Results),
+      _ => None,
+    }
+  }
+
+  /// Handles the leader key and the keys of a chord after it, returning `None` for keys that aren't part of one.
+  fn handle_chord_key(&mut self, key: KeyEvent) -> Option<Result<Option<Action>>> {
+    let scope = self.chord_scope()?;
+    let Some(typed) = self.chord.take() else {
+      if self.config.leader.is_leader(&key) {
+        self.chord = Some(String::new());
+        return Some(Ok(None));
+      }
+      return None;
+    };
+    let KeyCode::Char(c) = key.code else {
+      // Esc or any other key cancels the chord.
+      return Some(Ok(None));
+    };
+    let typed = format!("{typed}{c}");
+    match keymap::match_chord(&self.config.leader.chords(scope), &typed) {
+      ChordMatch::Command(command) => return keymap::key_for(scope, command).map(|key| self.handle_key_events(key)),
+      ChordMatch::Pending(_) => self.chord = Some(typed),
+      ChordMatch::None => self.status_message = Some(format!("Nothing bound to leader {typed}")),
+    }
+    Some(Ok(None))
+  }
+
+  /// Lists the keys that can follow the ones typed after the leader.
+  fn render_chord_hints(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    let (Some(typed), Some(scope)) = (&self.chord, self.chord_scope()) else {
+      return Ok(());
+    };
+    let ChordMatch::Pending(hints) = keymap::match_chord(&self.config.leader.chords(scope), typed) else {
+      return Ok(());
+    };
+    let screen = f.size();
+    let width =
+      hints.iter().map(|(_, hint)| hint.chars().count() as u16 + 6).max().unwrap_or_default().min(screen.width);
+    let height = (hints.len() as u16 + 2).min(screen.height);
+    let area = Rect::new(screen.right() - width, screen.bottom() - height, width, height);
+    let lines: Vec<Line> = hints
+      .into_iter()
+      .map(|(key, hint)| {
+        Line::from(vec![
+          Span::styled(format!(" {key}  "), self.config.settings.theme.highlight_style()),
+          Span::raw(hint),
+        ])
+      })
+      .collect();
+    let title = format!("Leader {typed}");
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(lines).block(capabilities::block().borders(Borders::ALL).title(title)), area);
+
+    Ok(())
+  }
+
+  fn render_outline(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    if let Some(selected) = self.outline_index {
+      let area = centered_rect(60, 60, f.size());
+      let items: Vec<ListItem> = self
+        .outline
+        .iter()
+        .map(|(entry, (row, _))| {
+          ListItem::new(Line::from(vec![
+            Span::styled(format!("{:>4}  ", row + 1), Style::default().dim()),
+            Span::raw(format!("{}{}", "  ".repeat(entry.depth), entry.label)),
+          ]))
+        })
+        .collect();
+      let list = List::new(items)
+        .block(capabilities::block().borders(Borders::ALL).title("Outline (enter to jump, esc to close)"))
+        .highlight_style(self.config.settings.theme.highlight_style());
+      let mut state = ListState::default();
+      state.select(Some(selected));
+      f.render_widget(Clear, area);
+      f.render_stateful_widget(list, area, &mut state);
+    }
+
+    Ok(())
+  }
+
+  fn render_index_hints(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    if let Some(selected) = self.index_hint_index {
+      let area = centered_rect(70, 50, f.size());
+      let items: Vec<ListItem> = self
+        .index_hints
+        .iter()
+        .map(|hint| {
+          ListItem::new(vec![
+            Line::from(hint.create_statement()),
+            Line::styled(format!("  {}", hint.reason), Style::default().add_modifier(Modifier::DIM)),
+          ])
+        })
+        .collect();
+      let list = List::new(items)
+        .block(capabilities::block().borders(Borders::ALL).title("Index Hints (Enter copies to the editor)"))
+        .highlight_style(self.config.settings.theme.highlight_style());
+      let mut state = ListState::default();
+      state.select(Some(selected));
+      f.render_widget(Clear, area);
+      f.render_stateful_widget(list, area, &mut state);
+    }
+
+    Ok(())
+  }
+
+  fn render_generated_sql(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    if let Some(generated) = &self.generated_sql {
+      let area = centered_rect(60, 30, f.size());
+      let choices: Vec<Span> = GENERATED_SQL_CHOICES
+        .iter()
+        .enumerate()
+        .flat_map(|(i, choice)| {
+          let style =
+            if i == generated.choice { self.config.settings.theme.highlight_style() } else { Style::default() };
+          [Span::styled(format!(" {choice} "), style), Span::raw("  ")]
+        })
+        .collect();
+      let mut lines: Vec<Line> = generated.sql.lines().map(Line::from).collect();
+      lines.push(Line::default());
+      lines.push(Line::from(choices));
+      let paragraph = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(capabilities::block().borders(Borders::ALL).title("Generated SQL (x execute, e edit, esc cancel)"));
+      f.render_widget(Clear, area);
+      f.render_widget(paragraph, area);
+    }
+
+    Ok(())
+  }
+
+  fn render_export_menu(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    if let Some(selected) = self.export_menu_index {
+      let area = centered_rect(30, 30, f.size());
+      let items: Vec<ListItem> = self.export_targets().into_iter().map(|(label, _)| ListItem::new(label)).collect();
+      let list = List::new(items)
+        .block(capabilities::block().borders(Borders::ALL).title("Export Results (s: save profile as job)"))
+        .highlight_style(self.config.settings.theme.highlight_style());
+      let mut state = ListState::default();
+      state.select(Some(selected));
+      f.render_widget(Clear, area);
+      f.render_stateful_widget(list, area, &mut state);
+    }
+
+    Ok(())
+  }
+
+  fn render_busy_query(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    let Some((query, selected)) = &self.busy_query else {
+      return Ok(());
+    };
+    let area = centered_rect(50, 20, f.size());
+    let area = Rect { height: 6.min(area.height), ..area };
+    let items: Vec<ListItem> = BusyQueryChoice::iter().map(|choice| ListItem::new(choice.label())).collect();
+    let first_line = query.lines().find(|line| !line.trim().is_empty()).unwrap_or_default().trim();
+    let list = List::new(items)
+      .block(capabilities::block().borders(Borders::ALL).title(format!("A query is still running, run {first_line}?")))
+      .highlight_style(self.config.settings.theme.highlight_style());
+    let mut state = ListState::default();
+    state.select(Some(*selected));
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut state);
+
+    Ok(())
+  }
+
+  fn render_column_picker(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    let Some((input, selected)) = &self.column_picker else {
+      return Ok(());
+    };
+    let area = centered_rect(40, 50, f.size());
+    let chunks = Layout::default()
+      .direction(Direction::Vertical)
+      .constraints([Constraint::Length(3), Constraint::Min(1)])
+      .split(area);
+    let headers = self.ordered(&self.selected_headers);
+    let matches = self.column_picker_matches();
+    let items: Vec<ListItem> = matches
+      .iter()
+      .map(|column| {
+        ListItem::new(Line::from(vec![
+          Span::raw(headers[*column].to_string()),
+          Span::styled(format!("  column {}", column + 1), Style::default().dim()),
+        ]))
+      })
+      .collect();
+    let list = List::new(items)
+      .block(capabilities::block().borders(Borders::ALL).title(format!(
+        "{} of {} columns",
+        matches.len(),
+        headers.len()
+      )))
+      .highlight_style(self.config.settings.theme.highlight_style());
+    let mut state = ListState::default();
+    state.select((!matches.is_empty()).then(|| (*selected).min(matches.len() - 1)));
+    f.render_widget(Clear, area);
+    input.render(
+      f,
+      chunks[0],
+      capabilities::block().borders(Borders::ALL).title("Jump to Column"),
+      Style::default(),
+      true,
+    );
+    f.render_stateful_widget(list, chunks[1], &mut state);
+
+    Ok(())
+  }
+
+  fn render_command_palette(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    let Some((input, selected)) = &self.command_palette else {
+      return Ok(());
+    };
+    let area = centered_rect(50, 50, f.size());
+    let chunks = Layout::default()
+      .direction(Direction::Vertical)
+      .constraints([Constraint::Length(3), Constraint::Min(1)])
+      .split(area);
+    let matches = self.command_palette_matches();
+    let items: Vec<ListItem> = matches
+      .iter()
+      .map(|index| {
+        let command = &self.config.commands[*index];
+        let key = command.key.as_deref().map(|key| format!("  {key}")).unwrap_or_default();
+        ListItem::new(Line::from(vec![Span::raw(command.name.clone()), Span::styled(key, Style::default().dim())]))
+      })
+      .collect();
+    let title = format!("{} of {} commands", matches.len(), self.config.commands.len());
+    let list = List::new(items)
+      .block(capabilities::block().borders(Borders::ALL).title(title))
+      .highlight_style(self.config.settings.theme.highlight_style());
+    let mut state = ListState::default();
+    state.select((!matches.is_empty()).then(|| (*selected).min(matches.len() - 1)));
+    f.render_widget(Clear, area);
+    input.render(
+      f,
+      chunks[0],
+      capabilities::block().borders(Borders::ALL).title("Run Command"),
+      Style::default(),
+      true,
+    );
+    f.render_stateful_widget(list, chunks[1], &mut state);
+
+    Ok(())
+  }
+
+  fn render_pending_command(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    if let Some((pending, value)) = &self.pending_command {
+      let area = centered_rect(40, 10, f.size());
+      let area = Rect { height: 3.min(area.height), ..area };
+      let name = pending.missing().unwrap_or_default();
+      let block =
+        capabilities::block().borders(Borders::ALL).title(format!("Value for {{{name}}} of {}", pending.command.name));
+      f.render_widget(Clear, area);
+      value.render(f, area, block, Style::default(), true);
+    }
+
+    Ok(())
+  }
+
+  fn render_format_menu(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    if let Some(selected) = self.format_menu_index {
+      let area = centered_rect(30, 30, f.size());
+      let items: Vec<ListItem> = Self::format_choices()
+        .into_iter()
+        .map(|choice| ListItem::new(choice.map_or("Raw value", |format| format.label())))
+        .collect();
+      let title = format!("Display {}", self.selected_header().map(String::as_str).unwrap_or_default());
+      let list = List::new(items)
+        .block(capabilities::block().borders(Borders::ALL).title(title))
+        .highlight_style(self.config.settings.theme.highlight_style());
+      let mut state = ListState::default();
+      state.select(Some(selected));
+      f.render_widget(Clear, area);
+      f.render_stateful_widget(list, area, &mut state);
+    }
+
+    Ok(())
+  }
+
+  /// Draws the completion list next to the cursor, below it when there is room and above it otherwise.
+  fn render_completion(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    let (Some(completion), Some((cursor_x, cursor_y))) = (&self.completion, self.editor_cursor) else {
+      return Ok(());
+    };
+    let screen = f.size();
+    let text_width = completion.suggestions.iter().map(|s| s.text.chars().count()).max().unwrap_or_default();
+    let width = ((text_width + 14) as u16).min(screen.width);
+    let height = (completion.suggestions.len() as u16 + 2).min(screen.height);
+    let (_, col) = self.query_input.cursor();
+    let word_x = cursor_x.saturating_sub((col - completion.start_col) as u16 + 2);
+    let x = word_x.min(screen.right().saturating_sub(width));
+    let y = if cursor_y + 1 + height <= screen.bottom() { cursor_y + 1 } else { cursor_y.saturating_sub(height) };
+    let area = Rect::new(x, y, width, height);
+
+    let items: Vec<ListItem> = completion
+      .suggestions
+      .iter()
+      .map(|s| {
+        let icon_style = match s.kind {
+          SuggestionKind::Snippet => Style::default().fg(Color::Magenta),
+          _ => Style::default().fg(Color::DarkGray),
+        };
+        ListItem::new(Line::from(vec![
+          Span::styled(format!("{} ", s.kind.icon()), icon_style),
+          Span::raw(format!("{:<text_width$} ", s.text)),
+          Span::styled(s.kind.to_string().to_lowercase(), Style::default().fg(Color::DarkGray)),
+        ]))
+      })
+      .collect();
+    let list = List::new(items)
+      .block(capabilities::block().borders(Borders::ALL))
+      .highlight_style(self.config.settings.theme.highlight_style());
+    let mut state = ListState::default();
+    state.select(Some(completion.selected));
+    f.render_widget(Clear, area);
+    f.render_stateful_widget(list, area, &mut state);
+
+    // Documentation for the selected suggestion goes beside the list, on whichever side has room.
+    let suggestion = &completion.suggestions[completion.selected];
+    if !suggestion.documentation.is_empty() {
+      let doc_width = 40.min(screen.width);
+      let doc_x =
+        if area.right() + doc_width <= screen.right() { area.right() } else { area.x.saturating_sub(doc_width) };
+      let doc_height = (suggestion.documentation.len() as u16 + 2).max(area.height.min(6)).min(screen.height);
+      let doc_area = Rect::new(doc_x, area.y, doc_width, doc_height);
+      let lines: Vec<Line> = suggestion.documentation.iter().map(|l| Line::from(l.as_str())).collect();
+      let panel = Paragraph::new(lines)
+        .wrap(Wrap { trim: true })
+        .block(capabilities::block().borders(Borders::ALL).title(suggestion.text.as_str()));
+      f.render_widget(Clear, doc_area);
+      f.render_widget(panel, doc_area);
+    }
+    Ok(())
+  }
+
+  fn render_help(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    if let Some(selected) = self.help_index {
+      let area = centered_rect(70, 80, f.size());
+      let match_style = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+      let search = &self.help_search;
+      let rows = keymap::BINDINGS.iter().map(|binding| {
+        ratatui::widgets::Row::new(vec![
+          Cell::from(search.highlight(&binding.scope.to_string(), Style::default().fg(Color::Cyan), match_style)),
+          Cell::from(search.highlight(
+            &binding.key_label(),
+            Style::default().add_modifier(Modifier::BOLD),
+            match_style,
+          )),
+          Cell::from(search.highlight(binding.description, Style::default(), match_style)),
+        ])
+      });
+      let table = Table::new(rows, [Constraint::Length(8), Constraint::Length(8), Constraint::Min(10)])
+        .header(ratatui::widgets::Row::new(vec!["Scope", "Key", "Action"]).style(Style::default().fg(Color::Yellow)))
+        .block(
+          capabilities::block()
+            .borders(Borders::ALL)
+            .title("Help")
+            .title_bottom(search.hint("j/k: scroll  esc: close")),
+        )
+        .highlight_style(self.config.settings.theme.highlight_style());
+      let mut state = TableState::default();
+      state.select(Some(selected));
+      f.render_widget(Clear, area);
+      f.render_stateful_widget(table, area, &mut state);
+    }
+
+    Ok(())
+  }
+
+  fn render_settings(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    if let Some(selected) = self.settings_index {
+      let area = centered_rect(60, 40, f.size());
+      let settings = &self.config.settings;
+      let rows = SettingKind::iter().map(|kind| {
+        ratatui::widgets::Row::new(vec![
+          Cell::from(kind.category()).style(Style::default().fg(Color::Cyan)),
+          Cell::from(kind.label()),
+          Cell::from(settings.value(kind)),
+        ])
+      });
+      let table = Table::new(rows, [Constraint::Length(12), Constraint::Length(24), Constraint::Min(10)])
+        .block(
+          capabilities::block()
+            .borders(Borders::ALL)
+            .title("Settings")
+            .title_bottom("j/k: select  h/l/enter: change  esc: close"),
+        )
+        .highlight_style(settings.theme.highlight_style());
+      let mut state = TableState::default();
+      state.select(Some(selected));
+      f.render_widget(Clear, area);
+      f.render_stateful_widget(table, area, &mut state);
+    }
+
+    Ok(())
+  }
+
+  fn render_cell_digests(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    if let Some(digests) = &self.cell_digests {
+      let text = format!(
+        "value:  {}\nmd5:    {}\nsha256: {}\n\nm: copy md5  s: copy sha256  esc: close",
+        digests.value, digests.md5, digests.sha256
+      );
+      let popup = Popup::new("Hashes", text);
+      f.render_widget(popup.to_widget(), f.size());
+    }
+
+    Ok(())
+  }
+
+  fn render_plan_view(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    let Some(view) = &self.plan_view else {
+      return Ok(());
+    };
+    let area = centered_rect(90, 80, f.size());
+    let lines: Vec<Line> = view
+      .lines
+      .iter()
+      .skip(view.scroll)
+      .map(|line| {
+        let indent = "  ".repeat(line.depth);
+        if line.is_node {
+          let mut spans =
+            vec![Span::raw(format!("{indent}→ ")), Span::styled(line.text.clone(), Style::default().bold())];
+          if let Some(detail) = &line.detail {
+            spans.push(Span::styled(format!("  {detail}"), Style::default().dim()));
+          }
+          Line::from(spans)
+        } else {
+          Line::styled(format!("{indent}  {}", line.text), Style::default().dim().italic())
+        }
+      })
+      .collect();
+    let title = format!("{} (j/k to scroll, esc to close)", view.format.label());
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(lines).block(capabilities::block().borders(Borders::ALL).title(title)), area);
+
+    Ok(())
+  }
+
+  fn render_notices(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    let Some(scroll) = self.notices_scroll else {
+      return Ok(());
+    };
+    let area = centered_rect(80, 60, f.size());
+    let mut lines = Vec::new();
+    for statement in self.notices.statements() {
+      let query = statement.query.lines().next().unwrap_or_default();
+      lines.push(Line::from(vec![
+        Span::styled(format!("{} ", statement.executed_at), Style::default().dim()),
+        Span::styled(query.to_string(), Style::default().bold()),
+      ]));
+      lines.extend(statement.notices.iter().map(|notice| {
+        let mut line = notice_line(notice);
+        line.spans.insert(0, Span::raw("  "));
+        line
+      }));
+    }
+    if self.notices.is_empty() {
+      lines.push(Line::styled("No notices", Style::default().dim()));
+    }
+    let block = capabilities::block().borders(Borders::ALL).title("Notices (j/k to scroll, esc to close)");
+    f.render_widget(Clear, area);
+    f.render_widget(Paragraph::new(lines).block(block).scroll((scroll as u16, 0)), area);
+
+    Ok(())
+  }
+
+  fn render_cell_diff(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    let Some(cell_diff) = &self.cell_diff else {
+      return Ok(());
+    };
+    let area = centered_rect(90, 80, f.size());
+    let block = capabilities::block()
+      .borders(Borders::ALL)
+      .title(format!("Diff {} ↔ {} (n next change, esc to close)", cell_diff.left, cell_diff.right));
+    let inner = block.inner(area);
+    f.render_widget(Clear, area);
+    f.render_widget(block, area);
+    let halves = Layout::default()
+      .direction(Direction::Horizontal)
+      .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+      .split(inner);
+    let side = |segments: &Option<Vec<diff::Segment>>, changed: Color| {
+      match segments {
+        Some(segments) => {
+          Line::from(
+            segments
+              .iter()
+              .map(|s| {
+                let style = if s.changed { Style::default().fg(Color::Black).bg(changed) } else { Style::default() };
+                Span::styled(s.text.clone(), style)
+              })
+              .collect::<Vec<_>>(),
+          )
+        },
+        None => Line::styled("~", Style::default().dim()),
+      }
+    };
+    let rows = cell_diff.rows.iter().skip(cell_diff.scroll);
+    let left: Vec<Line> = rows.clone().map(|row| side(&row.left, Color::Red)).collect();
+    let right: Vec<Line> = rows.map(|row| side(&row.right, Color::Green)).collect();
+    f.render_widget(Paragraph::new(left).block(capabilities::block().borders(Borders::RIGHT)), halves[0]);
+    f.render_widget(Paragraph::new(right).block(Block::default().padding(Padding::horizontal(1))), halves[1]);
+
+    Ok(())
+  }
+
+  fn render_table_preview(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    if let Some(preview) = &self.table_preview {
+      let area = TablePreview::area(f.size());
+      let header_cells = preview
+        .headers
+        .iter()
+        .skip(preview.column_offset)
+        .map(|h| Cell::from(h.to_string()).style(Style::default().fg(Color::Red).bg(Color::Green)));
+      let header = ratatui::widgets::Row::new(header_cells).height(1);
+      let rows = preview
+        .rows
+        .iter()
+        .map(|r| ratatui::widgets::Row::new(r.iter().skip(preview.column_offset).map(|c| Cell::from(c.to_string()))));
+      let widths = preview
+        .headers
+        .iter()
+        .skip(preview.column_offset)
+        .map(|_| Constraint::Length(PREVIEW_COLUMN_WIDTH))
+        .collect::<Vec<_>>();
+      let title =
+        format!("Preview: {} (first {} rows, h/l to scroll, esc to close)", preview.table, preview.rows.len());
+      let table = Table::new(rows, widths)
+        .header(header)
+        .column_spacing(PREVIEW_COLUMN_SPACING)
+        .block(capabilities::block().borders(Borders::ALL).title(title));
+
+      f.render_widget(Clear, area);
+      f.render_widget(table, area);
+    }
+
+    Ok(())
+  }
+
+  fn render_schema_popup(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    if let Some(popup) = &self.schema_popup {
+      let area = centered_rect(60, 70, f.size());
+      f.render_widget(Clear, area);
+
+      let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(1)].as_ref())
+        .split(area);
+
+      let is_editing = popup.filter.is_editing;
+      let filter_style = if is_editing { Style::default().fg(Color::Yellow) } else { Style::default() };
+      let filter_block = capabilities::block()
+        .borders(Borders::ALL)
+        .title("Filter (/ to search, n/N next/previous, y to copy into SELECT, esc to close)");
+      popup.filter.input.render(f, chunks[0], filter_block, filter_style, is_editing);
+      let match_style = Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD);
+
+      let columns = popup.filtered_columns();
+      let title = format!("Columns: {} ({} of {})", popup.table.name, columns.len(), popup.columns.len());
+      let items: Vec<ListItem> = columns
+        .iter()
+        .map(|c| {
+          let nullable = if c.is_nullable { "NULL" } else { "NOT NULL" };
+          let mut spans = popup.filter.highlight(&format!("{:<32}", c.name), Style::default(), match_style).spans;
+          spans.push(Span::styled(format!("{:<24}", c.data_type), Style::default().fg(Color::Cyan)));
+          spans.push(nullable.dim());
+          ListItem::new(Line::from(spans))
+        })
+        .collect();
+      let list = List::new(items)
+        .block(capabilities::block().borders(Borders::ALL).title(title))
+        .highlight_style(self.config.settings.theme.highlight_style());
+      let mut state = ListState::default();
+      state.select(Some(popup.selected));
+      f.render_stateful_widget(list, chunks[1], &mut state);
+    }
+
+    Ok(())
+  }
+
+  fn render_history_popup(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    if let Some(popup) = &self.history_popup {
+      let area = centered_rect(80, 70, f.size());
+      f.render_widget(Clear, area);
+
+      let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(1), Constraint::Length(3)].as_ref())
+        .split(area);
+
+      let search_style = if popup.is_searching { Style::default().fg(Color::Yellow) } else { Style::default() };
+      let search_title = if popup.is_searching {
+        "Search (#tag filters by tag, tab completes a tag, enter to keep, esc to clear)"
+      } else {
+        "Search (/ to search, enter to load, n to edit the note, J/S to export as JSON/SQL, esc to close)"
+      };
+      let search_block = capabilities::block().borders(Borders::ALL).title(search_title);
+      popup.search.render(f, chunks[0], search_block, search_style, popup.is_searching);
+
+      let entries = self.history.entries();
+      let items: Vec<ListItem> = popup
+        .matches
+        .iter()
+        .map(|i| {
+          let entry = &entries[*i];
+          let time = Local.timestamp_opt(entry.executed_at, 0).single().map(|t| t.format("%Y-%m-%d %H:%M").to_string());
+          let query = entry.query.split_whitespace().collect::<Vec<_>>().join(" ");
+          let mut lines = vec![Line::from(vec![format!("{} ", time.unwrap_or_default()).dim(), Span::raw(query)])];
+          if let Some(outcome) = entry.outcome() {
+            let color = if entry.error.is_some() { Color::Red } else { Color::DarkGray };
+            lines.push(Line::styled(format!("  {outcome}"), Style::default().fg(color)));
+          }
+          if let Some(note) = &entry.note {
+            lines.push(Line::styled(
+              format!("  {note}"),
+              Style::default().fg(Color::Yellow).add_modifier(Modifier::ITALIC),
+            ));
+          }
+          ListItem::new(lines)
+        })
+        .collect();
+      let title = format!("History ({} of {})", popup.matches.len(), entries.len());
+      let list = List::new(items)
+        .block(capabilities::block().borders(Borders::ALL).title(title))
+        .highlight_style(self.config.settings.theme.highlight_style());
+      let mut state = ListState::default();
+      state.select(Some(popup.selected));
+      f.render_stateful_widget(list, chunks[1], &mut state);
+
+      let note_block = capabilities::block()
+        .borders(Borders::ALL)
+        .title("Note (#tag tags the query, tab completes a tag, enter to save, esc to cancel)");
+      match &popup.note {
+        Some(note) => note.render(f, chunks[2], note_block, Style::default().fg(Color::Yellow), true),
+        None => f.render_widget(note_block, chunks[2]),
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Shows `message` in the error popup, without a hint.
+  fn show_error(&mut self, message: String) {
+    self.error_message = Some(message);
+    self.error_hint = None;
+    self.error_raw = false;
+  }
+
+  fn render_error(&mut self, f: &mut Frame<'_>) -> Result<()> {
+    if let Some(error_message) = &self.error_message {
+      let text = match self.error_hint {
+        Some(hint) if !self.error_raw => {
+          format!("{}\n\nPress e for the full error, esc to close", hint.lines(ERROR_HINT_WIDTH).join("\n"))
+        },
+        Some(_) => format!("{error_message}\n\nPress e for the hint"),
+        None => error_message.to_string(),
+      };
+      let title = match self.error_hint {
+        Some(hint) if !self.error_raw => format!("{} (SQLSTATE {})", hint.title, hint.code),
+        _ => "Error".to_string(),
+      };
+      let popup = Popup::new(title, text);
+      f.render_widget(popup.to_widget(), f.size());
+    }
+
+    Ok(())
+  }
+}
+
+impl<'a> Component for Db<'a> {
+  fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
+    self.command_tx = Some(tx);
+    Ok(())
+  }
+
+  fn register_config_handler(&mut self, config: Config) -> Result<()> {
+    self.config = config;
+    self.library = self.config.library.directory.as_deref().and_then(|directory| {
+      Library::load(directory).map_err(|e| log::warn!("Unable to load query library {}: {e}", directory.display())).ok()
+    });
+    Ok(())
+  }
+
+  fn handle_paste_event(&mut self, text: String) -> Result<Option<Action>> {
+    if let Some(name) = &mut self.library_save_name {
+      name.insert_str(&text);
+    } else if let Some((input, _)) = &mut self.column_picker {
+      input.insert_str(&text);
+    } else if let Some((input, _)) = &mut self.command_palette {
+      input.insert_str(&text);
+    } else if let Some((_, value)) = &mut self.pending_command {
+      value.insert_str(&text);
+    } else if let Some((_, name)) = &mut self.job_save {
+      name.insert_str(&text);
+    } else if let Some(input) = &mut self.attach_input {
+      input.insert_str(&text);
+    } else if let Some(name) = &mut self.snapshot_name {
+      name.insert_str(&text);
+    } else if let Some((_, value)) = &mut self.preamble_prompt {
+      value.insert_str(&text);
+    } else if let Some((_, path)) = &mut self.json_path_prompt {
+      path.insert_str(&text);
+    } else if let Some(editor) = &mut self.values_editor {
+      editor.insert_str(&text);
+    } else if let Some(popup) = &mut self.history_popup {
+      match &mut popup.note {
+        Some(note) => note.insert_str(&text),
+        None if popup.is_searching => {
+          popup.search.insert_str(&text);
+          popup.matches = self.history.search(popup.search.value());
+          popup.selected = 0;
+        },
+        None => {},
+      }
+    } else if let Some(popup) = self.schema_popup.as_mut().filter(|p| p.filter.is_editing) {
+      popup.filter.input.insert_str(&text);
+      popup.selected = 0;
+    } else if self.results_filter.is_editing && self.selected_component == ComponentKind::Results {
+      self.results_filter.input.insert_str(&text);
+      self.apply_results_filter();
+    } else if self.is_searching_tables {
+      self.table_search.insert_str(&text);
+      return Ok(Some(Action::LoadTables(self.table_search.value().to_string())));
+    } else if self.selected_component == ComponentKind::Query {
+      self.query_input.insert_str(text);
+    }
+    Ok(None)
+  }
+
+  fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+    // Movement keys arrive as actions after this handler, so a typed count is kept for them until the next key.
+    self.pending_count = self.count_prefix.take();
+    // A large copy asks for a second press, any other key in between cancels it.
+    let copy_confirmed = std::mem::take(&mut self.large_copy_confirmed);
+    let analyze_announced = self.analyze_announced.take();
+
+    if let Some(hint) = self.error_hint.filter(|_| self.error_message.is_some()) {
+      match key.code {
+        KeyCode::Char('e') => {
+          self.error_raw = !self.error_raw;
+          return Ok(None);
+        },
+        KeyCode::Char('1') if hint.action == Some(HintAction::BrowseTables) => {
+          self.error_message = None;
+          return Ok(Some(Action::FocusHome));
+        },
+        KeyCode::Esc => {
+          self.error_message = None;
+          return Ok(None);
+        },
+        _ => {},
+      }
+    }
+
+    if let Some(selected) = self.settings_index {
+      let kinds = SettingKind::iter().collect::<Vec<_>>();
+      match key.code {
+        KeyCode::Char('j') | KeyCode::Down => self.settings_index = Some((selected + 1) % kinds.len()),
+        KeyCode::Char('k') | KeyCode::Up => {
+          self.settings_index = Some(selected.checked_sub(1).unwrap_or(kinds.len() - 1))
+        },
+        KeyCode::Char('l') | KeyCode::Right | KeyCode::Enter | KeyCode::Char('h') | KeyCode::Left => {
+          let forward = !matches!(key.code, KeyCode::Char('h') | KeyCode::Left);
+          self.config.settings.cycle(kinds[selected], forward);
+          if kinds[selected] == SettingKind::SystemObjects {
+            self.apply_table_visibility();
+          }
+          match self.config.settings.save() {
+            Ok(path) => self.status_message = Some(format!("Settings saved to {}", path.display())),
+            Err(e) => self.show_error(format!("Unable to save settings: {e}")),
+          }
+        },
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::F(2) => self.settings_index = None,
+        _ => {},
+      }
+      return Ok(None);
+    }
+
+    if let Some(selected) = self.help_index {
+      let texts = help_texts();
+      if self.help_search.is_editing {
+        if self.help_search.handle_key(key) {
+          let matches = self.help_search.matches(texts.iter().map(String::as_str));
+          self.help_index = PopupSearch::step(&matches, selected, true, true).or(Some(selected));
+        }
+        return Ok(None);
+      }
+      let matches = self.help_search.matches(texts.iter().map(String::as_str));
+      match key.code {
+        KeyCode::Char('j') | KeyCode::Down => self.help_index = Some((selected + 1).min(keymap::BINDINGS.len() - 1)),
+        KeyCode::Char('k') | KeyCode::Up => self.help_index = Some(selected.saturating_sub(1)),
+        KeyCode::Char('/') => self.help_search.is_editing = true,
+        KeyCode::Char(c @ ('n' | 'N')) => {
+          self.help_index = PopupSearch::step(&matches, selected, c == 'n', false).or(Some(selected))
+        },
+        KeyCode::Esc if self.help_search.is_active() => self.help_search = PopupSearch::default(),
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::F(1) => {
+          self.help_index = None;
+          self.help_search = PopupSearch::default();
+        },
+        _ => {},
+      }
+      return Ok(None);
+    }
+
+    match keymap::lookup(Scope::Global, &key) {
+      Some(Command::Help) => {
+        self.help_index = Some(0);
+        return Ok(None);
+      },
+      Some(Command::Settings) => {
+        self.settings_index = Some(0);
+        return Ok(None);
+      },
+      Some(Command::History) => {
+        self.open_history();
+        return Ok(None);
+      },
+      Some(Command::CommandPalette) => {
+        if self.config.commands.is_empty() {
+          self.status_message = Some("No custom commands, add [[commands]] entries to the config".to_string());
+        } else {
+          self.command_palette = Some((TextInput::default(), 0));
+        }
+        return Ok(None);
+      },
+      Some(Command::Undo) => {
+        self.undo();
+        return Ok(None);
+      },
+      _ => {},
+    }
+
+    if let Some(editor) = &mut self.values_editor {
+      match editor.handle_key(key) {
+        Some(ValuesEditorEvent::Insert(sql)) => {
+          self.query_input.insert_str(sql);
+          self.values_editor = None;
+        },
+        Some(ValuesEditorEvent::Status(message)) => self.status_message = Some(message),
+        Some(ValuesEditorEvent::Cancel) => self.values_editor = None,
+        None => {},
+      }
+      return Ok(None);
+    }
+
+    if let Some(name) = &mut self.library_save_name {
+      match key.code {
+        KeyCode::Enter => {
+          let name = name.value().to_string();
+          self.library_save_name = None;
+          self.save_to_library(&name);
+        },
+        KeyCode::Esc => self.library_save_name = None,
+        _ => {
+          name.handle_key(key);
+        },
+      }
+      return Ok(None);
+    }
+
+    if let Some(selected) = self.outline_index {
+      let count = self.outline.len();
+      match key.code {
+        KeyCode::Char('j') | KeyCode::Down => self.outline_index = Some((selected + 1) % count),
+        KeyCode::Char('k') | KeyCode::Up => self.outline_index = Some(selected.checked_sub(1).unwrap_or(count - 1)),
+        KeyCode::Enter => {
+          self.outline_index = None;
+          let (row, col) = self.outline[selected].1;
+          self.query_input.move_cursor(CursorMove::Jump(row as u16, col as u16));
+          self.selected_component = ComponentKind::Query;
+          return Ok(Some(Action::SelectComponent(ComponentKind::Query)));
+        },
+        KeyCode::Esc | KeyCode::Char('q') => self.outline_index = None,
+        _ => {},
+      }
+      return Ok(None);
+    }
+
+    if let Some((query, selected)) = &mut self.busy_query {
+      let choices: Vec<BusyQueryChoice> = BusyQueryChoice::iter().collect();
+      let choice = match key.code {
+        KeyCode::Char('j') | KeyCode::Down => {
+          *selected = (*selected + 1) % choices.len();
+          None
+        },
+        KeyCode::Char('k') | KeyCode::Up => {
+          *selected = selected.checked_sub(1).unwrap_or(choices.len() - 1);
+          None
+        },
+        KeyCode::Enter => Some(choices[*selected]),
+        KeyCode::Char('c') => Some(BusyQueryChoice::CancelCurrent),
+        KeyCode::Char('q') => Some(BusyQueryChoice::Queue),
+        KeyCode::Char('d') | KeyCode::Esc => Some(BusyQueryChoice::Discard),
+        _ => None,
+      };
+      if let Some(choice) = choice {
+        let query = std::mem::take(query);
+        self.busy_query = None;
+        return Ok(Some(Action::ResolveBusyQuery(query, choice)));
+      }
+      return Ok(None);
+    }
+
+    if let Some((input, selected)) = &mut self.command_palette {
+      let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+      match key.code {
+        KeyCode::Down => *selected += 1,
+        KeyCode::Up => *selected = selected.saturating_sub(1),
+        KeyCode::Char('n') if ctrl => *selected += 1,
+        KeyCode::Char('p') if ctrl => *selected = selected.saturating_sub(1),
+        KeyCode::Enter => {
+          let matches = self.command_palette_matches();
+          let selected = self.command_palette.take().map_or(0, |(_, selected)| selected);
+          if let Some(index) = matches.get(selected.min(matches.len().saturating_sub(1))) {
+            let command = self.config.commands[*index].clone();
+            return Ok(self.run_custom_command(PendingCommand::new(command)));
+          }
+        },
+        KeyCode::Esc => self.command_palette = None,
+        _ => {
+          if input.handle_key(key) {
+            *selected = 0;
+          }
+        },
+      }
+      return Ok(None);
+    }
+
+    if let Some((pending, value)) = &mut self.pending_command {
+      match key.code {
+        KeyCode::Enter => {
+          if let Some(name) = pending.missing() {
+            pending.set(&name, value.value());
+          }
+          let pending = pending.clone();
+          self.pending_command = None;
+          return Ok(self.run_custom_command(pending));
+        },
+        KeyCode::Esc => {
+          self.pending_command = None;
+          self.status_message = Some("Custom command not run".to_string());
+        },
+        _ => {
+          value.handle_key(key);
+        },
+      }
+      return Ok(None);
+    }
+
+    if let Some((input, selected)) = &mut self.column_picker {
+      let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+      match key.code {
+        KeyCode::Down => *selected += 1,
+        KeyCode::Up => *selected = selected.saturating_sub(1),
+        KeyCode::Char('n') if ctrl => *selected += 1,
+        KeyCode::Char('p') if ctrl => *selected = selected.saturating_sub(1),
+        KeyCode::Enter => {
+          let matches = self.column_picker_matches();
+          let selected = self.column_picker.take().map_or(0, |(_, selected)| selected);
+          if let Some(column) = matches.get(selected.min(matches.len().saturating_sub(1))) {
+            self.select_column(*column);
+          }
+        },
+        KeyCode::Esc => self.column_picker = None,
+        _ => {
+          if input.handle_key(key) {
+            *selected = 0;
+          }
+        },
+      }
+      return Ok(None);
+    }
+
+    if let Some(selected) = self.register_index {
+      let count = self.registers.len();
+      match key.code {
+        KeyCode::Char('j') | KeyCode::Down => self.register_index = Some((selected + 1) % count),
+        KeyCode::Char('k') | KeyCode::Up => self.register_index = Some(selected.checked_sub(1).unwrap_or(count - 1)),
+        KeyCode::Char(c @ '0'..='9') if (c as usize - '0' as usize) < count => {
+          self.register_index = Some(c as usize - '0' as usize)
+        },
+        KeyCode::Enter => {
+          if let Some(text) = self.registers.get(selected) {
+            self.query_input.insert_str(text);
+          }
+          self.register_index = None;
+        },
+        KeyCode::Esc | KeyCode::Char('q') => self.register_index = None,
+        _ => {},
+      }
+      return Ok(None);
+    }
+
+    if let Some(selected) = self.time_snippet_index {
+      match key.code {
+        KeyCode::Char('j') | KeyCode::Down => {
+          self.time_snippet_index = Some((selected + 1) % snippets::TIME_EXPRESSIONS.len());
+        },
+        KeyCode::Char('k') | KeyCode::Up => {
+          self.time_snippet_index = Some(selected.checked_sub(1).unwrap_or(snippets::TIME_EXPRESSIONS.len() - 1));
+        },
+        KeyCode::Enter => {
+          self.query_input.insert_str(snippets::TIME_EXPRESSIONS[selected]);
+          self.time_snippet_index = None;
+        },
+        KeyCode::Esc => self.time_snippet_index = None,
+        _ => {},
+      }
+      return Ok(None);
+    }
+
+    if self.join_builder.is_some() {
+      return Ok(self.handle_join_builder_key(key));
+    }
+
+    if let Some((name, value)) = &mut self.preamble_prompt {
+      match key.code {
+        KeyCode::Enter => {
+          let action = Action::PreambleValue(name.clone(), value.value().to_string());
+          self.preamble_prompt = None;
+          return Ok(Some(action));
+        },
+        KeyCode::Esc => {
+          self.preamble_prompt = None;
+          self.status_message = Some("Query not run, the connection preamble needs a value".to_string());
+        },
+        _ => {
+          value.handle_key(key);
+        },
+      }
+      return Ok(None);
+    }
+
+    if let Some((source, path)) = &mut self.json_path_prompt {
+      match key.code {
+        KeyCode::Enter => {
+          let (source, path) = (*source, path.value().to_string());
+          self.json_path_prompt = None;
+          self.add_json_path_column(source, &path);
+        },
+        KeyCode::Esc => self.json_path_prompt = None,
+        _ => {
+          path.handle_key(key);
+        },
+      }
+      return Ok(None);
+    }
+
+    if let Some(name) = &mut self.snapshot_name {
+      match key.code {
+        KeyCode::Enter => {
+          let name = name.value().to_string();
+          self.snapshot_name = None;
+          self.take_snapshot(&name);
+        },
+        KeyCode::Esc => self.snapshot_name = None,
+        _ => {
+          name.handle_key(key);
+        },
+      }
+      return Ok(None);
+    }
 
-      let results_border_color =
-        if self.selected_component == ComponentKind::Results { Color::Cyan } else { Color::White };
-      let mut table_state = TableState::default();
-      table_state.select(Some(self.detail_row_index));
-      let result_table = Table::default()
-        .rows(rows)
-        .header(header)
-        .column_spacing(10)
-        .block(
-          Block::default()
-            .borders(Borders::ALL)
-            .title("Results")
-            .fg(results_border_color)
-            .border_type(BorderType::Plain),
-        )
-        .highlight_symbol(">>")
-        .highlight_style(Style::default().bg(Color::Yellow).fg(Color::Black).add_modifier(Modifier::BOLD))
-        .widths(&[Constraint::Length(40), Constraint::Length(40), Constraint::Length(40)]);
+    if let Some(selected) = self.snapshot_index {
+      let count = self.snapshots.len();
+      match key.code {
+        KeyCode::Char('j') | KeyCode::Down => self.snapshot_index = Some((selected + 1) % count),
+        KeyCode::Char('k') | KeyCode::Up => self.snapshot_index = Some(selected.checked_sub(1).unwrap_or(count - 1)),
+        KeyCode::Enter => {
+          self.snapshot_index = None;
+          self.open_snapshot(selected);
+        },
+        KeyCode::Char('d') => {
+          let snapshot = self.snapshots.remove(selected);
+          self.undo.push(format!("deleting snapshot '{}'", snapshot.name), UndoEntry::Snapshot(selected, snapshot));
+          self.snapshot_index = (!self.snapshots.is_empty()).then(|| selected.min(self.snapshots.len() - 1));
+        },
+        KeyCode::Esc | KeyCode::Char('q') => self.snapshot_index = None,
+        _ => {},
+      }
+      return Ok(None);
+    }
 
-      f.render_stateful_widget(result_table, table_chunks[0], &mut table_state);
+    if let Some(generated) = &mut self.generated_sql {
+      let count = GENERATED_SQL_CHOICES.len();
+      let choice = match key.code {
+        KeyCode::Char('l') | KeyCode::Right | KeyCode::Tab => {
+          generated.choice = (generated.choice + 1) % count;
+          return Ok(None);
+        },
+        KeyCode::Char('h') | KeyCode::Left | KeyCode::BackTab => {
+          generated.choice = generated.choice.checked_sub(1).unwrap_or(count - 1);
+          return Ok(None);
+        },
+        KeyCode::Enter => generated.choice,
+        KeyCode::Char('x') => 0,
+        KeyCode::Char('e') => 1,
+        KeyCode::Esc | KeyCode::Char('q') => 2,
+        _ => return Ok(None),
+      };
+      let Some(GeneratedSql { sql, action, .. }) = self.generated_sql.take() else {
+        return Ok(None);
+      };
+      return Ok(match choice {
+        0 => {
+          if let Action::HandleQuery(query) = &action {
+            self.set_query(query);
+          }
+          Some(action)
+        },
+        1 => {
+          self.set_query(&sql);
+          self.selected_component = ComponentKind::Query;
+          Some(Action::SelectComponent(ComponentKind::Query))
+        },
+        _ => None,
+      });
     }
 
-    Ok(chunks)
-  }
+    if let Some(selected) = self.index_hint_index {
+      let count = self.index_hints.len();
+      match key.code {
+        KeyCode::Char('j') | KeyCode::Down => self.index_hint_index = Some((selected + 1) % count),
+        KeyCode::Char('k') | KeyCode::Up => self.index_hint_index = Some(selected.checked_sub(1).unwrap_or(count - 1)),
+        KeyCode::Enter => {
+          self.index_hint_index = None;
+          let statement = self.index_hints[selected].create_statement();
+          self.query_input.move_cursor(CursorMove::Bottom);
+          self.query_input.move_cursor(CursorMove::End);
+          self.query_input.insert_newline();
+          self.query_input.insert_str(&statement);
+          self.status_message = Some(format!("Added {statement}"));
+          self.selected_component = ComponentKind::Query;
+          return Ok(Some(Action::SelectComponent(ComponentKind::Query)));
+        },
+        KeyCode::Esc | KeyCode::Char('q') => self.index_hint_index = None,
+        _ => {},
+      }
+      return Ok(None);
+    }
 
-  fn render_query_results(&mut self, f: &mut Frame<'_>, chunks: Rc<[Rect]>) -> Result<Rc<[Rect]>> {
-    if self.row_is_selected {
-      self.render_query_result_details(f, chunks)
-    } else {
-      self.render_query_results_table(f, chunks)
+    if let Some(selected) = self.export_menu_index {
+      let mut targets = self.export_targets();
+      match key.code {
+        KeyCode::Char('j') | KeyCode::Down => self.export_menu_index = Some((selected + 1) % targets.len()),
+        KeyCode::Char('k') | KeyCode::Up => {
+          self.export_menu_index = Some(selected.checked_sub(1).unwrap_or(targets.len() - 1))
+        },
+        KeyCode::Enter => {
+          self.export_menu_index = None;
+          self.export_to_target(targets.swap_remove(selected).1);
+        },
+        KeyCode::Char('s') => {
+          match targets.swap_remove(selected).1 {
+            ExportTarget::Profile(profile) => {
+              self.export_menu_index = None;
+              self.job_save = Some((profile, TextInput::default()));
+            },
+            ExportTarget::Format(_) => {
+              self.status_message = Some("Jobs export with a profile, select one to save a job".to_string())
+            },
+          }
+        },
+        KeyCode::Esc | KeyCode::Char('q') => self.export_menu_index = None,
+        _ => {},
+      }
+      return Ok(None);
     }
-  }
 
-  fn render_query_results_table(&mut self, f: &mut Frame<'_>, chunks: Rc<[Rect]>) -> Result<Rc<[Rect]>> {
-    let table_chunks = Layout::default()
-      .direction(Direction::Vertical)
-      .constraints([Constraint::Min(1), Constraint::Length(1)].as_ref())
-      .split(chunks[1]);
+    if let Some((profile, name)) = &mut self.job_save {
+      match key.code {
+        KeyCode::Enter => {
+          let (profile, name) = (profile.clone(), name.value().to_string());
+          self.job_save = None;
+          self.save_job(&name, profile);
+        },
+        KeyCode::Esc => self.job_save = None,
+        _ => {
+          name.handle_key(key);
+        },
+      }
+      return Ok(None);
+    }
 
-    let skip_count = self.horizonal_scroll_offset * VISIBLE_COLUMNS;
-    let normal_style = Style::default();
-    let header_cells = self
-      .selected_headers
-      .iter()
-      .skip(skip_count)
-      .take(VISIBLE_COLUMNS)
-      .map(|h| Cell::from(h.to_string()).style(Style::default().fg(Color::Red).bg(Color::Green)));
-    let header = ratatui::widgets::Row::new(header_cells).style(normal_style).height(1);
+    if let Some(input) = &mut self.attach_input {
+      match key.code {
+        KeyCode::Enter => {
+          match attach::parse(input.value()) {
+            Ok((file, alias)) => {
+              self.attach_input = None;
+              return Ok(Some(Action::AttachDatabase(file, alias)));
+            },
+            Err(e) => self.status_message = Some(e),
+          }
+        },
+        KeyCode::Esc => self.attach_input = None,
+        _ => {
+          input.handle_key(key);
+        },
+      }
+      return Ok(None);
+    }
 
-    let rows = self
-      .query_results
-      .iter()
-      .map(|r| {
-        let cells = r.iter().skip(skip_count).take(VISIBLE_COLUMNS).map(|c| Cell::from(c.to_string()));
-        ratatui::widgets::Row::new(cells).height(1).bottom_margin(1)
-      })
-      .collect::<Vec<_>>();
+    if let Some(selected) = self.format_menu_index {
+      let choices = Self::format_choices();
+      match key.code {
+        KeyCode::Char('j') | KeyCode::Down => self.format_menu_index = Some((selected + 1) % choices.len()),
+        KeyCode::Char('k') | KeyCode::Up => {
+          self.format_menu_index = Some(selected.checked_sub(1).unwrap_or(choices.len() - 1))
+        },
+        KeyCode::Enter => {
+          self.format_menu_index = None;
+          self.set_column_format(choices[selected]);
+        },
+        KeyCode::Esc | KeyCode::Char('q') => self.format_menu_index = None,
+        _ => {},
+      }
+      return Ok(None);
+    }
 
-    let status_text = Paragraph::new(Text::styled(format!("Rows: {}", rows.len()), Style::default().fg(Color::Yellow)));
-    f.render_widget(status_text, table_chunks[1]);
+    if let Some(scroll) = &mut self.notices_scroll {
+      match key.code {
+        KeyCode::Char('j') | KeyCode::Down => *scroll += 1,
+        KeyCode::Char('k') | KeyCode::Up => *scroll = scroll.saturating_sub(1),
+        KeyCode::Char('g') => *scroll = 0,
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('!') => self.notices_scroll = None,
+        _ => {},
+      }
+      return Ok(None);
+    }
 
-    let results_border_color =
-      if self.selected_component == ComponentKind::Results { Color::Cyan } else { Color::White };
-    let mut table_state = TableState::default();
-    table_state.select(Some(self.selected_row_index));
-    let result_table = Table::default()
-      .rows(rows)
-      .header(header)
-      .column_spacing(10)
-      .block(
-        Block::default().borders(Borders::ALL).title("Results").fg(results_border_color).border_type(BorderType::Plain),
-      )
-      .highlight_style(Style::default().bg(Color::Yellow).fg(Color::Black).add_modifier(Modifier::BOLD))
-      .widths(&[Constraint::Length(40), Constraint::Length(40), Constraint::Length(40)]);
+    if let Some(view) = &mut self.plan_view {
+      match key.code {
+        KeyCode::Char('j') | KeyCode::Down => view.scroll = (view.scroll + 1).min(view.lines.len().saturating_sub(1)),
+        KeyCode::Char('k') | KeyCode::Up => view.scroll = view.scroll.saturating_sub(1),
+        KeyCode::Char('g') => view.scroll = 0,
+        KeyCode::Char('G') => view.scroll = view.lines.len().saturating_sub(1),
+        KeyCode::Esc | KeyCode::Char('q') => self.plan_view = None,
+        _ => {},
+      }
+      return Ok(None);
+    }
 
-    f.render_stateful_widget(result_table, table_chunks[0], &mut table_state);
+    if let Some(cell_diff) = &mut self.cell_diff {
+      let last = cell_diff.rows.len().saturating_sub(1);
+      match key.code {
+        KeyCode::Char('j') | KeyCode::Down => cell_diff.scroll = (cell_diff.scroll + 1).min(last),
+        KeyCode::Char('k') | KeyCode::Up => cell_diff.scroll = cell_diff.scroll.saturating_sub(1),
+        KeyCode::Char('n') => {
+          // Start of the next run of changed rows.
+          let rows = &cell_diff.rows;
+          let next = (cell_diff.scroll + 1..rows.len()).find(|i| rows[*i].is_changed() && !rows[i - 1].is_changed());
+          if let Some(next) = next {
+            cell_diff.scroll = next;
+          }
+        },
+        KeyCode::Esc | KeyCode::Char('q') => self.cell_diff = None,
+        _ => {},
+      }
+      return Ok(None);
+    }
 
-    if self.show_row_details {
-      if let Some(json_str) = self.json() {
-        let popup = Popup::new("Row Details", json_str);
-        f.render_widget(popup.to_widget(), f.size());
+    if let Some(digests) = &self.cell_digests {
+      match key.code {
+        KeyCode::Char('m') => {
+          let md5 = digests.md5.clone();
+          self.copy_to_clipboard(md5);
+          self.cell_digests = None;
+        },
+        KeyCode::Char('s') => {
+          let sha256 = digests.sha256.clone();
+          self.copy_to_clipboard(sha256);
+          self.cell_digests = None;
+        },
+        KeyCode::Esc | KeyCode::Char('q') => self.cell_digests = None,
+        _ => {},
       }
+      return Ok(None);
     }
 
-    Ok(chunks)
-  }
+    if self.schema_popup.is_some() {
+      return self.handle_schema_popup_key(key);
+    }
 
-  fn render_error(&mut self, f: &mut Frame<'_>) -> Result<()> {
-    if let Some(error_message) = &self.error_message {
-      let popup = Popup::new("Error", error_message.to_string());
-      f.render_widget(popup.to_widget(), f.size());
+    if self.history_popup.is_some() {
+      return self.handle_history_popup_key(key);
     }
 
-    Ok(())
-  }
-}
+    if let Some(preview) = &mut self.table_preview {
+      match key.code {
+        KeyCode::Char('h') | KeyCode::Left => preview.column_offset = preview.column_offset.saturating_sub(1),
+        KeyCode::Char('l') | KeyCode::Right => {
+          preview.column_offset = (preview.column_offset + 1).min(preview.max_column_offset(self.screen))
+        },
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('P') => self.table_preview = None,
+        _ => {},
+      }
+      return Ok(None);
+    }
 
-impl<'a> Component for Db<'a> {
-  fn register_action_handler(&mut self, tx: UnboundedSender<Action>) -> Result<()> {
-    self.command_tx = Some(tx);
-    Ok(())
-  }
+    let typing = self.selected_component == ComponentKind::Query || self.is_searching_tables;
+    if let Some(command) = self.config.commands.iter().find(|command| command.is_bound_to(&key, typing)) {
+      let pending = PendingCommand::new(command.clone());
+      return Ok(self.run_custom_command(pending));
+    }
 
-  fn register_config_handler(&mut self, config: Config) -> Result<()> {
-    self.config = config;
-    Ok(())
-  }
+    if let Some(count) = self.count_digit(key) {
+      self.count_prefix = Some(count);
+      self.pending_count = None;
+      return Ok(None);
+    }
+
+    if let Some(result) = self.handle_chord_key(key) {
+      return result;
+    }
 
-  fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
     match self.selected_component {
       ComponentKind::Home => {
         // Searching for a table
         match key.code {
-          KeyCode::Char(c) => {
-            if c == '/' {
+          KeyCode::Enter | KeyCode::Esc if self.is_searching_tables => {
+            self.is_searching_tables = false;
+            if key.code == KeyCode::Esc {
+              self.table_search.clear();
+            }
+          },
+          _ if self.is_searching_tables => {
+            let previous = self.table_search.value().to_string();
+            if self.table_search.handle_key(key) && self.table_search.value() != previous {
+              return Ok(Some(Action::LoadTables(self.table_search.value().to_string())));
+            }
+          },
+          KeyCode::Char(_) => {
+            let command = keymap::lookup(Scope::Tables, &key);
+            if command == Some(Command::SearchTables) {
               self.is_searching_tables = true;
+            } else if command == Some(Command::ToggleSystemObjects) {
+              self.config.settings.show_system_objects = !self.config.settings.show_system_objects;
+              self.apply_table_visibility();
+              let shown = if self.config.settings.show_system_objects { "shown" } else { "hidden" };
+              match self.config.settings.save() {
+                Ok(_) => self.status_message = Some(format!("System schemas and tables {shown}")),
+                Err(e) => self.show_error(format!("Unable to save settings: {e}")),
+              }
+            } else if command == Some(Command::AttachDatabase) {
+              if self.database_file.is_some() {
+                self.attach_input = Some(TextInput::default());
+              } else {
+                self.status_message = Some("Only SQLite connections can attach database files".to_string());
+              }
+            } else if let Some(table) = self.tables.get(self.selected_table_index) {
+              match command {
+                Some(Command::DetachDatabase) if self.is_attached(table) => {
+                  return Ok(Some(Action::DetachDatabase(table.schema.clone())));
+                },
+                Some(Command::DetachDatabase) => {
+                  self.status_message = Some(format!("{} isn't in an attached database", table.display_name()));
+                },
+                Some(Command::CountRows) => {
+                  let table = table.qualified_name();
+                  return Ok(self.confirm_generated(snippets::count_rows_query(&table), Action::CountTableRows(table)));
+                },
+                Some(Command::PreviewTable) => {
+                  let table = table.qualified_name();
+                  return Ok(self.confirm_generated(snippets::preview_query(&table), Action::PreviewTable(table)));
+                },
+                Some(Command::ShowColumns) => return Ok(Some(Action::LoadTableColumns(table.clone()))),
+                Some(Command::JoinBuilder) => {
+                  self.join_builder = Some(JoinBuilder::new(table.clone()));
+                  return Ok(Some(Action::LoadForeignKeys(table.clone())));
+                },
+                _ => {},
+              }
             }
+          },
+          KeyCode::Esc => {
+            self.table_search.clear();
+            return Ok(Some(Action::LoadTables(String::new())));
+          },
+          _ => {},
+        }
+      },
+      ComponentKind::Query => {
+        if self.handle_completion_key(key) {
+          return Ok(None);
+        }
 
-            if self.is_searching_tables && c != '/' {
-              self.table_search_query.push(c);
-              return Ok(Some(Action::LoadTables(self.table_search_query.clone())));
-            }
+        let command = keymap::lookup(Scope::Editor, &key);
+        match command {
+          Some(Command::Complete) => {
+            self.update_completion();
+            return Ok(None);
+          },
+          Some(Command::PasteInList) => {
+            self.paste_in_list();
+            return Ok(None);
           },
-          KeyCode::Enter => {
-            if self.is_searching_tables {
-              self.is_searching_tables = false;
+          Some(Command::TimeSnippets) => {
+            self.time_snippet_index = Some(0);
+            return Ok(None);
+          },
+          Some(Command::Registers) => {
+            if self.registers.is_empty() {
+              self.status_message = Some("Nothing yanked or copied yet".to_string());
+            } else {
+              self.register_index = Some(0);
             }
+            return Ok(None);
           },
-          KeyCode::Backspace => {
-            self.table_search_query.pop();
+          Some(Command::TeamHistory) => return Ok(Some(Action::LoadTeamHistory)),
+          Some(Command::ValuesEditor) => {
+            self.values_editor = Some(ValuesEditor::new());
+            return Ok(None);
           },
-          KeyCode::Esc => {
-            self.table_search_query.clear();
-            if !self.is_searching_tables {
-              return Ok(Some(Action::LoadTables(String::new())));
+          Some(Command::AlternateQuery) => {
+            self.swap_alternate_query();
+            return Ok(None);
+          },
+          Some(Command::SaveToLibrary) => {
+            if self.library.is_some() {
+              let mut name = TextInput::default();
+              name.set_value(statement::from_table(&self.statement_text()).unwrap_or_default());
+              self.library_save_name = Some(name);
             } else {
-              self.is_searching_tables = false;
+              self.status_message = Some("Set library.directory in the config to save queries".to_string());
             }
+            return Ok(None);
+          },
+          Some(Command::InsertUuid) => {
+            self.insert_uuid();
+            return Ok(None);
+          },
+          Some(Command::FormatQuery) => {
+            self.format_query();
+            return Ok(None);
+          },
+          Some(Command::Outline) => {
+            self.open_outline();
+            return Ok(None);
+          },
+          Some(Command::Explain) => return Ok(self.explain_current_statement(false, false)),
+          Some(Command::ExplainAnalyze) => {
+            return Ok(self.explain_current_statement(true, analyze_announced == Some(Command::ExplainAnalyze)));
+          },
+          Some(Command::ExportPlan) => {
+            return Ok(self.export_plan(analyze_announced == Some(Command::ExportPlan)));
           },
           _ => {},
         }
-      },
-      ComponentKind::Query => {
+
+        let yanked = self.query_input.yank_text();
         let transition = self.vim_editor.transition(Input::from(key), &mut self.query_input);
+        if self.query_input.yank_text() != yanked {
+          self.editor_yanked(self.query_input.yank_text());
+        }
         match transition {
           Transition::Mode(mode) if self.vim_editor.mode() != mode => {
             self.query_input.set_cursor_style(mode.cursor_style());
@@ -342,9 +3668,13 @@ impl<'a> Component for Db<'a> {
           },
           Transition::Quit => {},
         }
+        // Typing `alias.` lists the columns of the aliased table without asking.
+        if self.completion.is_some() || (self.vim_editor.mode() == Mode::Insert && key.code == KeyCode::Char('.')) {
+          self.update_completion();
+        }
         if let Transition::Pending(ref input) = transition {
-          if self.vim_editor.mode() == Mode::Normal && key.code == KeyCode::Enter {
-            return Ok(Some(Action::HandleQuery(self.query_input.lines().join(" "))));
+          if self.vim_editor.mode() == Mode::Normal && command == Some(Command::ExecuteStatement) {
+            return Ok(Some(self.execute(self.statement_text())));
           }
         }
 
@@ -358,18 +3688,160 @@ impl<'a> Component for Db<'a> {
         }
       },
       ComponentKind::Results => {
-        match key.code {
-          KeyCode::Char('y') => {
+        if self.results_filter.is_editing {
+          self.handle_results_filter_key(key);
+          return Ok(None);
+        }
+        match keymap::lookup(Scope::Results, &key) {
+          Some(Command::CopyJson) => {
             if let Some(json_str) = self.json() {
-              let mut ctx: ClipboardContext = ClipboardProvider::new().unwrap();
-              ctx.set_contents(json_str).unwrap();
+              self.copy_to_clipboard(json_str);
+            }
+          },
+          Some(Command::CopyAllRows) => {
+            self.copy_all_rows(copy_confirmed);
+          },
+          Some(Command::CopyFieldCondition) => self.copy_field(cell::sql_condition),
+          Some(Command::CopyFieldJson) => self.copy_field(cell::json_field),
+          Some(Command::CopyColumnName) => {
+            if let Some(name) = self.selected_header().map(|h| snippets::quote_identifier(h)) {
+              self.status_message = Some(format!("Copied {name}"));
+              self.copy_to_clipboard(name);
             }
           },
-          KeyCode::Char('r') => {
-            return Ok(Some(Action::HandleQuery(self.query_input.lines().join(" "))));
+          Some(Command::CopyHeaders) if !self.selected_headers.is_empty() => {
+            let names: Vec<String> =
+              self.ordered(&self.selected_headers).iter().map(|h| snippets::quote_identifier(h)).collect();
+            let list = names.join(", ");
+            self.status_message = Some(format!("Copied {} column names", self.selected_headers.len()));
+            self.copy_to_clipboard(list);
           },
-          KeyCode::Char(' ') => {
+          Some(Command::RerunQuery) => {
+            let query = match &self.last_query {
+              Some(query) if self.results_dropped => query.clone(),
+              _ => self.statement_text(),
+            };
+            return Ok(Some(self.execute(query)));
+          },
+          Some(Command::DropResults) if !self.all_results.is_empty() => self.drop_results(),
+          Some(Command::ToggleRowDetails) => {
             self.row_is_selected = !self.row_is_selected;
+            self.detail_value_offset = 0;
+          },
+          Some(Command::ExpandField) if self.row_is_selected => {
+            if !self.expanded_fields.remove(&self.detail_row_index) {
+              self.expanded_fields.insert(self.detail_row_index);
+            }
+          },
+          Some(Command::ConvertEpoch) => {
+            self.show_epoch();
+          },
+          Some(Command::InsertUuid) => {
+            self.insert_uuid();
+          },
+          Some(Command::ExportMenu) => {
+            let default_format = ExportTarget::Format(self.config.settings.default_export_format);
+            self.export_menu_index =
+              Some(self.export_targets().iter().position(|(_, target)| *target == default_format).unwrap_or_default());
+          },
+          Some(Command::MarkCell) => {
+            if let Some(value) = self.selected_cell().cloned() {
+              let label = self.selected_cell_label();
+              self.status_message = Some(format!("Marked {label}, press d on another cell to compare"));
+              self.marked_cell = Some((label, value));
+            }
+          },
+          Some(Command::DiffCell) => self.diff_cell(),
+          Some(Command::Notices) => self.notices_scroll = Some(0),
+          Some(Command::PreviousResultSet) => {
+            self
+              .show_result_set(self.result_set_index.checked_sub(1).unwrap_or(self.result_sets.len().saturating_sub(1)))
+          },
+          Some(Command::NextResultSet) => {
+            self.show_result_set((self.result_set_index + 1) % self.result_sets.len().max(1))
+          },
+          Some(Command::PlanView) => {
+            match self.plan_format {
+              Some(format) => {
+                let lines = plan::tree(format, &self.selected_headers, &self.all_results);
+                self.plan_view = Some(PlanView { format, lines, scroll: 0 });
+              },
+              None => self.status_message = Some("The results aren't an EXPLAIN plan".to_string()),
+            }
+          },
+          Some(command @ (Command::PageDown | Command::PageUp | Command::HalfPageDown | Command::HalfPageUp)) => {
+            let page = self.results_page_rows.max(1) as isize;
+            let rows = match command {
+              Command::PageDown => page,
+              Command::PageUp => -page,
+              Command::HalfPageDown => (page / 2).max(1),
+              _ => -(page / 2).max(1),
+            };
+            self.page_results(rows);
+          },
+          Some(Command::JumpToColumn) => {
+            if self.column_count() > 0 {
+              self.column_picker = Some((TextInput::default(), 0));
+            }
+          },
+          Some(Command::ColumnFormat) => {
+            let column = if self.row_is_selected { self.detail_row_index } else { self.selected_column_index };
+            if let Some(style) = self.ordered(&self.column_styles).get(column) {
+              let current = Self::format_choices().iter().position(|choice| *choice == style.format);
+              self.format_menu_index = Some(current.unwrap_or_default());
+            }
+          },
+          Some(Command::JsonPathColumn) => {
+            let column = if self.row_is_selected { self.detail_row_index } else { self.selected_column_index };
+            if let Some(source) = self.column_order.get(column) {
+              self.json_path_prompt = Some((*source, TextInput::default()));
+            }
+          },
+          Some(Command::CellHashes) => {
+            self.cell_digests = self.selected_cell().map(|value| cell::digests(value));
+          },
+          Some(Command::PreviousColumn) => {
+            self.select_column(self.selected_column_index.saturating_sub(1));
+          },
+          Some(Command::NextColumn) => {
+            self.select_column(self.selected_column_index + 1);
+          },
+          Some(Command::MoveColumnLeft) => {
+            self.shift_selected_column(-1);
+          },
+          Some(Command::MoveColumnRight) => {
+            self.shift_selected_column(1);
+          },
+          Some(Command::FilterResults) => {
+            self.results_filter.start_editing();
+          },
+          Some(Command::ReapplyFilter) => {
+            match self.results_filter.offered.take() {
+              Some(filter) => {
+                self.results_filter.input.set_value(filter);
+                self.apply_results_filter();
+              },
+              None => self.status_message = Some("No filter saved for this query".to_string()),
+            }
+          },
+          Some(Command::SnapshotResults) if !self.all_results.is_empty() => {
+            let mut name = TextInput::default();
+            name.set_value(format!("snapshot {}", self.snapshots.len() + 1));
+            self.snapshot_name = Some(name);
+          },
+          Some(Command::OpenSnapshots) => {
+            if self.snapshots.is_empty() {
+              self.status_message = Some("No snapshots, press s in the results to take one".to_string());
+            } else {
+              self.snapshot_index = Some(self.snapshots.len() - 1);
+            }
+          },
+          Some(Command::IndexHints) => {
+            if self.index_hints.is_empty() {
+              self.status_message = Some("No index hints, run EXPLAIN ANALYZE on a statement first".to_string());
+            } else {
+              self.index_hint_index = Some(0);
+            }
           },
           _ => {},
         }
@@ -381,9 +3853,35 @@ impl<'a> Component for Db<'a> {
 
   fn update(&mut self, action: Action) -> Result<Option<Action>> {
     match action {
+      Action::TableMoveUp
+      | Action::TableMoveDown
+      | Action::RowMoveUp
+      | Action::RowMoveDown
+      | Action::ScrollTableLeft
+      | Action::ScrollTableRight
+        if self.pending_count.is_some() =>
+      {
+        for _ in 0..self.pending_count.take().unwrap_or(1) {
+          self.update(action.clone())?;
+        }
+      },
+      Action::Tick => self.poll_library(),
       Action::TablesLoaded(tables) => {
-        let tables = tables.iter().filter(|t| t.schema == "public").cloned().collect();
-        self.tables = tables;
+        self.all_tables = tables;
+        self.apply_table_visibility();
+      },
+      Action::DatabasesAttached(databases, tables) => {
+        let names = |databases: &[AttachedDatabase]| databases.iter().map(|d| d.alias.clone()).collect::<Vec<_>>();
+        let (before, after) = (names(&self.attached), names(&databases));
+        self.status_message = if let Some(added) = after.iter().find(|alias| !before.contains(alias)) {
+          let count = tables.iter().filter(|t| &t.schema == added).count();
+          Some(format!("Attached {added} with {count} tables, query them as {added}.<table>"))
+        } else {
+          before.iter().find(|alias| !after.contains(alias)).map(|removed| format!("Detached {removed}"))
+        };
+        self.attached = databases;
+        self.attached_tables = tables;
+        self.apply_table_visibility();
       },
       Action::TableMoveDown => {
         if self.selected_table_index < self.table_row_count() {
@@ -400,9 +3898,23 @@ impl<'a> Component for Db<'a> {
             (self.table_row_count() as i32 - 1i32).clamp(0, self.table_row_count() as i32 - 1) as usize;
         }
       },
+      Action::ScrollTableLeft if self.row_is_selected => {
+        self.detail_value_offset = self.detail_value_offset.saturating_sub(DETAIL_SCROLL_STEP);
+      },
+      Action::ScrollTableRight if self.row_is_selected => {
+        let longest = self
+          .query_results
+          .get(self.selected_row_index)
+          .and_then(|row| row.iter().map(|v| v.chars().count()).max())
+          .unwrap_or_default();
+        if self.detail_value_offset + DETAIL_SCROLL_STEP < longest {
+          self.detail_value_offset += DETAIL_SCROLL_STEP;
+        }
+      },
       Action::ScrollTableLeft => {
         if self.selected_component == ComponentKind::Results && self.horizonal_scroll_offset > 0 {
           self.horizonal_scroll_offset -= 1;
+          self.selected_column_index = self.horizonal_scroll_offset * VISIBLE_COLUMNS;
         }
       },
       Action::ScrollTableRight => {
@@ -410,6 +3922,7 @@ impl<'a> Component for Db<'a> {
           && self.horizonal_scroll_offset * VISIBLE_COLUMNS < self.column_count() - VISIBLE_COLUMNS
         {
           self.horizonal_scroll_offset += 1;
+          self.selected_column_index = self.horizonal_scroll_offset * VISIBLE_COLUMNS;
         }
       },
       Action::RowMoveDown => {
@@ -437,24 +3950,88 @@ impl<'a> Component for Db<'a> {
       },
       Action::LoadSelectedTable => {
         if let Some(selected_table) = self.tables.get(self.selected_table_index) {
-          let query = format!("SELECT * FROM {}", selected_table.name);
-          self.query_input.select_all();
-          self.query_input.cut();
-          self.query_input.insert_str(&query);
+          let query = snippets::select_all_query(&selected_table.display_name());
+          if self.config.settings.confirm_generated_sql {
+            return Ok(self.confirm_generated(query.clone(), Action::HandleQuery(query)));
+          }
+          self.set_query(&query);
           return Ok(Some(Action::HandleQuery(query)));
         } else {
           return Ok(None);
         }
       },
-      Action::QueryResult(headers, results) => {
-        self.selected_headers = headers;
-        self.query_results = results;
+      Action::QueryResult(headers, results, nulls) => {
+        let started = Instant::now();
+        self.result_sets = vec![Default::default()];
+        self.result_set_index = 0;
+        let previous_headers = std::mem::replace(&mut self.selected_headers, headers);
+        let previous_order = std::mem::take(&mut self.column_order);
+        let previous_query = std::mem::replace(&mut self.results_query, self.last_query.clone());
+        let is_rerun = previous_query.is_some()
+          && previous_query.as_deref().map(results_filter::normalize_query)
+            == self.last_query.as_deref().map(results_filter::normalize_query);
+        self.all_results = Arc::new(results);
+        self.all_nulls = nulls;
+        self.column_styles = self.resolve_column_styles();
+        self.expanded_fields.clear();
+        self.large_copy_confirmed = false;
+        self.results_filter.reset_for(self.last_query.as_deref());
+        self.results_filter.invalidate();
+        self.query_results = Arc::clone(&self.all_results);
+        self.filtered_rows = None;
+        self.results_bytes = perf::estimate_result_bytes(&self.selected_headers, &self.all_results);
+        self.results_dropped = false;
+        if let Some(query_started) = self.query_started.take() {
+          self.complete_history(Some(self.all_results.len()), query_started.elapsed(), None);
+        }
+        if let Some(attempt) = self.retry_attempt.take() {
+          self.status_message = Some(format!("Succeeded on attempt {attempt}"));
+        }
+        if let Some(filter) = &self.results_filter.offered {
+          self.status_message = Some(format!("Press F to reapply the filter '{filter}' used with this query"));
+        }
+        let plan: Vec<String> = self.all_results.iter().filter_map(|row| row.first().cloned()).collect();
+        self.index_hints = plan_hints::index_hints(&plan);
+        self.detect_plan();
+        if !self.index_hints.is_empty() {
+          self.status_message =
+            Some(format!("{} index hint(s) for this plan, press I to show", self.index_hints.len()));
+        } else if let Some(format) = self.plan_format {
+          self.status_message = Some(format!("{}, press v to show it as a tree", format.label()));
+        }
+        self.reset_column_order();
+        let json_path_columns = std::mem::take(&mut self.json_path_columns);
+        let query_headers: Vec<String> =
+          previous_headers.iter().filter(|h| !json_path_columns.contains(*h)).cloned().collect();
+        if let Some(change) = is_rerun.then(|| ColumnChange::detect(&query_headers, &self.selected_headers)).flatten() {
+          if !self.saved_column_orders.contains_key(&self.selected_headers) {
+            self.column_order = change.carry_order(&previous_headers, &previous_order, &self.selected_headers);
+          }
+          self.status_message = Some(change.notice());
+        }
         self.horizonal_scroll_offset = 0;
         self.selected_row_index = 0;
         self.detail_row_index = 0;
         self.selected_component = ComponentKind::Results;
+        perf::record_query_apply(started.elapsed(), &self.selected_headers, &self.query_results, self.results_bytes);
         return Ok(Some(Action::SelectComponent(ComponentKind::Results)));
       },
+      Action::QueryResultSet(headers, results, nulls) => {
+        if self.result_sets.is_empty() {
+          return self.update(Action::QueryResult(headers, results, nulls));
+        }
+        self.result_sets.push((headers, results, nulls));
+        self.status_message = Some(format!("{} result sets, press {{ and }} to switch", self.result_sets.len()));
+      },
+      Action::TeamHistoryLoaded(headers, rows) => {
+        // The rows came from the shared table, re-running or saving the previous query would not reproduce them.
+        self.last_query = None;
+        return self.update(Action::QueryResult(headers, rows, Nulls::default()));
+      },
+      Action::QueryNotice(notice) => {
+        log::info!("{notice}");
+        self.notices.push(notice);
+      },
       Action::FocusQuery => {
         self.selected_component = ComponentKind::Query;
         return Ok(Some(Action::SelectComponent(ComponentKind::Query)));
@@ -468,30 +4045,127 @@ impl<'a> Component for Db<'a> {
         return Ok(Some(Action::SelectComponent(ComponentKind::Home)));
       },
       Action::ExecuteQuery => {
-        println!("execute query");
-        return Ok(Some(Action::HandleQuery(self.query_input.lines().join(" "))));
+        return Ok(Some(self.execute(self.statement_text())));
+      },
+      Action::Resize(width, height) => {
+        self.clamp_to_screen(Rect::new(0, 0, width, height));
+      },
+      Action::Help => {
+        self.help_index = Some(0);
       },
       Action::RowDetails => {
         self.show_row_details = !self.show_row_details;
       },
-      Action::Error(e) => {
-        self.error_message = Some(e);
+      Action::QueryBusy(query) => self.busy_query = Some((query, 0)),
+      Action::ResolveBusyQuery(_, choice) => {
+        let is_sqlite = self.server.as_ref().is_some_and(|server| server.kind == ServerKind::Sqlite);
+        self.status_message = Some(match choice {
+          BusyQueryChoice::CancelCurrent if is_sqlite => {
+            "SQLite queries can't be cancelled, queued to run next instead".to_string()
+          },
+          BusyQueryChoice::CancelCurrent => "Cancelling the running query, the new one runs next".to_string(),
+          BusyQueryChoice::Queue => "Queued to run after the running query".to_string(),
+          BusyQueryChoice::Discard => "Discarded the query, the running one continues".to_string(),
+        });
+        if choice != BusyQueryChoice::Discard {
+          self.queued_queries += 1;
+        }
+      },
+      Action::QueryNotCancelled(reason) => {
+        self.status_message = Some(format!("Could not cancel the running query ({reason}), the new one runs after it"));
+      },
+      Action::HandleQuery(query) => {
+        let query = self.limited_queries.remove(&query).unwrap_or(query);
+        self.queued_queries = self.queued_queries.saturating_sub(1);
+        self.result_sets.clear();
+        self.result_set_index = 0;
+        self.notices.start(&query);
+        self.history.record(&query);
+        self.query_started = Some(Instant::now());
+        self.last_query = Some(query);
+      },
+      Action::QueryRetrying(attempt, max_attempts, error) => {
+        self.retry_attempt = Some(attempt);
+        self.status_message = Some(format!("Retrying ({attempt}/{max_attempts}) after: {error}"));
+      },
+      Action::PlanLoaded(plan) => {
+        self.save_plan(plan);
+      },
+      Action::CopyProgress(done, total) => {
+        if self.copy_progress.is_some() {
+          self.copy_progress = Some((done, total));
+          self.status_message = Some(format!("Copying rows {}% ({done}/{total})", done * 100 / total.max(1)));
+        }
+      },
+      Action::ResultsFiltered(generation, matches, shown) => self.show_filtered(generation, matches, shown),
+      Action::RowsCopied(rows, bytes) => {
+        self.copy_progress = None;
+        self.status_message = Some(format!("Copied {rows} rows ({})", perf::format_bytes(bytes)));
+      },
+      Action::CopyFailed(error) => {
+        self.copy_progress = None;
+        self.status_message = Some(format!("Copy failed: {error}"));
+      },
+      Action::PostCommandFailed(error) => self.status_message = Some(error),
+      Action::ServerDetected(server) => {
+        self.server = Some(server);
+      },
+      Action::TableRowCount(table, count) => {
+        self.table_row_counts.insert(table, count);
+      },
+      Action::PromptPreambleValue(name) => {
+        self.preamble_prompt = Some((name, TextInput::default()));
+      },
+      Action::ForeignKeysLoaded(table, foreign_keys) => {
+        if let Some(builder) = self.join_builder.as_mut().filter(|builder| builder.base == table) {
+          builder.foreign_keys = Some(foreign_keys);
+        }
+      },
+      Action::TablePreview(table, headers, rows) => {
+        self.table_preview = Some(TablePreview { table, headers, rows, column_offset: 0 });
+      },
+      Action::TableColumnsLoaded(table, columns) => {
+        self.column_cache.insert(table.name.clone(), columns.clone());
+        if self.join_builder.as_ref().is_some_and(|builder| builder.tables().contains(&&table)) {
+          return Ok(None);
+        }
+        if self.completion_columns_pending.as_ref() == Some(&table.name) {
+          self.completion_columns_pending = None;
+          self.update_completion();
+          return Ok(None);
+        }
+        self.schema_popup = Some(SchemaPopup { table, columns, ..SchemaPopup::default() });
+      },
+      Action::QueryError(e) => {
+        self.retry_attempt = None;
+        if let Some(started) = self.query_started.take() {
+          self.complete_history(None, started.elapsed(), Some(e.clone()));
+        }
+        self.show_error(e);
+        self.error_hint = self.error_message.as_deref().and_then(error_hints::hint_for);
       },
+      Action::Error(e) => self.show_error(e),
       _ => {},
     }
     Ok(None)
   }
 
-  fn draw(&mut self, f: &mut Frame<'_>, area: Rect) -> Result<()> {
+  fn draw(&mut self, f: &mut Frame<'_>, _area: Rect) -> Result<()> {
+    self.screen = f.size();
+
     // Create the layout sections.
     let chunks = Layout::default()
       .direction(Direction::Vertical)
       .constraints([Constraint::Length(3), Constraint::Min(1)])
       .split(f.size());
 
-    let title_block = Block::default().borders(Borders::ALL).style(Style::default());
+    let title_block = capabilities::block().borders(Borders::ALL).style(Style::default());
 
-    let title = Paragraph::new(Text::styled("Query Crafter", Style::default().fg(Color::Green))).block(title_block);
+    let mut title_spans = vec![Span::styled("Query Crafter", Style::default().fg(Color::Green))];
+    if let Some(server) = &self.server {
+      title_spans.push(Span::styled(format!("  {server}"), Style::default().fg(Color::DarkGray)));
+    }
+    let title = Paragraph::new(Line::from(title_spans)).block(title_block);
 
     f.render_widget(title, chunks[0]);
 
@@ -501,8 +4175,127 @@ impl<'a> Component for Db<'a> {
 
     self.render_query_results(f, query_chunks)?;
 
+    self.render_completion(f)?;
+
+    self.render_time_snippets(f)?;
+    self.render_registers(f)?;
+
+    self.render_library_save(f)?;
+    self.render_job_save(f)?;
+    self.render_attach_input(f)?;
+    self.render_preamble_prompt(f)?;
+    self.render_json_path_prompt(f)?;
+
+    self.render_snapshots(f)?;
+
+    if let Some(editor) = &self.values_editor {
+      editor.render(f, centered_rect(80, 60, f.size()), self.config.settings.theme.highlight_style());
+    }
+
+    self.render_index_hints(f)?;
+    self.render_outline(f)?;
+    self.render_generated_sql(f)?;
+
+    self.render_export_menu(f)?;
+    self.render_format_menu(f)?;
+    self.render_column_picker(f)?;
+    self.render_command_palette(f)?;
+    self.render_pending_command(f)?;
+    self.render_busy_query(f)?;
+    self.render_chord_hints(f)?;
+
+    self.render_cell_digests(f)?;
+    self.render_cell_diff(f)?;
+    self.render_plan_view(f)?;
+    self.render_notices(f)?;
+
+    self.render_table_preview(f)?;
+
+    self.render_schema_popup(f)?;
+    self.render_join_builder(f)?;
+    self.render_history_popup(f)?;
+    self.render_settings(f)?;
+    self.render_help(f)?;
+
     self.render_error(f)?;
 
     Ok(())
   }
 }
+
+/// The rows of `all` at `matches`, sharing `all` instead of copying it when every row matched.
+fn matching_rows(all: &Arc<Vec<Vec<String>>>, matches: &[usize]) -> Arc<Vec<Vec<String>>> {
+  if matches.len() == all.len() {
+    return Arc::clone(all);
+  }
+  Arc::new(matches.iter().filter_map(|i| all.get(*i).cloned()).collect())
+}
+
+/// Serializes the columns of `rows` in `order`, sending progress after each chunk, and puts the text on the
+/// clipboard. Returns the size of the copied text.
+fn copy_rows(
+  tx: &UnboundedSender<Action>,
+  headers: &[String],
+  rows: &[Vec<String>],
+  nulls: &Nulls,
+  order: &[usize],
+  options: &export::CsvOptions,
+) -> Result<usize> {
+  let mut text = Vec::new();
+  export::write_csv_chunks(&mut text, headers, rows, nulls, order, options, |copied| {
+    let _ = tx.send(Action::CopyProgress(copied, rows.len()));
+  })?;
+  let bytes = text.len();
+  let mut clipboard: ClipboardContext = ClipboardProvider::new().map_err(|e| eyre!("Clipboard unavailable: {e}"))?;
+  clipboard.set_contents(String::from_utf8(text)?).map_err(|e| eyre!("Clipboard unavailable: {e}"))?;
+  Ok(bytes)
+}
+
+/// A column completion documented with its table, type and nullability.
+fn column_suggestion(table: &str, column: &DbColumn) -> Suggestion {
+  Suggestion::new(&column.name, SuggestionKind::Column).with_documentation(vec![
+    format!("table: {table}"),
+    format!("type: {}", column.data_type),
+    format!("nullable: {}", column.is_nullable),
+  ])
+}
+
+fn cte_column_suggestion(cte: &str, column: &str) -> Suggestion {
+  Suggestion::new(column, SuggestionKind::Column).with_documentation(vec![format!("common table expression: {cte}")])
+}
+
+/// A notice with its severity colored, warnings in yellow.
+fn notice_line(notice: &Notice) -> Line<'_> {
+  let style = match notice.severity {
+    Severity::Warning => Style::default().fg(Color::Yellow),
+    Severity::Notice => Style::default().fg(Color::Cyan),
+    _ => Style::default().dim(),
+  };
+  Line::from(vec![Span::styled(format!("{}: ", notice.severity), style), Span::raw(notice.message.as_str())])
+}
+
+/// Searchable text of each help overlay row.
+fn help_texts() -> Vec<String> {
+  keymap::BINDINGS.iter().map(|b| format!("{} {} {}", b.scope, b.key_label(), b.description)).collect()
+}
+
+/// Returns a rectangle centered in `r` taking up the given percentages of its width and height.
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+  let popup_layout = Layout::default()
+    .direction(Direction::Vertical)
+    .constraints([
+      Constraint::Percentage((100 - percent_y) / 2),
+      Constraint::Percentage(percent_y),
+      Constraint::Percentage((100 - percent_y) / 2),
+    ])
+    .split(r);
+
+  Layout::default()
+    .direction(Direction::Horizontal)
+    .constraints([
+      Constraint::Percentage((100 - percent_x) / 2),
+      Constraint::Percentage(percent_x),
+      Constraint::Percentage((100 - percent_x) / 2),
+    ])
+    .split(popup_layout[1])[1]
+}
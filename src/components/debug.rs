@@ -0,0 +1,60 @@
+use color_eyre::eyre::Result;
+use crossterm::event::KeyEvent;
+use ratatui::{prelude::*, widgets::*};
+
+use super::Component;
+use crate::{
+  action::Action,
+  capabilities, crash,
+  keymap::{self, Command, Scope},
+  tui::Frame,
+};
+
+/// Overlay (F12 or `--debug`) listing the most recently dispatched actions, newest first.
+#[derive(Debug, Default)]
+pub struct DebugOverlay {
+  visible: bool,
+}
+
+impl DebugOverlay {
+  pub fn new(visible: bool) -> Self {
+    Self { visible }
+  }
+}
+
+impl Component for DebugOverlay {
+  fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+    if keymap::lookup(Scope::Global, &key) == Some(Command::ToggleDebug) {
+      return Ok(Some(Action::ToggleDebug));
+    }
+    Ok(None)
+  }
+
+  fn update(&mut self, action: Action) -> Result<Option<Action>> {
+    if action == Action::ToggleDebug {
+      self.visible = !self.visible;
+    }
+    Ok(None)
+  }
+
+  fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+    if !self.visible {
+      return Ok(());
+    }
+
+    let width = (rect.width / 2).max(40).min(rect.width);
+    let height = (rect.height / 2).max(10).min(rect.height);
+    let area = Rect::new(rect.right() - width, rect.y, width, height);
+    let items: Vec<ListItem> = crash::recent_actions().into_iter().rev().map(ListItem::new).collect();
+    let list = List::new(items).block(
+      capabilities::block()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Magenta))
+        .title("Action Log")
+        .title_bottom("F12: close"),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(list, area);
+    Ok(())
+  }
+}
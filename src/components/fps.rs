@@ -1,13 +1,22 @@
 use std::time::Instant;
 
 use color_eyre::eyre::Result;
+use crossterm::event::KeyEvent;
 use ratatui::{prelude::*, widgets::*};
 
 use super::Component;
-use crate::{action::Action, tui::Frame};
+use crate::{
+  action::Action,
+  keymap::{self, Command, Scope},
+  perf,
+  tui::Frame,
+};
 
+/// Performance HUD, toggled with F11, showing tick and frame rates alongside the timings collected in [`perf`].
 #[derive(Debug, Clone, PartialEq)]
 pub struct FpsCounter {
+  visible: bool,
+
   app_start_time: Instant,
   app_frames: u32,
   app_fps: f64,
@@ -26,6 +35,7 @@ impl Default for FpsCounter {
 impl FpsCounter {
   pub fn new() -> Self {
     Self {
+      visible: false,
       app_start_time: Instant::now(),
       app_frames: 0,
       app_fps: 0.0,
@@ -43,6 +53,7 @@ impl FpsCounter {
       self.app_fps = self.app_frames as f64 / elapsed;
       self.app_start_time = now;
       self.app_frames = 0;
+      perf::reset_max_action_latency();
     }
     Ok(())
   }
@@ -61,7 +72,17 @@ impl FpsCounter {
 }
 
 impl Component for FpsCounter {
+  fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+    if keymap::lookup(Scope::Global, &key) == Some(Command::TogglePerf) {
+      return Ok(Some(Action::TogglePerf));
+    }
+    Ok(None)
+  }
+
   fn update(&mut self, action: Action) -> Result<Option<Action>> {
+    if let Action::TogglePerf = action {
+      self.visible = !self.visible;
+    };
     if let Action::Tick = action {
       self.app_tick()?
     };
@@ -72,6 +93,10 @@ impl Component for FpsCounter {
   }
 
   fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+    if !self.visible {
+      return Ok(());
+    }
+
     let rects = Layout::default()
       .direction(Direction::Vertical)
       .constraints(vec![
@@ -82,7 +107,18 @@ impl Component for FpsCounter {
 
     let rect = rects[0];
 
-    let s = format!("{:.2} ticks per sec (app) {:.2} frames per sec (render)", self.app_fps, self.render_fps);
+    let metrics = perf::snapshot();
+    let mut s = format!(
+      "draw {:.1?} | action {:.1?} (max {:.1?}) | results {}",
+      metrics.draw_time,
+      metrics.action_latency,
+      metrics.max_action_latency,
+      perf::format_bytes(metrics.result_bytes)
+    );
+    if let Some(query) = &metrics.query {
+      s.push_str(&format!(" | query {} ({} rows)", query.breakdown(), query.rows));
+    }
+    s.push_str(&format!(" | {:.2} tps {:.2} fps ", self.app_fps, self.render_fps));
     let block = Block::default().title(block::Title::from(s.dim()).alignment(Alignment::Right));
     f.render_widget(block, rect);
     Ok(())
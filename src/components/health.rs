@@ -0,0 +1,119 @@
+use std::time::{Duration, Instant};
+
+use color_eyre::eyre::Result;
+use crossterm::event::KeyEvent;
+use ratatui::{prelude::*, widgets::*};
+use serde::{Deserialize, Serialize};
+
+use super::Component;
+use crate::{
+  action::Action,
+  capabilities,
+  keymap::{self, Command, Scope},
+  tui::Frame,
+};
+
+/// How often the panel asks for fresh health data while it is visible.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Health snapshot of a single database connection.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionHealth {
+  pub name: String,
+  pub checked_at: String,
+  pub latency_ms: Option<u64>,
+  pub version: Option<String>,
+  pub backends: Option<String>,
+  pub replication_lag: Option<String>,
+  pub error: Option<String>,
+}
+
+/// Toggleable panel (F3) showing connection health, refreshed in the background while visible.
+#[derive(Debug, Default)]
+pub struct HealthPanel {
+  visible: bool,
+  last_refresh: Option<Instant>,
+  connections: Vec<ConnectionHealth>,
+}
+
+impl HealthPanel {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  fn refresh_due(&self) -> bool {
+    self.visible && self.last_refresh.is_none_or(|at| at.elapsed() >= REFRESH_INTERVAL)
+  }
+
+  fn lines(health: &ConnectionHealth) -> Vec<Line<'static>> {
+    let value = |v: &Option<String>| v.clone().unwrap_or_else(|| "-".to_string());
+    let latency = match (&health.error, health.latency_ms) {
+      (Some(_), None) => Span::styled("unreachable", Style::default().fg(Color::Red)),
+      (_, Some(ms)) => Span::styled(format!("{ms} ms"), Style::default().fg(Color::Green)),
+      (None, None) => Span::raw("-"),
+    };
+    let mut lines = vec![
+      Line::from(Span::styled(health.name.clone(), Style::default().add_modifier(Modifier::BOLD))),
+      Line::from(vec![Span::raw("  ping:        "), latency]),
+      Line::from(format!("  version:     {}", value(&health.version))),
+      Line::from(format!("  backends:    {}", value(&health.backends))),
+      Line::from(format!("  replica lag: {}", value(&health.replication_lag))),
+      Line::from(format!("  checked at:  {}", health.checked_at)),
+    ];
+    if let Some(error) = &health.error {
+      lines.push(Line::from(Span::styled(format!("  {error}"), Style::default().fg(Color::Red))));
+    }
+    lines
+  }
+}
+
+impl Component for HealthPanel {
+  fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
+    if keymap::lookup(Scope::Global, &key) == Some(Command::ToggleHealth) {
+      return Ok(Some(Action::ToggleHealth));
+    }
+    Ok(None)
+  }
+
+  fn update(&mut self, action: Action) -> Result<Option<Action>> {
+    match action {
+      Action::ToggleHealth => {
+        self.visible = !self.visible;
+        self.last_refresh = None;
+      },
+      Action::Tick if self.refresh_due() => {
+        self.last_refresh = Some(Instant::now());
+        return Ok(Some(Action::CheckHealth));
+      },
+      Action::HealthChecked(health) => {
+        match self.connections.iter_mut().find(|c| c.name == health.name) {
+          Some(existing) => *existing = health,
+          None => self.connections.push(health),
+        }
+      },
+      _ => {},
+    }
+    Ok(None)
+  }
+
+  fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+    if !self.visible {
+      return Ok(());
+    }
+
+    let lines: Vec<Line> = if self.connections.is_empty() {
+      vec![Line::from("Checking...")]
+    } else {
+      self.connections.iter().flat_map(Self::lines).collect()
+    };
+    let width = 50.min(rect.width);
+    let height = (lines.len() as u16 + 2).min(rect.height);
+    let area = Rect::new(rect.right() - width, rect.bottom() - height, width, height);
+    let paragraph = Paragraph::new(lines)
+      .wrap(Wrap { trim: false })
+      .block(capabilities::block().borders(Borders::ALL).title("Connection Health").title_bottom("F3: close"));
+    f.render_widget(Clear, area);
+    f.render_widget(paragraph, area);
+    Ok(())
+  }
+}
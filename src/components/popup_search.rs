@@ -0,0 +1,118 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::prelude::*;
+
+use super::text_input::TextInput;
+
+/// Incremental `/` search inside a popup list: matching rows are found as the query is typed, `n`/`N` step through
+/// them and the matched text is highlighted.
+#[derive(Debug, Clone, Default)]
+pub struct PopupSearch {
+  pub input: TextInput,
+  pub is_editing: bool,
+}
+
+impl PopupSearch {
+  pub fn query(&self) -> String {
+    self.input.value().to_lowercase()
+  }
+
+  pub fn is_active(&self) -> bool {
+    !self.input.is_empty()
+  }
+
+  /// Handles a key while the query is edited, returning whether it changed. Enter keeps the query, Esc clears it.
+  pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+    match key.code {
+      KeyCode::Enter => {
+        self.is_editing = false;
+        false
+      },
+      KeyCode::Esc => {
+        self.is_editing = false;
+        self.input.clear();
+        true
+      },
+      _ => self.input.handle_key(key),
+    }
+  }
+
+  /// Indices of the rows whose text contains the query, ignoring case.
+  pub fn matches<'a>(&self, rows: impl IntoIterator<Item = &'a str>) -> Vec<usize> {
+    let query = self.query();
+    if query.is_empty() {
+      return Vec::new();
+    }
+    rows.into_iter().enumerate().filter(|(_, text)| text.to_lowercase().contains(&query)).map(|(i, _)| i).collect()
+  }
+
+  /// The match after `current` (or before it, when not `forward`), wrapping around. With `inclusive`, `current`
+  /// itself counts, so a query typed on a matching row stays there.
+  pub fn step(matches: &[usize], current: usize, forward: bool, inclusive: bool) -> Option<usize> {
+    let found = if forward {
+      matches.iter().find(|&&i| i > current || (inclusive && i == current)).or(matches.first())
+    } else {
+      matches.iter().rev().find(|&&i| i < current || (inclusive && i == current)).or(matches.last())
+    };
+    found.copied()
+  }
+
+  /// `text` as spans with every occurrence of the query in `highlight`.
+  pub fn highlight(&self, text: &str, style: Style, highlight: Style) -> Line<'static> {
+    let query = self.query();
+    let lower = text.to_lowercase();
+    // Lower casing can change byte offsets for some characters, such text is shown without highlighting.
+    if query.is_empty() || lower.len() != text.len() {
+      return Line::from(Span::styled(text.to_string(), style));
+    }
+    let mut spans = Vec::new();
+    let mut last = 0;
+    for (start, _) in lower.match_indices(&query) {
+      if start < last {
+        continue;
+      }
+      spans.push(Span::styled(text[last..start].to_string(), style));
+      spans.push(Span::styled(text[start..start + query.len()].to_string(), highlight));
+      last = start + query.len();
+    }
+    spans.push(Span::styled(text[last..].to_string(), style));
+    Line::from(spans)
+  }
+
+  /// Bottom title describing the search state.
+  pub fn hint(&self, idle: &str) -> String {
+    match (self.is_editing, self.is_active()) {
+      (true, _) => format!("/{}  enter: keep  esc: clear", self.input.value()),
+      (false, true) => format!("/{}  n/N: next/previous match  {idle}", self.input.value()),
+      (false, false) => format!("/: search  {idle}"),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_matches_and_step() {
+    let mut search = PopupSearch::default();
+    search.input.set_value("Col");
+    let matches = search.matches(["id", "column_a", "name", "other_COLUMN"]);
+    assert_eq!(matches, vec![1, 3]);
+    assert_eq!(PopupSearch::step(&matches, 1, true, true), Some(1));
+    assert_eq!(PopupSearch::step(&matches, 1, true, false), Some(3));
+    assert_eq!(PopupSearch::step(&matches, 3, true, false), Some(1));
+    assert_eq!(PopupSearch::step(&matches, 1, false, false), Some(3));
+    assert_eq!(PopupSearch::step(&[], 0, true, false), None);
+  }
+
+  #[test]
+  fn test_highlight() {
+    let mut search = PopupSearch::default();
+    search.input.set_value("ab");
+    let line = search.highlight("xAbyab", Style::default(), Style::default().bold());
+    let texts: Vec<&str> = line.spans.iter().map(|s| s.content.as_ref()).collect();
+    assert_eq!(texts, vec!["x", "Ab", "y", "ab", ""]);
+  }
+}
@@ -0,0 +1,155 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+  layout::Rect,
+  style::Style,
+  text::Text,
+  widgets::{Block, Paragraph},
+};
+
+use crate::tui::Frame;
+
+/// Single line text field with a cursor, shared by the search and filter boxes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextInput {
+  value: String,
+  /// Cursor position as a character index.
+  cursor: usize,
+}
+
+impl TextInput {
+  pub fn value(&self) -> &str {
+    &self.value
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.value.is_empty()
+  }
+
+  pub fn cursor(&self) -> usize {
+    self.cursor
+  }
+
+  /// Replaces the text and moves the cursor to its end.
+  pub fn set_value(&mut self, value: impl Into<String>) {
+    self.value = value.into();
+    self.cursor = self.len();
+  }
+
+  pub fn clear(&mut self) {
+    self.set_value(String::new());
+  }
+
+  /// Inserts `text` at the cursor, e.g. from a paste. Line breaks become spaces.
+  pub fn insert_str(&mut self, text: &str) {
+    let text: String = text.chars().map(|c| if c == '\n' || c == '\r' { ' ' } else { c }).collect();
+    let at = self.byte_index(self.cursor);
+    self.value.insert_str(at, &text);
+    self.cursor += text.chars().count();
+  }
+
+  /// Applies an editing or cursor key, returning false for keys the input doesn't handle (Enter, Esc, ...).
+  pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = key.modifiers.contains(KeyModifiers::ALT);
+    match key.code {
+      KeyCode::Char('a') if ctrl => self.cursor = 0,
+      KeyCode::Char('e') if ctrl => self.cursor = self.len(),
+      KeyCode::Char('u') if ctrl => self.delete_range(0, self.cursor),
+      KeyCode::Char('w') if ctrl => self.delete_range(self.word_start(), self.cursor),
+      KeyCode::Backspace if ctrl || alt => self.delete_range(self.word_start(), self.cursor),
+      KeyCode::Char(_) if ctrl || alt => return false,
+      KeyCode::Char(c) => self.insert_str(&c.to_string()),
+      KeyCode::Backspace => self.delete_range(self.cursor.saturating_sub(1), self.cursor),
+      KeyCode::Delete => self.delete_range(self.cursor, (self.cursor + 1).min(self.len())),
+      KeyCode::Left if ctrl || alt => self.cursor = self.word_start(),
+      KeyCode::Right if ctrl || alt => self.cursor = self.word_end(),
+      KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+      KeyCode::Right => self.cursor = (self.cursor + 1).min(self.len()),
+      KeyCode::Home => self.cursor = 0,
+      KeyCode::End => self.cursor = self.len(),
+      _ => return false,
+    }
+    true
+  }
+
+  /// Draws the text inside `block`, scrolled so the cursor stays visible, and places the terminal cursor when
+  /// `focused`.
+  pub fn render(&self, f: &mut Frame<'_>, area: Rect, block: Block<'_>, style: Style, focused: bool) {
+    let inner = block.inner(area);
+    let width = inner.width.saturating_sub(1) as usize;
+    let scroll = self.cursor.saturating_sub(width);
+    let visible: String = self.value.chars().skip(scroll).collect();
+    f.render_widget(Paragraph::new(Text::styled(visible, style)).block(block), area);
+    if focused && inner.width > 0 && inner.height > 0 {
+      f.set_cursor(inner.x + (self.cursor - scroll) as u16, inner.y);
+    }
+  }
+
+  fn len(&self) -> usize {
+    self.value.chars().count()
+  }
+
+  fn byte_index(&self, index: usize) -> usize {
+    self.value.char_indices().nth(index).map_or(self.value.len(), |(i, _)| i)
+  }
+
+  fn delete_range(&mut self, start: usize, end: usize) {
+    let (start_byte, end_byte) = (self.byte_index(start), self.byte_index(end));
+    self.value.replace_range(start_byte..end_byte, "");
+    self.cursor = start;
+  }
+
+  /// Start of the word before the cursor, skipping whitespace first.
+  fn word_start(&self) -> usize {
+    let chars: Vec<char> = self.value.chars().take(self.cursor).collect();
+    let end = chars.iter().rposition(|c| !c.is_whitespace()).map_or(0, |i| i + 1);
+    chars[..end].iter().rposition(|c| c.is_whitespace()).map_or(0, |i| i + 1)
+  }
+
+  /// End of the word after the cursor, skipping whitespace first.
+  fn word_end(&self) -> usize {
+    let rest: Vec<char> = self.value.chars().skip(self.cursor).collect();
+    let start = rest.iter().position(|c| !c.is_whitespace()).unwrap_or(rest.len());
+    self.cursor + rest[start..].iter().position(|c| c.is_whitespace()).map_or(rest.len(), |i| start + i)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn press(input: &mut TextInput, code: KeyCode, modifiers: KeyModifiers) {
+    input.handle_key(KeyEvent::new(code, modifiers));
+  }
+
+  #[test]
+  fn test_cursor_editing() {
+    let mut input = TextInput::default();
+    input.set_value("user_id");
+    press(&mut input, KeyCode::Home, KeyModifiers::NONE);
+    press(&mut input, KeyCode::Char('é'), KeyModifiers::NONE);
+    press(&mut input, KeyCode::Right, KeyModifiers::NONE);
+    press(&mut input, KeyCode::Delete, KeyModifiers::NONE);
+    assert_eq!(input.value(), "éuer_id");
+    press(&mut input, KeyCode::End, KeyModifiers::NONE);
+    press(&mut input, KeyCode::Backspace, KeyModifiers::NONE);
+    assert_eq!(input.value(), "éuer_i");
+    assert_eq!(input.cursor(), 6);
+  }
+
+  #[test]
+  fn test_word_deletion_and_paste() {
+    let mut input = TextInput::default();
+    input.set_value("order items  ");
+    press(&mut input, KeyCode::Char('w'), KeyModifiers::CONTROL);
+    assert_eq!(input.value(), "order ");
+    input.insert_str("line\nitems");
+    assert_eq!(input.value(), "order line items");
+    press(&mut input, KeyCode::Left, KeyModifiers::CONTROL);
+    assert_eq!(input.cursor(), 11);
+    press(&mut input, KeyCode::Right, KeyModifiers::ALT);
+    assert_eq!(input.cursor(), 16);
+  }
+}
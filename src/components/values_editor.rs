@@ -0,0 +1,283 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{prelude::*, widgets::*};
+
+use super::text_input::TextInput;
+use crate::{capabilities, export, snippets::quote_identifier, sql, tui::Frame};
+
+/// Name of the table created by the temp-table output.
+const TEMP_TABLE: &str = "values_input";
+const CELL_WIDTH: u16 = 16;
+
+/// Result of a key press in the values editor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValuesEditorEvent {
+  Cancel,
+  /// SQL to insert into the query editor.
+  Insert(String),
+  /// Why nothing was inserted, for the status line. The editor stays open.
+  Status(String),
+}
+
+/// A small spreadsheet for typing rows that become a `VALUES` list or a temp table. The first row holds the column
+/// names. Empty cells and cells reading `NULL` become `NULL`.
+#[derive(Debug, Clone)]
+pub struct ValuesEditor {
+  headers: Vec<String>,
+  rows: Vec<Vec<String>>,
+  /// Selected cell, where row 0 is the header row.
+  row: usize,
+  col: usize,
+  /// Text of the selected cell while it is edited.
+  input: TextInput,
+}
+
+impl Default for ValuesEditor {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl ValuesEditor {
+  pub fn new() -> Self {
+    let mut editor = Self {
+      headers: vec!["column1".to_string()],
+      rows: vec![vec![String::new()]],
+      row: 1,
+      col: 0,
+      input: TextInput::default(),
+    };
+    editor.select(1, 0);
+    editor
+  }
+
+  pub fn insert_str(&mut self, text: &str) {
+    self.input.insert_str(text);
+  }
+
+  pub fn handle_key(&mut self, key: KeyEvent) -> Option<ValuesEditorEvent> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let alt = key.modifiers.contains(KeyModifiers::ALT);
+    match key.code {
+      KeyCode::Esc => return Some(ValuesEditorEvent::Cancel),
+      KeyCode::Char('s') if ctrl => {
+        self.commit();
+        return Some(match self.values_list() {
+          Some(sql) => ValuesEditorEvent::Insert(sql),
+          None => ValuesEditorEvent::Status("Fill in a row first, a VALUES list needs one".to_string()),
+        });
+      },
+      KeyCode::Char('t') if ctrl => {
+        self.commit();
+        return Some(ValuesEditorEvent::Insert(self.temp_table()));
+      },
+      KeyCode::Char('n') if alt => {
+        self.commit();
+        self.headers.push(format!("column{}", self.headers.len() + 1));
+        self.rows.iter_mut().for_each(|row| row.push(String::new()));
+        self.select(self.row, self.headers.len() - 1);
+      },
+      KeyCode::Char('d') if alt && self.row > 0 && self.rows.len() > 1 => {
+        self.rows.remove(self.row - 1);
+        self.select(self.row.min(self.rows.len()), self.col);
+      },
+      KeyCode::Tab => {
+        if self.col + 1 < self.headers.len() {
+          self.move_to(self.row, self.col + 1);
+        } else {
+          self.move_to(self.row + 1, 0);
+        }
+      },
+      KeyCode::BackTab => {
+        if self.col > 0 {
+          self.move_to(self.row, self.col - 1);
+        } else if self.row > 0 {
+          self.move_to(self.row - 1, self.headers.len() - 1);
+        }
+      },
+      KeyCode::Enter | KeyCode::Down => self.move_to(self.row + 1, self.col),
+      KeyCode::Up => self.move_to(self.row.saturating_sub(1), self.col),
+      _ => {
+        self.input.handle_key(key);
+      },
+    }
+    None
+  }
+
+  /// Selects a cell after saving the edited one, adding a row when moving past the last.
+  fn move_to(&mut self, row: usize, col: usize) {
+    self.commit();
+    if row > self.rows.len() {
+      self.rows.push(vec![String::new(); self.headers.len()]);
+    }
+    self.select(row, col);
+  }
+
+  fn select(&mut self, row: usize, col: usize) {
+    self.row = row;
+    self.col = col;
+    let value = self.cell(row, col).to_string();
+    self.input.set_value(value);
+  }
+
+  fn cell(&self, row: usize, col: usize) -> &str {
+    match row {
+      0 => &self.headers[col],
+      _ => &self.rows[row - 1][col],
+    }
+  }
+
+  fn commit(&mut self) {
+    let value = self.input.value().to_string();
+    match self.row {
+      0 => self.headers[self.col] = value,
+      row => self.rows[row - 1][self.col] = value,
+    }
+  }
+
+  /// Rows typed so far, without trailing empty rows and with empty cells and `NULL` as `None`.
+  fn filled_rows(&self) -> Vec<Vec<Option<&String>>> {
+    let last = self.rows.iter().rposition(|row| row.iter().any(|cell| !cell.is_empty())).map_or(0, |i| i + 1);
+    self.rows[..last]
+      .iter()
+      .map(|row| row.iter().map(|cell| (!cell.is_empty() && cell != sql::NULL).then_some(cell)).collect())
+      .collect()
+  }
+
+  /// Literals of each row, typed by the values of their column.
+  fn literal_rows(&self) -> Vec<String> {
+    let rows = self.filled_rows();
+    let types = export::infer_column_types(self.headers.len(), &rows);
+    rows
+      .iter()
+      .map(|row| {
+        let literals: Vec<String> =
+          row.iter().zip(&types).map(|(value, t)| t.sql_literal(value.map(String::as_str))).collect();
+        format!("({})", literals.join(", "))
+      })
+      .collect()
+  }
+
+  /// `(VALUES ...) AS v (columns)`, usable in a FROM clause or a CTE. `None` while no row is filled in, as a `VALUES`
+  /// list can't be empty.
+  pub fn values_list(&self) -> Option<String> {
+    let rows = self.literal_rows();
+    (!rows.is_empty()).then(|| format!("(VALUES\n  {}\n) AS v ({})", rows.join(",\n  "), self.column_list()))
+  }
+
+  /// The headers quoted as identifiers where needed, separated by commas.
+  fn column_list(&self) -> String {
+    self.headers.iter().map(|h| quote_identifier(h)).collect::<Vec<_>>().join(", ")
+  }
+
+  /// A temp table with column types inferred from the values, filled with the rows.
+  pub fn temp_table(&self) -> String {
+    let rows = self.filled_rows();
+    let types = export::infer_column_types(self.headers.len(), &rows);
+    let definitions: Vec<String> =
+      self.headers.iter().zip(&types).map(|(h, t)| format!("{} {}", quote_identifier(h), t.sql_type())).collect();
+    let mut sql = format!("CREATE TEMP TABLE {TEMP_TABLE} ({});", definitions.join(", "));
+    if !rows.is_empty() {
+      sql.push_str(&format!(
+        "\nINSERT INTO {TEMP_TABLE} ({}) VALUES\n  {};",
+        self.column_list(),
+        self.literal_rows().join(",\n  ")
+      ));
+    }
+    sql
+  }
+
+  pub fn render(&self, f: &mut Frame<'_>, area: Rect, highlight: Style) {
+    f.render_widget(Clear, area);
+    let block = capabilities::block().borders(Borders::ALL).title(
+      "Values (Tab/Enter next cell, Alt-n add column, Alt-d delete row, Ctrl-s insert VALUES, Ctrl-t insert temp \
+       table, Esc cancel)",
+    );
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let cell_text = |row: usize, col: usize| {
+      if (row, col) == (self.row, self.col) {
+        self.input.value().to_string()
+      } else {
+        self.cell(row, col).to_string()
+      }
+    };
+    let cell_style =
+      |row: usize, col: usize| if (row, col) == (self.row, self.col) { highlight } else { Style::default() };
+    let header = Row::new(
+      (0..self.headers.len())
+        .map(|col| Cell::from(cell_text(0, col)).style(cell_style(0, col).add_modifier(Modifier::BOLD))),
+    );
+    let rows = (1..=self.rows.len()).map(|row| {
+      Row::new((0..self.headers.len()).map(|col| Cell::from(cell_text(row, col)).style(cell_style(row, col))))
+    });
+    let widths = vec![Constraint::Length(CELL_WIDTH); self.headers.len()];
+    f.render_widget(Table::new(rows, widths).header(header).column_spacing(1), inner);
+
+    // The cursor of the edited cell, when it fits on screen.
+    let x = inner.x + self.col as u16 * (CELL_WIDTH + 1) + (self.input.cursor() as u16).min(CELL_WIDTH - 1);
+    let y = inner.y + self.row as u16;
+    if x < inner.right() && y < inner.bottom() {
+      f.set_cursor(x, y);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn type_text(editor: &mut ValuesEditor, text: &str) {
+    for c in text.chars() {
+      let code = match c {
+        '\t' => KeyCode::Tab,
+        '\n' => KeyCode::Enter,
+        c => KeyCode::Char(c),
+      };
+      editor.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+  }
+
+  #[test]
+  fn test_values_and_temp_table() {
+    let mut editor = ValuesEditor::new();
+    editor.handle_key(KeyEvent::new(KeyCode::Char('n'), KeyModifiers::ALT));
+    editor.handle_key(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE));
+    editor.input.set_value("name");
+    editor.handle_key(KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT));
+    editor.input.set_value("id");
+    type_text(&mut editor, "\t\t1\tO'Brien\t2\t\t");
+    assert_eq!(editor.values_list().unwrap(), "(VALUES\n  (1, 'O''Brien'),\n  (2, NULL)\n) AS v (id, name)");
+    assert_eq!(
+      editor.temp_table(),
+      "CREATE TEMP TABLE values_input (id BIGINT, name TEXT);\nINSERT INTO values_input (id, name) VALUES\n  (1, \
+       'O''Brien'),\n  (2, NULL);"
+    );
+  }
+
+  #[test]
+  fn test_values_keep_text() {
+    let mut editor = ValuesEditor::new();
+    editor.handle_key(KeyEvent::new(KeyCode::Char('n'), KeyModifiers::ALT));
+    editor.handle_key(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE));
+    editor.input.set_value("flag");
+    editor.handle_key(KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT));
+    editor.input.set_value("zip");
+    type_text(&mut editor, "\t\t02134\tt\t");
+    assert_eq!(editor.values_list().unwrap(), "(VALUES\n  ('02134', 't')\n) AS v (zip, flag)");
+  }
+
+  #[test]
+  fn test_values_need_a_row_and_quote_headers() {
+    let mut editor = ValuesEditor::new();
+    let event = editor.handle_key(KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL));
+    assert!(matches!(event, Some(ValuesEditorEvent::Status(_))));
+    editor.handle_key(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE));
+    editor.input.set_value("order");
+    type_text(&mut editor, "\t1\t");
+    assert_eq!(editor.values_list().unwrap(), "(VALUES\n  (1)\n) AS v (\"order\")");
+    assert!(editor.temp_table().starts_with("CREATE TEMP TABLE values_input (\"order\" BIGINT);"));
+  }
+}
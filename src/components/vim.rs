@@ -1,15 +1,6 @@
-use std::{env, fmt, fs, io, io::BufRead};
+use std::fmt;
 
-use crossterm::{
-  event::{DisableMouseCapture, EnableMouseCapture},
-  terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
-use ratatui::{
-  backend::CrosstermBackend,
-  style::{Color, Modifier, Style},
-  widgets::{Block, Borders},
-  Terminal,
-};
+use ratatui::style::{Color, Modifier, Style};
 use tui_textarea::{CursorMove, Input, Key, Scrolling, TextArea};
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
@@ -1,17 +1,19 @@
-use std::{collections::HashMap, fmt, path::PathBuf};
+use std::{
+  collections::{BTreeMap, HashMap},
+  path::PathBuf,
+};
 
 use color_eyre::eyre::Result;
-use config::Value;
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use derive_deref::{Deref, DerefMut};
 use ratatui::style::{Color, Modifier, Style};
-use serde::{
-  de::{self, Deserializer, MapAccess, Visitor},
-  Deserialize, Serialize,
-};
-use serde_json::Value as JsonValue;
+use serde::{Deserialize, Deserializer};
 
-use crate::{action::Action, mode::Mode};
+use crate::{
+  action::Action, autocomplete::CompletionConfig, column_format::ColumnRule, custom_commands::CustomCommand,
+  export::ExportConfig, format::FormatConfig, keymap::LeaderConfig, library::LibraryConfig, lock::LockConfig,
+  mode::Mode, retry::RetryConfig, settings::Settings, team_history::TeamHistoryConfig,
+};
 
 const CONFIG: &str = include_str!("../.config/config.json5");
 
@@ -31,6 +33,34 @@ pub struct Config {
   pub keybindings: KeyBindings,
   #[serde(default)]
   pub styles: Styles,
+  #[serde(default)]
+  pub export: ExportConfig,
+  #[serde(default)]
+  pub settings: Settings,
+  #[serde(default)]
+  pub completion: CompletionConfig,
+  #[serde(default)]
+  pub retry: RetryConfig,
+  #[serde(default)]
+  pub format: FormatConfig,
+  #[serde(default)]
+  pub lock: LockConfig,
+  #[serde(default)]
+  pub team_history: TeamHistoryConfig,
+  #[serde(default)]
+  pub leader: LeaderConfig,
+  /// Alignment and formatting rules for result columns.
+  #[serde(default)]
+  pub columns: Vec<ColumnRule>,
+  /// Directory of `.sql` files used as snippets and saved queries.
+  #[serde(default)]
+  pub library: LibraryConfig,
+  /// Completion snippets, trigger to expansion.
+  #[serde(default)]
+  pub snippets: BTreeMap<String, String>,
+  /// User defined commands, SQL templates run from the command palette or their own key.
+  #[serde(default)]
+  pub commands: Vec<CustomCommand>,
 }
 
 impl Config {
@@ -102,7 +132,7 @@ impl<'de> Deserialize<'de> for KeyBindings {
   }
 }
 
-fn parse_key_event(raw: &str) -> Result<KeyEvent, String> {
+pub fn parse_key_event(raw: &str) -> Result<KeyEvent, String> {
   let raw_lower = raw.to_ascii_lowercase();
   let (remaining, modifiers) = extract_modifiers(&raw_lower);
   parse_key_code_with_modifiers(remaining, modifiers)
@@ -0,0 +1,48 @@
+//! The database to connect to: a SQLite file given on the command line, or the first connection of the bundled
+//! `config.toml`, a Postgres server.
+
+use std::sync::Arc;
+
+use color_eyre::eyre::Result;
+use toml::Value;
+
+use crate::{
+  preamble::Preamble,
+  sql::{Postgres, Queryer, Sqlite},
+};
+
+static CONFIG: &[u8] = include_bytes!("../config.toml");
+
+fn to_connection() -> Result<String> {
+  let app_config = toml::from_str::<Value>(std::str::from_utf8(CONFIG)?)?;
+  let host = app_config["connections"][0]["host"].as_str().map_or("localhost", |v| v);
+  let username = app_config["connections"][0]["username"].as_str().map_or("postgres", |v| v);
+  let password = app_config["connections"][0]["password"].as_str().map_or("", |v| v);
+  let database = app_config["connections"][0]["database"].as_str().map_or("postgres", |v| v);
+  let connection = format!("postgres://{}:{}@{}/{}", username, password, host, database);
+
+  Ok(connection)
+}
+
+/// Connects to the SQLite `filename`, or the configured Postgres connection, returning the Postgres connection URL
+/// with the client. Shared by the TUI and the scripting subcommands.
+pub async fn connect(filename: Option<&str>) -> Result<(String, Arc<dyn Queryer>)> {
+  let connection = to_connection()?;
+  let db: Arc<dyn Queryer> = match filename {
+    Some(f) => Arc::new(Sqlite::new(f).await?),
+    None => Arc::new(Postgres::new(&connection).await?),
+  };
+  Ok((connection, db))
+}
+
+/// The `preamble` of the configured connection, if it has one.
+pub fn preamble() -> Option<Preamble> {
+  let app_config = toml::from_str::<Value>(std::str::from_utf8(CONFIG).ok()?).ok()?;
+  app_config["connections"][0]["preamble"].as_str().and_then(Preamble::new)
+}
+
+/// Password of the configured connection, if it has one.
+pub fn password() -> Option<String> {
+  let app_config = toml::from_str::<Value>(std::str::from_utf8(CONFIG).ok()?).ok()?;
+  app_config["connections"][0]["password"].as_str().filter(|p| !p.is_empty()).map(String::from)
+}
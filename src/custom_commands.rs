@@ -0,0 +1,109 @@
+use std::collections::BTreeMap;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+
+use crate::{config, preamble};
+
+/// A user defined command, a `[[commands]]` entry of the config, e.g. `name = "Kill idle sessions"` with
+/// `sql = "SELECT pg_terminate_backend(pid) FROM pg_stat_activity WHERE state = 'idle' AND usename = '{user}'"`. It is
+/// run from the command palette or its `key`, asking for each `{name}` placeholder first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomCommand {
+  pub name: String,
+  pub sql: String,
+  /// Key running the command, e.g. `ctrl-g` or `f7`. It is checked before the keys of the focused panel.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub key: Option<String>,
+}
+
+impl CustomCommand {
+  pub fn placeholders(&self) -> Vec<String> {
+    preamble::placeholders(&self.sql)
+  }
+
+  /// The SQL with placeholders filled in by [`preamble::fill`]: quoted placeholders as the contents of the string,
+  /// bare ones as numbers such as `LIMIT {n}` or quoted identifiers such as `FROM {table}`.
+  pub fn fill(&self, values: &BTreeMap<String, String>) -> String {
+    preamble::fill(&self.sql, values)
+  }
+
+  /// Whether `key` runs the command. Plain characters only do outside of text input, where `typing` is false.
+  pub fn is_bound_to(&self, key: &KeyEvent, typing: bool) -> bool {
+    let Some(binding) = self.key.as_deref().and_then(|raw| config::parse_key_event(raw).ok()) else {
+      return false;
+    };
+    let modifiers = |modifiers: KeyModifiers| modifiers - KeyModifiers::SHIFT;
+    let plain = matches!(binding.code, KeyCode::Char(_)) && modifiers(binding.modifiers).is_empty();
+    binding.code == key.code && modifiers(binding.modifiers) == modifiers(key.modifiers) && !(typing && plain)
+  }
+}
+
+/// Values of the placeholders of a command being run, asked for one at a time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingCommand {
+  pub command: CustomCommand,
+  pub values: BTreeMap<String, String>,
+}
+
+impl PendingCommand {
+  pub fn new(command: CustomCommand) -> Self {
+    Self { command, values: BTreeMap::new() }
+  }
+
+  /// The first placeholder that has no value yet.
+  pub fn missing(&self) -> Option<String> {
+    self.command.placeholders().into_iter().find(|name| !self.values.contains_key(name))
+  }
+
+  pub fn set(&mut self, name: &str, value: &str) {
+    self.values.insert(name.to_string(), value.to_string());
+  }
+
+  pub fn sql(&self) -> String {
+    self.command.fill(&self.values)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_pending_command() {
+    let command = CustomCommand {
+      name: "Rows of a user".to_string(),
+      sql: "SELECT * FROM {table} WHERE owner = '{user}' AND created_by = '{user}'".to_string(),
+      key: Some("ctrl-g".to_string()),
+    };
+    let mut pending = PendingCommand::new(command.clone());
+    assert_eq!(pending.missing(), Some("table".to_string()));
+    pending.set("table", "orders");
+    pending.set("user", "o'brien");
+    assert_eq!(pending.missing(), None);
+    assert_eq!(pending.sql(), "SELECT * FROM orders WHERE owner = 'o''brien' AND created_by = 'o''brien'");
+    pending.set("table", "orders; DROP TABLE users");
+    pending.set("user", "{table}");
+    assert_eq!(
+      pending.sql(),
+      "SELECT * FROM \"orders; DROP TABLE users\" WHERE owner = '{table}' AND created_by = '{table}'"
+    );
+
+    let terminate = CustomCommand {
+      name: "Terminate backends".to_string(),
+      sql: "SELECT pg_terminate_backend({pid}) FROM {table} LIMIT {n}".to_string(),
+      key: None,
+    };
+    let values = [("pid", "123"), ("table", "order"), ("n", "10")];
+    let values: BTreeMap<String, String> = values.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+    assert_eq!(terminate.fill(&values), "SELECT pg_terminate_backend(123) FROM \"order\" LIMIT 10");
+
+    assert!(command.is_bound_to(&KeyEvent::new(KeyCode::Char('g'), KeyModifiers::CONTROL), true));
+    assert!(!command.is_bound_to(&KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE), false));
+    let plain = CustomCommand { key: Some("shift-k".to_string()), ..command };
+    assert!(plain.is_bound_to(&KeyEvent::new(KeyCode::Char('K'), KeyModifiers::SHIFT), false));
+    assert!(!plain.is_bound_to(&KeyEvent::new(KeyCode::Char('K'), KeyModifiers::SHIFT), true));
+  }
+}
@@ -0,0 +1,179 @@
+/// Largest product of the two sequence lengths diffed exactly, beyond which lines are paired by position.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// A run of text within a diff line, `changed` when it differs from the other side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+  pub text: String,
+  pub changed: bool,
+}
+
+/// One row of a side-by-side diff. A side is `None` where the other side has a line it lacks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffRow {
+  pub left: Option<Vec<Segment>>,
+  pub right: Option<Vec<Segment>>,
+}
+
+impl DiffRow {
+  pub fn is_changed(&self) -> bool {
+    self.left.iter().chain(&self.right).flatten().any(|segment| segment.changed)
+      || self.left.is_none()
+      || self.right.is_none()
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+  Equal(usize, usize),
+  Delete(usize),
+  Insert(usize),
+}
+
+/// Side-by-side line diff of `left` and `right`, with the changed characters marked within lines that differ. JSON
+/// values are pretty printed first so differences in large documents land on their own lines.
+pub fn side_by_side(left: &str, right: &str) -> Vec<DiffRow> {
+  let (left, right) = (pretty_json(left), pretty_json(right));
+  let (left, right): (Vec<&str>, Vec<&str>) = (left.lines().collect(), right.lines().collect());
+  let mut rows = Vec::new();
+  let (mut deleted, mut inserted) = (Vec::new(), Vec::new());
+  for op in ops(&left, &right).into_iter().chain([Op::Equal(left.len(), right.len())]) {
+    match op {
+      Op::Delete(i) => deleted.push(left[i]),
+      Op::Insert(j) => inserted.push(right[j]),
+      Op::Equal(i, j) => {
+        for k in 0..deleted.len().max(inserted.len()) {
+          let row = match (deleted.get(k), inserted.get(k)) {
+            (Some(l), Some(r)) => {
+              let (l, r) = char_segments(l, r);
+              DiffRow { left: Some(l), right: Some(r) }
+            },
+            (l, r) => DiffRow { left: l.map(|l| changed(l)), right: r.map(|r| changed(r)) },
+          };
+          rows.push(row);
+        }
+        deleted.clear();
+        inserted.clear();
+        if let (Some(l), Some(r)) = (left.get(i), right.get(j)) {
+          rows.push(DiffRow { left: Some(unchanged(l)), right: Some(unchanged(r)) });
+        }
+      },
+    }
+  }
+  rows
+}
+
+fn pretty_json(value: &str) -> String {
+  match serde_json::from_str::<serde_json::Value>(value) {
+    Ok(json @ (serde_json::Value::Object(_) | serde_json::Value::Array(_))) => {
+      serde_json::to_string_pretty(&json).unwrap_or_else(|_| value.to_string())
+    },
+    _ => value.to_string(),
+  }
+}
+
+fn changed(text: &str) -> Vec<Segment> {
+  vec![Segment { text: text.to_string(), changed: true }]
+}
+
+fn unchanged(text: &str) -> Vec<Segment> {
+  vec![Segment { text: text.to_string(), changed: false }]
+}
+
+/// Segments of two differing lines, marking the characters that aren't common to both.
+fn char_segments(left: &str, right: &str) -> (Vec<Segment>, Vec<Segment>) {
+  let (l, r): (Vec<char>, Vec<char>) = (left.chars().collect(), right.chars().collect());
+  let (mut left_segments, mut right_segments) = (Vec::new(), Vec::new());
+  let push = |segments: &mut Vec<Segment>, c: char, changed: bool| {
+    match segments.last_mut() {
+      Some(last) if last.changed == changed => last.text.push(c),
+      _ => segments.push(Segment { text: c.to_string(), changed }),
+    }
+  };
+  for op in ops(&l, &r) {
+    match op {
+      Op::Equal(i, j) => {
+        push(&mut left_segments, l[i], false);
+        push(&mut right_segments, r[j], false);
+      },
+      Op::Delete(i) => push(&mut left_segments, l[i], true),
+      Op::Insert(j) => push(&mut right_segments, r[j], true),
+    }
+  }
+  (left_segments, right_segments)
+}
+
+/// Edit script turning `a` into `b` from their longest common subsequence. Inputs too large to diff exactly are
+/// compared position by position.
+fn ops<T: PartialEq>(a: &[T], b: &[T]) -> Vec<Op> {
+  if a.len().saturating_mul(b.len()) > MAX_DIFF_CELLS {
+    let mut ops = Vec::new();
+    for k in 0..a.len().max(b.len()) {
+      match (a.get(k), b.get(k)) {
+        (Some(x), Some(y)) if x == y => ops.push(Op::Equal(k, k)),
+        (x, y) => {
+          ops.extend(x.map(|_| Op::Delete(k)));
+          ops.extend(y.map(|_| Op::Insert(k)));
+        },
+      }
+    }
+    return ops;
+  }
+  // lengths[i][j] is the LCS length of a[i..] and b[j..].
+  let mut lengths = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+  for i in (0..a.len()).rev() {
+    for j in (0..b.len()).rev() {
+      lengths[i][j] = if a[i] == b[j] { lengths[i + 1][j + 1] + 1 } else { lengths[i + 1][j].max(lengths[i][j + 1]) };
+    }
+  }
+  let (mut i, mut j) = (0, 0);
+  let mut ops = Vec::new();
+  while i < a.len() || j < b.len() {
+    if i < a.len() && j < b.len() && a[i] == b[j] {
+      ops.push(Op::Equal(i, j));
+      i += 1;
+      j += 1;
+    } else if j == b.len() || (i < a.len() && lengths[i + 1][j] >= lengths[i][j + 1]) {
+      ops.push(Op::Delete(i));
+      i += 1;
+    } else {
+      ops.push(Op::Insert(j));
+      j += 1;
+    }
+  }
+  ops
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn text(segments: &Option<Vec<Segment>>) -> Option<String> {
+    segments.as_ref().map(|segments| {
+      segments.iter().map(|s| if s.changed { format!("[{}]", s.text) } else { s.text.clone() }).collect()
+    })
+  }
+
+  #[test]
+  fn test_side_by_side() {
+    let rows = side_by_side("a\nname: alice\nb", "a\nname: alicia\nc\nd");
+    let rendered: Vec<(Option<String>, Option<String>)> =
+      rows.iter().map(|row| (text(&row.left), text(&row.right))).collect();
+    assert_eq!(rendered, vec![
+      (Some("a".to_string()), Some("a".to_string())),
+      (Some("name: alic[e]".to_string()), Some("name: alic[ia]".to_string())),
+      (Some("[b]".to_string()), Some("[c]".to_string())),
+      (None, Some("[d]".to_string())),
+    ]);
+    assert!(!rows[0].is_changed());
+    assert!(rows[3].is_changed());
+  }
+
+  #[test]
+  fn test_json_is_pretty_printed() {
+    let rows = side_by_side(r#"{"a":1,"b":2}"#, r#"{"a":1,"b":3}"#);
+    assert_eq!(rows.iter().filter(|row| row.is_changed()).count(), 1);
+  }
+}
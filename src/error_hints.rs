@@ -0,0 +1,175 @@
+/// Something the error popup offers to do about an error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HintAction {
+  /// Focus the tables list, bound to `1` in the error popup.
+  BrowseTables,
+}
+
+/// A friendly explanation of a database error and what to try next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorHint {
+  /// SQLSTATE of the error.
+  pub code: &'static str,
+  pub title: &'static str,
+  pub hint: &'static str,
+  /// Lower cased message fragments of the same error from databases without SQLSTATEs, such as SQLite.
+  pub fragments: &'static [&'static str],
+  pub action: Option<HintAction>,
+}
+
+impl ErrorHint {
+  /// The hint broken into lines of at most `width` characters at spaces, for the error popup.
+  pub fn lines(&self, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in self.hint.split_whitespace() {
+      match lines.last_mut() {
+        Some(line) if line.chars().count() + 1 + word.chars().count() <= width => {
+          line.push(' ');
+          line.push_str(word);
+        },
+        _ => lines.push(word.to_string()),
+      }
+    }
+    lines
+  }
+}
+
+const fn hint(
+  code: &'static str,
+  title: &'static str,
+  hint: &'static str,
+  fragments: &'static [&'static str],
+) -> ErrorHint {
+  ErrorHint { code, title, hint, fragments, action: None }
+}
+
+const fn browse(
+  code: &'static str,
+  title: &'static str,
+  hint: &'static str,
+  fragments: &'static [&'static str],
+) -> ErrorHint {
+  ErrorHint { code, title, hint, fragments, action: Some(HintAction::BrowseTables) }
+}
+
+pub const HINTS: &[ErrorHint] = &[
+  browse(
+    "42P01",
+    "Table not found",
+    "Check the table name and its schema, the search_path decides which schemas unqualified names are looked up in \
+     (SHOW search_path). Press 1 to browse the tables.",
+    &["no such table"],
+  ),
+  browse(
+    "42703",
+    "Column not found",
+    "Check the column name, quoted identifiers are case sensitive. Press 1 to browse the tables and their columns.",
+    &["no such column", "unknown column"],
+  ),
+  hint(
+    "42601",
+    "Syntax error",
+    "Look just before the position the error points at, a missing comma, parenthesis or closing quote is the usual \
+     cause.",
+    &["syntax error"],
+  ),
+  hint(
+    "42883",
+    "Function or operator not found",
+    "No overload takes these argument types, add explicit casts such as ::text or ::int.",
+    &["no such function"],
+  ),
+  hint("42P07", "Table already exists", "Use CREATE TABLE IF NOT EXISTS, or drop the existing table first.", &[
+    "already exists",
+  ]),
+  hint(
+    "23505",
+    "Duplicate key",
+    "A row with this key already exists. Use INSERT ... ON CONFLICT to update it instead, or check the columns of the \
+     unique constraint.",
+    &["unique constraint failed", "duplicate entry"],
+  ),
+  hint(
+    "23503",
+    "Foreign key violation",
+    "The referenced row doesn't exist, or rows still reference the one being deleted. Insert the parent row first or \
+     delete the referencing rows.",
+    &["foreign key constraint failed"],
+  ),
+  hint(
+    "23502",
+    "Missing required value",
+    "A NOT NULL column got no value. Supply one, or give the column a default.",
+    &["not null constraint failed"],
+  ),
+  hint(
+    "22P02",
+    "Invalid input value",
+    "A literal doesn't parse as the column's type, check its quoting and add a cast if needed.",
+    &[],
+  ),
+  hint(
+    "42501",
+    "Permission denied",
+    "The connected role lacks privileges on the object. Ask for a GRANT or connect as another role.",
+    &["permission denied"],
+  ),
+  hint(
+    "57014",
+    "Query canceled",
+    "The statement was canceled or ran into statement_timeout. Narrow it down with a WHERE or LIMIT, or raise the \
+     timeout with SET statement_timeout.",
+    &[],
+  ),
+  hint(
+    "25P02",
+    "Transaction aborted",
+    "An earlier statement of this transaction failed, so the rest are ignored. Run ROLLBACK to continue.",
+    &[],
+  ),
+  hint("28P01", "Authentication failed", "Check the username and password of the connection in config.toml.", &[]),
+  hint("3D000", "Database not found", "Check the database name of the connection in config.toml.", &[]),
+];
+
+/// The SQLSTATE in an error message, as Postgres drivers report it in `code: "42P01"` or `SQLSTATE 42P01`.
+pub fn sqlstate(error: &str) -> Option<&str> {
+  ["code: \"", "SQLSTATE ", "SQLSTATE: ", "(SQLSTATE "].iter().find_map(|prefix| {
+    let start = error.find(prefix)? + prefix.len();
+    let code = error.get(start..start + 5)?;
+    code.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()).then_some(code)
+  })
+}
+
+/// The hint for an error, found by its SQLSTATE or by a fragment of its message.
+pub fn hint_for(error: &str) -> Option<&'static ErrorHint> {
+  if let Some(code) = sqlstate(error) {
+    return HINTS.iter().find(|hint| hint.code == code);
+  }
+  let error = error.to_lowercase();
+  HINTS.iter().find(|hint| hint.fragments.iter().any(|fragment| error.contains(fragment)))
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_hint_for() {
+    let postgres = r#"Error executing query: Database(PgDatabaseError { severity: Error, code: "42P01", message: "relation \"ordres\" does not exist" })"#;
+    assert_eq!(sqlstate(postgres), Some("42P01"));
+    assert_eq!(hint_for(postgres).map(|hint| hint.action), Some(Some(HintAction::BrowseTables)));
+    assert_eq!(
+      hint_for("error returned from database: (code: 1) no such column: nmae").map(|hint| hint.code),
+      Some("42703")
+    );
+    // A known SQLSTATE without a hint isn't matched by its message instead.
+    assert_eq!(hint_for(r#"code: "XX000", message: "syntax error in internal query""#), None);
+    assert_eq!(hint_for("Unable to save the note: disk full"), None);
+    assert_eq!(HINTS[4].lines(40), vec![
+      "Use CREATE TABLE IF NOT EXISTS, or drop".to_string(),
+      "the existing table first.".to_string()
+    ]);
+  }
+}
@@ -0,0 +1,103 @@
+use std::{
+  fs::{File, OpenOptions},
+  io::Write,
+  path::Path,
+  sync::{Arc, Mutex},
+  time::Duration,
+};
+
+use chrono::Local;
+use color_eyre::eyre::{eyre, Result};
+use serde::Serialize;
+
+/// Something the UI did that wrappers may want to observe, written as one JSON object per line.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+  Started {
+    version: String,
+  },
+  QueryStarted {
+    query: String,
+    attempt: u32,
+  },
+  QueryCompleted {
+    query: String,
+    duration_ms: u64,
+  },
+  QueryFailed {
+    query: String,
+    attempt: u32,
+    error: String,
+  },
+  /// A result set arrived in the grid.
+  Results {
+    columns: usize,
+    rows: usize,
+  },
+  Error {
+    message: String,
+  },
+  Exited,
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+  timestamp: String,
+  #[serde(flatten)]
+  event: &'a Event,
+}
+
+/// JSON-lines event output enabled with `--json-events`, shared by the app and its background tasks.
+#[derive(Debug, Clone)]
+pub struct EventLog {
+  file: Arc<Mutex<File>>,
+}
+
+impl EventLog {
+  /// Appends to `path`, which may be a regular file, a named pipe or an inherited descriptor such as `/dev/fd/3`.
+  pub fn open(path: &Path) -> Result<Self> {
+    let file = OpenOptions::new()
+      .create(true)
+      .append(true)
+      .open(path)
+      .map_err(|e| eyre!("Unable to open {} for JSON events: {e}", path.display()))?;
+    Ok(Self { file: Arc::new(Mutex::new(file)) })
+  }
+
+  /// Writes `event`. Failures are logged only, a reader going away must not disturb the UI.
+  pub fn emit(&self, event: Event) {
+    let line = to_line(&event, &Local::now().to_rfc3339());
+    let Ok(mut file) = self.file.lock() else {
+      return;
+    };
+    if let Err(e) = writeln!(file, "{line}").and_then(|_| file.flush()) {
+      log::warn!("Unable to write JSON event: {e}");
+    }
+  }
+}
+
+pub fn duration_ms(duration: Duration) -> u64 {
+  duration.as_millis() as u64
+}
+
+fn to_line(event: &Event, timestamp: &str) -> String {
+  serde_json::to_string(&Line { timestamp: timestamp.to_string(), event }).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_to_line() {
+    let event = Event::QueryCompleted { query: "SELECT 1".to_string(), duration_ms: 12 };
+    assert_eq!(
+      to_line(&event, "2024-05-01T10:00:00+00:00"),
+      r#"{"timestamp":"2024-05-01T10:00:00+00:00","event":"query_completed","query":"SELECT 1","duration_ms":12}"#
+    );
+    assert_eq!(to_line(&Event::Exited, "t"), r#"{"timestamp":"t","event":"exited"}"#);
+  }
+}
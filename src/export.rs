@@ -0,0 +1,696 @@
+use std::{
+  collections::BTreeMap,
+  fs::File,
+  io::{BufWriter, Write},
+  path::{Path, PathBuf},
+  process::Command,
+  sync::Arc,
+};
+
+use arrow::{
+  array::{ArrayRef, BooleanArray, Float64Array, Int64Array, StringArray},
+  datatypes::{DataType, Field, Schema},
+  record_batch::RecordBatch,
+};
+use chrono::Local;
+use clap::ValueEnum;
+use color_eyre::eyre::{eyre, Result};
+use parquet::arrow::ArrowWriter;
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter};
+
+use crate::{snippets::quote_literal, sql::Nulls, statement};
+
+/// Rows [`write_csv_chunks`] serializes between two progress reports.
+pub const CSV_CHUNK_ROWS: usize = 5000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Display, EnumIter, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+  #[default]
+  #[strum(serialize = "CSV")]
+  Csv,
+  #[strum(serialize = "Parquet")]
+  Parquet,
+  #[strum(serialize = "SQL INSERT dump")]
+  Sql,
+}
+
+impl ExportFormat {
+  pub fn extension(&self) -> &'static str {
+    match self {
+      Self::Csv => "csv",
+      Self::Parquet => "parquet",
+      Self::Sql => "sql",
+    }
+  }
+}
+
+/// Column type inferred from the textual cell values of a result set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnType {
+  Integer,
+  Float,
+  Boolean,
+  Text,
+}
+
+impl ColumnType {
+  pub fn sql_type(&self) -> &'static str {
+    match self {
+      ColumnType::Integer => "BIGINT",
+      ColumnType::Float => "DOUBLE PRECISION",
+      ColumnType::Boolean => "BOOLEAN",
+      ColumnType::Text => "TEXT",
+    }
+  }
+
+  /// `value` as a SQL literal of this type, `NULL` when it is NULL.
+  pub fn sql_literal(&self, value: Option<&str>) -> String {
+    let Some(value) = value else {
+      return "NULL".to_string();
+    };
+    match self {
+      ColumnType::Integer => value.to_string(),
+      ColumnType::Float if is_number(value) => value.to_string(),
+      // NaN and the infinities are only valid as quoted strings.
+      ColumnType::Float => format!("{}::double precision", quote_literal(value)),
+      ColumnType::Boolean => parse_bool(value).map_or("NULL", |b| if b { "TRUE" } else { "FALSE" }).to_string(),
+      ColumnType::Text => quote_literal(value),
+    }
+  }
+}
+
+/// Infers the narrowest type that fits every non-NULL value of each column, NULLs being `None`. Values only count as
+/// numbers or booleans when they are written the way the database prints them, so the text `02134` or `t` stays
+/// text. NaN and the infinities only count as floats in a column that also holds numbers.
+pub fn infer_column_types(column_count: usize, rows: &[Vec<Option<&String>>]) -> Vec<ColumnType> {
+  (0..column_count)
+    .map(|i| {
+      let values = || rows.iter().filter_map(|r| r.get(i).copied().flatten());
+      if values().next().is_none() {
+        ColumnType::Text
+      } else if values().all(|v| is_integer(v)) {
+        ColumnType::Integer
+      } else if values().all(|v| is_number(v) || is_non_finite(v)) && values().any(|v| is_number(v)) {
+        ColumnType::Float
+      } else if values().all(|v| parse_bool(v).is_some()) {
+        ColumnType::Boolean
+      } else {
+        ColumnType::Text
+      }
+    })
+    .collect()
+}
+
+/// An `i64` printed back exactly as `value`, without a plus sign or leading zeros.
+fn is_integer(value: &str) -> bool {
+  value.parse::<i64>().is_ok_and(|n| n.to_string() == value)
+}
+
+/// A finite decimal number like `-12`, `0.5` or `1e+20`, without leading zeros.
+fn is_number(value: &str) -> bool {
+  let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+  let unsigned = value.strip_prefix('-').unwrap_or(value);
+  let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+    Some((mantissa, exponent)) => (mantissa, Some(exponent.strip_prefix(['+', '-']).unwrap_or(exponent))),
+    None => (unsigned, None),
+  };
+  let (whole, fraction) = match mantissa.split_once('.') {
+    Some((whole, fraction)) => (whole, Some(fraction)),
+    None => (mantissa, None),
+  };
+  digits(whole)
+    && (whole == "0" || !whole.starts_with('0'))
+    && fraction.is_none_or(digits)
+    && exponent.is_none_or(digits)
+}
+
+/// `NaN`, `Infinity`, `-inf` and the other spellings of the values that aren't finite numbers.
+fn is_non_finite(value: &str) -> bool {
+  value.parse::<f64>().is_ok_and(|v| !v.is_finite())
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+  match value {
+    "true" => Some(true),
+    "false" => Some(false),
+    _ => None,
+  }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Delimiter {
+  #[default]
+  Comma,
+  Semicolon,
+  Tab,
+}
+
+impl Delimiter {
+  pub fn as_char(&self) -> char {
+    match self {
+      Self::Comma => ',',
+      Self::Semicolon => ';',
+      Self::Tab => '\t',
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CsvOptions {
+  pub delimiter: Delimiter,
+  /// Prefix the file with a UTF-8 byte order mark so Excel detects the encoding.
+  pub bom: bool,
+  /// Use `\r\n` line endings instead of `\n`.
+  pub crlf: bool,
+  pub header: bool,
+  /// What to write for NULL values. Empty strings are always written quoted so they stay distinguishable.
+  pub null_output: String,
+}
+
+impl Default for CsvOptions {
+  fn default() -> Self {
+    Self { delimiter: Delimiter::Comma, bom: false, crlf: false, header: true, null_output: String::new() }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SqlDumpOptions {
+  /// Emit a `CREATE TABLE` statement derived from the inferred column types before the inserts.
+  pub create_table: bool,
+  /// Table name used when it cannot be derived from the query, e.g. for joins.
+  pub default_table: String,
+}
+
+impl Default for SqlDumpOptions {
+  fn default() -> Self {
+    Self { create_table: true, default_table: "exported_rows".to_string() }
+  }
+}
+
+/// A named, reusable export destination selectable from the export menu.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportProfile {
+  pub format: ExportFormat,
+  /// Directory the file is written to, defaults to the export directory.
+  pub directory: Option<PathBuf>,
+  /// File name template supporting `{profile}`, `{table}`, `{date}`, `{time}` and `{ext}` placeholders.
+  pub filename: String,
+  /// Shell command run after a successful export, `{file}` is replaced with the exported path.
+  pub post_command: Option<String>,
+}
+
+impl Default for ExportProfile {
+  fn default() -> Self {
+    Self {
+      format: ExportFormat::Csv,
+      directory: None,
+      filename: "{profile}-{date}-{time}.{ext}".to_string(),
+      post_command: None,
+    }
+  }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportConfig {
+  /// Directory exports are written to, defaults to the current directory.
+  pub directory: Option<PathBuf>,
+  pub csv: CsvOptions,
+  pub sql: SqlDumpOptions,
+  pub profiles: BTreeMap<String, ExportProfile>,
+}
+
+impl ExportConfig {
+  /// Path for a new export file with the given extension, named after the current time.
+  pub fn next_path(&self, extension: &str) -> PathBuf {
+    self.directory().join(format!("query-crafter-{}.{}", Local::now().format("%Y%m%d-%H%M%S"), extension))
+  }
+
+  /// Writes `rows`, NULLs being `None`, to `path` in `format` using the configured CSV and SQL dump options. `table`
+  /// names the table in SQL dumps.
+  pub fn write(
+    &self,
+    format: ExportFormat,
+    path: &Path,
+    table: &str,
+    headers: &[&String],
+    rows: &[Vec<Option<&String>>],
+  ) -> Result<()> {
+    match format {
+      ExportFormat::Csv => write_csv_file(path, headers, rows, &self.csv),
+      ExportFormat::Parquet => write_parquet_file(path, headers, rows),
+      ExportFormat::Sql => write_sql_dump_file(path, table, headers, rows, &self.sql),
+    }
+  }
+
+  /// Writes `rows` with the profile `name` to a new file in its directory, created when missing, and returns the path.
+  pub fn write_profile(
+    &self,
+    name: &str,
+    profile: &ExportProfile,
+    table: &str,
+    headers: &[&String],
+    rows: &[Vec<Option<&String>>],
+  ) -> Result<PathBuf> {
+    let path = self.profile_path(name, profile, table);
+    if let Some(parent) = path.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+    self.write(profile.format, &path, table, headers, rows)?;
+    Ok(path)
+  }
+
+  /// Table name for exported file names and SQL dumps: the table `query` reads from, or the configured default.
+  pub fn table_name(&self, query: Option<&str>) -> String {
+    query.and_then(statement::from_table).unwrap_or_else(|| self.sql.default_table.to_string())
+  }
+
+  /// Path for a new export using the named profile's directory and file name template.
+  pub fn profile_path(&self, name: &str, profile: &ExportProfile, table: &str) -> PathBuf {
+    let directory = profile.directory.clone().unwrap_or_else(|| self.directory());
+    let now = Local::now();
+    let filename = render_filename(&profile.filename, &[
+      ("profile", name),
+      ("table", table),
+      ("date", &now.format("%Y%m%d").to_string()),
+      ("time", &now.format("%H%M%S").to_string()),
+      ("ext", profile.format.extension()),
+    ]);
+    directory.join(filename)
+  }
+
+  fn directory(&self) -> PathBuf {
+    self.directory.clone().unwrap_or_else(|| PathBuf::from("."))
+  }
+}
+
+/// Replaces `{name}` placeholders in `template` with their values.
+fn render_filename(template: &str, placeholders: &[(&str, &str)]) -> String {
+  placeholders.iter().fold(template.to_string(), |acc, (name, value)| {
+    acc.replace(&format!("{{{name}}}"), &value.replace(['/', '\\'], "_"))
+  })
+}
+
+/// Runs a profile's post-export command to completion, failing with its error output when it doesn't succeed.
+pub fn wait_for_post_command(command: &str, file: &Path) -> Result<()> {
+  let quoted = format!("'{}'", file.display().to_string().replace('\'', "'\\''"));
+  let command = command.replace("{file}", &quoted);
+  let output = Command::new("sh")
+    .arg("-c")
+    .arg(&command)
+    .output()
+    .map_err(|e| eyre!("Unable to run post-export command `{command}`: {e}"))?;
+  if output.status.success() {
+    Ok(())
+  } else {
+    Err(eyre!("Post-export command `{command}` failed: {}", String::from_utf8_lossy(&output.stderr)))
+  }
+}
+
+/// Writes an EXPLAIN (FORMAT JSON) plan pretty printed, as pev2 and explain.dalibo.com accept it.
+pub fn write_plan_file(path: &Path, plan: &str) -> Result<()> {
+  let plan: serde_json::Value = serde_json::from_str(plan)?;
+  std::fs::write(path, serde_json::to_string_pretty(&plan)?)?;
+  Ok(())
+}
+
+/// JSON with the plan and its query in the shape the explain.dalibo.com share API takes.
+pub fn plan_share_blob(title: &str, plan: &str, query: &str) -> Result<String> {
+  let blob = serde_json::json!({ "title": title, "plan": plan, "query": query });
+  Ok(serde_json::to_string_pretty(&blob)?)
+}
+
+/// Writes the rows as a single row group Parquet file with column types inferred from the values.
+pub fn write_parquet_file(path: &Path, headers: &[&String], rows: &[Vec<Option<&String>>]) -> Result<()> {
+  let types = infer_column_types(headers.len(), rows);
+  let fields = headers
+    .iter()
+    .zip(&types)
+    .map(|(header, column_type)| {
+      let data_type = match column_type {
+        ColumnType::Integer => DataType::Int64,
+        ColumnType::Float => DataType::Float64,
+        ColumnType::Boolean => DataType::Boolean,
+        ColumnType::Text => DataType::Utf8,
+      };
+      Field::new(header.as_str(), data_type, true)
+    })
+    .collect::<Vec<_>>();
+  let schema = Arc::new(Schema::new(fields));
+
+  let columns = types
+    .iter()
+    .enumerate()
+    .map(|(i, column_type)| {
+      let values = rows.iter().map(|r| r.get(i).copied().flatten().map(String::as_str));
+      match column_type {
+        ColumnType::Integer => {
+          Arc::new(values.map(|v| v.and_then(|v| v.parse::<i64>().ok())).collect::<Int64Array>()) as ArrayRef
+        },
+        ColumnType::Float => Arc::new(values.map(|v| v.and_then(|v| v.parse::<f64>().ok())).collect::<Float64Array>()),
+        ColumnType::Boolean => Arc::new(values.map(|v| v.and_then(parse_bool)).collect::<BooleanArray>()),
+        ColumnType::Text => Arc::new(values.collect::<StringArray>()),
+      }
+    })
+    .collect::<Vec<_>>();
+
+  let batch = RecordBatch::try_new(schema.clone(), columns)?;
+  let mut writer = ArrowWriter::try_new(File::create(path)?, schema, None)?;
+  writer.write(&batch)?;
+  writer.close()?;
+  Ok(())
+}
+
+pub fn write_sql_dump_file(
+  path: &Path,
+  table: &str,
+  headers: &[&String],
+  rows: &[Vec<Option<&String>>],
+  options: &SqlDumpOptions,
+) -> Result<()> {
+  let mut writer = BufWriter::new(File::create(path)?);
+  write_sql_dump(&mut writer, table, headers, rows, options)?;
+  writer.flush()?;
+  Ok(())
+}
+
+/// Writes the rows as one `INSERT` statement per row, optionally preceded by a `CREATE TABLE`.
+pub fn write_sql_dump<W: Write>(
+  writer: &mut W,
+  table: &str,
+  headers: &[&String],
+  rows: &[Vec<Option<&String>>],
+  options: &SqlDumpOptions,
+) -> Result<()> {
+  let types = infer_column_types(headers.len(), rows);
+  let table = quote_identifier(table);
+  let columns = headers.iter().map(|h| quote_identifier(h)).collect::<Vec<_>>();
+
+  if options.create_table {
+    let definitions = columns
+      .iter()
+      .zip(&types)
+      .map(|(column, column_type)| format!("  {column} {}", column_type.sql_type()))
+      .collect::<Vec<_>>()
+      .join(",\n");
+    writeln!(writer, "CREATE TABLE {table} (\n{definitions}\n);\n")?;
+  }
+
+  let column_list = columns.join(", ");
+  for row in rows {
+    let values = row
+      .iter()
+      .zip(&types)
+      .map(|(value, column_type)| column_type.sql_literal(value.map(String::as_str)))
+      .collect::<Vec<_>>()
+      .join(", ");
+    writeln!(writer, "INSERT INTO {table} ({column_list}) VALUES ({values});")?;
+  }
+
+  Ok(())
+}
+
+fn quote_identifier(ident: &str) -> String {
+  format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+pub fn write_csv_file(
+  path: &Path,
+  headers: &[&String],
+  rows: &[Vec<Option<&String>>],
+  options: &CsvOptions,
+) -> Result<()> {
+  let mut writer = BufWriter::new(File::create(path)?);
+  write_csv(&mut writer, headers, rows, options)?;
+  writer.flush()?;
+  Ok(())
+}
+
+pub fn write_csv<W: Write>(
+  writer: &mut W,
+  headers: &[&String],
+  rows: &[Vec<Option<&String>>],
+  options: &CsvOptions,
+) -> Result<()> {
+  let line_ending = if options.crlf { "\r\n" } else { "\n" };
+  let delimiter = options.delimiter.as_char().to_string();
+
+  if options.bom {
+    writer.write_all("\u{feff}".as_bytes())?;
+  }
+
+  if options.header {
+    let line = headers.iter().map(|h| quote_field(h, options)).collect::<Vec<_>>().join(&delimiter);
+    write!(writer, "{line}{line_ending}")?;
+  }
+
+  for row in rows {
+    let line = row
+      .iter()
+      .map(|value| {
+        match value {
+          None => options.null_output.clone(),
+          Some(value) if value.is_empty() => "\"\"".to_string(),
+          Some(value) => quote_field(value, options),
+        }
+      })
+      .collect::<Vec<_>>()
+      .join(&delimiter);
+    write!(writer, "{line}{line_ending}")?;
+  }
+
+  Ok(())
+}
+
+/// Writes the columns of `rows` at the indices of `order` as CSV, [`CSV_CHUNK_ROWS`] rows at a time, calling `progress`
+/// with the rows written so far after each chunk. `nulls` holds the NULL cells of `rows`.
+pub fn write_csv_chunks<W: Write>(
+  writer: &mut W,
+  headers: &[String],
+  rows: &[Vec<String>],
+  nulls: &Nulls,
+  order: &[usize],
+  options: &CsvOptions,
+  mut progress: impl FnMut(usize),
+) -> Result<()> {
+  let headers: Vec<&String> = order.iter().filter_map(|i| headers.get(*i)).collect();
+  for (i, chunk) in rows.chunks(CSV_CHUNK_ROWS).enumerate() {
+    let chunk: Vec<Vec<Option<&String>>> = chunk
+      .iter()
+      .enumerate()
+      .map(|(j, r)| {
+        let cells = nulls.cells(i * CSV_CHUNK_ROWS + j, r);
+        order.iter().filter_map(|i| cells.get(*i).copied()).collect()
+      })
+      .collect();
+    let options = CsvOptions { header: options.header && i == 0, bom: options.bom && i == 0, ..options.clone() };
+    write_csv(writer, &headers, &chunk, &options)?;
+    progress(((i + 1) * CSV_CHUNK_ROWS).min(rows.len()));
+  }
+  Ok(())
+}
+
+fn quote_field(value: &str, options: &CsvOptions) -> String {
+  let needs_quotes = value.contains(options.delimiter.as_char()) || value.contains(['"', '\r', '\n']);
+  if needs_quotes {
+    format!("\"{}\"", value.replace('"', "\"\""))
+  } else {
+    value.to_string()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  /// Owned rows with NULLs as `None`.
+  fn owned(rows: &[&[Option<&str>]]) -> Vec<Vec<Option<String>>> {
+    rows.iter().map(|r| r.iter().map(|c| c.map(String::from)).collect()).collect()
+  }
+
+  /// The rows as the export functions take them.
+  fn refs(rows: &[Vec<Option<String>>]) -> Vec<Vec<Option<&String>>> {
+    rows.iter().map(|r| r.iter().map(Option::as_ref).collect()).collect()
+  }
+
+  fn csv(headers: &[&str], rows: &[&[Option<&str>]], options: &CsvOptions) -> String {
+    let headers: Vec<String> = headers.iter().map(|h| h.to_string()).collect();
+    let rows = owned(rows);
+    let mut out = Vec::new();
+    write_csv(&mut out, &headers.iter().collect::<Vec<_>>(), &refs(&rows), options).unwrap();
+    String::from_utf8(out).unwrap()
+  }
+
+  #[test]
+  fn test_infer_column_types() {
+    let rows = owned(&[&[Some("1"), Some("1.5"), Some("true"), Some("x"), None], &[
+      None,
+      Some("2"),
+      Some("false"),
+      Some("3"),
+      None,
+    ]]);
+    assert_eq!(infer_column_types(5, &refs(&rows)), vec![
+      ColumnType::Integer,
+      ColumnType::Float,
+      ColumnType::Boolean,
+      ColumnType::Text,
+      ColumnType::Text
+    ]);
+  }
+
+  #[test]
+  fn test_infer_column_types_keeps_text() {
+    let column = |values: &[&str]| {
+      let rows: Vec<Vec<Option<String>>> = values.iter().map(|v| vec![Some(v.to_string())]).collect();
+      infer_column_types(1, &refs(&rows))[0]
+    };
+    assert_eq!(column(&["02134", "90210"]), ColumnType::Text);
+    assert_eq!(column(&["+5"]), ColumnType::Text);
+    assert_eq!(column(&["t", "f"]), ColumnType::Text);
+    assert_eq!(column(&["NaN", "inf"]), ColumnType::Text);
+    assert_eq!(column(&["007.5"]), ColumnType::Text);
+    assert_eq!(column(&["-0", "1e+20", "0.25"]), ColumnType::Float);
+    assert_eq!(column(&["1.5", "NaN", "-Infinity"]), ColumnType::Float);
+  }
+
+  #[test]
+  fn test_sql_literal() {
+    assert_eq!(ColumnType::Float.sql_literal(Some("-2.5e3")), "-2.5e3");
+    assert_eq!(ColumnType::Float.sql_literal(Some("NaN")), "'NaN'::double precision");
+    assert_eq!(ColumnType::Float.sql_literal(Some("-Infinity")), "'-Infinity'::double precision");
+    assert_eq!(ColumnType::Boolean.sql_literal(Some("false")), "FALSE");
+    assert_eq!(ColumnType::Text.sql_literal(Some("02134")), "'02134'");
+    assert_eq!(ColumnType::Text.sql_literal(Some("NULL")), "'NULL'");
+    assert_eq!(ColumnType::Integer.sql_literal(None), "NULL");
+  }
+
+  #[test]
+  fn test_plan_share_blob() {
+    let blob: serde_json::Value = serde_json::from_str(&plan_share_blob("t", "[{}]", "SELECT 1").unwrap()).unwrap();
+    assert_eq!(blob["plan"], "[{}]");
+    assert_eq!(blob["query"], "SELECT 1");
+  }
+
+  #[test]
+  fn test_render_filename() {
+    let name = render_filename("{profile}-{table}.{ext}", &[("profile", "daily"), ("table", "a/b"), ("ext", "csv")]);
+    assert_eq!(name, "daily-a_b.csv");
+  }
+
+  #[test]
+  fn test_sql_dump() {
+    let headers = ["id", "name", "zip", "score"].map(String::from);
+    let rows = owned(&[&[Some("1"), Some("o'neil"), Some("02134"), Some("NaN")], &[
+      Some("2"),
+      None,
+      Some("90210"),
+      Some("0.5"),
+    ]]);
+    let mut out = Vec::new();
+    write_sql_dump(&mut out, "users", &headers.iter().collect::<Vec<_>>(), &refs(&rows), &SqlDumpOptions::default())
+      .unwrap();
+    assert_eq!(
+      String::from_utf8(out).unwrap(),
+      "CREATE TABLE \"users\" (\n  \"id\" BIGINT,\n  \"name\" TEXT,\n  \"zip\" TEXT,\n  \"score\" DOUBLE \
+       PRECISION\n);\n\n\
+       INSERT INTO \"users\" (\"id\", \"name\", \"zip\", \"score\") VALUES (1, 'o''neil', '02134', \
+       'NaN'::double precision);\n\
+       INSERT INTO \"users\" (\"id\", \"name\", \"zip\", \"score\") VALUES (2, NULL, '90210', 0.5);\n"
+    );
+  }
+
+  #[test]
+  fn test_null_string_survives_export() {
+    let headers = ["nickname".to_string()];
+    let headers: Vec<&String> = headers.iter().collect();
+    let rows = owned(&[&[Some("NULL")], &[None]]);
+    let mut out = Vec::new();
+    write_sql_dump(&mut out, "people", &headers, &refs(&rows), &SqlDumpOptions {
+      create_table: false,
+      ..Default::default()
+    })
+    .unwrap();
+    assert_eq!(
+      String::from_utf8(out).unwrap(),
+      "INSERT INTO \"people\" (\"nickname\") VALUES ('NULL');\nINSERT INTO \"people\" (\"nickname\") VALUES (NULL);\n"
+    );
+    let options = CsvOptions { null_output: "\\N".to_string(), ..CsvOptions::default() };
+    assert_eq!(csv(&["nickname"], &[&[Some("NULL")], &[None]], &options), "nickname\nNULL\n\\N\n");
+  }
+
+  #[test]
+  fn test_parquet_round_trip() {
+    use arrow::array::Array;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReader;
+
+    let headers = ["id", "name", "score", "active"].map(String::from);
+    let rows =
+      owned(&[&[Some("1"), Some("NULL"), Some("0.5"), Some("true")], &[Some("2"), None, Some("NaN"), Some("false")]]);
+    let path = std::env::temp_dir().join(format!("query-crafter-export-{}.parquet", std::process::id()));
+    write_parquet_file(&path, &headers.iter().collect::<Vec<_>>(), &refs(&rows)).unwrap();
+
+    let reader = ParquetRecordBatchReader::try_new(File::open(&path).unwrap(), 1024).unwrap();
+    let batches = reader.collect::<std::result::Result<Vec<_>, _>>().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(batches.len(), 1);
+    let batch = &batches[0];
+    let types: Vec<DataType> = batch.schema().fields().iter().map(|f| f.data_type().clone()).collect();
+    assert_eq!(types, vec![DataType::Int64, DataType::Utf8, DataType::Float64, DataType::Boolean]);
+    let column = |i: usize| batch.column(i).as_any();
+    assert_eq!(column(0).downcast_ref::<Int64Array>().unwrap(), &Int64Array::from(vec![1, 2]));
+    assert_eq!(column(1).downcast_ref::<StringArray>().unwrap(), &StringArray::from(vec![Some("NULL"), None]));
+    let scores = column(2).downcast_ref::<Float64Array>().unwrap();
+    assert_eq!(scores.value(0), 0.5);
+    assert!(scores.value(1).is_nan());
+    assert_eq!(column(3).downcast_ref::<BooleanArray>().unwrap(), &BooleanArray::from(vec![true, false]));
+  }
+
+  #[test]
+  fn test_csv_defaults() {
+    let out =
+      csv(&["id", "name"], &[&[Some("1"), Some("a,b")], &[Some("2"), Some("say \"hi\"")]], &CsvOptions::default());
+    assert_eq!(out, "id,name\n1,\"a,b\"\n2,\"say \"\"hi\"\"\"\n");
+  }
+
+  #[test]
+  fn test_csv_nulls_and_empty_strings() {
+    let options = CsvOptions { null_output: "\\N".to_string(), ..CsvOptions::default() };
+    let out = csv(&["a", "b"], &[&[None, Some("")]], &options);
+    assert_eq!(out, "a,b\n\\N,\"\"\n");
+  }
+
+  #[test]
+  fn test_csv_chunks() {
+    let headers = vec!["id".to_string(), "name".to_string()];
+    let rows: Vec<Vec<String>> = (0..CSV_CHUNK_ROWS + 1).map(|i| vec![i.to_string(), "a".to_string()]).collect();
+    let mut nulls = Nulls::default();
+    nulls.insert(CSV_CHUNK_ROWS, 1);
+    let (mut out, mut progress) = (Vec::new(), Vec::new());
+    write_csv_chunks(&mut out, &headers, &rows, &nulls, &[1, 0], &CsvOptions::default(), |done| progress.push(done))
+      .unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.starts_with("name,id\na,0\na,1\n"));
+    assert!(out.ends_with(&format!("\n,{}\n", CSV_CHUNK_ROWS)));
+    assert_eq!(out.lines().count(), CSV_CHUNK_ROWS + 2);
+    assert_eq!(progress, vec![CSV_CHUNK_ROWS, CSV_CHUNK_ROWS + 1]);
+  }
+
+  #[test]
+  fn test_csv_options() {
+    let options =
+      CsvOptions { delimiter: Delimiter::Semicolon, bom: true, crlf: true, header: false, ..CsvOptions::default() };
+    let out = csv(&["a", "b"], &[&[Some("1;2"), Some("3")]], &options);
+    assert_eq!(out, "\u{feff}\"1;2\";3\r\n");
+  }
+}
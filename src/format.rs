@@ -0,0 +1,625 @@
+use serde::{Deserialize, Serialize};
+
+use crate::statement::dollar_tag;
+
+/// SQL formatting options, the `[format]` config table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FormatConfig {
+  /// Write keywords in upper case. Identifiers are never changed.
+  pub uppercase_keywords: bool,
+  /// Spaces per nesting level of subqueries and continued conditions.
+  pub indent: usize,
+  /// Leave dollar-quoted bodies (function definitions, `DO` blocks) exactly as written.
+  pub skip_dollar_quoted: bool,
+}
+
+impl Default for FormatConfig {
+  fn default() -> Self {
+    Self { uppercase_keywords: true, indent: 2, skip_dollar_quoted: true }
+  }
+}
+
+/// Keywords that start a clause on a new line.
+const CLAUSES: &[&str] = &[
+  "SELECT",
+  "FROM",
+  "WHERE",
+  "GROUP",
+  "ORDER",
+  "HAVING",
+  "LIMIT",
+  "OFFSET",
+  "UNION",
+  "EXCEPT",
+  "INTERSECT",
+  "JOIN",
+  "LEFT",
+  "RIGHT",
+  "INNER",
+  "FULL",
+  "CROSS",
+  "VALUES",
+  "SET",
+  "RETURNING",
+  "INSERT",
+  "UPDATE",
+  "DELETE",
+  "WITH",
+  "WINDOW",
+];
+
+const KEYWORDS: &[&str] = &[
+  "ALL",
+  "ALTER",
+  "AND",
+  "AS",
+  "ASC",
+  "BEGIN",
+  "BETWEEN",
+  "BY",
+  "CASCADE",
+  "CASE",
+  "COMMIT",
+  "CONFLICT",
+  "CONSTRAINT",
+  "CREATE",
+  "CROSS",
+  "DEFAULT",
+  "DELETE",
+  "DESC",
+  "DISTINCT",
+  "DO",
+  "DROP",
+  "ELSE",
+  "END",
+  "EXCEPT",
+  "EXISTS",
+  "EXPLAIN",
+  "FALSE",
+  "FILTER",
+  "FIRST",
+  "FOR",
+  "FROM",
+  "FULL",
+  "FUNCTION",
+  "GROUP",
+  "HAVING",
+  "ILIKE",
+  "IN",
+  "INDEX",
+  "INNER",
+  "INSERT",
+  "INTERSECT",
+  "INTO",
+  "IS",
+  "JOIN",
+  "LANGUAGE",
+  "LAST",
+  "LATERAL",
+  "LEFT",
+  "LIKE",
+  "LIMIT",
+  "NOT",
+  "NOTHING",
+  "NULL",
+  "NULLS",
+  "OFFSET",
+  "ON",
+  "OR",
+  "ORDER",
+  "OUTER",
+  "OVER",
+  "PARTITION",
+  "PRIMARY",
+  "RECURSIVE",
+  "REFERENCES",
+  "REPLACE",
+  "RETURNING",
+  "RETURNS",
+  "RIGHT",
+  "ROLLBACK",
+  "SELECT",
+  "SET",
+  "TABLE",
+  "THEN",
+  "TRUE",
+  "UNION",
+  "UNIQUE",
+  "UPDATE",
+  "USING",
+  "VALUES",
+  "VIEW",
+  "WHEN",
+  "WHERE",
+  "WINDOW",
+  "WITH",
+];
+
+/// Words after which `JOIN` or `SELECT` continue the clause instead of starting a new line.
+const CLAUSE_CONTINUATIONS: &[&str] = &["LEFT", "RIGHT", "INNER", "FULL", "CROSS", "OUTER", "NATURAL", "LATERAL"];
+
+/// Characters Postgres builds operators from.
+const OPERATOR_CHARS: &str = "+-*/<>=~!@#%^&|`?";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+  Word(String),
+  /// Numbers, string literals, quoted identifiers and parameters, written as is.
+  Literal(String),
+  /// A dollar-quoted string with its opening tag and body.
+  DollarQuoted {
+    tag: String,
+    body: String,
+  },
+  LineComment(String),
+  BlockComment(String),
+  Operator(String),
+  Comma,
+  Open,
+  Close,
+  Dot,
+  Semicolon,
+}
+
+/// Formats every statement in `sql`: clauses on their own lines, nested subqueries indented and keywords upper
+/// cased. Comments, literals, quoted identifiers, dollar-quoted bodies and operators are kept as written.
+pub fn format_all(sql: &str, config: &FormatConfig) -> String {
+  let mut formatter = Formatter { config, out: String::new(), depth: 0, parens: Vec::new(), between: false };
+  let tokens = tokenize(sql);
+  for (i, token) in tokens.iter().enumerate() {
+    formatter.push(token, i.checked_sub(1).map(|p| &tokens[p]));
+  }
+  formatter.out.trim_end().to_string()
+}
+
+struct Formatter<'a> {
+  config: &'a FormatConfig,
+  out: String,
+  depth: usize,
+  /// For each open parenthesis, whether it holds a subquery.
+  parens: Vec<bool>,
+  /// Inside `BETWEEN x AND y`, where the `AND` doesn't start a condition.
+  between: bool,
+}
+
+impl Formatter<'_> {
+  fn push(&mut self, token: &Token, previous: Option<&Token>) {
+    let at_line_start = self.out.is_empty() || self.out.ends_with('\n') || self.out.ends_with(' ') && self.line_blank();
+    match token {
+      Token::Word(word) => {
+        let upper = word.to_uppercase();
+        let keyword = KEYWORDS.contains(&upper.as_str());
+        let text = if keyword && self.config.uppercase_keywords { upper.clone() } else { word.clone() };
+        let previous_word = match previous {
+          Some(Token::Word(w)) => w.to_uppercase(),
+          _ => String::new(),
+        };
+        let in_query = self.parens.last().is_none_or(|subquery| *subquery);
+        let after_open = matches!(previous, Some(Token::Open));
+        let continues = CLAUSE_CONTINUATIONS.contains(&previous_word.as_str())
+          || (upper == "FROM" && previous_word == "DELETE")
+          || (upper == "SELECT" && previous_word == "AS")
+          || (upper == "SET" && previous_word == "UPDATE")
+          || (upper == "SET" && previous_word == "DO")
+          || (upper == "UPDATE" && previous_word == "DO")
+          || (upper == "UPDATE" && previous_word == "FOR");
+        if CLAUSES.contains(&upper.as_str()) && in_query && !after_open && !continues && !at_line_start {
+          self.newline(0);
+        } else if (upper == "AND" || upper == "OR") && in_query && !self.between {
+          self.newline(self.config.indent);
+        } else {
+          self.space(previous);
+        }
+        if upper == "BETWEEN" {
+          self.between = true;
+        } else if upper == "AND" {
+          self.between = false;
+        }
+        self.out.push_str(&text);
+      },
+      Token::Literal(text) | Token::BlockComment(text) => {
+        self.space(previous);
+        self.out.push_str(text);
+      },
+      Token::DollarQuoted { tag, body } => {
+        self.space(previous);
+        self.out.push_str(tag);
+        if self.config.skip_dollar_quoted {
+          self.out.push_str(body);
+        } else {
+          let indent = " ".repeat((self.depth + 1) * self.config.indent);
+          let formatted = format_all(body, self.config);
+          let lines: Vec<String> =
+            formatted.lines().map(|line| format!("{indent}{line}").trim_end().to_string()).collect();
+          self.out.push('\n');
+          self.out.push_str(&lines.join("\n"));
+          self.newline(0);
+        }
+        self.out.push_str(tag);
+      },
+      Token::LineComment(text) => {
+        self.space(previous);
+        self.out.push_str(text);
+        self.newline(0);
+      },
+      Token::Operator(op) => {
+        if op != "::" {
+          self.space(previous);
+        }
+        self.out.push_str(op);
+      },
+      Token::Comma => self.out.push(','),
+      Token::Dot => self.out.push('.'),
+      Token::Open => {
+        let function_call = matches!(previous, Some(Token::Word(w)) if !KEYWORDS.contains(&w.to_uppercase().as_str()))
+          || matches!(previous, Some(Token::Literal(_)));
+        if !function_call {
+          self.space(previous);
+        }
+        self.out.push('(');
+        self.parens.push(false);
+        self.depth += 1;
+      },
+      Token::Close => {
+        if self.parens.pop() == Some(true) {
+          self.depth -= 1;
+          self.newline(0);
+        } else {
+          self.depth = self.depth.saturating_sub(1);
+        }
+        self.out.push(')');
+      },
+      Token::Semicolon => {
+        self.out.truncate(self.out.trim_end().len());
+        self.out.push_str(";\n\n");
+        self.depth = 0;
+        self.parens.clear();
+        self.between = false;
+      },
+    }
+    // A subquery starts on its own line, indented one level deeper.
+    if let (Token::Word(word), Some(Token::Open)) = (token, previous) {
+      if word.eq_ignore_ascii_case("SELECT") || word.eq_ignore_ascii_case("WITH") {
+        if let Some(subquery) = self.parens.last_mut() {
+          *subquery = true;
+          let start = self.out.len() - word.len();
+          let indent = " ".repeat(self.depth * self.config.indent);
+          self.out.insert_str(start, &format!("\n{indent}"));
+        }
+      }
+    }
+  }
+
+  fn line_blank(&self) -> bool {
+    self.out.rsplit('\n').next().is_some_and(|line| line.trim().is_empty())
+  }
+
+  fn newline(&mut self, extra: usize) {
+    self.out.truncate(self.out.trim_end_matches(' ').len());
+    if !self.out.is_empty() && !self.out.ends_with('\n') {
+      self.out.push('\n');
+    }
+    self.out.push_str(&" ".repeat(self.depth * self.config.indent + extra));
+  }
+
+  /// Separates the next token from `previous`, except after an opening parenthesis, a dot or a cast.
+  fn space(&mut self, previous: Option<&Token>) {
+    let joined = match previous {
+      None | Some(Token::Open) | Some(Token::Dot) => true,
+      Some(Token::Operator(op)) => op == "::",
+      _ => self.out.is_empty() || self.out.ends_with('\n') || self.out.ends_with(' '),
+    };
+    if !joined {
+      self.out.push(' ');
+    }
+  }
+}
+
+fn tokenize(sql: &str) -> Vec<Token> {
+  attach_subscripts(lex(sql).into_iter().map(|(token, _)| token).collect())
+}
+
+/// Splits `sql` into tokens with the character offset each starts at. Whitespace is dropped, comments are kept.
+pub fn lex(sql: &str) -> Vec<(Token, usize)> {
+  let chars: Vec<char> = sql.chars().collect();
+  let mut tokens: Vec<(Token, usize)> = Vec::new();
+  let mut i = 0;
+  let text = |from: usize, to: usize| chars[from..to.min(chars.len())].iter().collect::<String>();
+  while i < chars.len() {
+    let c = chars[i];
+    let next = chars.get(i + 1).copied();
+    let start = i;
+    let token = match c {
+      c if c.is_whitespace() => {
+        i += 1;
+        continue;
+      },
+      '-' if next == Some('-') => {
+        while i < chars.len() && chars[i] != '\n' {
+          i += 1;
+        }
+        Token::LineComment(text(start, i).trim_end().to_string())
+      },
+      '/' if next == Some('*') => {
+        // Postgres block comments nest.
+        let mut depth = 0;
+        while i < chars.len() {
+          if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+            depth += 1;
+            i += 2;
+          } else if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+              break;
+            }
+          } else {
+            i += 1;
+          }
+        }
+        Token::BlockComment(text(start, i))
+      },
+      '\'' | '"' => {
+        i = quoted_end(&chars, i, c);
+        Token::Literal(text(start, i))
+      },
+      '$' => {
+        match dollar_tag(&chars, i) {
+          Some(tag) => {
+            let body_start = i + tag.chars().count();
+            let tag_chars: Vec<char> = tag.chars().collect();
+            let body_end =
+              (body_start..chars.len()).find(|&j| chars[j..].starts_with(&tag_chars)).unwrap_or(chars.len());
+            i = (body_end + tag_chars.len()).min(chars.len());
+            Token::DollarQuoted { tag, body: text(body_start, body_end) }
+          },
+          None => {
+            i += 1;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+              i += 1;
+            }
+            Token::Literal(text(start, i))
+          },
+        }
+      },
+      ':' if next == Some(':') => {
+        i += 2;
+        Token::Operator("::".to_string())
+      },
+      ':' if next.is_some_and(|n| n.is_alphabetic() || n == '_' || n == '\'' || n == '"') => {
+        // psql variables: `:name`, `:'name'` and `:"name"`.
+        i += 1;
+        i = match chars[i] {
+          q @ ('\'' | '"') => quoted_end(&chars, i, q),
+          _ => word_end(&chars, i),
+        };
+        Token::Literal(text(start, i))
+      },
+      c if c.is_ascii_digit() || (c == '.' && next.is_some_and(|n| n.is_ascii_digit())) => {
+        i = number_end(&chars, i + 1);
+        Token::Literal(text(start, i))
+      },
+      '+' | '-' if next.is_some_and(|n| n.is_ascii_digit()) && is_unary(tokens.last().map(|(token, _)| token)) => {
+        i = number_end(&chars, i + 1);
+        Token::Literal(text(start, i))
+      },
+      c if c.is_alphabetic() || c == '_' => {
+        i = word_end(&chars, i);
+        // String prefixes: E'..', B'..', X'..', U&'..' and N'..'.
+        let word = text(start, i);
+        let prefixed = matches!(word.to_uppercase().as_str(), "E" | "B" | "X" | "N" | "U");
+        if prefixed && chars.get(i) == Some(&'\'') {
+          i = quoted_end(&chars, i, '\'');
+          Token::Literal(text(start, i))
+        } else if word.eq_ignore_ascii_case("U") && chars.get(i) == Some(&'&') && chars.get(i + 1) == Some(&'\'') {
+          i = quoted_end(&chars, i + 1, '\'');
+          Token::Literal(text(start, i))
+        } else {
+          Token::Word(word)
+        }
+      },
+      ',' => {
+        i += 1;
+        Token::Comma
+      },
+      '(' | '[' => {
+        i += 1;
+        if c == '(' {
+          Token::Open
+        } else {
+          Token::Operator("[".to_string())
+        }
+      },
+      ')' => {
+        i += 1;
+        Token::Close
+      },
+      ']' => {
+        i += 1;
+        Token::Literal("]".to_string())
+      },
+      '.' => {
+        i += 1;
+        Token::Dot
+      },
+      ';' => {
+        i += 1;
+        Token::Semicolon
+      },
+      c if OPERATOR_CHARS.contains(c) => {
+        i = operator_end(&chars, i);
+        Token::Operator(text(start, i))
+      },
+      _ => {
+        i += 1;
+        Token::Literal(text(start, i))
+      },
+    };
+    tokens.push((token, start));
+  }
+  tokens
+}
+
+/// Joins array subscripts (`a[1]`, `a[1:2]`) to the expression they index, so they aren't spaced out.
+fn attach_subscripts(tokens: Vec<Token>) -> Vec<Token> {
+  let mut joined: Vec<Token> = Vec::new();
+  let mut subscript: Option<String> = None;
+  for token in tokens {
+    match (&mut subscript, token) {
+      (None, Token::Operator(op)) if op == "[" => subscript = Some(op),
+      (Some(text), Token::Literal(close)) if close == "]" => {
+        text.push(']');
+        let text = subscript.take().unwrap_or_default();
+        match joined.pop() {
+          Some(Token::Word(w)) | Some(Token::Literal(w)) => joined.push(Token::Literal(format!("{w}{text}"))),
+          Some(other) => {
+            joined.push(other);
+            joined.push(Token::Literal(text));
+          },
+          None => joined.push(Token::Literal(text)),
+        }
+      },
+      (Some(text), token) => text.push_str(&token_text(&token)),
+      (None, token) => joined.push(token),
+    }
+  }
+  if let Some(text) = subscript {
+    joined.push(Token::Literal(text));
+  }
+  joined
+}
+
+fn token_text(token: &Token) -> String {
+  match token {
+    Token::Word(s) | Token::Literal(s) | Token::LineComment(s) | Token::BlockComment(s) | Token::Operator(s) => {
+      s.clone()
+    },
+    Token::DollarQuoted { tag, body } => format!("{tag}{body}{tag}"),
+    Token::Comma => ",".to_string(),
+    Token::Open => "(".to_string(),
+    Token::Close => ")".to_string(),
+    Token::Dot => ".".to_string(),
+    Token::Semicolon => ";".to_string(),
+  }
+}
+
+/// Whether a sign after `previous` belongs to the number that follows it, as in `x > -1` or `(-1, +2)`.
+fn is_unary(previous: Option<&Token>) -> bool {
+  match previous {
+    Some(Token::Word(word)) => KEYWORDS.contains(&word.to_uppercase().as_str()),
+    Some(Token::Literal(_)) | Some(Token::DollarQuoted { .. }) | Some(Token::Close) => false,
+    _ => true,
+  }
+}
+
+fn number_end(chars: &[char], mut i: usize) -> usize {
+  while i < chars.len() {
+    let c = chars[i];
+    let exponent_sign = (c == '+' || c == '-') && matches!(chars[i - 1], 'e' | 'E');
+    if c.is_ascii_alphanumeric() || c == '.' || c == '_' || exponent_sign {
+      i += 1;
+    } else {
+      break;
+    }
+  }
+  i
+}
+
+fn word_end(chars: &[char], mut i: usize) -> usize {
+  while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '$') {
+    i += 1;
+  }
+  i
+}
+
+/// End of a quoted string or identifier starting at `i`, where a doubled quote is an escaped one.
+fn quoted_end(chars: &[char], mut i: usize, quote: char) -> usize {
+  i += 1;
+  while i < chars.len() {
+    if chars[i] == quote {
+      if chars.get(i + 1) == Some(&quote) {
+        i += 2;
+        continue;
+      }
+      return i + 1;
+    }
+    i += 1;
+  }
+  i
+}
+
+/// End of an operator starting at `i`, following the Postgres lexer: the longest run of operator characters that
+/// doesn't start a comment, where a trailing `+` or `-` belongs to the next token unless the operator contains one of
+/// `~!@#%^&|`?`.
+fn operator_end(chars: &[char], start: usize) -> usize {
+  let mut end = start;
+  while end < chars.len() && OPERATOR_CHARS.contains(chars[end]) {
+    if end > start
+      && ((chars[end] == '-' && chars.get(end + 1) == Some(&'-'))
+        || (chars[end] == '/' && chars.get(end + 1) == Some(&'*')))
+    {
+      break;
+    }
+    end += 1;
+  }
+  let special = chars[start..end].iter().any(|c| "~!@#%^&|`?".contains(*c));
+  while end - start > 1 && !special && matches!(chars[end - 1], '+' | '-') {
+    end -= 1;
+  }
+  end
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_format_clauses() {
+    let sql =
+      "select u.id, count(*) from users u left join orders o on o.user_id = u.id where u.age between 18 and 30 \
+               and u.name ilike 'a%' group by u.id order by 2 desc limit 10";
+    assert_eq!(
+      format_all(sql, &FormatConfig::default()),
+      "SELECT u.id, count(*)\nFROM users u\nLEFT JOIN orders o ON o.user_id = u.id\nWHERE u.age BETWEEN 18 AND 30\n  \
+       AND u.name ILIKE 'a%'\nGROUP BY u.id\nORDER BY 2 DESC\nLIMIT 10"
+    );
+  }
+
+  #[test]
+  fn test_format_preserves_comments_and_operators() {
+    let sql = "-- active users\nselect data->>'name', tags @> array['x'], id::text /* cast */ from t where a !~* 'B' \
+               and b <-> c < 0.5 and x>-1; select 2";
+    assert_eq!(
+      format_all(sql, &FormatConfig::default()),
+      "-- active users\nSELECT data ->> 'name', tags @> array['x'], id::text /* cast */\nFROM t\nWHERE a !~* 'B'\n  AND \
+       b <-> c < 0.5\n  AND x > -1;\n\nSELECT 2"
+    );
+  }
+
+  #[test]
+  fn test_format_dollar_quoted() {
+    let sql = "create function f() returns int as $fn$ select  1 -- one\n  from t $fn$ language sql";
+    let config = FormatConfig::default();
+    assert_eq!(
+      format_all(sql, &config),
+      "CREATE FUNCTION f() RETURNS int AS $fn$ select  1 -- one\n  from t $fn$ LANGUAGE sql"
+    );
+    let config = FormatConfig { skip_dollar_quoted: false, ..config };
+    assert_eq!(
+      format_all(sql, &config),
+      "CREATE FUNCTION f() RETURNS int AS $fn$\n  SELECT 1 -- one\n  FROM t\n$fn$ LANGUAGE sql"
+    );
+  }
+
+  #[test]
+  fn test_format_subquery() {
+    let sql = "select * from (select id from t) s where id in (1, 2)";
+    assert_eq!(
+      format_all(sql, &FormatConfig::default()),
+      "SELECT *\nFROM (\n  SELECT id\n  FROM t\n) s\nWHERE id IN (1, 2)"
+    );
+  }
+}
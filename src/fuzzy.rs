@@ -0,0 +1,48 @@
+/// Score of `text` matching `query` as a case insensitive subsequence, higher for matches that are contiguous or
+/// start words, or `None` when some character of the query doesn't occur in order.
+pub fn score(query: &str, text: &str) -> Option<i64> {
+  let text: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+  let mut score = 0;
+  let mut position = 0;
+  let mut previous: Option<usize> = None;
+  for c in query.chars().flat_map(char::to_lowercase) {
+    let found = position + text[position..].iter().position(|t| *t == c)?;
+    score += 1;
+    if previous.is_some_and(|p| p + 1 == found) {
+      score += 5;
+    }
+    if found == 0 || !text[found - 1].is_alphanumeric() {
+      score += 3;
+    }
+    // Skipped characters count against the match, so tighter matches rank first.
+    score -= (found - position) as i64;
+    previous = Some(found);
+    position = found + 1;
+  }
+  Some(score)
+}
+
+/// Indices of the `candidates` matching `query`, best first and in their given order among equal scores.
+pub fn rank<'a>(query: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<usize> {
+  let mut matches: Vec<(usize, i64)> =
+    candidates.into_iter().enumerate().filter_map(|(i, text)| score(query, text).map(|score| (i, score))).collect();
+  matches.sort_by_key(|(i, score)| (-score, *i));
+  matches.into_iter().map(|(i, _)| i).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_rank() {
+    let columns = ["id", "created_at", "customer_id", "updated_at", "cat"];
+    assert_eq!(rank("cat", columns), vec![4, 1]);
+    assert_eq!(rank("uat", columns), vec![3]);
+    assert_eq!(rank("CID", columns), vec![2]);
+    assert_eq!(rank("", columns), vec![0, 1, 2, 3, 4]);
+    assert_eq!(score("xyz", "id"), None);
+  }
+}
@@ -0,0 +1,491 @@
+use std::{
+  collections::{HashMap, HashSet},
+  fs::OpenOptions,
+  io::Write,
+  path::{Path, PathBuf},
+  time::Duration,
+};
+
+use chrono::{Local, TimeZone};
+use color_eyre::eyre::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  autocomplete::{self, Suggestion, SuggestionKind},
+  tags,
+};
+
+pub const HISTORY_FILE: &str = "history.jsonl";
+
+/// Version of the history file format, written as its first line. Version 1 files have no header line and entries
+/// without the outcome fields.
+const HISTORY_VERSION: u32 = 2;
+
+/// Entries kept in memory and mined for suggestions; older lines stay in the file.
+const HISTORY_LIMIT: usize = 500;
+
+/// Keywords ending a WHERE clause.
+const CLAUSE_ENDS: &[&str] = &["GROUP", "ORDER", "LIMIT", "OFFSET", "HAVING", "RETURNING", "UNION"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileHeader {
+  history_version: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+  pub query: String,
+  /// Unix timestamp in seconds.
+  pub executed_at: i64,
+  /// Free-text annotation, e.g. what the query was for.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub note: Option<String>,
+  /// Rows returned, once the statement completed.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub row_count: Option<usize>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub execution_time_ms: Option<u64>,
+  /// Error message, when the statement failed.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub error: Option<String>,
+  /// Database the statement ran against: a SQLite file or a Postgres URL without its password.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub connection: Option<String>,
+}
+
+impl HistoryEntry {
+  pub fn new(query: String, executed_at: i64) -> Self {
+    Self { query, executed_at, ..Self::default() }
+  }
+
+  /// Row count or error and duration of the run, e.g. `42 rows in 12 ms`.
+  pub fn outcome(&self) -> Option<String> {
+    let result = match (&self.error, self.row_count) {
+      (Some(error), _) => format!("failed: {}", error.lines().next().unwrap_or_default()),
+      (None, Some(rows)) => format!("{rows} row{}", if rows == 1 { "" } else { "s" }),
+      (None, None) => return None,
+    };
+    Some(match self.execution_time_ms {
+      Some(ms) => format!("{result} in {ms} ms"),
+      None => result,
+    })
+  }
+
+  /// `#tags` of the note and of the query's comments.
+  pub fn tags(&self) -> Vec<String> {
+    let mut tags = tags::parse(self.note.as_deref().unwrap_or_default());
+    for tag in tags::in_comments(&self.query) {
+      if !tags.contains(&tag) {
+        tags.push(tag);
+      }
+    }
+    tags
+  }
+
+  /// Whether the entry has every `#tag` of `search` and its query or note contains the rest, ignoring case.
+  pub fn matches(&self, search: &str) -> bool {
+    let (wanted, text) = tags::split_search(search);
+    let text = text.to_lowercase();
+    let tags = if wanted.is_empty() { Vec::new() } else { self.tags() };
+    wanted.iter().all(|tag| tags.contains(tag))
+      && (self.query.to_lowercase().contains(&text)
+        || self.note.as_ref().is_some_and(|n| n.to_lowercase().contains(&text)))
+  }
+}
+
+/// Executed statements, oldest first, persisted as JSON lines in the data directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryHistory {
+  entries: Vec<HistoryEntry>,
+  path: Option<PathBuf>,
+  /// Recorded with new entries.
+  connection: Option<String>,
+}
+
+impl QueryHistory {
+  /// Loads the history file from the data directory, skipping lines that don't parse. Files in an older format are
+  /// migrated first.
+  pub fn load() -> Self {
+    let path = crate::dirs::get_data_dir().join(HISTORY_FILE);
+    if let Err(e) = migrate(&path) {
+      log::warn!("Failed to migrate query history {}: {e}", path.display());
+    }
+    let mut history = Self::read(&path).unwrap_or_else(|e| {
+      log::warn!("Failed to read query history {}: {e}", path.display());
+      Self::default()
+    });
+    history.path = Some(path);
+    history
+  }
+
+  fn read(path: &Path) -> Result<Self> {
+    let mut entries = read_entries(path)?;
+    entries.drain(..entries.len().saturating_sub(HISTORY_LIMIT));
+    Ok(Self { entries, ..Self::default() })
+  }
+
+  /// Sets the connection recorded with new entries.
+  pub fn set_connection(&mut self, connection: impl Into<String>) {
+    self.connection = Some(connection.into());
+  }
+
+  pub fn entries(&self) -> &[HistoryEntry] {
+    &self.entries
+  }
+
+  /// Remembers an executed statement and appends it to the history file.
+  pub fn record(&mut self, query: &str) {
+    let query = query.trim();
+    if query.is_empty() {
+      return;
+    }
+    let entry = HistoryEntry {
+      connection: self.connection.clone(),
+      ..HistoryEntry::new(query.to_string(), Local::now().timestamp())
+    };
+    if let Some(path) = &self.path {
+      if let Err(e) = append(path, &entry) {
+        log::warn!("Failed to save query history {}: {e}", path.display());
+      }
+    }
+    if self.entries.len() == HISTORY_LIMIT {
+      self.entries.remove(0);
+    }
+    self.entries.push(entry);
+  }
+
+  /// Records how the most recently recorded statement ended and rewrites its line in the history file.
+  pub fn complete_last(&mut self, row_count: Option<usize>, duration: Duration, error: Option<String>) -> Result<()> {
+    let Some(entry) = self.entries.last_mut() else {
+      return Ok(());
+    };
+    let previous = entry.clone();
+    entry.row_count = row_count;
+    entry.execution_time_ms = Some(duration.as_millis() as u64);
+    entry.error = error;
+    let entry = entry.clone();
+    match &self.path {
+      Some(path) => replace_line(path, &previous, &entry),
+      None => Ok(()),
+    }
+  }
+
+  /// Appends entries from another tool, oldest first, skipping ones already in the whole history file or imported
+  /// before them. Entries are the same when their queries are and, when `timestamped`, they ran at the same time;
+  /// tools that don't record when a statement ran only have their query to tell entries apart. Returns how many were
+  /// added.
+  pub fn import(&mut self, mut entries: Vec<HistoryEntry>, timestamped: bool) -> Result<usize> {
+    entries.sort_by_key(|e| e.executed_at);
+    let known = match &self.path {
+      Some(path) => read_entries(path)?,
+      None => self.entries.clone(),
+    };
+    let key = |e: &HistoryEntry| (e.query.clone(), timestamped.then_some(e.executed_at));
+    let mut seen: HashSet<(String, Option<i64>)> = known.iter().map(key).collect();
+    let mut imported = 0;
+    for entry in entries {
+      if !seen.insert(key(&entry)) {
+        continue;
+      }
+      if let Some(path) = &self.path {
+        append(path, &entry)?;
+      }
+      self.entries.push(entry);
+      imported += 1;
+    }
+    self.entries.drain(..self.entries.len().saturating_sub(HISTORY_LIMIT));
+    Ok(imported)
+  }
+
+  /// Tags used in the history, sorted.
+  pub fn tags(&self) -> Vec<String> {
+    let mut tags: Vec<String> = self.entries.iter().flat_map(HistoryEntry::tags).collect();
+    tags.sort();
+    tags.dedup();
+    tags
+  }
+
+  /// Indices of the entries matching `search`, most recent first.
+  pub fn search(&self, search: &str) -> Vec<usize> {
+    (0..self.entries.len()).rev().filter(|i| self.entries[*i].matches(search)).collect()
+  }
+
+  /// Writes the entries at `indices`, given most recent first like [`QueryHistory::search`] returns them, oldest first
+  /// to `path` as a SQL script or as JSON. Returns how many were written.
+  pub fn export(&self, indices: &[usize], as_sql: bool, path: &Path) -> Result<usize> {
+    let entries: Vec<&HistoryEntry> = indices.iter().rev().filter_map(|i| self.entries.get(*i)).collect();
+    let contents = if as_sql { export_sql(&entries) } else { export_json(&entries)? };
+    std::fs::write(path, contents)?;
+    Ok(entries.len())
+  }
+
+  /// Sets or clears the note of the entry at `index` and rewrites its line in the history file.
+  pub fn set_note(&mut self, index: usize, note: Option<String>) -> Result<()> {
+    let Some(entry) = self.entries.get_mut(index) else {
+      return Ok(());
+    };
+    let previous = entry.clone();
+    entry.note = note.filter(|n| !n.trim().is_empty());
+    let entry = entry.clone();
+    match &self.path {
+      Some(path) => replace_line(path, &previous, &entry),
+      None => Ok(()),
+    }
+  }
+
+  /// Statements, table aliases and WHERE conditions from the history, most frequently then most recently used
+  /// first.
+  pub fn suggestions(&self) -> Vec<Suggestion> {
+    // expression -> (kind of expression, uses, index of the most recent entry)
+    let mut seen: HashMap<String, (&str, usize, usize)> = HashMap::new();
+    for (index, entry) in self.entries.iter().enumerate() {
+      let statement = collapse_whitespace(&entry.query);
+      let expressions = autocomplete::table_aliases(&statement)
+        .into_iter()
+        .map(|(table, alias)| ("table alias", format!("{table} {alias}")))
+        .chain(where_condition(&statement).map(|condition| ("condition", condition)))
+        .chain(std::iter::once(("statement", statement.clone())));
+      for (label, expression) in expressions {
+        let used = seen.entry(expression).or_insert((label, 0, index));
+        used.1 += 1;
+        used.2 = index;
+      }
+    }
+
+    let mut ranked: Vec<_> = seen.into_iter().collect();
+    ranked.sort_by(|(_, (_, a_uses, a_last)), (_, (_, b_uses, b_last))| {
+      b_uses.cmp(a_uses).then_with(|| b_last.cmp(a_last))
+    });
+    ranked
+      .into_iter()
+      .map(|(expression, (label, uses, last))| {
+        let mut documentation =
+          vec![format!("{label} from history"), format!("used {uses} time{}", if uses == 1 { "" } else { "s" })];
+        if let Some(time) = Local.timestamp_opt(self.entries[last].executed_at, 0).single() {
+          documentation.push(format!("last run {}", time.format("%Y-%m-%d %H:%M")));
+        }
+        Suggestion::new(expression, SuggestionKind::History).with_documentation(documentation)
+      })
+      .collect()
+  }
+}
+
+/// Every entry of the history file at `path`, skipping lines that don't parse.
+fn read_entries(path: &Path) -> Result<Vec<HistoryEntry>> {
+  if !path.exists() {
+    return Ok(Vec::new());
+  }
+  Ok(std::fs::read_to_string(path)?.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+}
+
+fn append(path: &Path, entry: &HistoryEntry) -> Result<()> {
+  if let Some(directory) = path.parent() {
+    std::fs::create_dir_all(directory)?;
+  }
+  let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+  if file.metadata()?.len() == 0 {
+    writeln!(file, "{}", serde_json::to_string(&FileHeader { history_version: HISTORY_VERSION })?)?;
+  }
+  writeln!(file, "{}", serde_json::to_string(entry)?)?;
+  Ok(())
+}
+
+/// Version of the history file, from its header line.
+fn file_version(contents: &str) -> u32 {
+  contents
+    .lines()
+    .next()
+    .and_then(|line| serde_json::from_str::<FileHeader>(line).ok())
+    .map_or(1, |h| h.history_version)
+}
+
+/// Rewrites an older history file in the current format. Lines that don't parse are kept as they are.
+fn migrate(path: &Path) -> Result<()> {
+  if !path.exists() {
+    return Ok(());
+  }
+  let contents = std::fs::read_to_string(path)?;
+  if contents.is_empty() || file_version(&contents) >= HISTORY_VERSION {
+    return Ok(());
+  }
+  let mut lines = vec![serde_json::to_string(&FileHeader { history_version: HISTORY_VERSION })?];
+  for line in contents.lines() {
+    // Version 1 entries are valid version 2 entries without the outcome fields.
+    match serde_json::from_str::<HistoryEntry>(line) {
+      Ok(entry) => lines.push(serde_json::to_string(&entry)?),
+      Err(_) => lines.push(line.to_string()),
+    }
+  }
+  let temporary = path.with_extension("jsonl.tmp");
+  std::fs::write(&temporary, lines.join("\n") + "\n")?;
+  std::fs::rename(&temporary, path)?;
+  log::info!("Migrated query history {} to version {HISTORY_VERSION}", path.display());
+  Ok(())
+}
+
+/// Entries as a pretty-printed JSON array, notes included.
+pub fn export_json(entries: &[&HistoryEntry]) -> Result<String> {
+  Ok(serde_json::to_string_pretty(entries)?)
+}
+
+/// Entries as a SQL script, each statement preceded by comments with its run time and note.
+pub fn export_sql(entries: &[&HistoryEntry]) -> String {
+  let mut script = String::new();
+  for entry in entries {
+    if let Some(time) = Local.timestamp_opt(entry.executed_at, 0).single() {
+      script.push_str(&format!("-- executed at {}\n", time.format("%Y-%m-%d %H:%M:%S %z")));
+    }
+    if let Some(note) = &entry.note {
+      for line in note.lines() {
+        script.push_str(&format!("-- note: {line}\n"));
+      }
+    }
+    let query = entry.query.trim();
+    script.push_str(query);
+    if !query.ends_with(';') {
+      script.push(';');
+    }
+    script.push_str("\n\n");
+  }
+  script
+}
+
+/// Replaces the last line holding `previous` with `entry`, keeping lines that don't parse as they are.
+fn replace_line(path: &Path, previous: &HistoryEntry, entry: &HistoryEntry) -> Result<()> {
+  let contents = std::fs::read_to_string(path)?;
+  let mut lines: Vec<String> = contents.lines().map(String::from).collect();
+  let position =
+    lines.iter().rposition(|line| serde_json::from_str::<HistoryEntry>(line).is_ok_and(|e| e == *previous));
+  if let Some(position) = position {
+    lines[position] = serde_json::to_string(entry)?;
+    // Written next to the history and renamed over it, so a crash can't leave a truncated file.
+    let temporary = path.with_extension("jsonl.tmp");
+    std::fs::write(&temporary, lines.join("\n") + "\n")?;
+    std::fs::rename(&temporary, path)?;
+  }
+  Ok(())
+}
+
+fn collapse_whitespace(sql: &str) -> String {
+  sql.split_whitespace().collect::<Vec<_>>().join(" ").trim_end_matches(';').to_string()
+}
+
+/// The condition of the statement's WHERE clause, up to the next clause.
+fn where_condition(statement: &str) -> Option<String> {
+  let words: Vec<&str> = statement.split(' ').collect();
+  let start = words.iter().position(|w| w.eq_ignore_ascii_case("WHERE"))? + 1;
+  let end = words[start..]
+    .iter()
+    .position(|w| CLAUSE_ENDS.iter().any(|k| k.eq_ignore_ascii_case(w)))
+    .map_or(words.len(), |i| start + i);
+  let condition = words[start..end].join(" ");
+  (!condition.is_empty()).then_some(condition)
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn history(queries: &[&str]) -> QueryHistory {
+    let mut history = QueryHistory::default();
+    for query in queries {
+      history.record(query);
+    }
+    history
+  }
+
+  #[test]
+  fn test_expressions() {
+    let statement =
+      collapse_whitespace("SELECT *\n  FROM users u JOIN orders AS o ON o.user_id = u.id\nWHERE u.active ORDER BY 1;");
+    assert_eq!(where_condition(&statement), Some("u.active".to_string()));
+    assert_eq!(where_condition("SELECT 1"), None);
+  }
+
+  #[test]
+  fn test_notes_are_searchable_and_saved() {
+    let path = std::env::temp_dir().join(format!("query-crafter-history-{}.jsonl", std::process::id()));
+    let mut history = QueryHistory { path: Some(path.clone()), ..QueryHistory::default() };
+    history.record("SELECT * FROM invoices");
+    history.record("SELECT 1");
+    history.set_note(0, Some("May invoice bug".to_string())).unwrap();
+    assert_eq!(history.search("invoice bug"), vec![0]);
+    assert_eq!(history.search("select"), vec![1, 0]);
+    history.set_note(1, Some("#Debug check".to_string())).unwrap();
+    history.record("-- #billing\nSELECT * FROM refunds");
+    assert_eq!(history.tags(), vec!["billing".to_string(), "debug".to_string()]);
+    assert_eq!(history.search("#debug"), vec![1]);
+    assert_eq!(history.search("#billing refunds"), vec![2]);
+    assert_eq!(history.search("#billing invoices"), Vec::<usize>::new());
+    let saved = QueryHistory::read(&path).unwrap();
+    assert_eq!(saved.entries()[0].note.as_deref(), Some("May invoice bug"));
+    std::fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn test_import() {
+    let path = std::env::temp_dir().join(format!("query-crafter-history-import-{}.jsonl", std::process::id()));
+    let mut history = QueryHistory { path: Some(path.clone()), ..QueryHistory::default() };
+    history.record("SELECT 1");
+    for i in 0..HISTORY_LIMIT {
+      history.record(&format!("SELECT {}", i + 2));
+    }
+    let psql = |queries: &[&str], executed_at: i64| -> Vec<HistoryEntry> {
+      queries.iter().map(|q| HistoryEntry::new(q.to_string(), executed_at)).collect()
+    };
+    // The file's time differs on every import, and the first statement is only left in the file.
+    assert_eq!(history.import(psql(&["SELECT 1", "SELECT now()", "SELECT now()"], 10), false).unwrap(), 1);
+    assert_eq!(history.import(psql(&["SELECT now()"], 20), false).unwrap(), 0);
+    assert_eq!(history.import(psql(&["SELECT now()"], 30), true).unwrap(), 1);
+    std::fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn test_migrate_and_complete() {
+    let path = std::env::temp_dir().join(format!("query-crafter-history-v1-{}.jsonl", std::process::id()));
+    std::fs::write(&path, "{\"query\":\"SELECT 1\",\"executed_at\":5}\nnot json\n").unwrap();
+    migrate(&path).unwrap();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(contents, "{\"history_version\":2}\n{\"query\":\"SELECT 1\",\"executed_at\":5}\nnot json\n");
+    migrate(&path).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), contents);
+
+    let mut history = QueryHistory { path: Some(path.clone()), ..QueryHistory::read(&path).unwrap() };
+    history.complete_last(Some(1), Duration::from_millis(12), None).unwrap();
+    let saved = QueryHistory::read(&path).unwrap();
+    assert_eq!(saved.entries()[0].outcome().as_deref(), Some("1 row in 12 ms"));
+    std::fs::remove_file(&path).unwrap();
+  }
+
+  #[test]
+  fn test_export_sql() {
+    let entry = HistoryEntry { note: Some("check".to_string()), ..HistoryEntry::new("SELECT 1".to_string(), 0) };
+    let script = export_sql(&[&entry]);
+    assert!(script.starts_with("-- executed at "));
+    assert!(script.ends_with("-- note: check\nSELECT 1;\n\n"));
+    let parsed: Vec<HistoryEntry> = serde_json::from_str(&export_json(&[&entry]).unwrap()).unwrap();
+    assert_eq!(parsed, vec![entry]);
+  }
+
+  #[test]
+  fn test_export_matches() {
+    let history = history(&["SELECT 1", "SELECT * FROM users", "SELECT 2"]);
+    let path = std::env::temp_dir().join(format!("query-crafter-history-export-{}.json", std::process::id()));
+    assert_eq!(history.export(&[2, 0], false, &path).unwrap(), 2);
+    let exported: Vec<HistoryEntry> = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    let queries: Vec<&str> = exported.iter().map(|entry| entry.query.as_str()).collect();
+    assert_eq!(queries, vec!["SELECT 1", "SELECT 2"]);
+  }
+
+  #[test]
+  fn test_suggestions_ranked_by_frequency() {
+    let history = history(&["SELECT * FROM users u WHERE u.id = 1", "SELECT 1", "SELECT * FROM users u"]);
+    let texts: Vec<_> = history.suggestions().into_iter().map(|s| s.text).collect();
+    assert_eq!(texts[0], "users u");
+    assert!(texts.contains(&"u.id = 1".to_string()));
+    assert!(texts.contains(&"SELECT 1".to_string()));
+  }
+}
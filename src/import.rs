@@ -0,0 +1,191 @@
+use std::{
+  path::{Path, PathBuf},
+  time::UNIX_EPOCH,
+};
+
+use chrono::{Local, NaiveDateTime, TimeZone};
+use clap::ValueEnum;
+use color_eyre::eyre::{eyre, Result};
+use directories::BaseDirs;
+
+use crate::{
+  config::Config,
+  history::{HistoryEntry, QueryHistory},
+  library::Library,
+};
+
+/// Tools whose history or saved scripts can be imported with `--import`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ImportSource {
+  /// psql's `~/.psql_history` (or `$PSQL_HISTORY`), into the query history.
+  Psql,
+  /// pgcli's history file, into the query history.
+  Pgcli,
+  /// DBeaver's saved scripts, into the query library.
+  Dbeaver,
+}
+
+impl ImportSource {
+  /// Where the tool keeps its history or scripts by default.
+  pub fn default_path(&self) -> Option<PathBuf> {
+    let dirs = BaseDirs::new()?;
+    Some(match self {
+      ImportSource::Psql => {
+        std::env::var_os("PSQL_HISTORY").map_or_else(|| dirs.home_dir().join(".psql_history"), PathBuf::from)
+      },
+      ImportSource::Pgcli => dirs.config_dir().join("pgcli").join("history"),
+      ImportSource::Dbeaver => {
+        let data_dir =
+          if cfg!(target_os = "macos") { dirs.home_dir().join("Library") } else { dirs.data_dir().to_path_buf() };
+        data_dir.join("DBeaverData").join("workspace6").join("General").join("Scripts")
+      },
+    })
+  }
+}
+
+/// Imports from `source`, read from `path` or the tool's default location, and describes what was imported.
+pub fn run(source: ImportSource, path: Option<PathBuf>, config: &Config) -> Result<String> {
+  let path = path.or_else(|| source.default_path()).ok_or_else(|| eyre!("Unable to locate the home directory"))?;
+  match source {
+    ImportSource::Psql | ImportSource::Pgcli => {
+      let text = std::fs::read_to_string(&path).map_err(|e| eyre!("Unable to read {}: {e}", path.display()))?;
+      let entries = if source == ImportSource::Psql {
+        let modified = std::fs::metadata(&path)?.modified()?.duration_since(UNIX_EPOCH)?.as_secs() as i64;
+        parse_psql_history(&text).into_iter().map(|query| HistoryEntry::new(query, modified)).collect()
+      } else {
+        parse_pgcli_history(&text)
+      };
+      let read = entries.len();
+      // psql records no times, its entries all carry the file's modification time.
+      let imported = QueryHistory::load().import(entries, source == ImportSource::Pgcli)?;
+      Ok(format!("Imported {imported} of {read} statements from {} into the query history", path.display()))
+    },
+    ImportSource::Dbeaver => {
+      let directory = config
+        .library
+        .directory
+        .as_deref()
+        .ok_or_else(|| eyre!("Set library.directory in the config to import scripts into the query library"))?;
+      let mut library = Library::load(directory)?;
+      let scripts = read_scripts(&path)?;
+      for (name, body) in &scripts {
+        library.save(name, body)?;
+      }
+      Ok(format!("Imported {} scripts from {} into {}", scripts.len(), path.display(), directory.display()))
+    },
+  }
+}
+
+/// Statements of a psql history file. Backslash commands are skipped, and the octal escapes libedit writes (`\040`
+/// for a space) are decoded.
+pub fn parse_psql_history(text: &str) -> Vec<String> {
+  let mut statements = Vec::new();
+  let mut current = String::new();
+  for line in text.lines().filter(|line| *line != "_HiStOrY_V2_") {
+    let line = decode_octal_escapes(line);
+    if current.is_empty() && (line.trim().is_empty() || line.trim_start().starts_with('\\')) {
+      continue;
+    }
+    if !current.is_empty() {
+      current.push('\n');
+    }
+    current.push_str(&line);
+    if line.trim_end().ends_with(';') {
+      statements.push(std::mem::take(&mut current).trim().to_string());
+    }
+  }
+  if !current.trim().is_empty() {
+    statements.push(current.trim().to_string());
+  }
+  statements
+}
+
+/// Entries of a pgcli (prompt_toolkit) history file: a `# <timestamp>` line followed by `+`-prefixed lines.
+/// Backslash commands are skipped.
+pub fn parse_pgcli_history(text: &str) -> Vec<HistoryEntry> {
+  let mut entries: Vec<HistoryEntry> = Vec::new();
+  let mut executed_at = None;
+  let mut lines: Vec<&str> = Vec::new();
+  let mut finish = |executed_at: Option<i64>, lines: &mut Vec<&str>| {
+    let query = lines.join("\n").trim().to_string();
+    if !query.is_empty() && !query.starts_with('\\') {
+      entries.push(HistoryEntry::new(query, executed_at.unwrap_or_default()));
+    }
+    lines.clear();
+  };
+  for line in text.lines() {
+    if let Some(timestamp) = line.strip_prefix("# ") {
+      finish(executed_at, &mut lines);
+      executed_at = NaiveDateTime::parse_from_str(timestamp.trim(), "%Y-%m-%d %H:%M:%S%.f")
+        .ok()
+        .and_then(|t| Local.from_local_datetime(&t).single())
+        .map(|t| t.timestamp());
+    } else if let Some(line) = line.strip_prefix('+') {
+      lines.push(line);
+    }
+  }
+  finish(executed_at, &mut lines);
+  entries
+}
+
+/// `.sql` files below `directory` (DBeaver keeps scripts in per-project folders) as name and contents.
+fn read_scripts(directory: &Path) -> Result<Vec<(String, String)>> {
+  let mut scripts = Vec::new();
+  for entry in std::fs::read_dir(directory).map_err(|e| eyre!("Unable to read {}: {e}", directory.display()))? {
+    let path = entry?.path();
+    if path.is_dir() {
+      scripts.extend(read_scripts(&path)?);
+    } else if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("sql")) {
+      let name = path.file_stem().unwrap_or_default().to_string_lossy().to_string();
+      scripts.push((name, std::fs::read_to_string(&path)?));
+    }
+  }
+  Ok(scripts)
+}
+
+/// Replaces `\ooo` octal escapes with the characters they encode.
+fn decode_octal_escapes(line: &str) -> String {
+  let chars: Vec<char> = line.chars().collect();
+  let mut decoded = String::new();
+  let mut i = 0;
+  while i < chars.len() {
+    let digits: String = chars[i + 1..chars.len().min(i + 4)].iter().collect();
+    let code = (chars[i] == '\\' && digits.len() == 3 && digits.chars().all(|c| ('0'..='7').contains(&c)))
+      .then(|| u32::from_str_radix(&digits, 8).ok().and_then(char::from_u32))
+      .flatten();
+    match code {
+      Some(c) => {
+        decoded.push(c);
+        i += 4;
+      },
+      None => {
+        decoded.push(chars[i]);
+        i += 1;
+      },
+    }
+  }
+  decoded
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_parse_psql_history() {
+    let text = "_HiStOrY_V2_\n\\dt\nSELECT\\040*\\040FROM users\nWHERE id = 1;\nselect now()";
+    assert_eq!(parse_psql_history(text), vec!["SELECT * FROM users\nWHERE id = 1;", "select now()"]);
+  }
+
+  #[test]
+  fn test_parse_pgcli_history() {
+    let text = "\n# 2024-05-01 10:00:00.123456\n+SELECT 1\n+FROM dual\n\n# 2024-05-01 10:01:00.5\n+\\d users\n\n\
+                # 2024-05-01 10:02:00.5\n+select 2\n";
+    let entries = parse_pgcli_history(text);
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].query, "SELECT 1\nFROM dual");
+    assert_eq!(entries[1].executed_at - entries[0].executed_at, 120);
+  }
+}
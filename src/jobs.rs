@@ -0,0 +1,78 @@
+use std::{
+  collections::BTreeMap,
+  path::{Path, PathBuf},
+};
+
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+pub const JOBS_FILE: &str = "jobs.toml";
+
+/// A query whose results are exported with a profile, saved from the UI and run with `query-crafter run-job <name>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Job {
+  /// Sqlite database file the query runs against, the configured connection when absent.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub connection: Option<String>,
+  pub query: String,
+  /// Name of the export profile the results are written with.
+  pub profile: String,
+}
+
+/// Saved jobs by name, persisted as one TOML table per job in the data directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Jobs {
+  path: PathBuf,
+  jobs: BTreeMap<String, Job>,
+}
+
+impl Jobs {
+  pub fn load() -> Result<Self> {
+    Self::read(&crate::dirs::get_data_dir().join(JOBS_FILE))
+  }
+
+  fn read(path: &Path) -> Result<Self> {
+    let jobs = if path.exists() { toml::from_str(&std::fs::read_to_string(path)?)? } else { BTreeMap::new() };
+    Ok(Self { path: path.to_path_buf(), jobs })
+  }
+
+  pub fn get(&self, name: &str) -> Result<&Job> {
+    self.jobs.get(name).ok_or_else(|| eyre!("No job named {name} in {}", self.path.display()))
+  }
+
+  /// Adds or replaces the named job and rewrites the jobs file.
+  pub fn save(&mut self, name: &str, job: Job) -> Result<&Path> {
+    if name.trim().is_empty() {
+      return Err(eyre!("A job needs a name"));
+    }
+    self.jobs.insert(name.trim().to_string(), job);
+    if let Some(parent) = self.path.parent() {
+      std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&self.path, toml::to_string(&self.jobs)?)?;
+    Ok(&self.path)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_save_and_read() {
+    let path = std::env::temp_dir().join(format!("query-crafter-jobs-{}.toml", std::process::id()));
+    let mut jobs = Jobs::read(&path).unwrap();
+    let job =
+      Job { connection: None, query: "SELECT * FROM orders\nWHERE total > 10".to_string(), profile: "s3".to_string() };
+    jobs.save(" nightly ", job.clone()).unwrap();
+    jobs.save("local", Job { connection: Some("/tmp/app.db".to_string()), ..job.clone() }).unwrap();
+    let read = Jobs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(read.get("nightly").unwrap(), &job);
+    assert_eq!(read.get("local").unwrap().connection.as_deref(), Some("/tmp/app.db"));
+    assert!(read.get("weekly").is_err());
+    assert!(jobs.save("", job).is_err());
+  }
+}
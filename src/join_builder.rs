@@ -0,0 +1,235 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{schema::DbTable, snippets};
+
+/// A foreign key of `table` referencing `foreign_table`, with its columns in constraint order.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct ForeignKey {
+  pub name: String,
+  pub table: DbTable,
+  pub columns: Vec<String>,
+  pub foreign_table: DbTable,
+  pub foreign_columns: Vec<String>,
+}
+
+impl ForeignKey {
+  /// Groups `(constraint, schema, table, column, foreign schema, foreign table, foreign column)` rows, one per column
+  /// pair and ordered by constraint, into foreign keys.
+  pub fn from_rows(rows: Vec<Vec<String>>) -> Vec<Self> {
+    let mut keys: Vec<Self> = Vec::new();
+    for mut row in rows {
+      row.resize(7, String::new());
+      let table = DbTable { schema: row[1].clone(), name: row[2].clone() };
+      match keys.last_mut().filter(|key| key.name == row[0] && key.table == table) {
+        Some(key) => {
+          key.columns.push(row[3].clone());
+          key.foreign_columns.push(row[6].clone());
+        },
+        None => {
+          keys.push(Self {
+            name: row[0].clone(),
+            table,
+            columns: vec![row[3].clone()],
+            foreign_table: DbTable { schema: row[4].clone(), name: row[5].clone() },
+            foreign_columns: vec![row[6].clone()],
+          })
+        },
+      }
+    }
+    keys
+  }
+
+  /// The table at the other end from `base`, with the column pairs as `(base column, other column)`.
+  fn other_end(&self, base: &DbTable) -> (&DbTable, Vec<(&String, &String)>) {
+    if self.table == *base {
+      (&self.foreign_table, self.columns.iter().zip(&self.foreign_columns).collect())
+    } else {
+      (&self.table, self.foreign_columns.iter().zip(&self.columns).collect())
+    }
+  }
+
+  /// Description from `base`'s side, e.g. `orders.user_id → users.id`.
+  pub fn describe(&self, base: &DbTable) -> String {
+    let (other, pairs) = self.other_end(base);
+    let base_columns: Vec<&str> = pairs.iter().map(|(b, _)| b.as_str()).collect();
+    let other_columns: Vec<&str> = pairs.iter().map(|(_, o)| o.as_str()).collect();
+    let arrow = if self.table == *base { "→" } else { "←" };
+    format!(
+      "{}.{} {arrow} {}.{}",
+      base.display_name(),
+      base_columns.join(", "),
+      other.display_name(),
+      other_columns.join(", ")
+    )
+  }
+}
+
+/// Steps of the join builder popup.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JoinStep {
+  /// Choosing the related tables to join.
+  #[default]
+  Relations,
+  /// Choosing the columns to select.
+  Columns,
+}
+
+/// State of the join builder: a base table, the foreign keys joining it to related tables and the chosen columns.
+#[derive(Debug, Clone, Default)]
+pub struct JoinBuilder {
+  pub base: DbTable,
+  /// Foreign keys from and to the base table, `None` until they are loaded.
+  pub foreign_keys: Option<Vec<ForeignKey>>,
+  /// Chosen foreign keys, by index in `foreign_keys`.
+  pub joined: Vec<usize>,
+  pub step: JoinStep,
+  pub selected: usize,
+  /// Chosen columns, by index in [`JoinBuilder::tables`] and column name.
+  pub columns: Vec<(usize, String)>,
+}
+
+impl JoinBuilder {
+  pub fn new(base: DbTable) -> Self {
+    Self { base, ..Self::default() }
+  }
+
+  /// The base table followed by the table each chosen foreign key joins.
+  pub fn tables(&self) -> Vec<&DbTable> {
+    let keys = self.foreign_keys.as_deref().unwrap_or_default();
+    std::iter::once(&self.base).chain(self.joined.iter().map(|i| keys[*i].other_end(&self.base).0)).collect()
+  }
+
+  /// Adds the foreign key to the join, or removes it and its columns.
+  pub fn toggle_join(&mut self, key: usize) {
+    match self.joined.iter().position(|i| *i == key) {
+      Some(position) => {
+        self.joined.remove(position);
+        self.columns.retain(|(table, _)| *table != position + 1);
+        for (table, _) in &mut self.columns {
+          if *table > position + 1 {
+            *table -= 1;
+          }
+        }
+      },
+      None => self.joined.push(key),
+    }
+  }
+
+  pub fn toggle_column(&mut self, table: usize, column: &str) {
+    match self.columns.iter().position(|(t, c)| *t == table && c == column) {
+      Some(position) => {
+        self.columns.remove(position);
+      },
+      None => self.columns.push((table, column.to_string())),
+    }
+  }
+
+  pub fn is_column_selected(&self, table: usize, column: &str) -> bool {
+    self.columns.iter().any(|(t, c)| *t == table && c == column)
+  }
+
+  /// The SELECT joining the chosen tables on their foreign keys, listing the chosen columns or `*` when there are
+  /// none.
+  pub fn query(&self) -> String {
+    let tables = self.tables();
+    let aliases = aliases(&tables);
+    let columns: Vec<String> = self
+      .columns
+      .iter()
+      .map(|(table, column)| format!("{}.{}", aliases[*table], snippets::quote_identifier(column)))
+      .collect();
+    let columns = if columns.is_empty() { "*".to_string() } else { columns.join(", ") };
+    let mut query = format!("SELECT {columns}\nFROM {} {}", self.base.qualified_name(), aliases[0]);
+    let keys = self.foreign_keys.as_deref().unwrap_or_default();
+    for (position, key) in self.joined.iter().enumerate() {
+      let (other, pairs) = keys[*key].other_end(&self.base);
+      let alias = &aliases[position + 1];
+      let conditions: Vec<String> = pairs
+        .iter()
+        .map(|(base_column, other_column)| {
+          format!(
+            "{alias}.{} = {}.{}",
+            snippets::quote_identifier(other_column),
+            aliases[0],
+            snippets::quote_identifier(base_column)
+          )
+        })
+        .collect();
+      query.push_str(&format!("\nJOIN {} {alias} ON {}", other.qualified_name(), conditions.join(" AND ")));
+    }
+    query
+  }
+}
+
+/// Short aliases from the initials of each table name, e.g. `oi` for `order_items`, numbered when they repeat.
+fn aliases(tables: &[&DbTable]) -> Vec<String> {
+  let mut aliases: Vec<String> = Vec::new();
+  for table in tables {
+    let initials: String = table
+      .name
+      .split(|c: char| !c.is_ascii_alphanumeric())
+      .filter_map(|word| word.chars().next())
+      .map(|c| c.to_ascii_lowercase())
+      .filter(|c| c.is_ascii_lowercase())
+      .collect();
+    let initials = if initials.is_empty() { "t".to_string() } else { initials };
+    let mut alias = initials.clone();
+    let mut n = 2;
+    while aliases.contains(&alias) {
+      alias = format!("{initials}{n}");
+      n += 1;
+    }
+    aliases.push(alias);
+  }
+  aliases
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn table(name: &str) -> DbTable {
+    DbTable { schema: "public".to_string(), name: name.to_string() }
+  }
+
+  fn row(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+  }
+
+  #[test]
+  fn test_from_rows() {
+    let keys = ForeignKey::from_rows(vec![
+      row(&["orders_user_fk", "public", "orders", "user_id", "public", "users", "id"]),
+      row(&["lines_order_fk", "public", "order_lines", "order_id", "public", "orders", "id"]),
+      row(&["lines_order_fk", "public", "order_lines", "shop_id", "public", "orders", "shop_id"]),
+    ]);
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys[1].columns, vec!["order_id".to_string(), "shop_id".to_string()]);
+    assert_eq!(keys[0].describe(&table("orders")), "orders.user_id → users.id");
+    assert_eq!(keys[1].describe(&table("orders")), "orders.id, shop_id ← order_lines.order_id, shop_id");
+  }
+
+  #[test]
+  fn test_query() {
+    let mut builder = JoinBuilder::new(table("orders"));
+    builder.foreign_keys = Some(ForeignKey::from_rows(vec![
+      row(&["orders_user_fk", "public", "orders", "user_id", "public", "users", "id"]),
+      row(&["items_order_fk", "public", "order_items", "order_id", "public", "orders", "id"]),
+    ]));
+    assert_eq!(builder.query(), "SELECT *\nFROM \"public\".\"orders\" o");
+    builder.toggle_join(0);
+    builder.toggle_join(1);
+    builder.toggle_column(0, "id");
+    builder.toggle_column(1, "Email");
+    builder.toggle_column(2, "sku");
+    assert_eq!(
+      builder.query(),
+      "SELECT o.id, u.\"Email\", oi.sku\nFROM \"public\".\"orders\" o\nJOIN \"public\".\"users\" u ON u.id = \
+       o.user_id\nJOIN \"public\".\"order_items\" oi ON oi.order_id = o.id"
+    );
+    builder.toggle_join(0);
+    assert_eq!(builder.columns, vec![(0, "id".to_string()), (1, "sku".to_string())]);
+  }
+}
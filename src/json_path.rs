@@ -0,0 +1,126 @@
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Step {
+  Key(String),
+  Index(usize),
+  /// Every element of an array or value of an object.
+  Wildcard,
+}
+
+/// A path into a JSON document such as `$.user.emails[0]`, `.items[*].sku` or `tags["first name"]`. The leading `$`
+/// and dot are optional.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPath {
+  steps: Vec<Step>,
+}
+
+impl JsonPath {
+  pub fn parse(path: &str) -> Result<Self, String> {
+    let path = path.trim();
+    let mut rest = path.strip_prefix('$').unwrap_or(path);
+    let mut steps = Vec::new();
+    let mut first = true;
+    while !rest.is_empty() {
+      if let Some(bracket) = rest.strip_prefix('[') {
+        let (inner, after) = bracket.split_once(']').ok_or_else(|| format!("Unclosed '[' in {path}"))?;
+        let inner = inner.trim();
+        let step = if inner == "*" {
+          Step::Wildcard
+        } else if let Some(key) = unquote(inner) {
+          Step::Key(key.to_string())
+        } else {
+          Step::Index(inner.parse().map_err(|_| format!("Expected an index, * or a quoted key in [{inner}]"))?)
+        };
+        steps.push(step);
+        rest = after;
+      } else {
+        let name = match rest.strip_prefix('.') {
+          Some(name) => name,
+          None if first => rest,
+          None => return Err(format!("Expected '.' or '[' at {rest}")),
+        };
+        let end = name.find(['.', '[']).unwrap_or(name.len());
+        match &name[..end] {
+          "" => return Err(format!("Missing key name in {path}")),
+          "*" => steps.push(Step::Wildcard),
+          key => steps.push(Step::Key(key.to_string())),
+        }
+        rest = &name[end..];
+      }
+      first = false;
+    }
+    Ok(Self { steps })
+  }
+
+  /// Values in `json` the path matches, in document order.
+  pub fn select<'a>(&self, json: &'a Value) -> Vec<&'a Value> {
+    let mut current = vec![json];
+    for step in &self.steps {
+      current = current
+        .into_iter()
+        .flat_map(|value| -> Vec<&Value> {
+          match (step, value) {
+            (Step::Key(key), Value::Object(map)) => map.get(key).into_iter().collect(),
+            (Step::Index(index), Value::Array(items)) => items.get(*index).into_iter().collect(),
+            (Step::Wildcard, Value::Array(items)) => items.iter().collect(),
+            (Step::Wildcard, Value::Object(map)) => map.values().collect(),
+            _ => Vec::new(),
+          }
+        })
+        .collect();
+    }
+    current
+  }
+
+  /// The matches in a cell holding JSON: a single string as its text, any other single value as compact JSON and
+  /// several matches as a JSON array. Empty when the cell isn't JSON or nothing matches.
+  pub fn extract(&self, cell: &str) -> String {
+    let Ok(json) = serde_json::from_str::<Value>(cell) else {
+      return String::new();
+    };
+    match self.select(&json).as_slice() {
+      [] => String::new(),
+      [Value::String(text)] => text.clone(),
+      [value] => value.to_string(),
+      values => Value::Array(values.iter().map(|value| (*value).clone()).collect()).to_string(),
+    }
+  }
+}
+
+fn unquote(text: &str) -> Option<&str> {
+  ['"', '\''].iter().find_map(|quote| text.strip_prefix(*quote)?.strip_suffix(*quote))
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_parse() {
+    let parsed = JsonPath::parse("$.items[*]['first name'][2]").unwrap();
+    assert_eq!(parsed.steps, vec![
+      Step::Key("items".to_string()),
+      Step::Wildcard,
+      Step::Key("first name".to_string()),
+      Step::Index(2),
+    ]);
+    assert_eq!(JsonPath::parse("user.id").unwrap(), JsonPath::parse(".user.id").unwrap());
+    assert!(JsonPath::parse("$.items[x]").is_err());
+    assert!(JsonPath::parse("$.a..b").is_err());
+  }
+
+  #[test]
+  fn test_extract() {
+    let cell = r#"{"user": {"id": 7, "name": "Ada"}, "items": [{"sku": "a1"}, {"sku": "b2"}, {"qty": 1}]}"#;
+    let extract = |path: &str| JsonPath::parse(path).unwrap().extract(cell);
+    assert_eq!(extract("$.user.name"), "Ada");
+    assert_eq!(extract("$.user.id"), "7");
+    assert_eq!(extract("$.items[*].sku"), r#"["a1","b2"]"#);
+    assert_eq!(extract("$.items[1]"), r#"{"sku":"b2"}"#);
+    assert_eq!(extract("$.missing"), "");
+    assert_eq!(JsonPath::parse("$.a").unwrap().extract("not json"), "");
+  }
+}
@@ -0,0 +1,401 @@
+use std::collections::BTreeMap;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter};
+
+use crate::config;
+
+/// Where a binding applies. Global bindings work everywhere unless a popup has focus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, EnumIter)]
+pub enum Scope {
+  Global,
+  Tables,
+  Editor,
+  Results,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Command {
+  Help,
+  Settings,
+  History,
+  ToggleHealth,
+  TogglePerf,
+  ToggleDebug,
+  Suspend,
+  Lock,
+  CommandPalette,
+  SearchTables,
+  CountRows,
+  PreviewTable,
+  ShowColumns,
+  JoinBuilder,
+  ToggleSystemObjects,
+  AttachDatabase,
+  DetachDatabase,
+  ExecuteStatement,
+  Complete,
+  PasteInList,
+  TimeSnippets,
+  Registers,
+  SaveToLibrary,
+  TeamHistory,
+  ValuesEditor,
+  InsertUuid,
+  FormatQuery,
+  Outline,
+  Explain,
+  ExplainAnalyze,
+  ExportPlan,
+  CopyJson,
+  CopyFieldCondition,
+  CopyFieldJson,
+  CopyAllRows,
+  CopyColumnName,
+  CopyHeaders,
+  RerunQuery,
+  DropResults,
+  ToggleRowDetails,
+  ExpandField,
+  ConvertEpoch,
+  ExportMenu,
+  CellHashes,
+  PreviousColumn,
+  NextColumn,
+  MoveColumnLeft,
+  MoveColumnRight,
+  FilterResults,
+  ReapplyFilter,
+  IndexHints,
+  SnapshotResults,
+  OpenSnapshots,
+  MarkCell,
+  DiffCell,
+  JsonPathColumn,
+  ColumnFormat,
+  Undo,
+  PlanView,
+  AlternateQuery,
+  PreviousResultSet,
+  NextResultSet,
+  Notices,
+  JumpToColumn,
+  PageDown,
+  PageUp,
+  HalfPageDown,
+  HalfPageUp,
+}
+
+/// A key bound to a command, with the description shown in the help overlay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Binding {
+  pub scope: Scope,
+  pub code: KeyCode,
+  pub modifiers: KeyModifiers,
+  pub command: Command,
+  pub description: &'static str,
+}
+
+const fn bind(scope: Scope, code: KeyCode, command: Command, description: &'static str) -> Binding {
+  Binding { scope, code, modifiers: KeyModifiers::NONE, command, description }
+}
+
+const fn alt(scope: Scope, c: char, command: Command, description: &'static str) -> Binding {
+  Binding { scope, code: KeyCode::Char(c), modifiers: KeyModifiers::ALT, command, description }
+}
+
+const fn ctrl(scope: Scope, c: char, command: Command, description: &'static str) -> Binding {
+  Binding { scope, code: KeyCode::Char(c), modifiers: KeyModifiers::CONTROL, command, description }
+}
+
+/// Every built-in binding. Key handlers look commands up here and the help overlay lists it, so the two can't drift.
+pub const BINDINGS: &[Binding] = &[
+  bind(Scope::Global, KeyCode::F(1), Command::Help, "Show this help"),
+  bind(Scope::Global, KeyCode::F(2), Command::Settings, "Settings"),
+  bind(Scope::Global, KeyCode::F(3), Command::ToggleHealth, "Toggle connection health panel"),
+  bind(Scope::Global, KeyCode::F(11), Command::TogglePerf, "Toggle performance HUD"),
+  bind(Scope::Global, KeyCode::F(12), Command::ToggleDebug, "Toggle action log"),
+  ctrl(Scope::Global, 'z', Command::Suspend, "Suspend to the shell"),
+  bind(Scope::Global, KeyCode::F(4), Command::Lock, "Lock the screen"),
+  bind(Scope::Global, KeyCode::F(5), Command::History, "Query history with notes (n to annotate an entry)"),
+  bind(Scope::Global, KeyCode::F(6), Command::CommandPalette, "Run a custom command from the config"),
+  alt(Scope::Global, 'z', Command::Undo, "Undo replacing the editor text, deleting a snapshot or changing a note"),
+  bind(Scope::Tables, KeyCode::Char('/'), Command::SearchTables, "Search tables"),
+  bind(Scope::Tables, KeyCode::Char('c'), Command::CountRows, "Count rows of the selected table"),
+  bind(Scope::Tables, KeyCode::Char('P'), Command::PreviewTable, "Preview the selected table"),
+  bind(Scope::Tables, KeyCode::Char('s'), Command::ShowColumns, "Show columns of the selected table"),
+  bind(Scope::Tables, KeyCode::Char('J'), Command::JoinBuilder, "Build a JOIN query from the selected table"),
+  bind(Scope::Tables, KeyCode::Char('H'), Command::ToggleSystemObjects, "Show or hide system schemas and tables"),
+  bind(Scope::Tables, KeyCode::Char('A'), Command::AttachDatabase, "Attach a SQLite database file (path AS alias)"),
+  bind(Scope::Tables, KeyCode::Char('X'), Command::DetachDatabase, "Detach the database of the selected table"),
+  bind(Scope::Editor, KeyCode::Enter, Command::ExecuteStatement, "Run the statement under the cursor (normal mode)"),
+  ctrl(Scope::Editor, ' ', Command::Complete, "Complete the word at the cursor"),
+  // Terminals send Ctrl-^ as Ctrl-6.
+  ctrl(Scope::Editor, '6', Command::AlternateQuery, "Swap the editor with the previously executed query (Ctrl-^)"),
+  alt(Scope::Editor, 'i', Command::PasteInList, "Paste clipboard as an IN (...) list"),
+  alt(Scope::Editor, 't', Command::TimeSnippets, "Insert a time expression"),
+  alt(Scope::Editor, 'r', Command::Registers, "Insert a recent yank or value copied from the results"),
+  alt(Scope::Editor, 's', Command::SaveToLibrary, "Save the statement to the query library"),
+  alt(Scope::Editor, 'h', Command::TeamHistory, "Show the team's recent queries"),
+  alt(Scope::Editor, 'v', Command::ValuesEditor, "Type rows into a grid and insert them as VALUES or a temp table"),
+  alt(Scope::Editor, 'u', Command::InsertUuid, "Insert a random UUID"),
+  alt(Scope::Editor, 'f', Command::FormatQuery, "Format the editor contents"),
+  alt(Scope::Editor, 'o', Command::Outline, "Outline of the statements and CTEs in the editor"),
+  alt(Scope::Editor, 'e', Command::Explain, "Explain the current statement"),
+  alt(
+    Scope::Editor,
+    'a',
+    Command::ExplainAnalyze,
+    "Explain analyze the current statement, one that writes on a second press",
+  ),
+  alt(
+    Scope::Editor,
+    'p',
+    Command::ExportPlan,
+    "Export the statement's plan as JSON for pev2, analyzed when it only reads and the key is pressed twice",
+  ),
+  bind(Scope::Results, KeyCode::Char('y'), Command::CopyJson, "Copy row (or field) as JSON"),
+  bind(Scope::Results, KeyCode::Char('C'), Command::CopyFieldCondition, "Copy field as column = value"),
+  bind(Scope::Results, KeyCode::Char('J'), Command::CopyFieldJson, "Copy field as a \"column\": value JSON member"),
+  bind(Scope::Results, KeyCode::Char('Y'), Command::CopyAllRows, "Copy all (filtered) rows as tab-separated text"),
+  bind(Scope::Results, KeyCode::Char('c'), Command::CopyColumnName, "Copy the column's name"),
+  bind(Scope::Results, KeyCode::Char('H'), Command::CopyHeaders, "Copy all column names as a comma-separated list"),
+  bind(Scope::Results, KeyCode::Char('r'), Command::RerunQuery, "Re-run the current statement"),
+  bind(Scope::Results, KeyCode::Char('D'), Command::DropResults, "Free the result rows, keeping the query to re-run"),
+  bind(Scope::Results, KeyCode::Char(' '), Command::ToggleRowDetails, "Toggle row details"),
+  bind(Scope::Results, KeyCode::Char('o'), Command::ExpandField, "Expand or collapse the field in the row details"),
+  bind(Scope::Results, KeyCode::Char('E'), Command::ConvertEpoch, "Show the cell as a date if it is an epoch"),
+  bind(Scope::Results, KeyCode::Char('u'), Command::InsertUuid, "Insert a random UUID into the editor"),
+  bind(Scope::Results, KeyCode::Char('x'), Command::ExportMenu, "Export results"),
+  bind(Scope::Results, KeyCode::Char('#'), Command::CellHashes, "Show md5/sha256 of the cell"),
+  bind(Scope::Results, KeyCode::Char('['), Command::PreviousColumn, "Select previous column"),
+  bind(Scope::Results, KeyCode::Char(']'), Command::NextColumn, "Select next column"),
+  bind(Scope::Results, KeyCode::Char('<'), Command::MoveColumnLeft, "Move column left"),
+  bind(Scope::Results, KeyCode::Char('>'), Command::MoveColumnRight, "Move column right"),
+  bind(Scope::Results, KeyCode::Char('/'), Command::FilterResults, "Filter rows (Up/Down recalls recent filters)"),
+  bind(Scope::Results, KeyCode::Char('F'), Command::ReapplyFilter, "Reapply the filter last used with this query"),
+  bind(Scope::Results, KeyCode::Char('I'), Command::IndexHints, "Show index hints for an EXPLAIN ANALYZE plan"),
+  bind(Scope::Results, KeyCode::Char('v'), Command::PlanView, "Show an EXPLAIN result as a plan tree"),
+  bind(Scope::Results, KeyCode::Char('s'), Command::SnapshotResults, "Snapshot the results view under a name"),
+  bind(Scope::Results, KeyCode::Char('O'), Command::OpenSnapshots, "Reopen a results snapshot"),
+  bind(Scope::Results, KeyCode::Char('m'), Command::MarkCell, "Mark the cell to compare against another"),
+  bind(Scope::Results, KeyCode::Char('d'), Command::DiffCell, "Diff the cell against the marked cell or the clipboard"),
+  bind(
+    Scope::Results,
+    KeyCode::Char('p'),
+    Command::JsonPathColumn,
+    "Extract a JSON path of the column into a new column",
+  ),
+  bind(Scope::Results, KeyCode::Char('f'), Command::ColumnFormat, "Choose how the column's values are displayed"),
+  bind(
+    Scope::Results,
+    KeyCode::Char('{'),
+    Command::PreviousResultSet,
+    "Show the previous result set of the statements",
+  ),
+  bind(Scope::Results, KeyCode::Char('}'), Command::NextResultSet, "Show the next result set of the statements"),
+  bind(Scope::Results, KeyCode::Char('!'), Command::Notices, "List the notices and warnings of recent statements"),
+  bind(Scope::Results, KeyCode::Char('|'), Command::JumpToColumn, "Jump to a column picked by fuzzy search"),
+  bind(Scope::Results, KeyCode::PageDown, Command::PageDown, "Scroll the rows down a page"),
+  bind(Scope::Results, KeyCode::PageUp, Command::PageUp, "Scroll the rows up a page"),
+  ctrl(Scope::Results, 'd', Command::HalfPageDown, "Scroll the rows down half a page"),
+  ctrl(Scope::Results, 'u', Command::HalfPageUp, "Scroll the rows up half a page"),
+];
+
+/// Built-in key sequences typed after the leader key.
+pub const LEADER_CHORDS: &[(&str, Command)] = &[
+  ("?", Command::Help),
+  ("s", Command::Settings),
+  ("h", Command::History),
+  ("u", Command::Undo),
+  ("ex", Command::ExportMenu),
+  ("es", Command::SnapshotResults),
+  ("eo", Command::OpenSnapshots),
+  ("cm", Command::MarkCell),
+  ("cd", Command::DiffCell),
+  ("cf", Command::ColumnFormat),
+  ("cp", Command::JsonPathColumn),
+  ("c#", Command::CellHashes),
+  ("tc", Command::CountRows),
+  ("tp", Command::PreviewTable),
+  ("ts", Command::ShowColumns),
+  ("tj", Command::JoinBuilder),
+];
+
+/// Names shown in the chord hints for the first key of a group of chords.
+const CHORD_GROUPS: &[(char, &str)] = &[('e', "export"), ('c', "cell"), ('t', "table")];
+
+/// Leader key bindings, the `[leader]` config table. After the leader key, a sequence of characters runs a command
+/// and a popup lists the keys that can follow.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LeaderConfig {
+  /// The leader key, e.g. `\\` or `space`. It only starts a chord in the table list and the results grid.
+  pub key: String,
+  /// Chords added to or replacing the built-in ones, e.g. `xc = "CopyAllRows"`.
+  pub chords: BTreeMap<String, Command>,
+}
+
+impl Default for LeaderConfig {
+  fn default() -> Self {
+    Self { key: "\\".to_string(), chords: BTreeMap::new() }
+  }
+}
+
+impl LeaderConfig {
+  pub fn is_leader(&self, key: &KeyEvent) -> bool {
+    config::parse_key_event(&self.key).is_ok_and(|leader| same_key(leader.code, leader.modifiers, key))
+  }
+
+  /// The built-in chords with the configured ones applied, limited to commands available in `scope`.
+  pub fn chords(&self, scope: Scope) -> Vec<(String, Command)> {
+    let mut chords: BTreeMap<String, Command> =
+      LEADER_CHORDS.iter().map(|(keys, command)| (keys.to_string(), *command)).collect();
+    chords.extend(self.chords.iter().map(|(keys, command)| (keys.replace(' ', ""), *command)));
+    chords.into_iter().filter(|(_, command)| key_for(scope, *command).is_some()).collect()
+  }
+}
+
+/// What typing more keys of a chord leads to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChordMatch {
+  Command(Command),
+  /// The keys start one or more chords, with a hint for each key that can follow.
+  Pending(Vec<(char, String)>),
+  None,
+}
+
+/// Resolves the keys typed after the leader against `chords`.
+pub fn match_chord(chords: &[(String, Command)], typed: &str) -> ChordMatch {
+  if let Some((_, command)) = chords.iter().find(|(keys, _)| keys == typed) {
+    return ChordMatch::Command(*command);
+  }
+  let mut hints: Vec<(char, String)> = Vec::new();
+  for (keys, command) in chords {
+    let Some(next) = keys.strip_prefix(typed).and_then(|rest| rest.chars().next()) else {
+      continue;
+    };
+    if hints.iter().any(|(c, _)| *c == next) {
+      continue;
+    }
+    let hint = if keys.chars().count() == typed.chars().count() + 1 {
+      description(*command).to_string()
+    } else {
+      let group = CHORD_GROUPS.iter().find(|(c, _)| typed.is_empty() && *c == next).map_or("more", |(_, name)| name);
+      format!("+{group}")
+    };
+    hints.push((next, hint));
+  }
+  if hints.is_empty() {
+    ChordMatch::None
+  } else {
+    ChordMatch::Pending(hints)
+  }
+}
+
+/// Description of the first binding of `command`.
+pub fn description(command: Command) -> &'static str {
+  BINDINGS.iter().find(|b| b.command == command).map_or("", |b| b.description)
+}
+
+/// The key running `command` in `scope`, or a global key for it.
+pub fn key_for(scope: Scope, command: Command) -> Option<KeyEvent> {
+  BINDINGS
+    .iter()
+    .find(|b| b.command == command && (b.scope == scope || b.scope == Scope::Global))
+    .map(|b| KeyEvent::new(b.code, b.modifiers))
+}
+
+fn same_key(code: KeyCode, modifiers: KeyModifiers, key: &KeyEvent) -> bool {
+  // Terminals differ on whether shifted characters like `E` or `#` also report SHIFT.
+  let unshifted = |modifiers: KeyModifiers| {
+    if matches!(key.code, KeyCode::Char(_)) {
+      modifiers - KeyModifiers::SHIFT
+    } else {
+      modifiers
+    }
+  };
+  code == key.code && unshifted(modifiers) == unshifted(key.modifiers)
+}
+
+impl Binding {
+  fn matches(&self, key: &KeyEvent) -> bool {
+    same_key(self.code, self.modifiers, key)
+  }
+
+  /// Human readable key, e.g. `Alt-i` or `F2`.
+  pub fn key_label(&self) -> String {
+    let key = match self.code {
+      KeyCode::Char(' ') => "Space".to_string(),
+      KeyCode::Char(c) => c.to_string(),
+      KeyCode::F(n) => format!("F{n}"),
+      code => format!("{code:?}"),
+    };
+    if self.modifiers.contains(KeyModifiers::CONTROL) {
+      format!("Ctrl-{key}")
+    } else if self.modifiers.contains(KeyModifiers::ALT) {
+      format!("Alt-{key}")
+    } else {
+      key
+    }
+  }
+}
+
+/// The command bound to `key` in `scope`.
+pub fn lookup(scope: Scope, key: &KeyEvent) -> Option<Command> {
+  BINDINGS.iter().find(|b| b.scope == scope && b.matches(key)).map(|b| b.command)
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_lookup_ignores_shift_on_characters() {
+    let key = KeyEvent::new(KeyCode::Char('E'), KeyModifiers::SHIFT);
+    assert_eq!(lookup(Scope::Results, &key), Some(Command::ConvertEpoch));
+    assert_eq!(lookup(Scope::Editor, &KeyEvent::new(KeyCode::Char('i'), KeyModifiers::NONE)), None);
+    assert_eq!(
+      lookup(Scope::Editor, &KeyEvent::new(KeyCode::Char('i'), KeyModifiers::ALT)),
+      Some(Command::PasteInList)
+    );
+  }
+
+  #[test]
+  fn test_no_duplicate_bindings() {
+    for (i, a) in BINDINGS.iter().enumerate() {
+      for b in &BINDINGS[i + 1..] {
+        assert!(a.scope != b.scope || a.code != b.code || a.modifiers != b.modifiers, "{} bound twice", a.key_label());
+      }
+    }
+  }
+
+  #[test]
+  fn test_match_chord() {
+    let leader = LeaderConfig {
+      chords: BTreeMap::from([("x c".to_string(), Command::CopyAllRows), ("ex".to_string(), Command::CopyHeaders)]),
+      ..LeaderConfig::default()
+    };
+    let chords = leader.chords(Scope::Results);
+    assert_eq!(match_chord(&chords, "xc"), ChordMatch::Command(Command::CopyAllRows));
+    assert_eq!(match_chord(&chords, "ex"), ChordMatch::Command(Command::CopyHeaders));
+    assert_eq!(match_chord(&chords, "z"), ChordMatch::None);
+    let ChordMatch::Pending(hints) = match_chord(&chords, "") else {
+      panic!("expected hints");
+    };
+    assert!(hints.contains(&('e', "+export".to_string())));
+    assert!(hints.contains(&('h', description(Command::History).to_string())));
+    // Table commands aren't offered in the results grid.
+    assert!(!hints.iter().any(|(c, _)| *c == 't'));
+    assert!(leader.is_leader(&KeyEvent::new(KeyCode::Char('\\'), KeyModifiers::NONE)));
+  }
+
+  #[test]
+  fn test_key_label() {
+    assert_eq!(BINDINGS.iter().find(|b| b.command == Command::PasteInList).unwrap().key_label(), "Alt-i");
+    assert_eq!(BINDINGS.iter().find(|b| b.command == Command::Suspend).unwrap().key_label(), "Ctrl-z");
+  }
+}
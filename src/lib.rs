@@ -0,0 +1,71 @@
+//! The SQL workspace of query-crafter: connecting, running queries, splitting and formatting statements, completion,
+//! query history, exports, schema introspection queries and the analysis of plans, notices and errors. The
+//! `query-crafter` binary is a TUI frontend over these modules, other tools can embed them by depending on the crate.
+//!
+//! [`connection::connect`] opens the configured database as a [`sql::Queryer`]. Queries run through
+//! [`sql::run`], which reports results as [`sql::QueryEvent`]s, the binary turns them into UI actions. The
+//! introspection functions of [`schema`] return the SQL to run for each [`server::ServerKind`] and parse its rows.
+//!
+//! The terminal UI itself is public too, so tools embedding its components can drive them in their tests. The
+//! `test-support` feature adds the `test_support` module, seeded databases and a harness driving the
+//! [`components::db::Db`] component.
+
+// Writing to the terminal while the alternate screen is active garbles the UI, use `log` and status messages.
+#![deny(clippy::print_stdout, clippy::print_stderr)]
+
+pub mod attach;
+pub mod autocomplete;
+pub mod connection;
+pub mod diff;
+pub mod dirs;
+pub mod error_hints;
+pub mod export;
+pub mod format;
+pub mod fuzzy;
+pub mod history;
+pub mod jobs;
+pub mod join_builder;
+pub mod json_path;
+pub mod library;
+pub mod notices;
+pub mod outline;
+pub mod perf;
+pub mod plan;
+pub mod plan_hints;
+pub mod preamble;
+pub mod retry;
+pub mod schema;
+pub mod server;
+pub mod snippets;
+pub mod sql;
+pub mod statement;
+pub mod tags;
+pub mod team_history;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
+
+// The terminal UI.
+pub mod action;
+pub mod app;
+pub mod capabilities;
+pub mod cell;
+pub mod cli;
+pub mod column_format;
+pub mod commands;
+pub mod components;
+pub mod config;
+pub mod crash;
+pub mod custom_commands;
+pub mod events;
+pub mod import;
+pub mod keymap;
+pub mod locale;
+pub mod lock;
+pub mod mode;
+pub mod registers;
+pub mod results_filter;
+pub mod schema_change;
+pub mod settings;
+pub mod tui;
+pub mod undo;
+pub mod utils;
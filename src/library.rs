@@ -0,0 +1,243 @@
+use std::{
+  collections::BTreeMap,
+  path::{Path, PathBuf},
+  time::{Duration, SystemTime},
+};
+
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::tags;
+
+/// Front-matter keys read from the leading `-- key: value` comment lines of a library file.
+const FRONT_MATTER_KEYS: &[&str] = &["name", "description", "tags"];
+
+/// A directory of `.sql` files shared as snippets and saved queries, the `[library]` config table. Keeping it in a
+/// git repository lets a team version its queries.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LibraryConfig {
+  pub directory: Option<PathBuf>,
+  /// Seconds between checks of the directory for changes made outside the app.
+  pub poll_seconds: u64,
+}
+
+impl Default for LibraryConfig {
+  fn default() -> Self {
+    Self { directory: None, poll_seconds: 2 }
+  }
+}
+
+impl LibraryConfig {
+  pub fn poll_interval(&self) -> Duration {
+    Duration::from_secs(self.poll_seconds.max(1))
+  }
+}
+
+/// One `.sql` file of the library. The name is its completion trigger and defaults to the file name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibraryEntry {
+  pub name: String,
+  pub description: Option<String>,
+  /// Lower cased tags from the `tags` front matter and `#tag`s in the body's comments.
+  pub tags: Vec<String>,
+  pub body: String,
+  pub path: PathBuf,
+}
+
+impl LibraryEntry {
+  /// Parses a library file, e.g.
+  ///
+  /// ```sql
+  /// -- name: active_users
+  /// -- description: Users seen in the last week
+  /// -- tags: #users, #weekly
+  /// SELECT * FROM users WHERE last_seen > now() - interval '7 days';
+  /// ```
+  pub fn parse(path: &Path, text: &str) -> Self {
+    let mut name = None;
+    let mut description = None;
+    let mut entry_tags = Vec::new();
+    let mut lines = text.lines().peekable();
+    while let Some((key, value)) = lines.peek().and_then(|line| front_matter(line)) {
+      match key {
+        "name" => name = Some(value),
+        "tags" => entry_tags = tags::parse_list(&value),
+        _ => description = Some(value),
+      }
+      lines.next();
+    }
+    let body = lines.collect::<Vec<_>>().join("\n").trim().to_string();
+    for tag in tags::in_comments(&body) {
+      if !entry_tags.contains(&tag) {
+        entry_tags.push(tag);
+      }
+    }
+    let name = name.unwrap_or_else(|| path.file_stem().unwrap_or_default().to_string_lossy().to_string());
+    Self { name, description, tags: entry_tags, body, path: path.to_path_buf() }
+  }
+
+  /// File contents with the front-matter comments followed by the body.
+  pub fn to_file_text(&self) -> String {
+    let mut text = format!("-- name: {}\n", self.name);
+    if let Some(description) = &self.description {
+      text.push_str(&format!("-- description: {description}\n"));
+    }
+    let front_matter_tags: Vec<String> = self
+      .tags
+      .iter()
+      .filter(|tag| !tags::in_comments(&self.body).contains(tag))
+      .map(|tag| format!("#{tag}"))
+      .collect();
+    if !front_matter_tags.is_empty() {
+      text.push_str(&format!("-- tags: {}\n", front_matter_tags.join(" ")));
+    }
+    text.push_str(&self.body);
+    text.push('\n');
+    text
+  }
+}
+
+/// `(key, value)` of a `-- key: value` line with a known key.
+fn front_matter(line: &str) -> Option<(&'static str, String)> {
+  let (key, value) = line.strip_prefix("--")?.split_once(':')?;
+  let key = FRONT_MATTER_KEYS.iter().find(|k| key.trim().eq_ignore_ascii_case(k))?;
+  Some((key, value.trim().to_string()))
+}
+
+/// The loaded library and what the directory looked like, to notice external changes.
+#[derive(Debug, Clone, Default)]
+pub struct Library {
+  pub directory: PathBuf,
+  entries: Vec<LibraryEntry>,
+  fingerprint: Vec<(PathBuf, SystemTime)>,
+}
+
+impl Library {
+  pub fn load(directory: &Path) -> Result<Self> {
+    let mut library = Self { directory: directory.to_path_buf(), ..Self::default() };
+    library.reload()?;
+    Ok(library)
+  }
+
+  pub fn entries(&self) -> &[LibraryEntry] {
+    &self.entries
+  }
+
+  /// Rereads the directory when files were added, removed or modified, returning whether anything changed.
+  pub fn reload_if_changed(&mut self) -> Result<bool> {
+    if fingerprint(&self.directory)? == self.fingerprint {
+      return Ok(false);
+    }
+    self.reload()?;
+    Ok(true)
+  }
+
+  fn reload(&mut self) -> Result<()> {
+    self.fingerprint = fingerprint(&self.directory)?;
+    self.entries = self
+      .fingerprint
+      .iter()
+      .filter_map(|(path, _)| {
+        std::fs::read_to_string(path)
+          .map_err(|e| log::warn!("Unable to read library file {}: {e}", path.display()))
+          .ok()
+          .map(|text| LibraryEntry::parse(path, &text))
+      })
+      .collect();
+    self.entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(())
+  }
+
+  /// Entries with a tag starting with `prefix`.
+  pub fn tagged(&self, prefix: &str) -> Vec<&LibraryEntry> {
+    let prefix = prefix.to_lowercase();
+    self.entries.iter().filter(|e| e.tags.iter().any(|tag| tag.starts_with(&prefix))).collect()
+  }
+
+  /// Tags used in the library, sorted.
+  pub fn tags(&self) -> Vec<String> {
+    let mut tags: Vec<String> = self.entries.iter().flat_map(|e| e.tags.clone()).collect();
+    tags.sort();
+    tags.dedup();
+    tags
+  }
+
+  /// Library entries as snippet triggers and bodies.
+  pub fn snippets(&self) -> BTreeMap<String, String> {
+    self.entries.iter().map(|e| (e.name.clone(), e.body.clone())).collect()
+  }
+
+  /// Saves `body` as `<name>.sql`, replacing the entry with the same name. Characters that can't be part of a
+  /// completion trigger become `_`.
+  pub fn save(&mut self, name: &str, body: &str) -> Result<PathBuf> {
+    let name: String =
+      name.trim().chars().map(|c| if c.is_alphanumeric() || c == '-' || c == '_' { c } else { '_' }).collect();
+    if name.is_empty() {
+      return Err(eyre!("A library entry needs a name"));
+    }
+    let existing = self.entries.iter().find(|e| e.name == name);
+    let path = existing.map_or_else(|| self.directory.join(format!("{name}.sql")), |e| e.path.clone());
+    let entry = LibraryEntry {
+      name: name.clone(),
+      description: existing.and_then(|e| e.description.clone()),
+      tags: existing.map(|e| e.tags.clone()).unwrap_or_default(),
+      body: body.trim().to_string(),
+      path: path.clone(),
+    };
+    std::fs::create_dir_all(&self.directory)?;
+    std::fs::write(&path, entry.to_file_text())?;
+    self.reload()?;
+    Ok(path)
+  }
+}
+
+/// `.sql` files in `directory` with their modification times, sorted by path.
+fn fingerprint(directory: &Path) -> Result<Vec<(PathBuf, SystemTime)>> {
+  if !directory.exists() {
+    return Ok(Vec::new());
+  }
+  let mut files = Vec::new();
+  for entry in std::fs::read_dir(directory)? {
+    let path = entry?.path();
+    if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("sql")) {
+      files.push((path.clone(), std::fs::metadata(&path)?.modified()?));
+    }
+  }
+  files.sort();
+  Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_parse_front_matter() {
+    let text =
+      "-- name: active\n-- Description: Recent users\n-- tags: #Users, weekly\n-- not front matter #ops\nSELECT 1;\n";
+    let entry = LibraryEntry::parse(Path::new("q/users.sql"), text);
+    assert_eq!(entry.name, "active");
+    assert_eq!(entry.description.as_deref(), Some("Recent users"));
+    assert_eq!(entry.tags, vec!["users".to_string(), "weekly".to_string(), "ops".to_string()]);
+    assert_eq!(entry.body, "-- not front matter #ops\nSELECT 1;");
+    assert_eq!(LibraryEntry::parse(&entry.path, &entry.to_file_text()), entry);
+    assert_eq!(LibraryEntry::parse(Path::new("q/users.sql"), "SELECT 2").name, "users");
+  }
+
+  #[test]
+  fn test_save_and_reload() {
+    let directory = std::env::temp_dir().join(format!("query-crafter-library-{}", std::process::id()));
+    let mut library = Library::load(&directory).unwrap();
+    let path = library.save("open orders", "SELECT * FROM orders WHERE open\n").unwrap();
+    assert_eq!(path, directory.join("open_orders.sql"));
+    assert_eq!(library.snippets().get("open_orders").map(String::as_str), Some("SELECT * FROM orders WHERE open"));
+    assert!(!library.reload_if_changed().unwrap());
+    std::fs::remove_file(&path).unwrap();
+    assert!(library.reload_if_changed().unwrap());
+    assert!(library.entries().is_empty());
+    std::fs::remove_dir_all(&directory).unwrap();
+  }
+}
@@ -0,0 +1,147 @@
+use std::{borrow::Cow, env};
+
+use crate::snippets;
+
+/// Integer parts shorter than this are shown without group separators.
+const GROUP_MIN_DIGITS: usize = 5;
+
+/// Order of the day, month and year in a date.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DateOrder {
+  /// `2024-03-01`, also used for the C and POSIX locales.
+  #[default]
+  YearMonthDay,
+  DayMonthYear,
+  MonthDayYear,
+}
+
+/// Number and date conventions of the user's locale, used to make the results grid easier to scan. Only the
+/// display changes, copies and exports keep the raw values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Locale {
+  pub decimal: char,
+  pub group: char,
+  pub date_order: DateOrder,
+  pub date_separator: char,
+}
+
+impl Default for Locale {
+  fn default() -> Self {
+    Self { decimal: '.', group: ',', date_order: DateOrder::YearMonthDay, date_separator: '-' }
+  }
+}
+
+impl Locale {
+  /// Locale of the environment: numbers from `LC_NUMERIC` and dates from `LC_TIME`, each overridden by `LC_ALL` and
+  /// falling back to `LANG`.
+  pub fn from_env() -> Self {
+    let var =
+      |name: &str| ["LC_ALL", name, "LANG"].iter().find_map(|key| env::var(key).ok().filter(|value| !value.is_empty()));
+    let numbers = Self::for_tag(&var("LC_NUMERIC").unwrap_or_default());
+    let dates = Self::for_tag(&var("LC_TIME").unwrap_or_default());
+    Self { date_order: dates.date_order, date_separator: dates.date_separator, ..numbers }
+  }
+
+  /// Conventions for a locale name such as `de_DE.UTF-8` or `en-US`. Unknown languages get the defaults.
+  pub fn for_tag(tag: &str) -> Self {
+    let tag = tag.split(['.', '@']).next().unwrap_or_default().replace('-', "_");
+    let (language, territory) = tag.split_once('_').unwrap_or((&tag, ""));
+    let (decimal, group) = match (language, territory) {
+      ("de" | "it", "CH") => ('.', '\''),
+      ("de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr" | "el" | "ro" | "hr" | "sl", _) => (',', '.'),
+      ("fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "nn" | "uk" | "hu" | "bg" | "lt", _) => (',', ' '),
+      _ => ('.', ','),
+    };
+    let (date_order, date_separator) = match (language, territory) {
+      ("" | "C" | "POSIX", _) => (DateOrder::YearMonthDay, '-'),
+      ("en", "US") => (DateOrder::MonthDayYear, '/'),
+      ("ja" | "zh", _) => (DateOrder::YearMonthDay, '/'),
+      ("ko" | "hu", _) => (DateOrder::YearMonthDay, '.'),
+      ("sv" | "lt", _) => (DateOrder::YearMonthDay, '-'),
+      ("nl", _) => (DateOrder::DayMonthYear, '-'),
+      ("de" | "ru" | "pl" | "cs" | "sk" | "fi" | "nb" | "nn" | "da" | "tr" | "uk" | "ro" | "hr" | "sl" | "bg", _) => {
+        (DateOrder::DayMonthYear, '.')
+      },
+      _ => (DateOrder::DayMonthYear, '/'),
+    };
+    Self { decimal, group, date_order, date_separator }
+  }
+
+  /// `value` with locale number grouping or date order, or unchanged when it is neither a number nor a date.
+  pub fn display<'a>(&self, value: &'a str) -> Cow<'a, str> {
+    self.number(value).or_else(|| self.date(value)).map_or(Cow::Borrowed(value), Cow::Owned)
+  }
+
+  /// Numbers with the locale's decimal separator, and their integer part grouped in thousands from five digits on.
+  /// Shorter ones such as years and small ids read better without a group separator, `2024` rather than `2,024`.
+  fn number(&self, value: &str) -> Option<String> {
+    let plain = value.strip_prefix('-').unwrap_or(value).chars().all(|c| c.is_ascii_digit() || c == '.');
+    if !plain || !snippets::is_numeric(value) {
+      return None;
+    }
+    let (sign, digits) = value.split_at(usize::from(value.starts_with('-')));
+    let (integer, fraction) = digits.split_once('.').map_or((digits, None), |(i, f)| (i, Some(f)));
+    let grouped = integer.len() >= GROUP_MIN_DIGITS;
+    let mut text = sign.to_string();
+    for (i, c) in integer.chars().enumerate() {
+      if grouped && i > 0 && (integer.len() - i).is_multiple_of(3) {
+        text.push(self.group);
+      }
+      text.push(c);
+    }
+    if let Some(fraction) = fraction {
+      text.push(self.decimal);
+      text.push_str(fraction);
+    }
+    Some(text)
+  }
+
+  /// Dates as `YYYY-MM-DD`, optionally followed by a time, which is kept as it is.
+  fn date(&self, value: &str) -> Option<String> {
+    let (date, time) = value.split_at_checked(10)?;
+    let mut parts = date.split('-');
+    let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+    let valid =
+      [(year, 4), (month, 2), (day, 2)].iter().all(|(p, len)| p.len() == *len && p.bytes().all(|b| b.is_ascii_digit()));
+    if !valid || !(time.is_empty() || time.starts_with([' ', 'T'])) {
+      return None;
+    }
+    let sep = self.date_separator;
+    let date = match self.date_order {
+      DateOrder::YearMonthDay => format!("{year}{sep}{month}{sep}{day}"),
+      DateOrder::DayMonthYear => format!("{day}{sep}{month}{sep}{year}"),
+      DateOrder::MonthDayYear => format!("{month}{sep}{day}{sep}{year}"),
+    };
+    let time = time.strip_prefix('T').map_or(Cow::Borrowed(time), |t| Cow::Owned(format!(" {t}")));
+    Some(format!("{date}{time}"))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_numbers() {
+    let german = Locale::for_tag("de_DE.UTF-8");
+    assert_eq!(german.display("-1234567.25"), "-1.234.567,25");
+    assert_eq!(Locale::for_tag("en-US").display("1234567"), "1,234,567");
+    assert_eq!(Locale::for_tag("fr_FR").display("12345.5"), "12 345,5");
+    // Years and short ids aren't grouped, only the decimal separator changes.
+    assert_eq!(german.display("2024"), "2024");
+    assert_eq!(german.display("-1234.5"), "-1234,5");
+    // Padded codes and exponents stay as they are.
+    assert_eq!(german.display("007"), "007");
+    assert_eq!(german.display("1e10"), "1e10");
+  }
+
+  #[test]
+  fn test_dates() {
+    assert_eq!(Locale::for_tag("en_US").display("2024-03-01"), "03/01/2024");
+    assert_eq!(Locale::for_tag("de_DE").display("2024-03-01T10:15:00"), "01.03.2024 10:15:00");
+    assert_eq!(Locale::for_tag("C").display("2024-03-01 10:15:00+00"), "2024-03-01 10:15:00+00");
+    assert_eq!(Locale::for_tag("en_GB").display("2024-03-01x"), "2024-03-01x");
+  }
+}
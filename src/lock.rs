@@ -0,0 +1,181 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{capabilities, components::text_input::TextInput, tui::Frame};
+
+/// Locking the UI after a period without input, the `[lock]` config table.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LockConfig {
+  /// Minutes without input before the UI locks, 0 disables the idle lock.
+  pub idle_minutes: u64,
+  /// Ask for the connection password to unlock instead of a confirmation, when the connection has one.
+  pub require_password: bool,
+}
+
+impl LockConfig {
+  pub fn idle_timeout(&self) -> Option<Duration> {
+    (self.idle_minutes > 0).then(|| Duration::from_secs(self.idle_minutes * 60))
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LockState {
+  Unlocked,
+  Locked,
+  /// A key was pressed on the lock screen, waiting for Enter (and the password, if required).
+  Unlocking,
+}
+
+/// Idle lock of the whole UI. While locked the screen is blanked and keys only go to the lock screen.
+#[derive(Debug, Clone)]
+pub struct IdleLock {
+  timeout: Option<Duration>,
+  /// Password needed to unlock, `None` asks for a confirmation only.
+  password: Option<String>,
+  state: LockState,
+  last_input: Instant,
+  input: TextInput,
+  wrong_password: bool,
+}
+
+impl IdleLock {
+  pub fn new(timeout: Option<Duration>, password: Option<String>) -> Self {
+    Self {
+      timeout,
+      password,
+      state: LockState::Unlocked,
+      last_input: Instant::now(),
+      input: TextInput::default(),
+      wrong_password: false,
+    }
+  }
+
+  pub fn is_locked(&self) -> bool {
+    self.state != LockState::Unlocked
+  }
+
+  pub fn lock(&mut self) {
+    self.state = LockState::Locked;
+    self.input.clear();
+    self.wrong_password = false;
+  }
+
+  /// Records user input, postponing the idle lock.
+  pub fn touch(&mut self) {
+    self.last_input = Instant::now();
+  }
+
+  /// Locks when the idle timeout has passed, returning true if the lock was engaged by this call.
+  pub fn check_idle(&mut self, now: Instant) -> bool {
+    let idle = self.timeout.is_some_and(|timeout| now.duration_since(self.last_input) >= timeout);
+    if idle && !self.is_locked() {
+      self.lock();
+      return true;
+    }
+    false
+  }
+
+  /// Handles a key while locked, returning true once the UI is unlocked.
+  pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+    match (&self.state, key.code) {
+      (LockState::Unlocked, _) => return true,
+      (LockState::Locked, _) => self.state = LockState::Unlocking,
+      (LockState::Unlocking, KeyCode::Esc) => self.lock(),
+      (LockState::Unlocking, KeyCode::Enter) => {
+        if self.password.as_deref().is_none_or(|password| self.input.value() == password) {
+          self.state = LockState::Unlocked;
+          self.input.clear();
+          self.touch();
+          return true;
+        }
+        self.input.clear();
+        self.wrong_password = true;
+      },
+      (LockState::Unlocking, _) if self.password.is_some() => {
+        self.input.handle_key(key);
+      },
+      (LockState::Unlocking, _) => {},
+    }
+    false
+  }
+
+  /// Covers the whole screen so no result data stays visible.
+  pub fn render(&self, f: &mut Frame<'_>) {
+    let screen = f.size();
+    f.render_widget(Clear, screen);
+    f.render_widget(Block::default().style(Style::default().bg(Color::Black)), screen);
+    let area = Rect::new(
+      screen.width.saturating_sub(50) / 2,
+      screen.height.saturating_sub(5) / 2,
+      50.min(screen.width),
+      5.min(screen.height),
+    );
+    let block = capabilities::block().borders(Borders::ALL).title("Locked");
+    match (&self.state, &self.password) {
+      (LockState::Unlocking, Some(_)) => {
+        let title = if self.wrong_password { "Wrong password, try again" } else { "Connection password" };
+        let mut masked = TextInput::default();
+        masked.set_value("*".repeat(self.input.value().chars().count()));
+        masked.render(f, area, block.title(title), Style::default(), true);
+      },
+      (LockState::Unlocking, None) => {
+        let text = Paragraph::new("Press Enter to unlock, Esc to stay locked").alignment(Alignment::Center);
+        f.render_widget(text.block(block), area);
+      },
+      _ => {
+        let text = Paragraph::new("Press any key to unlock").alignment(Alignment::Center);
+        f.render_widget(text.block(block), area);
+      },
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crossterm::event::KeyModifiers;
+
+  use super::*;
+
+  fn press(lock: &mut IdleLock, code: KeyCode) -> bool {
+    lock.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+  }
+
+  #[test]
+  fn test_idle_timeout() {
+    let mut lock = IdleLock::new(Some(Duration::from_secs(60)), None);
+    let start = Instant::now();
+    lock.last_input = start;
+    assert!(!lock.check_idle(start + Duration::from_secs(59)));
+    assert!(lock.check_idle(start + Duration::from_secs(60)));
+    assert!(lock.is_locked());
+    assert!(!IdleLock::new(None, None).check_idle(start + Duration::from_secs(3600)));
+  }
+
+  #[test]
+  fn test_unlock_with_confirmation() {
+    let mut lock = IdleLock::new(None, None);
+    lock.lock();
+    assert!(!press(&mut lock, KeyCode::Char('x')));
+    assert!(!press(&mut lock, KeyCode::Esc));
+    assert!(!press(&mut lock, KeyCode::Enter));
+    assert!(press(&mut lock, KeyCode::Enter));
+    assert!(!lock.is_locked());
+  }
+
+  #[test]
+  fn test_unlock_with_password() {
+    let mut lock = IdleLock::new(None, Some("pw".to_string()));
+    lock.lock();
+    press(&mut lock, KeyCode::Char(' '));
+    press(&mut lock, KeyCode::Char('p'));
+    assert!(!press(&mut lock, KeyCode::Enter));
+    assert!(lock.wrong_password);
+    press(&mut lock, KeyCode::Char('p'));
+    press(&mut lock, KeyCode::Char('w'));
+    assert!(press(&mut lock, KeyCode::Enter));
+  }
+}
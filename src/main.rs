@@ -1,24 +1,18 @@
-#![allow(dead_code)]
-#![allow(unused_imports)]
-#![allow(unused_variables)]
-
-pub mod action;
-pub mod app;
-pub mod cli;
-pub mod components;
-pub mod config;
-pub mod mode;
-pub mod sql;
-pub mod tui;
-pub mod utils;
+#![deny(clippy::print_stdout, clippy::print_stderr)]
+
+use std::io::Write;
 
 use clap::Parser;
-use cli::Cli;
 use color_eyre::eyre::Result;
-
-use crate::{
+use query_crafter::{
   app::App,
-  utils::{initialize_logging, initialize_panic_handler, version},
+  capabilities::Capabilities,
+  cli::{self, Cli},
+  commands,
+  config::Config,
+  events::EventLog,
+  import,
+  utils::{initialize_logging, initialize_panic_handler},
 };
 
 async fn tokio_main() -> Result<()> {
@@ -27,16 +21,33 @@ async fn tokio_main() -> Result<()> {
   initialize_panic_handler()?;
 
   let args = Cli::parse();
-  let mut app = App::new(args.tick_rate, args.frame_rate, args.filename).await?;
+  if let Some(source) = args.import {
+    let summary = import::run(source, args.import_path, &Config::new()?)?;
+    report(&summary);
+    return Ok(());
+  }
+  match args.command {
+    None | Some(cli::Commands::Tui) => {},
+    Some(command) => return commands::run(command, args.filename, &Config::new()?).await,
+  }
+  let events = args.json_events.as_deref().map(EventLog::open).transpose()?;
+  let capabilities = if args.ascii { Capabilities::BASIC } else { Capabilities::detect() };
+  let mut app = App::new(args.tick_rate, args.frame_rate, args.filename, args.debug, events, capabilities).await?;
   app.run().await?;
 
   Ok(())
 }
 
+/// Prints a line for commands that run without the terminal UI, such as imports.
+fn report(message: &str) {
+  let _ = writeln!(std::io::stdout(), "{message}");
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
   if let Err(e) = tokio_main().await {
-    eprintln!("{} error: Something went wrong", env!("CARGO_PKG_NAME"));
+    // The terminal has been restored by the time an error gets here.
+    let _ = writeln!(std::io::stderr(), "{} error: Something went wrong", env!("CARGO_PKG_NAME"));
     Err(e)
   } else {
     Ok(())
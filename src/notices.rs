@@ -0,0 +1,154 @@
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use strum::Display;
+
+/// Statements whose notices are kept for the notices popup.
+const NOTICE_LOG_STATEMENTS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Display)]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum Severity {
+  Debug,
+  Info,
+  Log,
+  Notice,
+  Warning,
+}
+
+/// An asynchronous server message, such as a Postgres `RAISE NOTICE` or a MySQL warning.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Notice {
+  pub severity: Severity,
+  pub message: String,
+}
+
+impl Notice {
+  /// A notice from server text such as `WARNING:  there is no transaction in progress`, a `NOTICE` when the text
+  /// doesn't start with a severity.
+  pub fn parse(text: &str) -> Self {
+    let severities =
+      [Severity::Debug, Severity::Info, Severity::Log, Severity::Notice, Severity::Warning].map(|s| (s.to_string(), s));
+    let parsed = text.split_once(':').and_then(|(prefix, message)| {
+      let prefix = prefix.trim().to_uppercase();
+      // Postgres reports DEBUG1 to DEBUG5.
+      let prefix = if prefix.starts_with("DEBUG") { "DEBUG" } else { prefix.as_str() };
+      severities.iter().find(|(name, _)| name == prefix).map(|(_, severity)| (*severity, message.trim()))
+    });
+    let (severity, message) = parsed.unwrap_or((Severity::Notice, text.trim()));
+    Self { severity, message: message.to_string() }
+  }
+}
+
+impl std::fmt::Display for Notice {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}: {}", self.severity, self.message)
+  }
+}
+
+/// The notices of an executed statement.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatementNotices {
+  pub query: String,
+  /// Local time the statement ran, as `HH:MM:SS`.
+  pub executed_at: String,
+  pub notices: Vec<Notice>,
+}
+
+/// Notices of the recently executed statements that raised any, oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NoticeLog {
+  statements: Vec<StatementNotices>,
+  /// Whether the last statement is the one whose results are shown.
+  current: bool,
+}
+
+impl NoticeLog {
+  /// Starts collecting the notices of a newly executed statement.
+  pub fn start(&mut self, query: &str) {
+    if self.current && self.statements.last().is_some_and(|last| last.notices.is_empty()) {
+      self.statements.pop();
+    }
+    if self.statements.len() == NOTICE_LOG_STATEMENTS {
+      self.statements.remove(0);
+    }
+    self.statements.push(StatementNotices {
+      query: query.to_string(),
+      executed_at: Local::now().format("%H:%M:%S").to_string(),
+      notices: Vec::new(),
+    });
+    self.current = true;
+  }
+
+  /// Records a notice of the statement last started.
+  pub fn push(&mut self, notice: Notice) {
+    match self.statements.last_mut() {
+      Some(last) if self.current => last.notices.push(notice),
+      _ => {
+        self.start("");
+        self.push(notice);
+      },
+    }
+  }
+
+  /// Stops treating the last statement's notices as those of the shown results, e.g. when a snapshot replaces them.
+  pub fn detach(&mut self) {
+    if self.current && self.statements.last().is_some_and(|last| last.notices.is_empty()) {
+      self.statements.pop();
+    }
+    self.current = false;
+  }
+
+  /// Notices of the statement whose results are shown.
+  pub fn current(&self) -> &[Notice] {
+    match self.statements.last() {
+      Some(last) if self.current => &last.notices,
+      _ => &[],
+    }
+  }
+
+  /// Statements that raised notices, newest first.
+  pub fn statements(&self) -> impl Iterator<Item = &StatementNotices> {
+    self.statements.iter().rev().filter(|statement| !statement.notices.is_empty())
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.statements().next().is_none()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_parse() {
+    assert_eq!(Notice::parse("WARNING:  there is no transaction in progress"), Notice {
+      severity: Severity::Warning,
+      message: "there is no transaction in progress".to_string()
+    });
+    assert_eq!(Notice::parse("debug2: planning").severity, Severity::Debug);
+    assert_eq!(Notice::parse("total: 3 rows"), Notice {
+      severity: Severity::Notice,
+      message: "total: 3 rows".to_string()
+    });
+  }
+
+  #[test]
+  fn test_log() {
+    let mut log = NoticeLog::default();
+    log.start("CALL refresh()");
+    log.push(Notice::parse("NOTICE: refreshed 3 views"));
+    log.start("SELECT 1");
+    assert_eq!(log.current(), &[]);
+    log.start("ROLLBACK");
+    log.push(Notice::parse("WARNING: there is no transaction in progress"));
+    let queries: Vec<&str> = log.statements().map(|statement| statement.query.as_str()).collect();
+    assert_eq!(queries, vec!["ROLLBACK", "CALL refresh()"]);
+    assert_eq!(log.current().len(), 1);
+    log.detach();
+    assert_eq!(log.current(), &[]);
+    assert!(!log.is_empty());
+  }
+}
@@ -0,0 +1,304 @@
+use crate::format::{self, Token};
+
+/// Words between `CREATE`/`ALTER`/`DROP` and the name of the object.
+const OBJECT_WORDS: &[&str] = &[
+  "OR",
+  "REPLACE",
+  "TEMP",
+  "TEMPORARY",
+  "UNLOGGED",
+  "MATERIALIZED",
+  "UNIQUE",
+  "CONCURRENTLY",
+  "IF",
+  "NOT",
+  "EXISTS",
+  "TABLE",
+  "VIEW",
+  "INDEX",
+  "FUNCTION",
+  "PROCEDURE",
+  "SEQUENCE",
+  "SCHEMA",
+  "TRIGGER",
+  "TYPE",
+];
+
+/// An entry of the outline of a SQL buffer: a statement (`depth` 0) or one of its common table expressions
+/// (`depth` 1).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutlineEntry {
+  pub depth: usize,
+  pub label: String,
+  /// Character offset in the buffer where the entry starts.
+  pub offset: usize,
+}
+
+/// A common table expression of a WITH clause.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cte {
+  pub name: String,
+  /// Character offset of the name in the buffer.
+  pub offset: usize,
+  /// The declared column list, or the names the body's select list projects.
+  pub columns: Vec<String>,
+}
+
+/// Statements of `sql` described by their kind and target table, each followed by its CTEs.
+pub fn outline(sql: &str) -> Vec<OutlineEntry> {
+  statements(sql).iter().flat_map(|tokens| statement_outline(tokens)).collect()
+}
+
+/// CTEs of the WITH clauses starting the statements of `sql`.
+pub fn ctes(sql: &str) -> Vec<Cte> {
+  statements(sql).iter().flat_map(|tokens| with_clause(tokens).0).collect()
+}
+
+/// Tokens of each statement, without comments.
+fn statements(sql: &str) -> Vec<Vec<(Token, usize)>> {
+  let tokens: Vec<(Token, usize)> = format::lex(sql)
+    .into_iter()
+    .filter(|(token, _)| !matches!(token, Token::LineComment(_) | Token::BlockComment(_)))
+    .collect();
+  tokens
+    .split(|(token, _)| *token == Token::Semicolon)
+    .filter(|statement| !statement.is_empty())
+    .map(<[_]>::to_vec)
+    .collect()
+}
+
+fn statement_outline(tokens: &[(Token, usize)]) -> Vec<OutlineEntry> {
+  let (ctes, main) = with_clause(tokens);
+  let mut label = describe(&tokens[main..]);
+  if !ctes.is_empty() {
+    label = format!("WITH … {label}");
+  }
+  let mut entries = vec![OutlineEntry { depth: 0, label, offset: tokens[0].1 }];
+  entries.extend(ctes.into_iter().map(|cte| OutlineEntry { depth: 1, label: cte.name, offset: cte.offset }));
+  entries
+}
+
+/// The CTEs of a statement starting with WITH, and the index of the token starting the main statement.
+fn with_clause(tokens: &[(Token, usize)]) -> (Vec<Cte>, usize) {
+  let mut ctes = Vec::new();
+  if keyword(tokens, 0).as_deref() != Some("WITH") {
+    return (ctes, 0);
+  }
+  let mut i = 1;
+  if keyword(tokens, i).as_deref() == Some("RECURSIVE") {
+    i += 1;
+  }
+  while let Some((name, offset)) = name_at(tokens, i).zip(tokens.get(i).map(|(_, offset)| *offset)) {
+    let mut columns = Vec::new();
+    // Skip the column list and AS [NOT] MATERIALIZED up to the body, then the body itself.
+    i = next_open(tokens, i);
+    if !matches!(keyword(tokens, i - 1).as_deref(), Some("AS" | "MATERIALIZED")) {
+      let end = skip_parens(tokens, i);
+      columns = (i..end).filter_map(|j| name_at(tokens, j).filter(|_| matches!(tokens[j].0, Token::Word(_)))).collect();
+      i = next_open(tokens, end);
+    }
+    let end = skip_parens(tokens, i);
+    // An unclosed body, while it is being typed, has no columns yet.
+    if columns.is_empty() && end > i + 1 && tokens[end - 1].0 == Token::Close {
+      columns = projected_columns(&tokens[i + 1..end - 1]);
+    }
+    ctes.push(Cte { name, offset, columns });
+    i = end;
+    if tokens.get(i).is_some_and(|(token, _)| *token == Token::Comma) {
+      i += 1;
+    } else {
+      break;
+    }
+  }
+  (ctes, i.min(tokens.len()))
+}
+
+/// Names of the columns the first top level select list in `tokens` produces, as Postgres names them: the alias,
+/// the column of a (qualified) column reference or the name of a function. `*` and unnamed expressions are skipped.
+fn projected_columns(tokens: &[(Token, usize)]) -> Vec<String> {
+  let mut depth = 0;
+  let mut items: Vec<Vec<&Token>> = Vec::new();
+  let mut in_select = false;
+  for (i, (token, _)) in tokens.iter().enumerate() {
+    match token {
+      Token::Open => depth += 1,
+      Token::Close => depth -= 1,
+      _ => {},
+    }
+    let word = keyword(tokens, i);
+    if depth == 0 && !in_select {
+      if word.as_deref() == Some("SELECT") {
+        in_select = true;
+        items.push(Vec::new());
+      }
+      continue;
+    }
+    if depth == 0 && matches!(word.as_deref(), Some("FROM" | "INTO" | "WHERE" | "UNION" | "INTERSECT" | "EXCEPT")) {
+      break;
+    }
+    match token {
+      Token::Comma if depth == 0 => items.push(Vec::new()),
+      Token::Word(w) if depth == 0 && items.last().is_some_and(Vec::is_empty) && is_set_quantifier(w) => {},
+      token => items.last_mut().into_iter().for_each(|item| item.push(token)),
+    }
+  }
+  items.iter().filter_map(|item| column_name(item)).collect()
+}
+
+fn is_set_quantifier(word: &str) -> bool {
+  word.eq_ignore_ascii_case("DISTINCT") || word.eq_ignore_ascii_case("ALL")
+}
+
+fn column_name(item: &[&Token]) -> Option<String> {
+  let name = |token: &Token| {
+    match token {
+      Token::Word(w) => Some(w.clone()),
+      Token::Literal(l) if l.starts_with('"') => Some(l.clone()),
+      _ => None,
+    }
+  };
+  match item {
+    [single] => name(single),
+    [.., Token::Dot, last] => name(last),
+    [.., Token::Word(as_), last] if as_.eq_ignore_ascii_case("AS") => name(last),
+    [Token::Word(case), .., Token::Word(end)]
+      if case.eq_ignore_ascii_case("CASE") && end.eq_ignore_ascii_case("END") =>
+    {
+      Some("case".to_string())
+    },
+    [.., Token::Close | Token::Literal(_) | Token::Word(_), last @ Token::Word(_)] => name(last),
+    [Token::Word(function), Token::Open, ..] => Some(function.to_lowercase()),
+    _ => None,
+  }
+}
+
+fn next_open(tokens: &[(Token, usize)], from: usize) -> usize {
+  tokens[from.min(tokens.len())..]
+    .iter()
+    .position(|(token, _)| *token == Token::Open)
+    .map_or(tokens.len(), |p| from + p)
+}
+
+/// The statement kind with its target, e.g. `SELECT FROM orders` or `INSERT INTO audit_log`.
+fn describe(tokens: &[(Token, usize)]) -> String {
+  let Some(kind) = keyword(tokens, 0) else {
+    return "(…)".to_string();
+  };
+  let after = |word: &str| {
+    let mut depth = 0;
+    for (i, (token, _)) in tokens.iter().enumerate() {
+      match token {
+        Token::Open => depth += 1,
+        Token::Close => depth -= 1,
+        Token::Word(w) if depth == 0 && w.eq_ignore_ascii_case(word) => return name_at(tokens, i + 1),
+        _ => {},
+      }
+    }
+    None
+  };
+  let target = match kind.as_str() {
+    "SELECT" | "TABLE" => after("FROM").map(|table| format!("FROM {table}")),
+    "INSERT" => after("INTO").map(|table| format!("INTO {table}")),
+    "UPDATE" => name_at(tokens, 1),
+    "DELETE" => after("FROM").map(|table| format!("FROM {table}")),
+    "CREATE" | "ALTER" | "DROP" => {
+      let words =
+        (1..tokens.len()).take_while(|i| keyword(tokens, *i).is_some_and(|w| OBJECT_WORDS.contains(&w.as_str())));
+      let end = words.last().map_or(1, |i| i + 1);
+      let object: Vec<String> = (1..end).filter_map(|i| keyword(tokens, i)).collect();
+      Some(format!("{} {}", object.join(" "), name_at(tokens, end).unwrap_or_default()).trim().to_string())
+    },
+    _ => None,
+  };
+  match target {
+    Some(target) => format!("{kind} {target}"),
+    None => kind,
+  }
+}
+
+/// The upper cased word at `i`.
+fn keyword(tokens: &[(Token, usize)], i: usize) -> Option<String> {
+  match tokens.get(i) {
+    Some((Token::Word(word), _)) => Some(word.to_uppercase()),
+    _ => None,
+  }
+}
+
+/// A possibly schema qualified name starting at `i`.
+fn name_at(tokens: &[(Token, usize)], mut i: usize) -> Option<String> {
+  let mut name = String::new();
+  while let Some((Token::Word(part) | Token::Literal(part), _)) = tokens.get(i) {
+    name.push_str(part);
+    if tokens.get(i + 1).is_some_and(|(token, _)| *token == Token::Dot) {
+      name.push('.');
+      i += 2;
+    } else {
+      break;
+    }
+  }
+  (!name.is_empty()).then_some(name)
+}
+
+/// Index after the parenthesis opened at `i` is closed.
+fn skip_parens(tokens: &[(Token, usize)], mut i: usize) -> usize {
+  let mut depth = 0;
+  while i < tokens.len() {
+    match tokens[i].0 {
+      Token::Open => depth += 1,
+      Token::Close => depth -= 1,
+      _ => {},
+    }
+    i += 1;
+    if depth == 0 {
+      break;
+    }
+  }
+  i
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn labels(sql: &str) -> Vec<(usize, String, usize)> {
+    outline(sql).into_iter().map(|entry| (entry.depth, entry.label, entry.offset)).collect()
+  }
+
+  #[test]
+  fn test_outline() {
+    let sql = "-- daily totals\nWITH recent (id) AS (SELECT id FROM orders), totals AS MATERIALIZED (SELECT 1)\n\
+               SELECT * FROM recent r JOIN totals t ON true;\nINSERT INTO audit.log VALUES (1);\n\
+               CREATE OR REPLACE VIEW \"Daily\" AS SELECT 1;\nupdate users set x = (select 1 from y)";
+    let line = |n: usize| sql.split('\n').take(n).map(|l| l.chars().count() + 1).sum::<usize>();
+    assert_eq!(labels(sql), vec![
+      (0, "WITH … SELECT FROM recent".to_string(), line(1)),
+      (1, "recent".to_string(), line(1) + 5),
+      (1, "totals".to_string(), line(1) + 45),
+      (0, "INSERT INTO audit.log".to_string(), line(3)),
+      (0, "CREATE OR REPLACE VIEW \"Daily\"".to_string(), line(4)),
+      (0, "UPDATE users".to_string(), line(5)),
+    ]);
+  }
+
+  #[test]
+  fn test_ctes() {
+    let sql = "WITH totals (user_id, amount) AS (SELECT 1, 2), recent AS (SELECT DISTINCT o.id, o.created_at AS \
+               at, count(*), sum(x) total, \"Label\", 1 + 2, o.* FROM orders o) SELECT * FROM recent";
+    let columns: Vec<(String, Vec<String>)> = ctes(sql).into_iter().map(|cte| (cte.name, cte.columns)).collect();
+    let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+    assert_eq!(columns, vec![
+      ("totals".to_string(), names(&["user_id", "amount"])),
+      ("recent".to_string(), names(&["id", "at", "count", "total", "\"Label\""])),
+    ]);
+  }
+
+  #[test]
+  fn test_unclosed_ctes() {
+    for sql in ["WITH x AS (", "WITH x AS (SELECT a FROM t", "WITH x (a, b"] {
+      let names: Vec<String> = ctes(sql).into_iter().map(|cte| cte.name).collect();
+      assert_eq!(names, vec!["x".to_string()], "{sql}");
+    }
+  }
+}
@@ -0,0 +1,180 @@
+use std::{
+  mem::size_of,
+  sync::{Mutex, MutexGuard},
+  time::Duration,
+};
+
+use lazy_static::lazy_static;
+
+/// Timings and sizes collected across the app for the performance HUD.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PerfMetrics {
+  pub draw_time: Duration,
+  pub action_latency: Duration,
+  pub max_action_latency: Duration,
+  pub result_bytes: usize,
+  pub query: Option<QueryTiming>,
+  /// The next draw shows a new result and is recorded as the query's render time.
+  awaiting_render: bool,
+}
+
+/// Round trip of the last query: waiting on the database (execution, network and fetching the rows), updating the
+/// results state and drawing the first frame showing them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryTiming {
+  pub database: Duration,
+  /// Execution time reported by the server, part of `database`. Only known for EXPLAIN ANALYZE output.
+  pub server: Option<Duration>,
+  pub apply: Duration,
+  pub render: Option<Duration>,
+  pub rows: usize,
+}
+
+impl QueryTiming {
+  pub fn total(&self) -> Duration {
+    self.database + self.apply + self.render.unwrap_or_default()
+  }
+
+  /// E.g. `12.1ms (server 3.0ms, db 10.0ms, ui 1.0ms, render 1.1ms)`.
+  pub fn breakdown(&self) -> String {
+    let mut parts = Vec::new();
+    if let Some(server) = self.server {
+      parts.push(format!("server {server:.1?}"));
+    }
+    parts.push(format!("db {:.1?}", self.database));
+    parts.push(format!("ui {:.1?}", self.apply));
+    if let Some(render) = self.render {
+      parts.push(format!("render {render:.1?}"));
+    }
+    format!("{:.1?} ({})", self.total(), parts.join(", "))
+  }
+}
+
+lazy_static! {
+  static ref METRICS: Mutex<PerfMetrics> = Mutex::new(PerfMetrics::default());
+}
+
+fn metrics() -> MutexGuard<'static, PerfMetrics> {
+  METRICS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn snapshot() -> PerfMetrics {
+  metrics().clone()
+}
+
+pub fn record_draw(elapsed: Duration) {
+  let mut metrics = metrics();
+  metrics.draw_time = elapsed;
+  if std::mem::take(&mut metrics.awaiting_render) {
+    if let Some(query) = &mut metrics.query {
+      query.render = Some(elapsed);
+    }
+  }
+}
+
+pub fn record_action(elapsed: Duration) {
+  let mut metrics = metrics();
+  metrics.action_latency = elapsed;
+  metrics.max_action_latency = metrics.max_action_latency.max(elapsed);
+}
+
+/// Starts a new window for the maximum action latency.
+pub fn reset_max_action_latency() {
+  metrics().max_action_latency = Duration::ZERO;
+}
+
+pub fn record_query_database(elapsed: Duration) {
+  metrics().query = Some(QueryTiming { database: elapsed, ..Default::default() });
+}
+
+pub fn record_query_apply(elapsed: Duration, headers: &[String], rows: &[Vec<String>], result_bytes: usize) {
+  let mut metrics = metrics();
+  metrics.result_bytes = result_bytes;
+  metrics.awaiting_render = true;
+  let query = metrics.query.get_or_insert_with(QueryTiming::default);
+  query.apply = elapsed;
+  query.rows = rows.len();
+  query.server = server_execution_time(headers, rows);
+}
+
+/// The `Execution Time: 1.234 ms` line Postgres adds to the end of EXPLAIN ANALYZE text plans, other results are
+/// not looked at.
+pub fn server_execution_time(headers: &[String], rows: &[Vec<String>]) -> Option<Duration> {
+  // Postgres names the single column of its text plans `QUERY PLAN`.
+  if !matches!(headers, [header] if header.eq_ignore_ascii_case("QUERY PLAN")) {
+    return None;
+  }
+  rows.iter().rev().filter_map(|row| row.first()).find_map(|cell| {
+    let millis = cell.trim().strip_prefix("Execution Time:")?.trim().strip_suffix("ms")?.trim();
+    let ms = millis.parse::<f64>().ok().filter(|ms| ms.is_finite() && *ms >= 0.0)?;
+    Duration::try_from_secs_f64(ms / 1000.0).ok()
+  })
+}
+
+/// Approximate heap and inline size of a result set.
+pub fn estimate_result_bytes(headers: &[String], rows: &[Vec<String>]) -> usize {
+  let strings = |values: &[String]| values.iter().map(|v| size_of::<String>() + v.capacity()).sum::<usize>();
+  strings(headers) + rows.iter().map(|row| size_of::<Vec<String>>() + strings(row)).sum::<usize>()
+}
+
+/// Formats a byte count with a binary unit, e.g. `1.5 MiB`.
+pub fn format_bytes(bytes: usize) -> String {
+  const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+  let mut value = bytes as f64;
+  let mut unit = 0;
+  while value >= 1024.0 && unit < UNITS.len() - 1 {
+    value /= 1024.0;
+    unit += 1;
+  }
+  if unit == 0 {
+    format!("{bytes} B")
+  } else {
+    format!("{value:.1} {}", UNITS[unit])
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_format_bytes() {
+    assert_eq!(format_bytes(512), "512 B");
+    assert_eq!(format_bytes(1536), "1.5 KiB");
+    assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MiB");
+  }
+
+  #[test]
+  fn test_server_execution_time() {
+    let headers = vec!["QUERY PLAN".to_string()];
+    let rows = vec![vec!["Seq Scan on users".to_string()], vec!["Execution Time: 1.500 ms".to_string()]];
+    assert_eq!(server_execution_time(&headers, &rows), Some(Duration::from_micros(1500)));
+    assert_eq!(server_execution_time(&headers, &rows[..1]), None);
+    for time in ["-1 ms", "NaN ms", "inf ms", "1e300 ms"] {
+      let rows = vec![vec!["Seq Scan on users".to_string()], vec![format!("Execution Time: {time}")]];
+      assert_eq!(server_execution_time(&headers, &rows), None, "{time}");
+    }
+    assert_eq!(server_execution_time(&["note".to_string()], &rows), None);
+  }
+
+  #[test]
+  fn test_breakdown() {
+    let timing = QueryTiming {
+      database: Duration::from_millis(10),
+      apply: Duration::from_millis(1),
+      render: Some(Duration::from_millis(2)),
+      ..Default::default()
+    };
+    assert_eq!(timing.breakdown(), "13.0ms (db 10.0ms, ui 1.0ms, render 2.0ms)");
+  }
+
+  #[test]
+  fn test_estimate_result_bytes() {
+    let headers = vec!["id".to_string()];
+    let rows = vec![vec!["1".to_string()], vec!["22".to_string()]];
+    let expected = 3 * size_of::<String>() + 2 * size_of::<Vec<String>>() + headers[0].capacity() + 1 + 2;
+    assert_eq!(estimate_result_bytes(&headers, &rows), expected);
+  }
+}
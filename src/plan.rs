@@ -0,0 +1,455 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+/// EXPLAIN output formats recognized in a result set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanFormat {
+  /// Postgres' default text plan, one `QUERY PLAN` row per line.
+  PostgresText,
+  /// Postgres' `EXPLAIN (FORMAT JSON)`.
+  PostgresJson,
+  /// SQLite's `EXPLAIN QUERY PLAN` with its `id`, `parent`, `notused` and `detail` columns.
+  SqliteQueryPlan,
+  /// MySQL's tabular EXPLAIN, one row per table access.
+  MysqlTable,
+  /// MySQL's `EXPLAIN FORMAT=JSON`.
+  MysqlJson,
+}
+
+impl PlanFormat {
+  pub fn label(&self) -> &'static str {
+    match self {
+      PlanFormat::PostgresText => "Postgres plan",
+      PlanFormat::PostgresJson => "Postgres JSON plan",
+      PlanFormat::SqliteQueryPlan => "SQLite query plan",
+      PlanFormat::MysqlTable => "MySQL plan",
+      PlanFormat::MysqlJson => "MySQL JSON plan",
+    }
+  }
+}
+
+/// A line of a plan tree: an operation or, when `is_node` is false, a property of the operation above it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanLine {
+  pub depth: usize,
+  pub text: String,
+  /// Costs, row estimates and timings of an operation.
+  pub detail: Option<String>,
+  pub is_node: bool,
+}
+
+impl PlanLine {
+  fn node(depth: usize, text: impl Into<String>, detail: Option<String>) -> Self {
+    Self { depth, text: text.into(), detail: detail.filter(|d| !d.is_empty()), is_node: true }
+  }
+
+  fn property(depth: usize, text: impl Into<String>) -> Self {
+    Self { depth, text: text.into(), detail: None, is_node: false }
+  }
+}
+
+/// The EXPLAIN format of a result set, judged by its columns and, for single column results, its first value.
+pub fn detect(headers: &[String], rows: &[Vec<String>]) -> Option<PlanFormat> {
+  let names: Vec<String> = headers.iter().map(|h| h.to_lowercase()).collect();
+  let first = rows.first().and_then(|row| row.first()).map(|value| value.trim_start()).unwrap_or_default();
+  match names.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+    ["query plan"] if first.starts_with('[') => Some(PlanFormat::PostgresJson),
+    ["query plan"] => Some(PlanFormat::PostgresText),
+    ["explain"] if first.starts_with('{') && first.contains("query_block") => Some(PlanFormat::MysqlJson),
+    ["id", "parent", "notused", "detail"] => Some(PlanFormat::SqliteQueryPlan),
+    names if ["select_type", "table", "type", "extra"].iter().all(|name| names.contains(name)) => {
+      Some(PlanFormat::MysqlTable)
+    },
+    _ => None,
+  }
+}
+
+/// The plan of a result set in `format` as an indented tree.
+pub fn tree(format: PlanFormat, headers: &[String], rows: &[Vec<String>]) -> Vec<PlanLine> {
+  let column = |row: &[String], name: &str| {
+    headers.iter().position(|h| h.eq_ignore_ascii_case(name)).and_then(|i| row.get(i)).cloned().unwrap_or_default()
+  };
+  let text = || rows.iter().filter_map(|row| row.first().cloned()).collect::<Vec<_>>();
+  match format {
+    PlanFormat::PostgresText => postgres_text(&text()),
+    PlanFormat::PostgresJson => {
+      let json = serde_json::from_str::<Value>(&text().concat()).unwrap_or_default();
+      let mut lines = Vec::new();
+      for plan in json.as_array().into_iter().flatten() {
+        postgres_node(&plan["Plan"], 0, &mut lines);
+        for key in ["Planning Time", "Execution Time"] {
+          if let Some(ms) = plan.get(key) {
+            lines.push(PlanLine::property(0, format!("{key}: {ms} ms")));
+          }
+        }
+      }
+      lines
+    },
+    PlanFormat::SqliteQueryPlan => {
+      let mut depths: HashMap<String, usize> = HashMap::new();
+      rows
+        .iter()
+        .map(|row| {
+          let depth = depths.get(&column(row, "parent")).map_or(0, |d| d + 1);
+          depths.insert(column(row, "id"), depth);
+          PlanLine::node(depth, column(row, "detail"), None)
+        })
+        .collect()
+    },
+    PlanFormat::MysqlTable => {
+      rows
+        .iter()
+        .map(|row| {
+          let select_type = column(row, "select_type");
+          let depth = usize::from(!matches!(select_type.as_str(), "SIMPLE" | "PRIMARY"));
+          let detail: Vec<String> = [
+            ("", select_type.clone()),
+            ("access ", column(row, "type")),
+            ("key ", column(row, "key")),
+            ("rows ", column(row, "rows")),
+            ("", column(row, "extra")),
+          ]
+          .into_iter()
+          .filter(|(_, value)| !value.is_empty() && !value.eq_ignore_ascii_case("null"))
+          .map(|(name, value)| format!("{name}{value}"))
+          .collect();
+          PlanLine::node(depth, column(row, "table"), Some(detail.join(", ")))
+        })
+        .collect()
+    },
+    PlanFormat::MysqlJson => {
+      let json = serde_json::from_str::<Value>(&text().concat()).unwrap_or_default();
+      let mut lines = Vec::new();
+      mysql_node("query_block", &json["query_block"], 0, &mut lines);
+      lines
+    },
+  }
+}
+
+/// Size of a Postgres buffer block.
+const BLOCK_SIZE: usize = 8192;
+
+/// Buffer and temp file usage of a Postgres plan run with `EXPLAIN (ANALYZE, BUFFERS)`, in blocks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BufferUsage {
+  pub shared_hit: u64,
+  pub shared_read: u64,
+  pub shared_dirtied: u64,
+  pub shared_written: u64,
+  pub temp_read: u64,
+  pub temp_written: u64,
+}
+
+impl BufferUsage {
+  /// Whether sorts or hashes ran out of work_mem and spilled to temp files.
+  pub fn spilled(&self) -> bool {
+    self.temp_read > 0 || self.temp_written > 0
+  }
+
+  /// Footer text such as `buffers: 120 hit, 40 read (25% from disk) | temp: 1.2 MiB written, spilled to disk`.
+  pub fn summary(&self) -> String {
+    let blocks = self.shared_hit + self.shared_read;
+    let mut summary = format!("buffers: {} hit, {} read", self.shared_hit, self.shared_read);
+    if self.shared_read > 0 {
+      summary.push_str(&format!(" ({}% from disk)", self.shared_read * 100 / blocks));
+    }
+    if self.shared_dirtied > 0 || self.shared_written > 0 {
+      summary.push_str(&format!(", {} dirtied, {} written", self.shared_dirtied, self.shared_written));
+    }
+    if self.spilled() {
+      let bytes = |blocks: u64| crate::perf::format_bytes(blocks as usize * BLOCK_SIZE);
+      summary.push_str(&format!(
+        " | temp: {} written, {} read, spilled to disk",
+        bytes(self.temp_written),
+        bytes(self.temp_read)
+      ));
+    }
+    summary
+  }
+}
+
+/// Buffer usage of the whole statement, from the top operation of a Postgres plan that was run with `BUFFERS`.
+pub fn buffers(format: PlanFormat, rows: &[Vec<String>]) -> Option<BufferUsage> {
+  let text = rows.iter().filter_map(|row| row.first().cloned()).collect::<Vec<_>>();
+  match format {
+    PlanFormat::PostgresText => {
+      // The top operation's properties come before the first nested operation and the Planning section.
+      text
+        .iter()
+        .skip(1)
+        .map(|line| line.trim())
+        .take_while(|line| !line.starts_with("->") && !line.starts_with("Planning"))
+        .find_map(|line| line.strip_prefix("Buffers:"))
+        .map(text_buffers)
+    },
+    PlanFormat::PostgresJson => {
+      let json = serde_json::from_str::<Value>(&text.concat()).ok()?;
+      let plan = &json.get(0)?["Plan"];
+      let blocks = |key: &str| plan[key].as_u64();
+      Some(BufferUsage {
+        shared_hit: blocks("Shared Hit Blocks")?,
+        shared_read: blocks("Shared Read Blocks")?,
+        shared_dirtied: blocks("Shared Dirtied Blocks").unwrap_or_default(),
+        shared_written: blocks("Shared Written Blocks").unwrap_or_default(),
+        temp_read: blocks("Temp Read Blocks").unwrap_or_default(),
+        temp_written: blocks("Temp Written Blocks").unwrap_or_default(),
+      })
+    },
+    _ => None,
+  }
+}
+
+/// Usage from a text plan's `shared hit=120 read=40 dirtied=2, temp read=10 written=20`.
+fn text_buffers(line: &str) -> BufferUsage {
+  let mut usage = BufferUsage::default();
+  for section in line.split(',') {
+    let mut words = section.split_whitespace();
+    let kind = words.next().unwrap_or_default();
+    for (key, value) in words.filter_map(|word| word.split_once('=')) {
+      let value = value.parse().unwrap_or_default();
+      match (kind, key) {
+        ("shared", "hit") => usage.shared_hit = value,
+        ("shared", "read") => usage.shared_read = value,
+        ("shared", "dirtied") => usage.shared_dirtied = value,
+        ("shared", "written") => usage.shared_written = value,
+        ("temp", "read") => usage.temp_read = value,
+        ("temp", "written") => usage.temp_written = value,
+        _ => {},
+      }
+    }
+  }
+  usage
+}
+
+/// Postgres text plans nest operations under `->` arrows, with their properties indented below them.
+fn postgres_text(plan: &[String]) -> Vec<PlanLine> {
+  let mut lines = Vec::new();
+  // Indentation of the operations enclosing the current line.
+  let mut nodes: Vec<usize> = Vec::new();
+  for (i, line) in plan.iter().enumerate() {
+    let indent = line.len() - line.trim_start().len();
+    let text = line.trim();
+    let operation = text.strip_prefix("->").map(str::trim_start).or((i == 0).then_some(text));
+    match operation {
+      Some(operation) => {
+        nodes.retain(|node| *node < indent);
+        let (name, detail) = match operation.split_once("  (") {
+          Some((name, detail)) => (name, Some(format!("({detail}"))),
+          None => (operation, None),
+        };
+        lines.push(PlanLine::node(nodes.len(), name, detail));
+        nodes.push(indent);
+      },
+      None => lines.push(PlanLine::property(nodes.iter().filter(|node| **node < indent).count(), text)),
+    }
+  }
+  lines
+}
+
+fn postgres_node(plan: &Value, depth: usize, lines: &mut Vec<PlanLine>) {
+  let Some(node_type) = plan["Node Type"].as_str() else {
+    return;
+  };
+  let mut name = node_type.to_string();
+  if let Some(index) = plan["Index Name"].as_str() {
+    name.push_str(&format!(" using {index}"));
+  }
+  if let Some(relation) = plan["Relation Name"].as_str() {
+    name.push_str(&format!(" on {relation}"));
+    if let Some(alias) = plan["Alias"].as_str().filter(|alias| *alias != relation) {
+      name.push_str(&format!(" {alias}"));
+    }
+  }
+  let mut detail = vec![format!("cost={}..{} rows={}", plan["Startup Cost"], plan["Total Cost"], plan["Plan Rows"])];
+  if plan.get("Actual Total Time").is_some() {
+    detail.push(format!(
+      "actual time={}..{} rows={} loops={}",
+      plan["Actual Startup Time"], plan["Actual Total Time"], plan["Actual Rows"], plan["Actual Loops"]
+    ));
+  }
+  lines.push(PlanLine::node(depth, name, Some(detail.join(" "))));
+  for key in ["Hash Cond", "Merge Cond", "Index Cond", "Join Filter", "Filter", "Rows Removed by Filter"] {
+    match &plan[key] {
+      Value::Null => {},
+      Value::String(value) => lines.push(PlanLine::property(depth + 1, format!("{key}: {value}"))),
+      value => lines.push(PlanLine::property(depth + 1, format!("{key}: {value}"))),
+    }
+  }
+  for child in plan["Plans"].as_array().into_iter().flatten() {
+    postgres_node(child, depth + 1, lines);
+  }
+}
+
+/// MySQL JSON plans nest operations such as `nested_loop` or `ordering_operation` as keys of their parent, down to
+/// `table` objects describing each access.
+fn mysql_node(name: &str, value: &Value, depth: usize, lines: &mut Vec<PlanLine>) {
+  match value {
+    Value::Object(map) if name == "table" => {
+      let field = |key: &str| map.get(key).map(|v| v.as_str().map_or_else(|| v.to_string(), String::from));
+      let detail: Vec<String> =
+        [("access ", field("access_type")), ("key ", field("key")), ("rows ", field("rows_examined_per_scan"))]
+          .into_iter()
+          .filter_map(|(label, value)| value.map(|value| format!("{label}{value}")))
+          .collect();
+      lines.push(PlanLine::node(depth, field("table_name").unwrap_or_default(), Some(detail.join(", "))));
+      if let Some(condition) = field("attached_condition") {
+        lines.push(PlanLine::property(depth + 1, format!("condition: {condition}")));
+      }
+      for (key, child) in map.iter().filter(|(key, child)| is_operation(key, child)) {
+        mysql_node(key, child, depth + 1, lines);
+      }
+    },
+    Value::Object(map) => {
+      let cost = map.get("cost_info").and_then(|cost| cost.get("query_cost")).and_then(Value::as_str);
+      lines.push(PlanLine::node(depth, name.replace('_', " "), cost.map(|cost| format!("cost {cost}"))));
+      for (key, child) in map.iter().filter(|(key, child)| is_operation(key, child)) {
+        mysql_node(key, child, depth + 1, lines);
+      }
+    },
+    // Arrays such as `nested_loop` list their steps, each a single key object.
+    Value::Array(items) => {
+      lines.push(PlanLine::node(depth, name.replace('_', " "), None));
+      for item in items {
+        for (key, child) in item.as_object().into_iter().flatten() {
+          mysql_node(key, child, depth + 1, lines);
+        }
+      }
+    },
+    _ => {},
+  }
+}
+
+/// Whether a key of a MySQL JSON plan holds nested operations rather than costs or lists like `used_columns`.
+fn is_operation(key: &str, value: &Value) -> bool {
+  match value {
+    Value::Object(_) => key != "cost_info",
+    Value::Array(items) => !items.is_empty() && items.iter().all(Value::is_object),
+    _ => false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|v| v.to_string()).collect()
+  }
+
+  fn outline(lines: &[PlanLine]) -> Vec<String> {
+    lines.iter().map(|line| format!("{}{}", "  ".repeat(line.depth), line.text)).collect()
+  }
+
+  #[test]
+  fn test_detect() {
+    let one = |header: &str, value: &str| detect(&strings(&[header]), &[strings(&[value])]);
+    assert_eq!(one("QUERY PLAN", "Seq Scan on users"), Some(PlanFormat::PostgresText));
+    assert_eq!(one("QUERY PLAN", "[{\"Plan\": {}}]"), Some(PlanFormat::PostgresJson));
+    assert_eq!(one("EXPLAIN", "{\"query_block\": {}}"), Some(PlanFormat::MysqlJson));
+    assert_eq!(detect(&strings(&["id", "parent", "notused", "detail"]), &[]), Some(PlanFormat::SqliteQueryPlan));
+    let mysql = strings(&["id", "select_type", "table", "partitions", "type", "key", "rows", "Extra"]);
+    assert_eq!(detect(&mysql, &[]), Some(PlanFormat::MysqlTable));
+    assert_eq!(one("name", "Seq Scan"), None);
+  }
+
+  #[test]
+  fn test_postgres_trees() {
+    let text = strings(&[
+      "Hash Join  (cost=1.09..2.20 rows=4 width=8)",
+      "  Hash Cond: (o.user_id = u.id)",
+      "  ->  Seq Scan on orders o  (cost=0.00..1.04 rows=4 width=8)",
+      "  ->  Hash  (cost=1.04..1.04 rows=4 width=4)",
+      "        ->  Seq Scan on users u  (cost=0.00..1.04 rows=4 width=4)",
+      "              Filter: active",
+      "Planning Time: 0.1 ms",
+    ]);
+    let rows: Vec<Vec<String>> = text.into_iter().map(|line| vec![line]).collect();
+    let lines = tree(PlanFormat::PostgresText, &strings(&["QUERY PLAN"]), &rows);
+    assert_eq!(
+      outline(&lines),
+      strings(&[
+        "Hash Join",
+        "  Hash Cond: (o.user_id = u.id)",
+        "  Seq Scan on orders o",
+        "  Hash",
+        "    Seq Scan on users u",
+        "      Filter: active",
+        "Planning Time: 0.1 ms",
+      ])
+    );
+    assert_eq!(lines[0].detail.as_deref(), Some("(cost=1.09..2.20 rows=4 width=8)"));
+
+    let json = r#"[{"Plan": {"Node Type": "Nested Loop", "Startup Cost": 0.0, "Total Cost": 9.5, "Plan Rows": 3,
+      "Plans": [{"Node Type": "Index Scan", "Index Name": "users_pkey", "Relation Name": "users", "Alias": "u",
+      "Startup Cost": 0.1, "Total Cost": 8.0, "Plan Rows": 1, "Index Cond": "(id = 1)"}]}, "Planning Time": 0.2}]"#;
+    let lines = tree(PlanFormat::PostgresJson, &strings(&["QUERY PLAN"]), &[vec![json.to_string()]]);
+    assert_eq!(
+      outline(&lines),
+      strings(&[
+        "Nested Loop",
+        "  Index Scan using users_pkey on users u",
+        "    Index Cond: (id = 1)",
+        "Planning Time: 0.2 ms",
+      ])
+    );
+  }
+
+  #[test]
+  fn test_buffers() {
+    let text = strings(&[
+      "Sort  (cost=1.09..2.20 rows=4 width=8) (actual time=0.1..0.2 rows=4 loops=1)",
+      "  Sort Method: external merge  Disk: 4096kB",
+      "  Buffers: shared hit=120 read=40 dirtied=2, temp read=512 written=512",
+      "  ->  Seq Scan on orders  (cost=0.00..1.04 rows=4 width=4)",
+      "        Buffers: shared hit=100 read=40",
+      "Planning:",
+      "  Buffers: shared hit=8",
+    ]);
+    let rows: Vec<Vec<String>> = text.into_iter().map(|line| vec![line]).collect();
+    let usage = buffers(PlanFormat::PostgresText, &rows).unwrap();
+    assert_eq!(usage, BufferUsage {
+      shared_hit: 120,
+      shared_read: 40,
+      shared_dirtied: 2,
+      temp_read: 512,
+      temp_written: 512,
+      ..BufferUsage::default()
+    });
+    assert_eq!(
+      usage.summary(),
+      "buffers: 120 hit, 40 read (25% from disk), 2 dirtied, 0 written | temp: 4.0 MiB written, 4.0 MiB read, spilled \
+       to disk"
+    );
+    let json = r#"[{"Plan": {"Node Type": "Seq Scan", "Shared Hit Blocks": 3, "Shared Read Blocks": 0}}]"#;
+    let usage = buffers(PlanFormat::PostgresJson, &[strings(&[json])]).unwrap();
+    assert_eq!((usage.shared_hit, usage.spilled()), (3, false));
+    assert_eq!(buffers(PlanFormat::PostgresText, &[strings(&["Seq Scan on orders"])]), None);
+  }
+
+  #[test]
+  fn test_sqlite_and_mysql_trees() {
+    let sqlite = vec![strings(&["2", "0", "0", "SCAN orders"]), strings(&["5", "2", "0", "SEARCH users USING INDEX"])];
+    let lines = tree(PlanFormat::SqliteQueryPlan, &strings(&["id", "parent", "notused", "detail"]), &sqlite);
+    assert_eq!(outline(&lines), strings(&["SCAN orders", "  SEARCH users USING INDEX"]));
+
+    let headers = strings(&["id", "select_type", "table", "type", "key", "rows", "Extra"]);
+    let rows = vec![strings(&["1", "SIMPLE", "orders", "ALL", "NULL", "1000", "Using where"])];
+    let lines = tree(PlanFormat::MysqlTable, &headers, &rows);
+    assert_eq!(lines[0].detail.as_deref(), Some("SIMPLE, access ALL, rows 1000, Using where"));
+
+    let json = r#"{"query_block": {"select_id": 1, "cost_info": {"query_cost": "4.5"}, "nested_loop": [
+      {"table": {"table_name": "o", "access_type": "ALL", "rows_examined_per_scan": 10, "used_columns": ["id"],
+        "cost_info": {"read_cost": "1.0"}}},
+      {"table": {"table_name": "u", "access_type": "eq_ref", "key": "PRIMARY", "attached_condition": "u.active"}}]}}"#;
+    let lines = tree(PlanFormat::MysqlJson, &strings(&["EXPLAIN"]), &[vec![json.to_string()]]);
+    assert_eq!(
+      outline(&lines),
+      strings(&["query block", "  nested loop", "    o", "    u", "      condition: u.active"])
+    );
+    assert_eq!(lines[0].detail.as_deref(), Some("cost 4.5"));
+    assert_eq!(lines[3].detail.as_deref(), Some("access eq_ref, key PRIMARY"));
+  }
+}
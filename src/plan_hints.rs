@@ -0,0 +1,132 @@
+/// Rows a sequential scan must read before it is worth an index hint.
+const MIN_ROWS_SCANNED: u64 = 1000;
+/// Share of the scanned rows the filter must discard.
+const MIN_REMOVED_RATIO: f64 = 0.9;
+
+/// Comparison operators as they appear in Postgres plan filters, longest first.
+const OPERATORS: &[&str] = &[" <= ", " >= ", " <> ", " = ", " < ", " > ", " ~~* ", " ~~ ", " IS "];
+
+/// An index that could replace a selective sequential scan of an EXPLAIN ANALYZE plan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexHint {
+  pub table: String,
+  pub columns: Vec<String>,
+  pub reason: String,
+}
+
+impl IndexHint {
+  pub fn create_statement(&self) -> String {
+    format!("CREATE INDEX ON {} ({});", self.table, self.columns.join(", "))
+  }
+}
+
+/// Index hints for sequential scans in the text output of a Postgres EXPLAIN ANALYZE that read many rows but keep
+/// few of them. These are heuristics, the suggested indexes still need judgement.
+pub fn index_hints(plan: &[String]) -> Vec<IndexHint> {
+  let mut hints = Vec::new();
+  for (i, line) in plan.iter().enumerate() {
+    let Some(table) = seq_scan_table(line) else {
+      continue;
+    };
+    let Some(kept) = actual_rows(line) else {
+      continue;
+    };
+    // The scan's own details are the following lines up to the next plan node.
+    let details = plan[i + 1..].iter().take_while(|l| !l.contains("->"));
+    let mut filter = None;
+    let mut removed = 0;
+    for detail in details {
+      let detail = detail.trim();
+      if let Some(f) = detail.strip_prefix("Filter:") {
+        filter = Some(f.trim().to_string());
+      } else if let Some(r) = detail.strip_prefix("Rows Removed by Filter:") {
+        removed = r.trim().parse().unwrap_or_default();
+      }
+    }
+    let Some(filter) = filter else {
+      continue;
+    };
+    let scanned = kept + removed;
+    if scanned < MIN_ROWS_SCANNED || (removed as f64) < scanned as f64 * MIN_REMOVED_RATIO {
+      continue;
+    }
+    let columns = filter_columns(&filter);
+    if columns.is_empty() {
+      continue;
+    }
+    let reason = format!("Seq Scan on {table} read {scanned} rows and kept {kept} with {filter}");
+    hints.push(IndexHint { table, columns, reason });
+  }
+  hints
+}
+
+/// Table of a `Seq Scan on table alias (...)` plan line.
+fn seq_scan_table(line: &str) -> Option<String> {
+  let rest = line.split("Seq Scan on ").nth(1)?;
+  rest.split_whitespace().next().map(String::from)
+}
+
+/// `rows=` of the `(actual ...)` part of a plan line.
+fn actual_rows(line: &str) -> Option<u64> {
+  let actual = line.split("(actual ").nth(1)?;
+  let rows = actual.split("rows=").nth(1)?;
+  rows.split(|c: char| !c.is_ascii_digit()).next()?.parse().ok()
+}
+
+/// Columns compared in a filter like `((status = 'open'::text) AND (created_at > now()))`.
+fn filter_columns(filter: &str) -> Vec<String> {
+  let mut columns = Vec::new();
+  for condition in filter.split(" AND ") {
+    let Some(position) = OPERATORS.iter().filter_map(|op| condition.find(op)).min() else {
+      continue;
+    };
+    let operand = condition[..position].trim_start_matches('(').trim();
+    // Drop casts and table qualifiers, e.g. `(u.email)::text`.
+    let operand = operand.split("::").next().unwrap_or_default().trim_matches(|c| c == '(' || c == ')');
+    let column = operand.rsplit('.').next().unwrap_or_default();
+    if !column.is_empty()
+      && column.chars().all(|c| c.is_alphanumeric() || c == '_')
+      && !columns.iter().any(|c| c == column)
+    {
+      columns.push(column.to_string());
+    }
+  }
+  columns
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn plan(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(|l| l.to_string()).collect()
+  }
+
+  #[test]
+  fn test_selective_seq_scan() {
+    let plan = plan(&[
+      "Seq Scan on users u  (cost=0.00..2041.00 rows=5 width=64) (actual time=0.01..9.1 rows=3 loops=1)",
+      "  Filter: ((u.email = 'a@b.c'::text) AND ((status)::text = 'active'::text))",
+      "  Rows Removed by Filter: 99997",
+      "Planning Time: 0.1 ms",
+    ]);
+    let hints = index_hints(&plan);
+    assert_eq!(hints.len(), 1);
+    assert_eq!(hints[0].create_statement(), "CREATE INDEX ON users (email, status);");
+  }
+
+  #[test]
+  fn test_unselective_or_small_scans_are_ignored() {
+    let plan = plan(&[
+      "->  Seq Scan on orders  (cost=0.00..1.00 rows=5 width=64) (actual time=0.01..0.1 rows=600 loops=1)",
+      "      Filter: (total > 10)",
+      "      Rows Removed by Filter: 500",
+      "->  Seq Scan on items  (cost=0.00..1.00 rows=5 width=64) (actual time=0.01..0.1 rows=1 loops=1)",
+      "      Filter: (sku = 'x'::text)",
+      "      Rows Removed by Filter: 10",
+    ]);
+    assert_eq!(index_hints(&plan), Vec::new());
+  }
+}
@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+
+use crate::snippets;
+
+/// Statements run before each query of a connection, from the `preamble` key of its `[[connections]]` entry, e.g.
+/// `SET app.tenant_id = '{tenant}'` for databases that use row level security per tenant. Each `{name}` placeholder
+/// is asked for once per session. The preamble runs on its own, on the connection the query then runs on.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Preamble {
+  template: String,
+  values: BTreeMap<String, String>,
+}
+
+impl Preamble {
+  pub fn new(template: &str) -> Option<Self> {
+    let template = template.trim().trim_end_matches(';').trim_end();
+    (!template.is_empty()).then(|| Self { template: template.to_string(), values: BTreeMap::new() })
+  }
+
+  /// Placeholder names in the order they first appear.
+  pub fn placeholders(&self) -> Vec<String> {
+    placeholders(&self.template)
+  }
+
+  /// The first placeholder that has no value yet.
+  pub fn missing(&self) -> Option<String> {
+    self.placeholders().into_iter().find(|name| !self.values.contains_key(name))
+  }
+
+  pub fn set(&mut self, name: &str, value: &str) {
+    self.values.insert(name.to_string(), value.to_string());
+  }
+
+  /// The preamble with placeholders filled in, see [`fill`].
+  pub fn sql(&self) -> String {
+    fill(&self.template, &self.values)
+  }
+}
+
+/// Names of the `{name}` placeholders of `template` in the order they first appear.
+pub fn placeholders(template: &str) -> Vec<String> {
+  let mut names: Vec<String> = Vec::new();
+  let mut rest = template;
+  while let Some((_, after)) = rest.split_once('{') {
+    let Some((name, tail)) = after.split_once('}') else {
+      break;
+    };
+    if is_placeholder(name) && !names.iter().any(|n| n == name) {
+      names.push(name.to_string());
+    }
+    rest = tail;
+  }
+  names
+}
+
+/// `template` with its placeholders replaced by `values` in a single pass, so braces in a value are never filled in
+/// again. Inside a quoted string or identifier a value is escaped as its contents. Elsewhere a number is written as
+/// it is, for placeholders such as `LIMIT {n}`, and any other value stands for an identifier and is quoted with
+/// [`snippets::quote_identifier`].
+pub fn fill(template: &str, values: &BTreeMap<String, String>) -> String {
+  let mut filled = String::new();
+  let mut quote: Option<char> = None;
+  let mut rest = template;
+  while let Some(c) = rest.chars().next() {
+    let placeholder = rest.strip_prefix('{').and_then(|after| after.split_once('}'));
+    if let Some((value, tail)) = placeholder.and_then(|(name, tail)| Some((values.get(name)?, tail))) {
+      match quote {
+        Some(quote) => filled.push_str(&value.replace(quote, &format!("{quote}{quote}"))),
+        None if snippets::is_numeric(value) => filled.push_str(value),
+        None => filled.push_str(&snippets::quote_identifier(value)),
+      }
+      rest = tail;
+      continue;
+    }
+    // A doubled quote inside a quoted string closes and reopens it.
+    quote = match (quote, c) {
+      (None, '\'' | '"') => Some(c),
+      (Some(open), c) if open == c => None,
+      (quote, _) => quote,
+    };
+    filled.push(c);
+    rest = &rest[c.len_utf8()..];
+  }
+  filled
+}
+
+fn is_placeholder(name: &str) -> bool {
+  !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_apply() {
+    let mut preamble = Preamble::new("SET app.tenant_id = '{tenant}'; SET app.region = '{region}';").unwrap();
+    assert_eq!(preamble.placeholders(), vec!["tenant".to_string(), "region".to_string()]);
+    assert_eq!(preamble.missing(), Some("tenant".to_string()));
+    preamble.set("tenant", "o'brien");
+    preamble.set("region", "eu");
+    assert_eq!(preamble.missing(), None);
+    assert_eq!(preamble.sql(), "SET app.tenant_id = 'o''brien'; SET app.region = 'eu'");
+    assert_eq!(Preamble::new("  "), None);
+
+    let mut preamble = Preamble::new("SET role {role}; SET app.note = 'it''s {note}'").unwrap();
+    preamble.set("role", "x; DROP TABLE users");
+    preamble.set("note", "{role}");
+    assert_eq!(preamble.sql(), "SET role \"x; DROP TABLE users\"; SET app.note = 'it''s {role}'");
+
+    let mut preamble = Preamble::new("SET statement_timeout = {ms}; SET app.tenant_id = '{ms}'").unwrap();
+    preamble.set("ms", "5000");
+    assert_eq!(preamble.sql(), "SET statement_timeout = 5000; SET app.tenant_id = '5000'");
+    preamble.set("ms", "0500");
+    assert_eq!(preamble.sql(), "SET statement_timeout = \"0500\"; SET app.tenant_id = '0500'");
+  }
+}
@@ -0,0 +1,67 @@
+use std::collections::VecDeque;
+
+/// Yanks kept, like Vim's numbered registers.
+const REGISTER_LIMIT: usize = 10;
+
+/// Text yanked in the editor or copied from the results, newest first. Kept in the app so the editor and the results
+/// can paste each other's yanks where the OS clipboard is unavailable, e.g. over SSH.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Registers {
+  yanks: VecDeque<String>,
+}
+
+impl Registers {
+  /// Makes `text` the latest yank, moving it to the front when it was yanked before.
+  pub fn yank(&mut self, text: impl Into<String>) {
+    let text = text.into();
+    if text.is_empty() {
+      return;
+    }
+    self.yanks.retain(|yank| *yank != text);
+    self.yanks.push_front(text);
+    self.yanks.truncate(REGISTER_LIMIT);
+  }
+
+  pub fn latest(&self) -> Option<&str> {
+    self.yanks.front().map(String::as_str)
+  }
+
+  pub fn get(&self, index: usize) -> Option<&str> {
+    self.yanks.get(index).map(String::as_str)
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = &str> {
+    self.yanks.iter().map(String::as_str)
+  }
+
+  pub fn len(&self) -> usize {
+    self.yanks.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.yanks.is_empty()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_yank() {
+    let mut registers = Registers::default();
+    registers.yank("SELECT 1");
+    registers.yank("");
+    registers.yank("42");
+    registers.yank("SELECT 1");
+    assert_eq!(registers.iter().collect::<Vec<_>>(), vec!["SELECT 1", "42"]);
+    for i in 0..REGISTER_LIMIT {
+      registers.yank(i.to_string());
+    }
+    assert_eq!(registers.len(), REGISTER_LIMIT);
+    assert_eq!(registers.latest(), Some("9"));
+    assert_eq!(registers.get(REGISTER_LIMIT - 1), Some("0"));
+  }
+}
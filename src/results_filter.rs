@@ -0,0 +1,249 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::components::text_input::TextInput;
+
+/// Filters kept for recall with Up/Down in the filter box.
+const RECENT_FILTER_LIMIT: usize = 20;
+/// Joins the cells of a row in its haystack, so a match can't span two cells.
+const CELL_SEPARATOR: char = '\u{1f}';
+
+/// Row filter of the results grid, with the last filter used per query and a list of recent filters.
+#[derive(Debug, Clone, Default)]
+pub struct ResultsFilter {
+  pub input: TextInput,
+  pub is_editing: bool,
+  /// Filter remembered for the query that was just re-run, applied on request.
+  pub offered: Option<String>,
+  /// Last filter per normalized query text.
+  saved: HashMap<String, String>,
+  /// Most recent first.
+  recent: Vec<String>,
+  recall_index: Option<usize>,
+  /// Lower cased text of each row of the current result set, built on the first filter.
+  haystacks: Option<Arc<Vec<String>>>,
+  /// The last filter and the rows it matched, narrowed further while the filter grows.
+  last_match: Option<(String, Vec<usize>)>,
+  /// Incremented for every filter run and result set, so results of outdated runs are discarded.
+  generation: u64,
+  /// Filter text of the run with the current generation, until it finishes.
+  pending: Option<String>,
+}
+
+/// One filter run, independent of the UI state so it can run on a blocking task.
+#[derive(Debug, Clone)]
+pub struct FilterJob {
+  pub generation: u64,
+  needle: String,
+  row_count: usize,
+  haystacks: Arc<Vec<String>>,
+  /// Rows matched by a shorter filter this one extends, `None` to search all rows.
+  candidates: Option<Vec<usize>>,
+}
+
+impl FilterJob {
+  /// Whether the run is cheap enough to do while handling the key.
+  pub fn is_trivial(&self) -> bool {
+    self.needle.is_empty()
+  }
+
+  /// Indices of the rows containing the filter text.
+  pub fn run(self) -> Vec<usize> {
+    if self.needle.is_empty() {
+      return (0..self.row_count).collect();
+    }
+    let haystacks = &self.haystacks;
+    match self.candidates {
+      Some(candidates) => candidates.into_iter().filter(|i| haystacks[*i].contains(&self.needle)).collect(),
+      None => (0..haystacks.len()).filter(|i| haystacks[*i].contains(&self.needle)).collect(),
+    }
+  }
+}
+
+impl ResultsFilter {
+  pub fn is_active(&self) -> bool {
+    !self.input.is_empty()
+  }
+
+  /// Rows containing the filter text in any cell, ignoring case.
+  pub fn apply(&mut self, rows: &[Vec<String>]) -> Vec<Vec<String>> {
+    self.matching(rows).into_iter().map(|i| rows[i].clone()).collect()
+  }
+
+  /// Indices of the rows containing the filter text, filtering in place.
+  pub fn matching(&mut self, rows: &[Vec<String>]) -> Vec<usize> {
+    let job = self.prepare(rows);
+    let generation = job.generation;
+    let matches = job.run();
+    self.finish(generation, &matches);
+    matches
+  }
+
+  /// Starts a run of the current filter over `rows`. Rows are lower cased once per result set, and a filter that
+  /// extends the previous one only searches the rows that matched before.
+  pub fn prepare(&mut self, rows: &[Vec<String>]) -> FilterJob {
+    let needle = self.input.value().to_lowercase();
+    if !needle.is_empty() && self.haystacks.as_ref().is_none_or(|h| h.len() != rows.len()) {
+      self.haystacks = Some(Arc::new(rows.iter().map(|row| haystack(row)).collect()));
+      self.last_match = None;
+    }
+    let candidates = match self.last_match.take() {
+      Some((previous, candidates)) if needle.contains(&previous) => Some(candidates),
+      _ => None,
+    };
+    self.generation += 1;
+    self.pending = Some(needle.clone());
+    let haystacks = self.haystacks.clone().unwrap_or_default();
+    FilterJob { generation: self.generation, needle, row_count: rows.len(), haystacks, candidates }
+  }
+
+  /// Accepts the matches of a finished run, returning false when a newer run or result set made them outdated.
+  pub fn finish(&mut self, generation: u64, matches: &[usize]) -> bool {
+    if generation != self.generation {
+      return false;
+    }
+    self.last_match = self.pending.take().filter(|needle| !needle.is_empty()).map(|needle| (needle, matches.to_vec()));
+    true
+  }
+
+  /// Forgets the indexed rows and discards running filters, for a new result set.
+  pub fn invalidate(&mut self) {
+    self.haystacks = None;
+    self.last_match = None;
+    self.pending = None;
+    self.generation += 1;
+  }
+
+  pub fn start_editing(&mut self) {
+    self.is_editing = true;
+    self.recall_index = None;
+  }
+
+  /// Keeps the current filter for `query` and in the recent list.
+  pub fn remember(&mut self, query: Option<&str>) {
+    self.is_editing = false;
+    let filter = self.input.value().trim().to_string();
+    if filter.is_empty() {
+      return;
+    }
+    if let Some(query) = query {
+      self.saved.insert(normalize_query(query), filter.clone());
+    }
+    self.recent.retain(|f| *f != filter);
+    self.recent.insert(0, filter);
+    self.recent.truncate(RECENT_FILTER_LIMIT);
+  }
+
+  /// Clears the filter for a new result set of `query`, offering the filter last used with it.
+  pub fn reset_for(&mut self, query: Option<&str>) {
+    self.input.clear();
+    self.is_editing = false;
+    self.offered = query.and_then(|q| self.saved.get(&normalize_query(q))).cloned();
+  }
+
+  /// Steps through recent filters, `older` moving back in time.
+  pub fn recall(&mut self, older: bool) {
+    let index = match (self.recall_index, older) {
+      (None, true) if !self.recent.is_empty() => Some(0),
+      (Some(i), true) if i + 1 < self.recent.len() => Some(i + 1),
+      (Some(0), false) => None,
+      (Some(i), false) => Some(i - 1),
+      (index, _) => index,
+    };
+    self.recall_index = index;
+    self.input.set_value(index.map(|i| self.recent[i].clone()).unwrap_or_default());
+  }
+}
+
+fn haystack(row: &[String]) -> String {
+  let mut text = String::with_capacity(row.iter().map(|cell| cell.len() + 1).sum());
+  for cell in row {
+    text.extend(cell.chars().flat_map(char::to_lowercase));
+    text.push(CELL_SEPARATOR);
+  }
+  text
+}
+
+/// Query text with whitespace collapsed and a trailing semicolon dropped, so reformatting doesn't lose the filter.
+pub fn normalize_query(sql: &str) -> String {
+  sql.split_whitespace().collect::<Vec<_>>().join(" ").trim_end_matches(';').trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn filter_with(value: &str) -> ResultsFilter {
+    let mut filter = ResultsFilter::default();
+    filter.input.set_value(value);
+    filter
+  }
+
+  #[test]
+  fn test_apply() {
+    let rows = vec![vec!["1".to_string(), "Alice".to_string()], vec!["2".to_string(), "Bob".to_string()]];
+    assert_eq!(filter_with("ali").apply(&rows), vec![rows[0].clone()]);
+    assert_eq!(filter_with("").apply(&rows), rows);
+    assert_eq!(filter_with("1alice").apply(&rows), Vec::<Vec<String>>::new());
+  }
+
+  #[test]
+  fn test_incremental_matching() {
+    let rows: Vec<Vec<String>> = ["Alice", "Alfred", "Bob", "alan"].iter().map(|name| vec![name.to_string()]).collect();
+    let mut filter = ResultsFilter::default();
+    let typed = |filter: &mut ResultsFilter, value: &str| {
+      filter.input.set_value(value);
+      filter.matching(&rows)
+    };
+    assert_eq!(typed(&mut filter, "a"), vec![0, 1, 3]);
+    assert_eq!(typed(&mut filter, "al"), vec![0, 1, 3]);
+    assert_eq!(typed(&mut filter, "ali"), vec![0]);
+    assert_eq!(typed(&mut filter, "b"), vec![2]);
+    filter.invalidate();
+    assert_eq!(typed(&mut filter, "bo"), vec![2]);
+  }
+
+  #[test]
+  fn test_outdated_runs_are_discarded() {
+    let rows = vec![vec!["Alice".to_string()], vec!["Bob".to_string()]];
+    let mut filter = filter_with("a");
+    let outdated = filter.prepare(&rows);
+    filter.input.set_value("b");
+    let current = filter.prepare(&rows);
+    let (outdated_generation, current_generation) = (outdated.generation, current.generation);
+    assert_eq!(current.run(), vec![1]);
+    assert!(filter.finish(current_generation, &[1]));
+    assert!(!filter.finish(outdated_generation, &outdated.run()));
+  }
+
+  #[test]
+  fn test_filter_offered_for_same_query() {
+    let mut filter = filter_with("active");
+    filter.remember(Some("SELECT *\n  FROM users;"));
+    filter.reset_for(Some("SELECT * FROM users"));
+    assert_eq!(filter.offered, Some("active".to_string()));
+    assert!(!filter.is_active());
+    filter.reset_for(Some("SELECT * FROM orders"));
+    assert_eq!(filter.offered, None);
+  }
+
+  #[test]
+  fn test_recall() {
+    let mut filter = ResultsFilter::default();
+    for value in ["a", "b", "a"] {
+      filter.input.set_value(value);
+      filter.remember(None);
+    }
+    filter.input.clear();
+    filter.recall(true);
+    assert_eq!(filter.input.value(), "a");
+    filter.recall(true);
+    assert_eq!(filter.input.value(), "b");
+    filter.recall(true);
+    assert_eq!(filter.input.value(), "b");
+    filter.recall(false);
+    filter.recall(false);
+    assert_eq!(filter.input.value(), "");
+  }
+}
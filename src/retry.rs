@@ -0,0 +1,166 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteError;
+
+use crate::statement;
+
+/// SQLSTATEs of failures that usually succeed when the statement is run again: serialization failures, deadlocks and
+/// an administrator terminating the connection.
+const TRANSIENT_SQLSTATES: &[&str] = &["40001", "40P01", "57P01"];
+
+/// Class of the SQLSTATEs of connection failures.
+const CONNECTION_EXCEPTION_CLASS: &str = "08";
+
+/// Primary result codes of SQLite's busy and locked errors, which it reports extended.
+const SQLITE_BUSY: i32 = 5;
+const SQLITE_LOCKED: i32 = 6;
+
+/// First words of statements that only read, so running them again changes nothing.
+const READ_ONLY_STATEMENTS: &[&str] = &["select", "with", "values", "table", "show", "explain"];
+
+/// Words that make a statement starting as a read write, such as a data modifying CTE, `SELECT INTO` or
+/// `EXPLAIN ANALYZE`.
+const WRITING_WORDS: &[&str] = &["insert", "update", "delete", "merge", "into", "analyze", "analyse"];
+
+/// Retrying of queries that fail with a transient error, the `[retry]` config table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryConfig {
+  /// Attempts including the first one, 1 disables retrying.
+  pub max_attempts: u32,
+  /// Delay before the first retry, doubled for each further one.
+  pub base_delay_ms: u64,
+}
+
+impl Default for RetryConfig {
+  fn default() -> Self {
+    Self { max_attempts: 3, base_delay_ms: 250 }
+  }
+}
+
+impl RetryConfig {
+  /// Delay before running `attempt` (2 for the first retry), or `None` when no attempts are left.
+  pub fn delay_before(&self, attempt: u32) -> Option<Duration> {
+    if attempt < 2 || attempt > self.max_attempts {
+      return None;
+    }
+    Some(Duration::from_millis(self.base_delay_ms.saturating_mul(1 << (attempt - 2).min(16))))
+  }
+}
+
+/// Whether `error` is a dropped connection, a serialization failure, a deadlock or a busy SQLite database.
+pub fn is_transient(error: &color_eyre::Report) -> bool {
+  match error.downcast_ref::<sqlx::Error>() {
+    // SQLite codes are numbers, which a SQLSTATE such as 42501 would pass for.
+    Some(sqlx::Error::Database(e)) if e.try_downcast_ref::<SqliteError>().is_some() => {
+      e.code()
+        .and_then(|code| code.parse::<i32>().ok())
+        .is_some_and(|code| [SQLITE_BUSY, SQLITE_LOCKED].contains(&(code & 0xff)))
+    },
+    Some(sqlx::Error::Database(e)) => {
+      e.code().is_some_and(|code| {
+        TRANSIENT_SQLSTATES.contains(&code.as_ref()) || code.starts_with(CONNECTION_EXCEPTION_CLASS)
+      })
+    },
+    Some(sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed | sqlx::Error::WorkerCrashed) => true,
+    _ => false,
+  }
+}
+
+/// Whether every statement of `sql` only reads, so a failed attempt can safely run again. Unsure cases such as a
+/// write keyword inside a string count as writes.
+pub fn is_read_only(sql: &str) -> bool {
+  let lines: Vec<String> = sql.lines().map(String::from).collect();
+  let statements = statement::split(&lines);
+  !statements.is_empty()
+    && statements.iter().all(|statement| {
+      let lower = statement.text.to_ascii_lowercase();
+      let mut words = lower.split(|c: char| !c.is_ascii_alphanumeric() && c != '_').filter(|word| !word.is_empty());
+      words.next().is_some_and(|first| READ_ONLY_STATEMENTS.contains(&first))
+        && words.all(|word| !WRITING_WORDS.contains(&word))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  /// A server error with a SQLSTATE, as Postgres reports them.
+  #[derive(Debug)]
+  struct ServerError(&'static str);
+
+  impl std::fmt::Display for ServerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+      write!(f, "error {}", self.0)
+    }
+  }
+
+  impl std::error::Error for ServerError {
+  }
+
+  impl sqlx::error::DatabaseError for ServerError {
+    fn message(&self) -> &str {
+      self.0
+    }
+
+    fn code(&self) -> Option<std::borrow::Cow<'_, str>> {
+      Some(self.0.into())
+    }
+
+    fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+      self
+    }
+
+    fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+      self
+    }
+
+    fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+      self
+    }
+
+    fn kind(&self) -> sqlx::error::ErrorKind {
+      sqlx::error::ErrorKind::Other
+    }
+  }
+
+  fn server_error(code: &'static str) -> color_eyre::Report {
+    sqlx::Error::Database(Box::new(ServerError(code))).into()
+  }
+
+  #[test]
+  fn test_is_transient() {
+    let reset = std::io::Error::new(std::io::ErrorKind::ConnectionReset, "connection reset by peer");
+    assert!(is_transient(&sqlx::Error::Io(reset).into()));
+    assert!(is_transient(&sqlx::Error::PoolTimedOut.into()));
+    assert!(!is_transient(&sqlx::Error::RowNotFound.into()));
+    assert!(!is_transient(&color_eyre::eyre::eyre!("deadlock detected")));
+    assert!(is_transient(&server_error("40P01")));
+    assert!(is_transient(&server_error("08006")));
+    // insufficient_privilege, whose low byte as a number is SQLITE_BUSY.
+    assert!(!is_transient(&server_error("42501")));
+  }
+
+  #[test]
+  fn test_is_read_only() {
+    assert!(is_read_only("SELECT * FROM orders;\nWITH t AS (SELECT 1) SELECT * FROM t"));
+    assert!(is_read_only("EXPLAIN SELECT 1"));
+    assert!(!is_read_only("SELECT 1; UPDATE orders SET total = 0"));
+    assert!(!is_read_only("WITH gone AS (DELETE FROM orders RETURNING id) SELECT * FROM gone"));
+    assert!(!is_read_only("SELECT * INTO copy FROM orders"));
+    assert!(!is_read_only("EXPLAIN ANALYZE DELETE FROM orders"));
+    assert!(!is_read_only(""));
+  }
+
+  #[test]
+  fn test_delay_before() {
+    let config = RetryConfig::default();
+    assert_eq!(config.delay_before(2), Some(Duration::from_millis(250)));
+    assert_eq!(config.delay_before(3), Some(Duration::from_millis(500)));
+    assert_eq!(config.delay_before(4), None);
+    assert_eq!(RetryConfig { max_attempts: 1, ..config }.delay_before(2), None);
+  }
+}
@@ -0,0 +1,129 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{server::ServerKind, snippets::quote_literal};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct DbTable {
+  pub name: String,
+  pub schema: String,
+}
+
+/// Postgres schemas holding the system catalogs.
+const SYSTEM_SCHEMAS: &[&str] = &["pg_catalog", "information_schema"];
+
+impl DbTable {
+  /// Catalog tables and views, and SQLite's internal `sqlite_*` tables.
+  pub fn is_system(&self) -> bool {
+    SYSTEM_SCHEMAS.contains(&self.schema.as_str())
+      || self.schema.starts_with("pg_toast")
+      || self.schema.starts_with("pg_temp")
+      || self.name.starts_with("sqlite_")
+  }
+
+  /// Whether the table is in the connection's own schema, `public` on Postgres and the main database on SQLite.
+  pub fn is_default_schema(&self) -> bool {
+    self.schema.is_empty() || self.schema == "public"
+  }
+
+  /// Name shown in the table panel and used to query the table, qualified outside the `public` schema.
+  pub fn display_name(&self) -> String {
+    if self.is_default_schema() {
+      self.name.clone()
+    } else {
+      format!("{}.{}", self.schema, self.name)
+    }
+  }
+
+  /// The schema qualified, quoted name suitable for use in generated SQL.
+  pub fn qualified_name(&self) -> String {
+    let quote = |ident: &str| format!("\"{}\"", ident.replace('"', "\"\""));
+    if self.schema.is_empty() {
+      quote(&self.name)
+    } else {
+      format!("{}.{}", quote(&self.schema), quote(&self.name))
+    }
+  }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+pub struct DbColumn {
+  pub name: String,
+  pub data_type: String,
+  pub is_nullable: bool,
+}
+
+impl DbColumn {
+  /// Columns from the `(column_name, data_type, is_nullable)` rows of [`columns_query`].
+  pub fn from_rows(rows: Vec<Vec<String>>) -> Vec<Self> {
+    rows
+      .into_iter()
+      .map(|mut row| {
+        row.resize(3, String::new());
+        Self { name: row[0].clone(), data_type: row[1].clone(), is_nullable: row[2].eq_ignore_ascii_case("yes") }
+      })
+      .collect()
+  }
+}
+
+/// Query returning the foreign keys from and to `table` as rows for [`ForeignKey::from_rows`](crate::join_builder::ForeignKey::from_rows).
+pub fn foreign_keys_query(kind: ServerKind, table: &DbTable) -> String {
+  if kind == ServerKind::Sqlite {
+    format!(
+      "SELECT m.name || '.' || fk.id, '', m.name, fk.\"from\", '', fk.\"table\", COALESCE(fk.\"to\", 'rowid') FROM \
+       sqlite_master m JOIN pragma_foreign_key_list(m.name) fk WHERE m.type = 'table' AND (m.name = {name} OR \
+       fk.\"table\" = {name}) ORDER BY m.name, fk.id, fk.seq",
+      name = quote_literal(&table.name)
+    )
+  } else {
+    format!(
+      "SELECT c.conname, ns.nspname, cl.relname, a.attname, fns.nspname, fcl.relname, fa.attname FROM pg_constraint c \
+       CROSS JOIN LATERAL unnest(c.conkey, c.confkey) WITH ORDINALITY AS k(col, fcol, n) JOIN pg_class cl ON cl.oid \
+       = c.conrelid JOIN pg_namespace ns ON ns.oid = cl.relnamespace JOIN pg_class fcl ON fcl.oid = c.confrelid JOIN \
+       pg_namespace fns ON fns.oid = fcl.relnamespace JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = \
+       k.col JOIN pg_attribute fa ON fa.attrelid = c.confrelid AND fa.attnum = k.fcol WHERE c.contype = 'f' AND \
+       {oid}::regclass IN (c.conrelid, c.confrelid) ORDER BY c.conname, cl.relname, k.n",
+      oid = quote_literal(&table.qualified_name())
+    )
+  }
+}
+
+/// Query returning `(column_name, data_type, is_nullable)` rows for `table` on a `kind` server, see [`DbColumn::from_rows`].
+pub fn columns_query(kind: ServerKind, table: &DbTable) -> String {
+  if kind == ServerKind::Sqlite {
+    // Tables of attached databases are qualified with the alias, which pragma_table_info takes as its schema.
+    let schema = if table.is_default_schema() { String::new() } else { format!(", {}", quote_literal(&table.schema)) };
+    format!(
+      "SELECT name, type, CASE WHEN \"notnull\" = 1 THEN 'NO' ELSE 'YES' END FROM pragma_table_info({}{schema})",
+      quote_literal(&table.name)
+    )
+  } else {
+    format!(
+      "SELECT column_name, data_type, is_nullable FROM information_schema.columns WHERE table_schema = {} AND \
+       table_name = {} ORDER BY ordinal_position",
+      quote_literal(&table.schema),
+      quote_literal(&table.name)
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_columns() {
+    let table = DbTable { name: "orders".to_string(), schema: "before".to_string() };
+    assert_eq!(
+      columns_query(ServerKind::Sqlite, &table),
+      "SELECT name, type, CASE WHEN \"notnull\" = 1 THEN 'NO' ELSE 'YES' END FROM pragma_table_info('orders', 'before')"
+    );
+    assert_eq!(table.display_name(), "before.orders");
+    let rows = vec![vec!["id".to_string(), "integer".to_string(), "NO".to_string()], vec!["note".to_string()]];
+    assert_eq!(DbColumn::from_rows(rows), vec![
+      DbColumn { name: "id".to_string(), data_type: "integer".to_string(), is_nullable: false },
+      DbColumn { name: "note".to_string(), data_type: String::new(), is_nullable: false },
+    ]);
+  }
+}
@@ -0,0 +1,97 @@
+/// How the columns of a re-run query differ from its previous result.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ColumnChange {
+  pub added: Vec<String>,
+  pub removed: Vec<String>,
+  /// Columns in the same position under a new name, as old and new name.
+  pub renamed: Vec<(String, String)>,
+}
+
+impl ColumnChange {
+  /// Compares the headers of two results of the same query, `None` when only the order changed or nothing did.
+  pub fn detect(old: &[String], new: &[String]) -> Option<Self> {
+    let mut change = Self {
+      added: new.iter().filter(|h| !old.contains(h)).cloned().collect(),
+      removed: old.iter().filter(|h| !new.contains(h)).cloned().collect(),
+      renamed: Vec::new(),
+    };
+    // A new name in the position of a dropped one is most likely a rename.
+    for (before, after) in old.iter().zip(new) {
+      if change.removed.contains(before) && change.added.contains(after) {
+        change.renamed.push((before.clone(), after.clone()));
+      }
+    }
+    change.added.retain(|h| !change.renamed.iter().any(|(_, after)| after == h));
+    change.removed.retain(|h| !change.renamed.iter().any(|(before, _)| before == h));
+    if change == Self::default() {
+      return None;
+    }
+    Some(change)
+  }
+
+  /// One line summary for the status bar.
+  pub fn notice(&self) -> String {
+    let mut parts = Vec::new();
+    if !self.renamed.is_empty() {
+      let renamed: Vec<String> = self.renamed.iter().map(|(before, after)| format!("{before} → {after}")).collect();
+      parts.push(format!("renamed {}", renamed.join(", ")));
+    }
+    if !self.added.is_empty() {
+      parts.push(format!("added {}", self.added.join(", ")));
+    }
+    if !self.removed.is_empty() {
+      parts.push(format!("dropped {}", self.removed.join(", ")));
+    }
+    format!("Columns changed since the last run: {}", parts.join("; "))
+  }
+
+  /// Carries the display order `old_order` of `old` headers over to `new` headers: columns that are still there, or
+  /// were renamed, keep their relative order and new columns follow in query order.
+  pub fn carry_order(&self, old: &[String], old_order: &[usize], new: &[String]) -> Vec<usize> {
+    let mut order: Vec<usize> = old_order
+      .iter()
+      .filter_map(|i| old.get(*i))
+      .filter_map(|header| {
+        let name = self.renamed.iter().find(|(before, _)| before == header).map_or(header, |(_, after)| after);
+        new.iter().position(|h| h == name)
+      })
+      .collect();
+    let added: Vec<usize> = (0..new.len()).filter(|i| !order.contains(i)).collect();
+    order.extend(added);
+    order
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn headers(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+  }
+
+  #[test]
+  fn test_detect() {
+    let old = headers(&["id", "name", "email"]);
+    assert_eq!(ColumnChange::detect(&old, &headers(&["email", "id", "name"])), None);
+    let renamed = ColumnChange::detect(&old, &headers(&["id", "full_name", "email"])).unwrap();
+    assert_eq!(renamed.renamed, vec![("name".to_string(), "full_name".to_string())]);
+    assert_eq!(renamed.notice(), "Columns changed since the last run: renamed name → full_name");
+    let changed = ColumnChange::detect(&old, &headers(&["id", "email", "created_at", "role"])).unwrap();
+    assert_eq!(changed.notice(), "Columns changed since the last run: added created_at, role; dropped name");
+  }
+
+  #[test]
+  fn test_carry_order() {
+    let old = headers(&["id", "name", "email"]);
+    let new = headers(&["id", "full_name", "email", "role"]);
+    let change = ColumnChange::detect(&old, &new).unwrap();
+    // Displayed as email, full_name, id, with role appended.
+    assert_eq!(change.carry_order(&old, &[2, 1, 0], &new), vec![2, 1, 0, 3]);
+    let dropped = headers(&["email", "id"]);
+    let change = ColumnChange::detect(&old, &dropped).unwrap();
+    assert_eq!(change.carry_order(&old, &[2, 1, 0], &dropped), vec![0, 1]);
+  }
+}
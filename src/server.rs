@@ -0,0 +1,115 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ServerKind {
+  Postgres,
+  Sqlite,
+}
+
+/// Server type and version detected at connect, used to pick SQL the server understands.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerInfo {
+  pub kind: ServerKind,
+  pub version: String,
+  /// Postgres `server_version_num`, e.g. `160001` for 16.1.
+  pub version_num: Option<u32>,
+  pub has_pg_stat_statements: bool,
+}
+
+impl ServerInfo {
+  /// Query returning `(version_num, version, has_pg_stat_statements)` for the given server kind.
+  pub fn detect_query(kind: ServerKind) -> &'static str {
+    match kind {
+      ServerKind::Postgres => {
+        "SELECT current_setting('server_version_num'), current_setting('server_version'), EXISTS (SELECT 1 FROM \
+         pg_extension WHERE extname = 'pg_stat_statements')::text"
+      },
+      ServerKind::Sqlite => "SELECT '', sqlite_version(), 'false'",
+    }
+  }
+
+  /// Builds the server info from the first row returned by [`ServerInfo::detect_query`].
+  pub fn from_row(kind: ServerKind, row: &[String]) -> Self {
+    let value = |i: usize| row.get(i).map(|v| v.trim().to_string()).unwrap_or_default();
+    Self {
+      kind,
+      version: value(1),
+      version_num: value(0).parse().ok(),
+      has_pg_stat_statements: matches!(value(2).as_str(), "true" | "t" | "1"),
+    }
+  }
+
+  /// Prefix for showing a statement's plan. `analyze` asks for actual run times, which executes the statement and
+  /// is only honoured where the server supports it.
+  pub fn explain_prefix(&self, analyze: bool) -> Result<&'static str, String> {
+    match (self.kind, analyze) {
+      (ServerKind::Sqlite, false) => Ok("EXPLAIN QUERY PLAN"),
+      (ServerKind::Sqlite, true) => Err("EXPLAIN ANALYZE is not supported by SQLite".to_string()),
+      (ServerKind::Postgres, false) => Ok("EXPLAIN"),
+      // The parenthesized option list and BUFFERS arrived in 9.0.
+      (ServerKind::Postgres, true) if self.version_num.is_none_or(|n| n >= 90000) => Ok("EXPLAIN (ANALYZE, BUFFERS)"),
+      (ServerKind::Postgres, true) => Ok("EXPLAIN ANALYZE"),
+    }
+  }
+
+  /// Prefix producing a plan as JSON, the input format of plan visualizers like pev2. `analyze` adds actual run times
+  /// and buffers, which executes the statement, so it should only be asked for statements that just read.
+  pub fn explain_json_prefix(&self, analyze: bool) -> Result<&'static str, String> {
+    match self.kind {
+      ServerKind::Sqlite => Err("JSON plans are not supported by SQLite".to_string()),
+      // FORMAT JSON arrived in 9.0.
+      ServerKind::Postgres if self.version_num.is_some_and(|n| n < 90000) => {
+        Err(format!("JSON plans need PostgreSQL 9.0 or later, connected to {}", self.version))
+      },
+      ServerKind::Postgres if analyze => Ok("EXPLAIN (ANALYZE, COSTS, VERBOSE, BUFFERS, FORMAT JSON)"),
+      ServerKind::Postgres => Ok("EXPLAIN (COSTS, VERBOSE, FORMAT JSON)"),
+    }
+  }
+}
+
+impl fmt::Display for ServerInfo {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.kind {
+      ServerKind::Postgres => write!(f, "PostgreSQL {}", self.version)?,
+      ServerKind::Sqlite => write!(f, "SQLite {}", self.version)?,
+    }
+    if self.has_pg_stat_statements {
+      write!(f, " (pg_stat_statements)")?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn postgres(version_num: &str) -> ServerInfo {
+    let row = vec![version_num.to_string(), "x".to_string(), "t".to_string()];
+    ServerInfo::from_row(ServerKind::Postgres, &row)
+  }
+
+  #[test]
+  fn test_from_row() {
+    let info = postgres("160001");
+    assert_eq!(info.version_num, Some(160001));
+    assert!(info.has_pg_stat_statements);
+  }
+
+  #[test]
+  fn test_explain_prefix() {
+    assert_eq!(postgres("160001").explain_prefix(true).unwrap(), "EXPLAIN (ANALYZE, BUFFERS)");
+    assert_eq!(postgres("80403").explain_prefix(true).unwrap(), "EXPLAIN ANALYZE");
+    assert_eq!(postgres("80403").explain_prefix(false).unwrap(), "EXPLAIN");
+    let sqlite = ServerInfo::from_row(ServerKind::Sqlite, &["".to_string(), "3.45.0".to_string()]);
+    assert_eq!(sqlite.explain_prefix(false).unwrap(), "EXPLAIN QUERY PLAN");
+    assert!(sqlite.explain_prefix(true).is_err());
+    assert!(sqlite.explain_json_prefix(false).is_err());
+    assert!(postgres("80403").explain_json_prefix(false).is_err());
+    assert_eq!(postgres("160001").explain_json_prefix(false).unwrap(), "EXPLAIN (COSTS, VERBOSE, FORMAT JSON)");
+  }
+}
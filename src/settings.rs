@@ -0,0 +1,236 @@
+use std::path::PathBuf;
+
+use color_eyre::eyre::Result;
+use ratatui::style::{Color, Modifier, Style};
+use serde::{Deserialize, Serialize};
+use strum::{Display, EnumIter, IntoEnumIterator};
+use toml_edit::DocumentMut;
+
+use crate::export::ExportFormat;
+
+/// Config file in the config directory the settings popup writes its `[settings]` table to.
+pub const SETTINGS_FILE: &str = "config.toml";
+
+/// Row limits offered for the auto `LIMIT` setting, `None` disables it.
+const AUTO_LIMITS: &[Option<usize>] = &[None, Some(100), Some(500), Some(1000), Some(5000)];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Display, EnumIter, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+  #[default]
+  Dark,
+  Light,
+}
+
+impl Theme {
+  pub fn border_color(&self, focused: bool) -> Color {
+    match (self, focused) {
+      (Theme::Dark, true) => Color::Cyan,
+      (Theme::Dark, false) => Color::White,
+      (Theme::Light, true) => Color::Blue,
+      (Theme::Light, false) => Color::DarkGray,
+    }
+  }
+
+  /// Background of every other row in the results grid.
+  pub fn stripe_style(&self) -> Style {
+    match self {
+      Theme::Dark => Style::default().bg(Color::Indexed(236)),
+      Theme::Light => Style::default().bg(Color::Indexed(254)),
+    }
+  }
+
+  pub fn highlight_style(&self) -> Style {
+    let style = match self {
+      Theme::Dark => Style::default().bg(Color::Yellow).fg(Color::Black),
+      Theme::Light => Style::default().bg(Color::Blue).fg(Color::White),
+    };
+    style.add_modifier(Modifier::BOLD)
+  }
+}
+
+/// Vertical spacing of the results grid.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Display, EnumIter, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Density {
+  /// A blank line between rows.
+  #[default]
+  Comfortable,
+  Compact,
+}
+
+impl Density {
+  pub fn row_margin(&self) -> u16 {
+    match self {
+      Density::Comfortable => 1,
+      Density::Compact => 0,
+    }
+  }
+}
+
+/// Options that can be changed at runtime from the settings popup.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+  pub theme: Theme,
+  /// Row limit appended to `SELECT` statements that don't have one.
+  pub auto_limit: Option<usize>,
+  /// Export format preselected in the export menu.
+  pub default_export_format: ExportFormat,
+  pub density: Density,
+  /// Shade every other row of the results grid.
+  pub striped_rows: bool,
+  /// Show numbers of five or more digits with thousands separators and dates in the order of the user's locale in the
+  /// results grid.
+  pub locale_format: bool,
+  /// Show each row's position in the full result in a leftmost column.
+  pub row_numbers: bool,
+  /// List catalog schemas (pg_catalog, information_schema) and SQLite internal tables in the table panel.
+  pub show_system_objects: bool,
+  /// Show SQL generated by UI actions (load, count and preview a table) for confirmation before running it.
+  pub confirm_generated_sql: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum SettingKind {
+  Theme,
+  Density,
+  StripedRows,
+  LocaleFormat,
+  RowNumbers,
+  SystemObjects,
+  AutoLimit,
+  ConfirmGeneratedSql,
+  DefaultExportFormat,
+}
+
+impl SettingKind {
+  pub fn category(&self) -> &'static str {
+    match self {
+      SettingKind::Theme
+      | SettingKind::Density
+      | SettingKind::StripedRows
+      | SettingKind::LocaleFormat
+      | SettingKind::RowNumbers => "Appearance",
+      SettingKind::SystemObjects => "Tables",
+      SettingKind::AutoLimit | SettingKind::ConfirmGeneratedSql => "Query",
+      SettingKind::DefaultExportFormat => "Export",
+    }
+  }
+
+  pub fn label(&self) -> &'static str {
+    match self {
+      SettingKind::Theme => "Theme",
+      SettingKind::Density => "Row density",
+      SettingKind::StripedRows => "Striped rows",
+      SettingKind::LocaleFormat => "Locale numbers and dates",
+      SettingKind::RowNumbers => "Row numbers",
+      SettingKind::SystemObjects => "System objects",
+      SettingKind::AutoLimit => "Auto LIMIT",
+      SettingKind::ConfirmGeneratedSql => "Confirm generated SQL",
+      SettingKind::DefaultExportFormat => "Default export format",
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SettingsFile {
+  settings: Settings,
+}
+
+impl Settings {
+  pub fn value(&self, kind: SettingKind) -> String {
+    match kind {
+      SettingKind::Theme => self.theme.to_string(),
+      SettingKind::Density => self.density.to_string(),
+      SettingKind::StripedRows => if self.striped_rows { "on" } else { "off" }.to_string(),
+      SettingKind::LocaleFormat => if self.locale_format { "on" } else { "off" }.to_string(),
+      SettingKind::RowNumbers => if self.row_numbers { "on" } else { "off" }.to_string(),
+      SettingKind::SystemObjects => if self.show_system_objects { "shown" } else { "hidden" }.to_string(),
+      SettingKind::AutoLimit => self.auto_limit.map_or_else(|| "off".to_string(), |limit| limit.to_string()),
+      SettingKind::ConfirmGeneratedSql => if self.confirm_generated_sql { "on" } else { "off" }.to_string(),
+      SettingKind::DefaultExportFormat => self.default_export_format.to_string(),
+    }
+  }
+
+  /// Moves the setting to its next (or previous) allowed value, wrapping around.
+  pub fn cycle(&mut self, kind: SettingKind, forward: bool) {
+    match kind {
+      SettingKind::Theme => self.theme = cycle(Theme::iter().collect(), self.theme, forward),
+      SettingKind::Density => self.density = cycle(Density::iter().collect(), self.density, forward),
+      SettingKind::StripedRows => self.striped_rows = !self.striped_rows,
+      SettingKind::LocaleFormat => self.locale_format = !self.locale_format,
+      SettingKind::RowNumbers => self.row_numbers = !self.row_numbers,
+      SettingKind::SystemObjects => self.show_system_objects = !self.show_system_objects,
+      SettingKind::AutoLimit => self.auto_limit = cycle(AUTO_LIMITS.to_vec(), self.auto_limit, forward),
+      SettingKind::ConfirmGeneratedSql => self.confirm_generated_sql = !self.confirm_generated_sql,
+      SettingKind::DefaultExportFormat => {
+        self.default_export_format = cycle(ExportFormat::iter().collect(), self.default_export_format, forward)
+      },
+    }
+  }
+
+  /// Writes the settings to the `[settings]` table of the TOML config file in the config directory, keeping the rest
+  /// of the file and its comments.
+  pub fn save(&self) -> Result<PathBuf> {
+    let config_dir = crate::utils::get_config_dir();
+    std::fs::create_dir_all(&config_dir)?;
+    let path = config_dir.join(SETTINGS_FILE);
+    let config = if path.exists() { std::fs::read_to_string(&path)? } else { String::new() };
+    std::fs::write(&path, self.write_into(&config)?)?;
+    Ok(path)
+  }
+
+  /// `config`, the text of a TOML config file, with its `[settings]` table replaced by these settings.
+  fn write_into(&self, config: &str) -> Result<String> {
+    let mut document: DocumentMut = config.parse()?;
+    let settings: DocumentMut = toml::to_string(&SettingsFile { settings: self.clone() })?.parse()?;
+    document["settings"] = settings["settings"].clone();
+    Ok(document.to_string())
+  }
+}
+
+fn cycle<T: PartialEq + Copy>(values: Vec<T>, current: T, forward: bool) -> T {
+  let index = values.iter().position(|v| *v == current).unwrap_or_default();
+  let next = if forward { (index + 1) % values.len() } else { index.checked_sub(1).unwrap_or(values.len() - 1) };
+  values[next]
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_cycle_auto_limit() {
+    let mut settings = Settings::default();
+    settings.cycle(SettingKind::AutoLimit, true);
+    assert_eq!(settings.auto_limit, Some(100));
+    settings.cycle(SettingKind::AutoLimit, false);
+    settings.cycle(SettingKind::AutoLimit, false);
+    assert_eq!(settings.auto_limit, Some(5000));
+  }
+
+  #[test]
+  fn test_settings_file_round_trip() {
+    let settings = Settings {
+      theme: Theme::Light,
+      auto_limit: Some(500),
+      default_export_format: ExportFormat::Parquet,
+      density: Density::Compact,
+      striped_rows: true,
+      locale_format: true,
+      row_numbers: true,
+      show_system_objects: true,
+      confirm_generated_sql: true,
+    };
+    let config = "# Keep the library in the repo\n[library]\ndirectory = \"sql\"\n\n[settings]\ntheme = \"dark\"\n";
+    let text = settings.write_into(config).unwrap();
+    assert!(text.starts_with("# Keep the library in the repo\n[library]\ndirectory = \"sql\"\n"));
+    let parsed: SettingsFile = toml::from_str(&text).unwrap();
+    assert_eq!(parsed.settings, settings);
+    let parsed: SettingsFile = toml::from_str(&settings.write_into("").unwrap()).unwrap();
+    assert_eq!(parsed.settings, settings);
+  }
+}
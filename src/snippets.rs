@@ -0,0 +1,265 @@
+/// Common time expressions offered by the editor's timestamp picker.
+pub const TIME_EXPRESSIONS: &[&str] = &[
+  "now()",
+  "current_date",
+  "now() - interval '1 hour'",
+  "now() - interval '1 day'",
+  "now() - interval '7 days'",
+  "now() - interval '30 days'",
+  "date_trunc('day', now())",
+  "date_trunc('month', now())",
+  "extract(epoch from now())",
+];
+
+/// Rows fetched for the quick-peek popup of a table.
+pub const PREVIEW_ROW_LIMIT: usize = 20;
+
+/// Statement run when a table is loaded into the results.
+pub fn select_all_query(table: &str) -> String {
+  format!("SELECT * FROM {table}")
+}
+
+/// Statement counting the rows of a table.
+pub fn count_rows_query(table: &str) -> String {
+  format!("SELECT count(*) FROM {table}")
+}
+
+/// Statement fetching the first rows of a table for the quick-peek popup.
+pub fn preview_query(table: &str) -> String {
+  format!("SELECT * FROM {table} LIMIT {PREVIEW_ROW_LIMIT}")
+}
+
+/// Builds an `IN (...)` list from CSV, TSV or newline separated values, typically pasted from a spreadsheet.
+///
+/// Values are trimmed, de-duplicated and quoted as SQL string literals unless every value is numeric, in which
+/// case they are emitted bare. Returns `None` when the input contains no values.
+pub fn in_list(text: &str) -> Option<String> {
+  let mut values: Vec<String> = Vec::new();
+  for value in split_values(text) {
+    let value = value.trim();
+    if !value.is_empty() && !values.iter().any(|v| v == value) {
+      values.push(value.to_string());
+    }
+  }
+
+  if values.is_empty() {
+    return None;
+  }
+
+  let items =
+    if values.iter().all(|v| is_numeric(v)) { values } else { values.iter().map(|v| quote_literal(v)).collect() };
+
+  Some(format!("IN ({})", items.join(", ")))
+}
+
+/// Quotes `value` as a SQL string literal.
+pub fn quote_literal(value: &str) -> String {
+  format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Key words Postgres reserves, and those SQLite needs quoted as names, which can't be used as bare identifiers.
+const RESERVED_WORDS: &[&str] = &[
+  "all",
+  "alter",
+  "analyse",
+  "analyze",
+  "and",
+  "any",
+  "array",
+  "as",
+  "asc",
+  "asymmetric",
+  "authorization",
+  "between",
+  "binary",
+  "both",
+  "by",
+  "case",
+  "cast",
+  "check",
+  "collate",
+  "collation",
+  "column",
+  "concurrently",
+  "constraint",
+  "create",
+  "cross",
+  "current_catalog",
+  "current_date",
+  "current_role",
+  "current_schema",
+  "current_time",
+  "current_timestamp",
+  "current_user",
+  "default",
+  "deferrable",
+  "delete",
+  "desc",
+  "distinct",
+  "do",
+  "drop",
+  "else",
+  "end",
+  "escape",
+  "except",
+  "exists",
+  "false",
+  "fetch",
+  "for",
+  "foreign",
+  "freeze",
+  "from",
+  "full",
+  "glob",
+  "grant",
+  "group",
+  "having",
+  "ilike",
+  "in",
+  "index",
+  "initially",
+  "inner",
+  "insert",
+  "intersect",
+  "into",
+  "is",
+  "isnull",
+  "join",
+  "lateral",
+  "leading",
+  "left",
+  "like",
+  "limit",
+  "localtime",
+  "localtimestamp",
+  "natural",
+  "not",
+  "notnull",
+  "null",
+  "offset",
+  "on",
+  "only",
+  "or",
+  "order",
+  "outer",
+  "overlaps",
+  "placing",
+  "primary",
+  "references",
+  "regexp",
+  "returning",
+  "right",
+  "select",
+  "session_user",
+  "set",
+  "similar",
+  "some",
+  "symmetric",
+  "system_user",
+  "table",
+  "tablesample",
+  "then",
+  "to",
+  "trailing",
+  "true",
+  "union",
+  "unique",
+  "update",
+  "user",
+  "using",
+  "values",
+  "variadic",
+  "verbose",
+  "when",
+  "where",
+  "window",
+  "with",
+];
+
+/// `name` as a SQL identifier, double-quoted only when it isn't a plain lower case name or is a reserved word.
+pub fn quote_identifier(name: &str) -> String {
+  let mut chars = name.chars();
+  let plain = chars.next().is_some_and(|c| c.is_ascii_lowercase() || c == '_')
+    && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '$')
+    && !RESERVED_WORDS.contains(&name);
+  if plain {
+    name.to_string()
+  } else {
+    format!("\"{}\"", name.replace('"', "\"\""))
+  }
+}
+
+/// Splits on commas, tabs and newlines while honouring double-quoted CSV fields.
+fn split_values(text: &str) -> Vec<String> {
+  let mut values = Vec::new();
+  let mut current = String::new();
+  let mut quoted = false;
+  let mut chars = text.chars().peekable();
+
+  while let Some(c) = chars.next() {
+    match c {
+      '"' if quoted && chars.peek() == Some(&'"') => {
+        current.push('"');
+        chars.next();
+      },
+      '"' if current.trim().is_empty() || quoted => quoted = !quoted,
+      ',' | '\t' | '\n' | '\r' if !quoted => values.push(std::mem::take(&mut current)),
+      c => current.push(c),
+    }
+  }
+  values.push(current);
+
+  values
+}
+
+/// Numbers with leading zeros (zip codes, padded ids) are treated as text so they keep their formatting.
+pub fn is_numeric(value: &str) -> bool {
+  let digits = value.strip_prefix('-').unwrap_or(value);
+  let leading_zero = digits.len() > 1 && digits.starts_with('0') && !digits.starts_with("0.");
+  !leading_zero && value.parse::<f64>().map(|n| n.is_finite()).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_in_list_strings() {
+    assert_eq!(in_list("a\nb\r\nc\n").unwrap(), "IN ('a', 'b', 'c')");
+    assert_eq!(in_list("o'neil,smith").unwrap(), "IN ('o''neil', 'smith')");
+  }
+
+  #[test]
+  fn test_in_list_numbers() {
+    assert_eq!(in_list("1\t2\t-3.5").unwrap(), "IN (1, 2, -3.5)");
+    assert_eq!(in_list("1, 2, 2, 1").unwrap(), "IN (1, 2)");
+  }
+
+  #[test]
+  fn test_in_list_leading_zeros_stay_text() {
+    assert_eq!(in_list("007\n42").unwrap(), "IN ('007', '42')");
+  }
+
+  #[test]
+  fn test_in_list_quoted_csv() {
+    assert_eq!(in_list("\"Smith, J\",\"say \"\"hi\"\"\"").unwrap(), "IN ('Smith, J', 'say \"hi\"')");
+  }
+
+  #[test]
+  fn test_in_list_empty() {
+    assert_eq!(in_list(" \n\t,"), None);
+  }
+
+  #[test]
+  fn test_quote_identifier() {
+    assert_eq!(quote_identifier("user_id"), "user_id");
+    assert_eq!(quote_identifier("createdAt"), "\"createdAt\"");
+    assert_eq!(quote_identifier("a \"b\""), "\"a \"\"b\"\"\"");
+    assert_eq!(quote_identifier("?column?"), "\"?column?\"");
+    assert_eq!(quote_identifier("order"), "\"order\"");
+    assert_eq!(quote_identifier("user"), "\"user\"");
+    assert_eq!(quote_identifier("users"), "users");
+  }
+}
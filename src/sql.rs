@@ -0,0 +1,537 @@
+use std::{
+  collections::BTreeMap,
+  sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use color_eyre::eyre::{eyre, Result};
+use futures::{stream::BoxStream, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use sqlx::{
+  postgres::{PgConnection, PgPool, PgPoolOptions, PgRow},
+  sqlite::{SqlitePool, SqlitePoolOptions, SqliteRow},
+  Column, Either, Executor, Row, TypeInfo, ValueRef,
+};
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::{
+  field::{Field, Visit},
+  Level, Subscriber,
+};
+use tracing_subscriber::{
+  filter::{LevelFilter, Targets},
+  layer::Context,
+  registry::LookupSpan,
+  Layer,
+};
+
+use crate::{
+  attach::{self, AttachedDatabase},
+  notices::{Notice, Severity},
+  retry::{self, RetryConfig},
+  schema::DbTable,
+};
+
+/// Target of the events sqlx logs the Postgres notices as.
+const NOTICE_TARGET: &str = "sqlx::postgres::notice";
+
+tokio::task_local! {
+  /// Events of the query the current task is running, which its notices are sent to.
+  static QUERY_EVENTS: UnboundedSender<QueryEvent>;
+}
+
+/// Text of NULL cells in the results grid, which [`Nulls`] tells apart from `'NULL'` strings.
+pub const NULL: &str = "NULL";
+
+/// Headers, rows and NULL cells of one statement's results, every value rendered as text.
+pub type ResultSet = (Vec<String>, Vec<Vec<String>>, Nulls);
+
+/// Positions of the NULL cells of a result set, since their text is the same as that of a `'NULL'` string. Maps the
+/// index of each row holding NULLs to the indices of its NULL columns.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Nulls(BTreeMap<usize, Vec<usize>>);
+
+impl Nulls {
+  pub fn insert(&mut self, row: usize, column: usize) {
+    self.0.entry(row).or_default().push(column);
+  }
+
+  pub fn contains(&self, row: usize, column: usize) -> bool {
+    self.0.get(&row).is_some_and(|columns| columns.contains(&column))
+  }
+
+  /// The cells of `row`, the row at `index`, with its NULL ones as `None`.
+  pub fn cells<'a>(&self, index: usize, row: &'a [String]) -> Vec<Option<&'a String>> {
+    row.iter().enumerate().map(|(column, value)| (!self.contains(index, column)).then_some(value)).collect()
+  }
+
+  /// The NULLs of the rows at `indices`, by their position in it.
+  pub fn select(&self, indices: &[usize]) -> Nulls {
+    let selected = indices.iter().enumerate().filter_map(|(i, index)| Some((i, self.0.get(index)?.clone())));
+    Nulls(selected.collect())
+  }
+}
+
+/// What a running query reports, in the order it happens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryEvent {
+  /// Headers, rows and NULL cells of the first result set of the query.
+  Results(Vec<String>, Vec<Vec<String>>, Nulls),
+  /// A further result set, of a later statement returning rows.
+  ResultSet(Vec<String>, Vec<Vec<String>>, Nulls),
+  /// A notice or warning the server sent while running the query, such as `RAISE NOTICE` output.
+  Notice(Notice),
+  /// The query failed with a transient error and runs again: the attempt, the maximum attempts and the error.
+  Retrying(u32, u32, String),
+}
+
+/// A database connection the statements typed in the editor and the introspection queries run on.
+#[async_trait]
+pub trait Queryer: Send + Sync {
+  /// Tables and views whose name contains `search`, sorted by name.
+  async fn load_tables(&self, search: &str) -> Result<Vec<DbTable>>;
+
+  /// Runs the statements of `q`, sending their results to `tx` as they arrive.
+  async fn query(&self, q: &str, tx: UnboundedSender<QueryEvent>) -> Result<()>;
+
+  /// Runs `q` and returns its first result set directly, for queries whose results don't go to the grid.
+  async fn fetch(&self, q: &str) -> Result<ResultSet>;
+
+  /// Asks the server to cancel the running [`Queryer::query`], which then fails. False when nothing was running or
+  /// the database can't cancel queries.
+  async fn cancel(&self) -> Result<bool> {
+    Ok(false)
+  }
+
+  /// Statements to run before each query, on the connection the query then runs on, see
+  /// [`Preamble`](crate::preamble::Preamble).
+  fn set_preamble(&self, sql: Option<String>) {
+    let _ = sql;
+  }
+
+  /// Attaches the database `file` as the schema `alias`, for the rest of the session.
+  async fn attach(&self, file: &str, alias: &str) -> Result<()> {
+    let _ = (file, alias);
+    Err(eyre!("Only SQLite connections can attach database files"))
+  }
+
+  async fn detach(&self, alias: &str) -> Result<()> {
+    let _ = alias;
+    Err(eyre!("Only SQLite connections can attach database files"))
+  }
+}
+
+/// Runs `q` on `db`, running it again with backoff while it fails with a transient error and only reads, and
+/// reporting each retry as [`QueryEvent::Retrying`]. Returns the error of the last attempt.
+pub async fn run(db: &dyn Queryer, q: &str, config: &RetryConfig, tx: UnboundedSender<QueryEvent>) -> Result<()> {
+  let mut attempt = 1;
+  loop {
+    let Err(e) = db.query(q, tx.clone()).await else {
+      return Ok(());
+    };
+    match config.delay_before(attempt + 1) {
+      Some(delay) if retry::is_transient(&e) && retry::is_read_only(q) => {
+        attempt += 1;
+        let _ = tx.send(QueryEvent::Retrying(attempt, config.max_attempts, format!("{e:#}")));
+        tokio::time::sleep(delay).await;
+      },
+      _ => return Err(e),
+    }
+  }
+}
+
+/// Runs `q` like [`run`], sending what it reports to `tx` converted to the caller's own events, such as UI actions.
+pub async fn run_into<T: From<QueryEvent>>(
+  db: &dyn Queryer,
+  q: &str,
+  config: &RetryConfig,
+  tx: UnboundedSender<T>,
+) -> Result<()> {
+  let (events_tx, mut events) = tokio::sync::mpsc::unbounded_channel();
+  let forward = async {
+    while let Some(event) = events.recv().await {
+      let _ = tx.send(T::from(event));
+    }
+  };
+  let (result, ()) = tokio::join!(run(db, q, config, events_tx), forward);
+  result
+}
+
+pub struct Postgres {
+  pool: PgPool,
+  /// Connection the queries run on, one at a time, with the pid of its backend process. Opened on first use, and again
+  /// after it broke.
+  session: tokio::sync::Mutex<Option<(PgConnection, i32)>>,
+  /// Backend pid of the session while it runs a query.
+  running: Mutex<Option<i32>>,
+  preamble: Mutex<Option<String>>,
+}
+
+impl Postgres {
+  pub async fn new(url: &str) -> Result<Self> {
+    let pool = PgPoolOptions::new().max_connections(5).connect(url).await?;
+    Ok(Self { pool, session: Default::default(), running: Default::default(), preamble: Default::default() })
+  }
+
+  async fn open_session(&self) -> Result<(PgConnection, i32)> {
+    let mut connection = self.pool.acquire().await?.detach();
+    let pid = sqlx::query_scalar("SELECT pg_backend_pid()").fetch_one(&mut connection).await?;
+    Ok((connection, pid))
+  }
+}
+
+#[async_trait]
+impl Queryer for Postgres {
+  async fn load_tables(&self, search: &str) -> Result<Vec<DbTable>> {
+    let (_, rows, _) = self
+      .fetch(
+        "SELECT table_schema, table_name FROM information_schema.tables WHERE table_catalog = current_database() ORDER \
+         BY table_name",
+      )
+      .await?;
+    Ok(tables(rows, search))
+  }
+
+  async fn query(&self, q: &str, tx: UnboundedSender<QueryEvent>) -> Result<()> {
+    let mut session = self.session.lock().await;
+    if session.is_none() {
+      *session = Some(self.open_session().await?);
+    }
+    let Some((connection, pid)) = session.as_mut() else {
+      unreachable!("the session was just opened");
+    };
+    *self.running.lock().unwrap_or_else(|e| e.into_inner()) = Some(*pid);
+    let preamble = self.preamble.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let result = QUERY_EVENTS
+      .scope(tx.clone(), async {
+        // Run on its own, its results aren't the query's, and the query keeps its own protocol.
+        if let Some(preamble) = &preamble {
+          connection.execute(preamble.as_str()).await?;
+        }
+        result_sets(connection.fetch_many(q), pg_cells).await
+      })
+      .await;
+    *self.running.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    let mut sets = match result {
+      Ok(sets) => sets,
+      Err(e) => {
+        if e.downcast_ref::<sqlx::Error>().is_some_and(|e| matches!(e, sqlx::Error::Io(_) | sqlx::Error::Protocol(_))) {
+          *session = None;
+        }
+        return Err(e);
+      },
+    };
+    if sets.iter().all(|(headers, ..)| headers.is_empty()) {
+      // Statements returning no rows carry no column names, describing the query gets them when it is a single one.
+      if let Ok(describe) = connection.describe(q).await {
+        if let Some((headers, ..)) = sets.first_mut() {
+          *headers = describe.columns().iter().map(|column| column.name().to_string()).collect();
+        }
+      }
+    }
+    send_results(sets, &tx);
+    Ok(())
+  }
+
+  async fn fetch(&self, q: &str) -> Result<ResultSet> {
+    Ok(result_sets(self.pool.fetch_many(q), pg_cells).await?.into_iter().next().unwrap_or_default())
+  }
+
+  fn set_preamble(&self, sql: Option<String>) {
+    *self.preamble.lock().unwrap_or_else(|e| e.into_inner()) = sql;
+  }
+
+  /// Cancels the backend of the session from another connection, which interrupts only the session's query.
+  async fn cancel(&self) -> Result<bool> {
+    let Some(pid) = *self.running.lock().unwrap_or_else(|e| e.into_inner()) else {
+      return Ok(false);
+    };
+    Ok(sqlx::query_scalar("SELECT pg_cancel_backend($1)").bind(pid).fetch_one(&self.pool).await?)
+  }
+}
+
+pub struct Sqlite {
+  /// A single connection, as attached databases belong to the connection that attached them.
+  pool: SqlitePool,
+  /// Databases attached so far, attached again when the connection is reopened.
+  attached: Arc<Mutex<Vec<AttachedDatabase>>>,
+}
+
+impl Sqlite {
+  pub async fn new(file: &str) -> Result<Self> {
+    Self::connect(&format!("sqlite://{file}")).await
+  }
+
+  /// Closes the connection, waiting for it to finish.
+  pub async fn close(&self) {
+    self.pool.close().await;
+  }
+
+  async fn connect(url: &str) -> Result<Self> {
+    let attached: Arc<Mutex<Vec<AttachedDatabase>>> = Default::default();
+    let reattach = attached.clone();
+    let pool = SqlitePoolOptions::new()
+      .max_connections(1)
+      .idle_timeout(None)
+      .max_lifetime(None)
+      .after_connect(move |connection, _| {
+        let databases = reattach.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        Box::pin(async move {
+          for database in databases {
+            connection.execute(attach::attach_statement(&database.file, &database.alias).as_str()).await?;
+          }
+          Ok(())
+        })
+      })
+      .connect(url)
+      .await?;
+    Ok(Self { pool, attached })
+  }
+}
+
+#[async_trait]
+impl Queryer for Sqlite {
+  async fn load_tables(&self, search: &str) -> Result<Vec<DbTable>> {
+    let (_, rows, _) =
+      self.fetch("SELECT '', name FROM sqlite_master WHERE type IN ('table', 'view') ORDER BY name").await?;
+    Ok(tables(rows, search))
+  }
+
+  async fn query(&self, q: &str, tx: UnboundedSender<QueryEvent>) -> Result<()> {
+    let mut sets = result_sets(self.pool.fetch_many(q), sqlite_cells).await?;
+    if sets.iter().all(|(headers, ..)| headers.is_empty()) {
+      if let Ok(describe) = self.pool.describe(q).await {
+        if let Some((headers, ..)) = sets.first_mut() {
+          *headers = describe.columns().iter().map(|column| column.name().to_string()).collect();
+        }
+      }
+    }
+    send_results(sets, &tx);
+    Ok(())
+  }
+
+  async fn fetch(&self, q: &str) -> Result<ResultSet> {
+    Ok(result_sets(self.pool.fetch_many(q), sqlite_cells).await?.into_iter().next().unwrap_or_default())
+  }
+
+  async fn attach(&self, file: &str, alias: &str) -> Result<()> {
+    self.pool.execute(attach::attach_statement(file, alias).as_str()).await?;
+    let database = AttachedDatabase { alias: alias.to_string(), file: file.to_string() };
+    self.attached.lock().unwrap_or_else(|e| e.into_inner()).push(database);
+    Ok(())
+  }
+
+  async fn detach(&self, alias: &str) -> Result<()> {
+    self.pool.execute(attach::detach_statement(alias).as_str()).await?;
+    self.attached.lock().unwrap_or_else(|e| e.into_inner()).retain(|database| database.alias != alias);
+    Ok(())
+  }
+}
+
+/// The layer of the tracing subscriber sending the notices sqlx logs for Postgres to the query that raised them.
+/// Without it the notices aren't logged at all, and so never reach the results.
+pub fn notice_layer<S: Subscriber + for<'a> LookupSpan<'a>>() -> impl Layer<S> {
+  NoticeLayer.with_filter(Targets::new().with_target(NOTICE_TARGET, LevelFilter::TRACE))
+}
+
+struct NoticeLayer;
+
+impl<S: Subscriber> Layer<S> for NoticeLayer {
+  fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+    let mut message = NoticeMessage::default();
+    event.record(&mut message);
+    // sqlx logs the severities as levels, INFO and LOG both as TRACE.
+    let severity = match *event.metadata().level() {
+      Level::ERROR | Level::WARN => Severity::Warning,
+      Level::INFO => Severity::Notice,
+      Level::DEBUG => Severity::Debug,
+      Level::TRACE => Severity::Info,
+    };
+    let notice = Notice { severity, message: message.0 };
+    let _ = QUERY_EVENTS.try_with(|tx| tx.send(QueryEvent::Notice(notice)));
+  }
+}
+
+#[derive(Default)]
+struct NoticeMessage(String);
+
+impl Visit for NoticeMessage {
+  fn record_str(&mut self, field: &Field, value: &str) {
+    if field.name() == "message" {
+      self.0 = value.to_string();
+    }
+  }
+
+  fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+    if field.name() == "message" {
+      self.0 = format!("{value:?}");
+    }
+  }
+}
+
+/// Tables from `(schema, name)` rows whose name contains `search`.
+fn tables(rows: Vec<Vec<String>>, search: &str) -> Vec<DbTable> {
+  rows
+    .into_iter()
+    .map(|mut row| {
+      row.resize(2, String::new());
+      DbTable { schema: row[0].clone(), name: row[1].clone() }
+    })
+    .filter(|table| table.name.contains(search))
+    .collect()
+}
+
+/// Collects the rows of `stream` into one result set per statement, rendering cells with `cells`. Statements
+/// returning no rows give an empty set.
+async fn result_sets<Q, R: Row>(
+  mut stream: BoxStream<'_, Result<Either<Q, R>, sqlx::Error>>,
+  cells: fn(&R) -> Vec<Option<String>>,
+) -> Result<Vec<ResultSet>> {
+  let mut sets = Vec::new();
+  let mut current: Option<ResultSet> = None;
+  while let Some(item) = stream.try_next().await? {
+    match item {
+      // Sent when a statement completes.
+      Either::Left(_) => sets.push(current.take().unwrap_or_default()),
+      Either::Right(row) => {
+        let (_, rows, nulls) = current.get_or_insert_with(|| {
+          (row.columns().iter().map(|column| column.name().to_string()).collect(), Vec::new(), Nulls::default())
+        });
+        let cells = cells(&row);
+        for column in cells.iter().enumerate().filter_map(|(column, value)| value.is_none().then_some(column)) {
+          nulls.insert(rows.len(), column);
+        }
+        rows.push(cells.into_iter().map(|value| value.unwrap_or_else(|| NULL.to_string())).collect());
+      },
+    }
+  }
+  sets.extend(current);
+  Ok(sets)
+}
+
+/// Sends the first result set with columns as the query's results, or an empty one when no statement returned rows,
+/// followed by the others.
+fn send_results(sets: Vec<ResultSet>, tx: &UnboundedSender<QueryEvent>) {
+  let mut sets = sets.into_iter().filter(|(headers, ..)| !headers.is_empty());
+  let (headers, rows, nulls) = sets.next().unwrap_or_default();
+  let _ = tx.send(QueryEvent::Results(headers, rows, nulls));
+  for (headers, rows, nulls) in sets {
+    let _ = tx.send(QueryEvent::ResultSet(headers, rows, nulls));
+  }
+}
+
+/// Queries without arguments use the simple protocol, whose values all arrive in their text form.
+fn pg_cells(row: &PgRow) -> Vec<Option<String>> {
+  (0..row.len())
+    .map(|i| {
+      match row.try_get_raw(i) {
+        Ok(value) if !value.is_null() => Some(value.as_str().map(String::from).unwrap_or_default()),
+        _ => None,
+      }
+    })
+    .collect()
+}
+
+/// SQLite values have the storage class of the value itself rather than the column's, decoded by that class.
+fn sqlite_cells(row: &SqliteRow) -> Vec<Option<String>> {
+  (0..row.len())
+    .map(|i| {
+      let class = match row.try_get_raw(i) {
+        Ok(value) if !value.is_null() => value.type_info().name().to_string(),
+        _ => return None,
+      };
+      let value = match class.as_str() {
+        "INTEGER" => row.try_get_unchecked::<i64, _>(i).map(|v| v.to_string()).unwrap_or_default(),
+        "REAL" => row.try_get_unchecked::<f64, _>(i).map(|v| format!("{v:?}")).unwrap_or_default(),
+        "BLOB" => {
+          let bytes = row.try_get_unchecked::<Vec<u8>, _>(i).unwrap_or_default();
+          format!("\\x{}", bytes.iter().map(|b| format!("{b:02x}")).collect::<String>())
+        },
+        _ => row.try_get_unchecked::<String, _>(i).unwrap_or_default(),
+      };
+      Some(value)
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+  use tokio::sync::mpsc;
+  use tracing_subscriber::layer::SubscriberExt;
+
+  use super::*;
+
+  #[tokio::test]
+  async fn test_sqlite_query() {
+    let db = Sqlite::connect("sqlite::memory:").await.unwrap();
+    db.fetch("CREATE TABLE orders (id INTEGER, total REAL, note TEXT, raw BLOB)").await.unwrap();
+    db.fetch("INSERT INTO orders VALUES (1, 9.5, NULL, x'0aff'), (2, 3, 'NULL', NULL)").await.unwrap();
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    db.query("SELECT * FROM orders ORDER BY id", tx).await.unwrap();
+    let headers = ["id", "total", "note", "raw"].map(String::from).to_vec();
+    let rows = vec![
+      ["1", "9.5", "NULL", "\\x0aff"].map(String::from).to_vec(),
+      ["2", "3.0", "NULL", "NULL"].map(String::from).to_vec(),
+    ];
+    // The 'NULL' string of the second row isn't a NULL.
+    let mut nulls = Nulls::default();
+    nulls.insert(0, 2);
+    nulls.insert(1, 3);
+    assert_eq!(rx.recv().await, Some(QueryEvent::Results(headers.clone(), rows, nulls)));
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    db.query("SELECT * FROM orders WHERE id > 2", tx).await.unwrap();
+    assert_eq!(rx.recv().await, Some(QueryEvent::Results(headers, Vec::new(), Nulls::default())));
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    db.query(
+      "UPDATE orders SET note = 'paid'; SELECT count(*) AS n FROM orders; SELECT max(id) AS top FROM orders",
+      tx,
+    )
+    .await
+    .unwrap();
+    let row = |value: &str| vec![vec![value.to_string()]];
+    assert_eq!(rx.recv().await, Some(QueryEvent::Results(vec!["n".to_string()], row("2"), Nulls::default())));
+    assert_eq!(rx.recv().await, Some(QueryEvent::ResultSet(vec!["top".to_string()], row("2"), Nulls::default())));
+    assert_eq!(rx.recv().await, None);
+
+    let tables = db.load_tables("ord").await.unwrap();
+    assert_eq!(tables, vec![DbTable { schema: String::new(), name: "orders".to_string() }]);
+  }
+
+  #[test]
+  fn test_notice_layer() {
+    let subscriber = tracing_subscriber::registry().with(notice_layer());
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    tracing::subscriber::with_default(subscriber, || {
+      let message = "relation \"orders\" already exists, skipping".to_string();
+      QUERY_EVENTS.sync_scope(tx, || tracing::event!(target: NOTICE_TARGET, Level::INFO, message));
+      tracing::event!(target: NOTICE_TARGET, Level::WARN, "outside of a query");
+    });
+    let notice =
+      Notice { severity: Severity::Notice, message: "relation \"orders\" already exists, skipping".to_string() };
+    assert_eq!(rx.try_recv(), Ok(QueryEvent::Notice(notice)));
+    assert!(rx.try_recv().is_err());
+  }
+
+  #[tokio::test]
+  async fn test_sqlite_attach() {
+    // Databases attached to an in-memory one are in memory too.
+    let path = |name: &str| std::env::temp_dir().join(format!("query-crafter-{name}-{}.db", std::process::id()));
+    let (main, file) = (path("main"), path("attach"));
+    let _ = std::fs::remove_file(&file);
+    let db = Sqlite::connect(&format!("sqlite://{}?mode=rwc", main.display())).await.unwrap();
+    db.attach(&file.to_string_lossy(), "before").await.unwrap();
+    db.fetch("CREATE TABLE before.orders (id INTEGER)").await.unwrap();
+
+    // A reopened connection attaches the database again.
+    db.pool.acquire().await.unwrap().close().await.unwrap();
+    assert_eq!(db.fetch("SELECT count(*) FROM before.orders").await.unwrap().1, vec![vec!["0".to_string()]]);
+    db.detach("before").await.unwrap();
+    assert!(db.fetch("SELECT count(*) FROM before.orders").await.is_err());
+    db.pool.close().await;
+    std::fs::remove_file(&main).unwrap();
+    std::fs::remove_file(&file).unwrap();
+  }
+}
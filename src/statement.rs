@@ -0,0 +1,303 @@
+/// A single SQL statement located in the query editor buffer.
+///
+/// Positions are `(row, col)` pairs using character columns, the same coordinates `tui_textarea` uses for its
+/// cursor. `start` points at the first character of the statement and `end` points one past its last character
+/// (the terminating `;` is not included).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Statement {
+  pub start: (usize, usize),
+  pub end: (usize, usize),
+  pub text: String,
+  /// Position of the terminating `;`, or the end of the buffer for the last statement.
+  terminator: (usize, usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum State {
+  Normal,
+  SingleQuote,
+  DoubleQuote,
+  LineComment,
+  BlockComment,
+  DollarQuote(String),
+}
+
+/// Splits the editor lines into statements separated by `;`.
+///
+/// Semicolons inside string literals, quoted identifiers, comments and dollar-quoted bodies do not end a
+/// statement. Statements that contain only whitespace are skipped.
+pub fn split(lines: &[String]) -> Vec<Statement> {
+  let mut statements = Vec::new();
+  let mut state = State::Normal;
+  let mut start: Option<(usize, usize)> = None;
+  let mut end = (0, 0);
+
+  for (row, line) in lines.iter().enumerate() {
+    let chars: Vec<char> = line.chars().collect();
+    let mut col = 0;
+    while col < chars.len() {
+      let c = chars[col];
+      let next = chars.get(col + 1).copied();
+      let mut consumed = 1;
+      match state {
+        State::Normal => {
+          match c {
+            ';' => {
+              if let Some(s) = start.take() {
+                statements.push(statement(lines, s, end, (row, col)));
+              }
+              col += 1;
+              continue;
+            },
+            '\'' => state = State::SingleQuote,
+            '"' => state = State::DoubleQuote,
+            '-' if next == Some('-') => {
+              state = State::LineComment;
+              consumed = 2;
+            },
+            '/' if next == Some('*') => {
+              state = State::BlockComment;
+              consumed = 2;
+            },
+            '$' => {
+              if let Some(tag) = dollar_tag(&chars, col) {
+                consumed = tag.chars().count();
+                state = State::DollarQuote(tag);
+              }
+            },
+            _ => {},
+          }
+        },
+        State::SingleQuote if c == '\'' => state = State::Normal,
+        State::DoubleQuote if c == '"' => state = State::Normal,
+        State::BlockComment if c == '*' && next == Some('/') => {
+          state = State::Normal;
+          consumed = 2;
+        },
+        State::DollarQuote(ref tag) if c == '$' && starts_with_at(&chars, col, tag) => {
+          consumed = tag.chars().count();
+          state = State::Normal;
+        },
+        _ => {},
+      }
+
+      if !c.is_whitespace() {
+        start.get_or_insert((row, col));
+        end = (row, (col + consumed).min(chars.len()));
+      }
+      col += consumed;
+    }
+
+    if state == State::LineComment {
+      state = State::Normal;
+    }
+  }
+
+  if let Some(s) = start {
+    let last_row = lines.len().saturating_sub(1);
+    let last_col = lines.last().map(|l| l.chars().count()).unwrap_or_default();
+    statements.push(statement(lines, s, end, (last_row, last_col)));
+  }
+
+  statements
+}
+
+/// Returns the statement that executing at `cursor` would run.
+///
+/// A cursor sitting between two statements belongs to the statement that follows the preceding `;`, and a cursor
+/// placed after the last `;` selects the last statement.
+pub fn at_cursor(lines: &[String], cursor: (usize, usize)) -> Option<Statement> {
+  let statements = split(lines);
+  let index = statements.iter().position(|s| cursor <= s.terminator).unwrap_or(statements.len().saturating_sub(1));
+  statements.into_iter().nth(index)
+}
+
+/// The table named by the first `FROM` clause of a simple query, without any schema qualification.
+pub fn from_table(sql: &str) -> Option<String> {
+  let mut tokens = sql.split_whitespace();
+  tokens.find(|t| t.eq_ignore_ascii_case("from"))?;
+  let table = tokens.next()?.trim_end_matches([';', ',', ')']);
+  if table.is_empty() || table.starts_with('(') {
+    return None;
+  }
+  let name = table.rsplit('.').next().unwrap_or(table);
+  Some(name.trim_matches('"').to_string())
+}
+
+/// Appends `LIMIT limit` to a read-only `SELECT` (or `WITH ... SELECT`) that doesn't already limit its rows.
+pub fn with_limit(sql: &str, limit: usize) -> String {
+  let trimmed = sql.trim_end().trim_end_matches(';').trim_end();
+  let mut tokens = trimmed.split_whitespace().map(|t| t.trim_matches(|c: char| !c.is_alphanumeric()));
+  let is_select = match tokens.next() {
+    Some(first) if first.eq_ignore_ascii_case("select") => true,
+    Some(first) if first.eq_ignore_ascii_case("with") => true,
+    _ => false,
+  };
+  let skip = ["limit", "fetch", "insert", "update", "delete"];
+  if !is_select || tokens.any(|t| skip.iter().any(|s| t.eq_ignore_ascii_case(s))) {
+    return sql.to_string();
+  }
+  // Newlines keep the limit out of a trailing `--` comment. SQLite only takes the limit before an OFFSET.
+  match top_level_offset(trimmed) {
+    Some(offset) => format!("{}\nLIMIT {limit}\n{}", trimmed[..offset].trim_end(), &trimmed[offset..]),
+    None => format!("{trimmed}\nLIMIT {limit}"),
+  }
+}
+
+/// Byte offset of the `OFFSET` keyword of the outer query, outside parentheses, quotes and comments.
+fn top_level_offset(sql: &str) -> Option<usize> {
+  let chars: Vec<(usize, char)> = sql.char_indices().collect();
+  let is_word = |c: char| c.is_alphanumeric() || c == '_';
+  let (mut depth, mut i) = (0usize, 0);
+  while i < chars.len() {
+    let (offset, c) = chars[i];
+    let next = chars.get(i + 1).map(|(_, c)| *c);
+    match c {
+      '\'' | '"' => {
+        i += 1;
+        while i < chars.len() && chars[i].1 != c {
+          i += 1;
+        }
+      },
+      '-' if next == Some('-') => {
+        while i < chars.len() && chars[i].1 != '\n' {
+          i += 1;
+        }
+      },
+      '/' if next == Some('*') => {
+        i += 2;
+        while i < chars.len() && !(chars[i].1 == '*' && chars.get(i + 1).is_some_and(|(_, c)| *c == '/')) {
+          i += 1;
+        }
+        i += 1;
+      },
+      '(' => depth += 1,
+      ')' => depth = depth.saturating_sub(1),
+      c if is_word(c) && (i == 0 || !is_word(chars[i - 1].1)) => {
+        let word: String = chars[i..].iter().map(|(_, c)| *c).take_while(|c| is_word(*c)).collect();
+        if depth == 0 && word.eq_ignore_ascii_case("offset") {
+          return Some(offset);
+        }
+        i += word.chars().count();
+        continue;
+      },
+      _ => {},
+    }
+    i += 1;
+  }
+  None
+}
+
+fn statement(lines: &[String], start: (usize, usize), end: (usize, usize), terminator: (usize, usize)) -> Statement {
+  Statement { start, end, text: text_between(lines, start, end), terminator }
+}
+
+/// Extracts the text between two `(row, col)` positions, joining lines with `\n`.
+pub fn text_between(lines: &[String], start: (usize, usize), end: (usize, usize)) -> String {
+  let mut text = Vec::new();
+  for (row, line) in lines.iter().enumerate().take(end.0 + 1).skip(start.0) {
+    let from = if row == start.0 { start.1 } else { 0 };
+    let to = if row == end.0 { end.1 } else { line.chars().count() };
+    text.push(line.chars().skip(from).take(to.saturating_sub(from)).collect::<String>());
+  }
+  text.join("\n")
+}
+
+fn starts_with_at(chars: &[char], col: usize, pattern: &str) -> bool {
+  pattern.chars().enumerate().all(|(i, p)| chars.get(col + i) == Some(&p))
+}
+
+/// Recognizes a Postgres dollar-quote opening tag (`$$` or `$tag$`) at `col`.
+pub fn dollar_tag(chars: &[char], col: usize) -> Option<String> {
+  if col > 0 && (chars[col - 1].is_alphanumeric() || chars[col - 1] == '_') {
+    return None;
+  }
+  let mut tag = String::from("$");
+  for (i, c) in chars.iter().enumerate().skip(col + 1) {
+    match c {
+      '$' => {
+        tag.push('$');
+        return Some(tag);
+      },
+      c if c.is_alphabetic() || *c == '_' || (c.is_ascii_digit() && i > col + 1) => tag.push(*c),
+      _ => return None,
+    }
+  }
+  None
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn lines(text: &str) -> Vec<String> {
+    text.lines().map(String::from).collect()
+  }
+
+  #[test]
+  fn test_split_single_statement() {
+    let statements = split(&lines("SELECT 1"));
+    assert_eq!(statements.len(), 1);
+    assert_eq!(statements[0].text, "SELECT 1");
+    assert_eq!(statements[0].start, (0, 0));
+    assert_eq!(statements[0].end, (0, 8));
+  }
+
+  #[test]
+  fn test_split_multiple_lines() {
+    let statements = split(&lines("SELECT 1;\n\nSELECT *\n  FROM users;"));
+    let texts: Vec<_> = statements.iter().map(|s| s.text.as_str()).collect();
+    assert_eq!(texts, vec!["SELECT 1", "SELECT *\n  FROM users"]);
+    assert_eq!(statements[1].start, (2, 0));
+    assert_eq!(statements[1].end, (3, 12));
+  }
+
+  #[test]
+  fn test_split_ignores_quoted_semicolons() {
+    let statements = split(&lines("SELECT ';' AS \"a;b\"; -- c;d\nSELECT 2 /* ; */"));
+    let texts: Vec<_> = statements.iter().map(|s| s.text.as_str()).collect();
+    assert_eq!(texts, vec!["SELECT ';' AS \"a;b\"", "-- c;d\nSELECT 2 /* ; */"]);
+  }
+
+  #[test]
+  fn test_split_dollar_quoted_body() {
+    let statements = split(&lines("CREATE FUNCTION f() AS $fn$ BEGIN; END; $fn$ LANGUAGE plpgsql; SELECT $1"));
+    assert_eq!(statements.len(), 2);
+    assert_eq!(statements[1].text, "SELECT $1");
+  }
+
+  #[test]
+  fn test_from_table() {
+    assert_eq!(from_table("SELECT * FROM users WHERE id = 1").unwrap(), "users");
+    assert_eq!(from_table("select * from \"public\".\"Orders\";").unwrap(), "Orders");
+    assert_eq!(from_table("SELECT * FROM (SELECT 1) t"), None);
+    assert_eq!(from_table("SELECT 1"), None);
+  }
+
+  #[test]
+  fn test_with_limit() {
+    assert_eq!(with_limit("SELECT * FROM users;", 100), "SELECT * FROM users\nLIMIT 100");
+    assert_eq!(with_limit("select 1 -- one", 5), "select 1 -- one\nLIMIT 5");
+    assert_eq!(with_limit("SELECT * FROM users LIMIT 10", 100), "SELECT * FROM users LIMIT 10");
+    assert_eq!(with_limit("WITH x AS (DELETE FROM t RETURNING *) SELECT * FROM x", 100).contains("LIMIT"), false);
+    assert_eq!(with_limit("UPDATE users SET a = 1", 100), "UPDATE users SET a = 1");
+    assert_eq!(with_limit("EXPLAIN SELECT * FROM users", 100), "EXPLAIN SELECT * FROM users");
+    assert_eq!(with_limit("SELECT * FROM users OFFSET 10;", 100), "SELECT * FROM users\nLIMIT 100\nOFFSET 10");
+    assert_eq!(
+      with_limit("SELECT * FROM (SELECT * FROM t OFFSET 1) s WHERE note = 'offset' OFFSET 2 -- skip", 5),
+      "SELECT * FROM (SELECT * FROM t OFFSET 1) s WHERE note = 'offset'\nLIMIT 5\nOFFSET 2 -- skip"
+    );
+  }
+
+  #[test]
+  fn test_at_cursor() {
+    let buffer = lines("SELECT 1;\nSELECT 2;\n");
+    assert_eq!(at_cursor(&buffer, (0, 3)).unwrap().text, "SELECT 1");
+    assert_eq!(at_cursor(&buffer, (0, 8)).unwrap().text, "SELECT 1");
+    assert_eq!(at_cursor(&buffer, (1, 0)).unwrap().text, "SELECT 2");
+    assert_eq!(at_cursor(&buffer, (2, 0)).unwrap().text, "SELECT 2");
+    assert_eq!(at_cursor(&lines("  "), (0, 1)), None);
+  }
+}
@@ -0,0 +1,102 @@
+use crate::format::{self, Token};
+
+fn is_tag_char(c: char) -> bool {
+  c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Lower cased `#tag` words in `text`, in order of first appearance. A `#` only starts a tag at the start of a word.
+pub fn parse(text: &str) -> Vec<String> {
+  let mut tags: Vec<String> = Vec::new();
+  let mut previous = ' ';
+  let mut chars = text.chars().peekable();
+  while let Some(c) = chars.next() {
+    if c == '#' && !is_tag_char(previous) && chars.peek().is_some_and(|next| is_tag_char(*next)) {
+      let mut tag = String::new();
+      while let Some(next) = chars.next_if(|next| is_tag_char(*next)) {
+        tag.extend(next.to_lowercase());
+      }
+      if !tags.contains(&tag) {
+        tags.push(tag);
+      }
+      previous = 'x';
+    } else {
+      previous = c;
+    }
+  }
+  tags
+}
+
+/// Tags of a comma or space separated list such as `billing, #debug`, where the `#` is optional.
+pub fn parse_list(list: &str) -> Vec<String> {
+  let words: Vec<String> = list
+    .split([',', ' '])
+    .filter(|word| !word.is_empty())
+    .map(|word| format!("#{}", word.trim_start_matches('#')))
+    .collect();
+  parse(&words.join(" "))
+}
+
+/// Tags in the comments of a SQL statement, e.g. `-- #billing`, so `#` operators aren't mistaken for tags.
+pub fn in_comments(sql: &str) -> Vec<String> {
+  let comments: Vec<String> = format::lex(sql)
+    .into_iter()
+    .filter_map(|(token, _)| {
+      match token {
+        Token::LineComment(text) | Token::BlockComment(text) => Some(text),
+        _ => None,
+      }
+    })
+    .collect();
+  parse(&comments.join("\n"))
+}
+
+/// The tags of a search and the rest of its text, e.g. `#billing refunds` is `billing` and `refunds`.
+pub fn split_search(search: &str) -> (Vec<String>, String) {
+  let (tags, words): (Vec<&str>, Vec<&str>) =
+    search.split_whitespace().partition(|word| word.len() > 1 && word.starts_with('#'));
+  (tags.iter().flat_map(|tag| parse(tag)).collect(), words.join(" "))
+}
+
+/// `text` with the `#prefix` it ends with completed to the first of the `known` tags starting with it, or `None`
+/// when it doesn't end with a tag or no tag matches.
+pub fn complete<'a>(text: &str, known: impl IntoIterator<Item = &'a String>) -> Option<String> {
+  let start = text.rfind('#')?;
+  let prefix = text[start + 1..].to_lowercase();
+  if !prefix.chars().all(is_tag_char) || text[..start].chars().last().is_some_and(is_tag_char) {
+    return None;
+  }
+  let mut candidates: Vec<&String> =
+    known.into_iter().filter(|tag| tag.starts_with(&prefix) && **tag != prefix).collect();
+  candidates.sort();
+  candidates.first().map(|tag| format!("{}#{tag}", &text[..start]))
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_parse() {
+    assert_eq!(parse("#Billing refunds for #debug, again #billing"), vec!["billing".to_string(), "debug".to_string()]);
+    assert_eq!(parse("issue#12 and # alone"), Vec::<String>::new());
+    assert_eq!(in_comments("-- #ops\nSELECT data #> '{a}', '#not' FROM t /* #slow */"), vec![
+      "ops".to_string(),
+      "slow".to_string()
+    ]);
+  }
+
+  #[test]
+  fn test_search_and_complete() {
+    assert_eq!(
+      split_search("#billing  late #Q4 fees"),
+      (vec!["billing".to_string(), "q4".to_string()], "late fees".to_string())
+    );
+    let known = vec!["debug".to_string(), "billing".to_string(), "billing-eu".to_string()];
+    assert_eq!(complete("late #bi", &known), Some("late #billing".to_string()));
+    assert_eq!(complete("#billing", &known), Some("#billing-eu".to_string()));
+    assert_eq!(complete("#x", &known), None);
+    assert_eq!(complete("no tag", &known), None);
+  }
+}
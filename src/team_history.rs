@@ -0,0 +1,197 @@
+use std::{sync::Arc, time::Duration};
+
+use color_eyre::eyre::Result;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgPoolOptions, PgPool, Row};
+use tokio::sync::OnceCell;
+
+use crate::format::{self, Token};
+
+/// Rows shown when browsing the team's history.
+const RECENT_LIMIT: i64 = 200;
+
+/// Mirroring of executed queries to a table shared by a team, the `[team_history]` config table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TeamHistoryConfig {
+  /// Postgres connection URL of the shared database, unset disables mirroring.
+  pub url: Option<String>,
+  /// Table the entries go to, created when missing.
+  pub table: String,
+  /// Label identifying the connection the queries ran against, e.g. `billing-prod`.
+  pub connection_label: String,
+  /// Name recorded with each query, defaults to `$USER`.
+  pub user: Option<String>,
+}
+
+impl Default for TeamHistoryConfig {
+  fn default() -> Self {
+    Self { url: None, table: "query_crafter_history".to_string(), connection_label: String::new(), user: None }
+  }
+}
+
+/// A connection to the shared history table.
+#[derive(Debug, Clone)]
+pub struct TeamHistory {
+  pool: PgPool,
+  config: TeamHistoryConfig,
+  user: String,
+  /// Set once the table was created, shared by the clones.
+  table_created: Arc<OnceCell<()>>,
+}
+
+impl TeamHistory {
+  /// Sets up a lazily connecting pool, `None` when no URL is configured or it doesn't parse.
+  pub fn new(config: &TeamHistoryConfig) -> Option<Self> {
+    let url = config.url.as_deref()?;
+    let pool = PgPoolOptions::new()
+      .max_connections(1)
+      .acquire_timeout(Duration::from_secs(5))
+      .connect_lazy(url)
+      .map_err(|e| log::warn!("Invalid team history URL: {e}"))
+      .ok()?;
+    let user = config.user.clone().or_else(|| std::env::var("USER").ok()).unwrap_or_default();
+    Some(Self { pool, config: config.clone(), user, table_created: Arc::default() })
+  }
+
+  /// Creates the table on first use, and again on the next use when that failed.
+  async fn ensure_table(&self) -> Result<()> {
+    self
+      .table_created
+      .get_or_try_init(|| {
+        async { sqlx::query(&create_table_statement(&self.config.table)).execute(&self.pool).await.map(|_| ()) }
+      })
+      .await?;
+    Ok(())
+  }
+
+  /// Records a successful query with its literals redacted, as the table is shared and values typed into queries
+  /// can be personal data or secrets. Failures are logged only, the shared table is best effort.
+  pub async fn record(&self, statement: &str, duration: Duration) {
+    if let Err(e) = self.insert(&redact_literals(statement), duration).await {
+      log::warn!("Unable to record query in the team history: {e}");
+    }
+  }
+
+  async fn insert(&self, statement: &str, duration: Duration) -> Result<()> {
+    self.ensure_table().await?;
+    let insert = format!(
+      "INSERT INTO {} (statement, duration_ms, connection_label, username) VALUES ($1, $2, $3, $4)",
+      self.config.table
+    );
+    sqlx::query(&insert)
+      .bind(statement)
+      .bind(duration.as_millis() as i64)
+      .bind(&self.config.connection_label)
+      .bind(&self.user)
+      .execute(&self.pool)
+      .await?;
+    Ok(())
+  }
+
+  /// The team's most recent queries as result headers and rows, searchable with the results filter.
+  pub async fn recent(&self) -> Result<(Vec<String>, Vec<Vec<String>>)> {
+    self.ensure_table().await?;
+    let rows = sqlx::query(&recent_statement(&self.config.table))
+      .bind(RECENT_LIMIT)
+      .fetch_all(&self.pool)
+      .await?
+      .iter()
+      .map(|row| {
+        Ok(vec![
+          row.try_get::<String, _>("username")?,
+          row.try_get::<String, _>("connection_label")?,
+          row.try_get::<i64, _>("duration_ms")?.to_string(),
+          row.try_get::<String, _>("executed_at")?,
+          row.try_get::<String, _>("statement")?,
+        ])
+      })
+      .collect::<Result<_, sqlx::Error>>()?;
+    let headers = ["user", "connection", "duration_ms", "executed_at", "statement"];
+    Ok((headers.iter().map(|h| h.to_string()).collect(), rows))
+  }
+}
+
+/// `statement` with its string, number and dollar-quoted literals replaced by `?`. Identifiers, parameters and
+/// comments are kept.
+pub fn redact_literals(statement: &str) -> String {
+  let chars: Vec<char> = statement.chars().collect();
+  let mut redacted = String::new();
+  let mut copied = 0;
+  for (token, start) in format::lex(statement) {
+    let len = match &token {
+      Token::Literal(text) if is_value(text) => text.chars().count(),
+      Token::DollarQuoted { tag, body } => 2 * tag.chars().count() + body.chars().count(),
+      _ => continue,
+    };
+    redacted.extend(&chars[copied..start]);
+    redacted.push('?');
+    copied = (start + len).min(chars.len());
+  }
+  redacted.extend(&chars[copied..]);
+  redacted
+}
+
+/// Whether a lexed literal is a value: a string, possibly prefixed like `E'..'`, or a number. Quoted identifiers,
+/// `$1` parameters and psql variables aren't.
+fn is_value(literal: &str) -> bool {
+  match literal.chars().next() {
+    Some(c) if c.is_ascii_digit() || matches!(c, '\'' | '.' | '+' | '-') => true,
+    Some(c) if c.is_alphabetic() => literal.contains('\''),
+    _ => false,
+  }
+}
+
+fn recent_statement(table: &str) -> String {
+  format!(
+    "SELECT username, connection_label, duration_ms, executed_at::text AS executed_at, statement FROM {table} ORDER BY \
+     id DESC LIMIT $1"
+  )
+}
+
+fn create_table_statement(table: &str) -> String {
+  format!(
+    "CREATE TABLE IF NOT EXISTS {table} (id bigserial PRIMARY KEY, statement text NOT NULL, duration_ms bigint NOT \
+     NULL, connection_label text NOT NULL, username text NOT NULL, executed_at timestamptz NOT NULL DEFAULT now())"
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[tokio::test]
+  async fn test_new() {
+    assert!(TeamHistory::new(&TeamHistoryConfig::default()).is_none());
+    let config = |url: &str| TeamHistoryConfig { url: Some(url.to_string()), ..TeamHistoryConfig::default() };
+    assert!(TeamHistory::new(&config("not a url")).is_none());
+    let history = TeamHistory::new(&config("postgres://team@localhost/history")).unwrap();
+    assert!(!history.table_created.initialized());
+    assert!(Arc::ptr_eq(&history.clone().table_created, &history.table_created));
+  }
+
+  #[test]
+  fn test_redact_literals() {
+    assert_eq!(
+      redact_literals("SELECT * FROM \"users\" WHERE email = 'a@b.c' AND age > -21 AND id = $1 -- 'kept'"),
+      "SELECT * FROM \"users\" WHERE email = ? AND age > ? AND id = $1 -- 'kept'"
+    );
+    assert_eq!(
+      redact_literals("UPDATE t SET secret = E'x''y', body = $b$ é $b$;"),
+      "UPDATE t SET secret = ?, body = ?;"
+    );
+    assert_eq!(redact_literals("SELECT 1.5e3, x - 1"), "SELECT ?, x - ?");
+  }
+
+  #[test]
+  fn test_statements() {
+    assert!(create_table_statement("shared.history").starts_with("CREATE TABLE IF NOT EXISTS shared.history ("));
+    assert_eq!(
+      recent_statement("history"),
+      "SELECT username, connection_label, duration_ms, executed_at::text AS executed_at, statement FROM history ORDER \
+       BY id DESC LIMIT $1"
+    );
+  }
+}
@@ -1,5 +1,9 @@
 use std::{
   ops::{Deref, DerefMut},
+  sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+  },
   time::Duration,
 };
 
@@ -41,6 +45,8 @@ pub enum Event {
   Key(KeyEvent),
   Mouse(MouseEvent),
   Resize(u16, u16),
+  Suspend,
+  Resume,
 }
 
 pub struct Tui {
@@ -53,6 +59,8 @@ pub struct Tui {
   pub tick_rate: f64,
   pub mouse: bool,
   pub paste: bool,
+  /// Set by [`Tui::suspend`] until the matching [`Tui::resume`], a SIGCONT only resumes while it's set.
+  suspended: Arc<AtomicBool>,
 }
 
 impl Tui {
@@ -65,7 +73,8 @@ impl Tui {
     let task = tokio::spawn(async {});
     let mouse = false;
     let paste = false;
-    Ok(Self { terminal, task, cancellation_token, event_rx, event_tx, frame_rate, tick_rate, mouse, paste })
+    let suspended = Arc::new(AtomicBool::new(false));
+    Ok(Self { terminal, task, cancellation_token, event_rx, event_tx, frame_rate, tick_rate, mouse, paste, suspended })
   }
 
   pub fn tick_rate(mut self, tick_rate: f64) -> Self {
@@ -95,10 +104,12 @@ impl Tui {
     self.cancellation_token = CancellationToken::new();
     let _cancellation_token = self.cancellation_token.clone();
     let _event_tx = self.event_tx.clone();
+    let suspended = Arc::clone(&self.suspended);
     self.task = tokio::spawn(async move {
       let mut reader = crossterm::event::EventStream::new();
       let mut tick_interval = tokio::time::interval(tick_delay);
       let mut render_interval = tokio::time::interval(render_delay);
+      let mut job_control = JobControl::new(suspended);
       _event_tx.send(Event::Init).unwrap();
       loop {
         let tick_delay = tick_interval.tick();
@@ -146,6 +157,9 @@ impl Tui {
           _ = render_delay => {
               _event_tx.send(Event::Render).unwrap();
           },
+          event = job_control.recv() => {
+              _event_tx.send(event).unwrap();
+          },
         }
       }
     });
@@ -203,13 +217,21 @@ impl Tui {
 
   pub fn suspend(&mut self) -> Result<()> {
     self.exit()?;
+    self.suspended.store(true, Ordering::SeqCst);
+    // SIGTSTP is handled by the event loop, so stop with the uncatchable SIGSTOP to avoid suspending again.
     #[cfg(not(windows))]
-    signal_hook::low_level::raise(signal_hook::consts::signal::SIGTSTP)?;
+    signal_hook::low_level::raise(signal_hook::consts::signal::SIGSTOP)?;
     Ok(())
   }
 
+  /// Restores raw mode and the alternate screen, then clears it so the next draw repaints everything. Does nothing
+  /// unless suspended, as both the code after [`Tui::suspend`] and the SIGCONT of the same continue call it.
   pub fn resume(&mut self) -> Result<()> {
+    if !self.suspended.swap(false, Ordering::SeqCst) {
+      return Ok(());
+    }
     self.enter()?;
+    self.terminal.clear()?;
     Ok(())
   }
 
@@ -218,6 +240,63 @@ impl Tui {
   }
 }
 
+/// Leaves raw mode and the alternate screen without needing a [`Tui`], for use when the app is going down.
+///
+/// Every step is attempted even if an earlier one fails.
+pub fn restore_terminal() {
+  let _ = crossterm::execute!(io(), DisableBracketedPaste, DisableMouseCapture, LeaveAlternateScreen, cursor::Show);
+  let _ = crossterm::terminal::disable_raw_mode();
+}
+
+/// Turns SIGTSTP (e.g. `kill -TSTP`) and SIGCONT into [`Event::Suspend`] and [`Event::Resume`]. A SIGCONT while
+/// the app isn't suspended, e.g. a stray `kill -CONT`, is ignored.
+///
+/// Ctrl+Z doesn't raise SIGTSTP while the terminal is in raw mode, it arrives as a key event instead.
+struct JobControl {
+  #[cfg(unix)]
+  signals: Option<(tokio::signal::unix::Signal, tokio::signal::unix::Signal)>,
+  #[cfg(unix)]
+  suspended: Arc<AtomicBool>,
+}
+
+impl JobControl {
+  fn new(suspended: Arc<AtomicBool>) -> Self {
+    #[cfg(unix)]
+    {
+      use tokio::signal::unix::{signal, SignalKind};
+      let signals = signal(SignalKind::from_raw(libc::SIGTSTP))
+        .and_then(|tstp| Ok((tstp, signal(SignalKind::from_raw(libc::SIGCONT))?)));
+      if let Err(e) = &signals {
+        log::error!("Unable to listen for job control signals: {e}");
+      }
+      Self { signals: signals.ok(), suspended }
+    }
+    #[cfg(not(unix))]
+    {
+      let _ = suspended;
+      Self {}
+    }
+  }
+
+  async fn recv(&mut self) -> Event {
+    #[cfg(unix)]
+    if let Some((tstp, cont)) = &mut self.signals {
+      loop {
+        tokio::select! {
+          Some(_) = tstp.recv() => return Event::Suspend,
+          Some(_) = cont.recv() => {
+            if self.suspended.load(Ordering::SeqCst) {
+              return Event::Resume;
+            }
+          },
+          else => break,
+        }
+      }
+    }
+    std::future::pending().await
+  }
+}
+
 impl Deref for Tui {
   type Target = ratatui::Terminal<Backend<IO>>;
 
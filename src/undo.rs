@@ -0,0 +1,52 @@
+use std::collections::VecDeque;
+
+/// Operations kept for undo, the oldest is dropped past this.
+const UNDO_LIMIT: usize = 50;
+
+/// Most recent destructive UI operations, each with a description for the status line once it's undone.
+#[derive(Debug)]
+pub struct UndoStack<T> {
+  entries: VecDeque<(String, T)>,
+}
+
+impl<T> Default for UndoStack<T> {
+  fn default() -> Self {
+    Self { entries: VecDeque::new() }
+  }
+}
+
+impl<T> UndoStack<T> {
+  pub fn push(&mut self, description: impl Into<String>, entry: T) {
+    if self.entries.len() == UNDO_LIMIT {
+      self.entries.pop_front();
+    }
+    self.entries.push_back((description.into(), entry));
+  }
+
+  /// The most recent operation and its description.
+  pub fn pop(&mut self) -> Option<(String, T)> {
+    self.entries.pop_back()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_most_recent_first_within_limit() {
+    let mut stack = UndoStack::default();
+    for i in 0..UNDO_LIMIT + 2 {
+      stack.push(format!("op {i}"), i);
+    }
+    assert_eq!(stack.pop(), Some((format!("op {}", UNDO_LIMIT + 1), UNDO_LIMIT + 1)));
+    let mut rest = Vec::new();
+    while let Some((_, i)) = stack.pop() {
+      rest.push(i);
+    }
+    assert_eq!(rest.len(), UNDO_LIMIT - 1);
+    assert_eq!(rest.last(), Some(&2));
+  }
+}
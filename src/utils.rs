@@ -1,29 +1,22 @@
-use std::path::PathBuf;
+use std::io::Write;
 
 use color_eyre::eyre::Result;
-use directories::ProjectDirs;
 use lazy_static::lazy_static;
 use tracing::error;
 use tracing_error::ErrorLayer;
 use tracing_subscriber::{self, prelude::__tracing_subscriber_SubscriberExt, util::SubscriberInitExt, Layer};
 
+pub use crate::dirs::{get_config_dir, get_data_dir, PROJECT_NAME};
+use crate::sql;
+
 const VERSION_MESSAGE: &str =
   concat!(env!("CARGO_PKG_VERSION"), "-", env!("VERGEN_GIT_DESCRIBE"), " (", env!("VERGEN_BUILD_DATE"), ")");
 
 lazy_static! {
-  pub static ref PROJECT_NAME: String = env!("CARGO_CRATE_NAME").to_uppercase().to_string();
-  pub static ref DATA_FOLDER: Option<PathBuf> =
-    std::env::var(format!("{}_DATA", PROJECT_NAME.clone())).ok().map(PathBuf::from);
-  pub static ref CONFIG_FOLDER: Option<PathBuf> =
-    std::env::var(format!("{}_CONFIG", PROJECT_NAME.clone())).ok().map(PathBuf::from);
   pub static ref LOG_ENV: String = format!("{}_LOGLEVEL", PROJECT_NAME.clone());
   pub static ref LOG_FILE: String = format!("{}.log", env!("CARGO_PKG_NAME"));
 }
 
-fn project_directory() -> Option<ProjectDirs> {
-  ProjectDirs::from("com", "kdheepak", env!("CARGO_PKG_NAME"))
-}
-
 pub fn initialize_panic_handler() -> Result<()> {
   let (panic_hook, eyre_hook) = color_eyre::config::HookBuilder::default()
     .panic_section(format!("This is a bug. Consider reporting it at {}", env!("CARGO_PKG_REPOSITORY")))
@@ -33,10 +26,14 @@ pub fn initialize_panic_handler() -> Result<()> {
     .into_hooks();
   eyre_hook.install()?;
   std::panic::set_hook(Box::new(move |panic_info| {
-    if let Ok(mut t) = crate::tui::Tui::new() {
-      if let Err(r) = t.exit() {
-        error!("Unable to exit Terminal: {:?}", r);
-      }
+    crate::tui::restore_terminal();
+
+    // The terminal was restored above, so stderr is visible again.
+    match crate::crash::write_report(&panic_info.to_string()) {
+      Ok(path) => {
+        let _ = writeln!(std::io::stderr(), "A crash report was written to {}", path.display());
+      },
+      Err(e) => error!("Unable to write crash report: {:?}", e),
     }
 
     #[cfg(not(debug_assertions))]
@@ -52,7 +49,8 @@ pub fn initialize_panic_handler() -> Result<()> {
       let file_path = handle_dump(&meta, panic_info);
       // prints human-panic message
       print_msg(file_path, &meta).expect("human-panic: printing error message to console failed");
-      eprintln!("{}", panic_hook.panic_report(panic_info)); // prints color-eyre stack trace to stderr
+      // prints color-eyre stack trace to stderr
+      let _ = writeln!(std::io::stderr(), "{}", panic_hook.panic_report(panic_info));
     }
     let msg = format!("{}", panic_hook.panic_report(panic_info));
     log::error!("Error: {}", strip_ansi_escapes::strip_str(msg));
@@ -72,28 +70,6 @@ pub fn initialize_panic_handler() -> Result<()> {
   Ok(())
 }
 
-pub fn get_data_dir() -> PathBuf {
-  let directory = if let Some(s) = DATA_FOLDER.clone() {
-    s
-  } else if let Some(proj_dirs) = project_directory() {
-    proj_dirs.data_local_dir().to_path_buf()
-  } else {
-    PathBuf::from(".").join(".data")
-  };
-  directory
-}
-
-pub fn get_config_dir() -> PathBuf {
-  let directory = if let Some(s) = CONFIG_FOLDER.clone() {
-    s
-  } else if let Some(proj_dirs) = project_directory() {
-    proj_dirs.config_local_dir().to_path_buf()
-  } else {
-    PathBuf::from(".").join(".config")
-  };
-  directory
-}
-
 pub fn initialize_logging() -> Result<()> {
   let directory = get_data_dir();
   std::fs::create_dir_all(directory.clone())?;
@@ -112,7 +88,7 @@ pub fn initialize_logging() -> Result<()> {
     .with_target(false)
     .with_ansi(false)
     .with_filter(tracing_subscriber::filter::EnvFilter::from_default_env());
-  tracing_subscriber::registry().with(file_subscriber).with(ErrorLayer::default()).init();
+  tracing_subscriber::registry().with(file_subscriber).with(sql::notice_layer()).with(ErrorLayer::default()).init();
   Ok(())
 }
 